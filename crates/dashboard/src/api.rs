@@ -18,7 +18,7 @@ fn clear_token_and_redirect() {
 
 fn with_auth(builder: RequestBuilder) -> RequestBuilder {
     match get_token() {
-        Some(token) => builder.header("Authorization", &format!("Bearer {}", token)),
+        Some(token) => builder.header("Authorization", &format!("Bearer {token}")),
         None => builder,
     }
 }
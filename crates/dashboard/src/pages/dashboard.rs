@@ -11,6 +11,17 @@ struct Project {
     slug: String,
 }
 
+#[derive(Deserialize)]
+struct ProjectPage {
+    data: Vec<Project>,
+}
+
+async fn load_projects() -> Result<Vec<Project>, String> {
+    api::get::<ProjectPage>("/api/v1/projects?limit=100")
+        .await
+        .map(|page| page.data)
+}
+
 fn logout() {
     let _ = gloo_storage::LocalStorage::raw().remove_item("token");
     if let Some(window) = web_sys::window() {
@@ -39,7 +50,7 @@ pub fn DashboardPage() -> impl IntoView {
     let fetch_projects = move || {
         set_loading.set(true);
         leptos::task::spawn_local(async move {
-            let result = api::get::<Vec<Project>>("/api/v1/projects").await;
+            let result = load_projects().await;
             set_projects.set(Some(result));
             set_loading.set(false);
         });
@@ -72,7 +83,7 @@ pub fn DashboardPage() -> impl IntoView {
                     set_new_name.set(String::new());
                     set_new_slug.set(String::new());
                     // Refetch projects
-                    let result = api::get::<Vec<Project>>("/api/v1/projects").await;
+                    let result = load_projects().await;
                     set_projects.set(Some(result));
                 }
                 Err(e) => set_form_error.set(Some(e)),
@@ -0,0 +1,53 @@
+//! Who may use the admin endpoints, which act on the whole instance rather
+//! than on a user's own projects.
+
+use std::collections::HashSet;
+
+use rapina::database::{Db, DbError};
+use rapina::prelude::*;
+use rapina::sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use crate::db::entities::user::{Column as UserColumn, Entity as User};
+
+use super::error::AdminError;
+
+/// Ids of the admins' accounts, shared as app state. Admins are named by id
+/// rather than email, since an address is whatever the user registered with
+/// and is never verified.
+#[derive(Clone, Debug, Default)]
+pub struct Admins(HashSet<Uuid>);
+
+impl Admins {
+    /// Parses a comma-separated list of user ids.
+    pub fn parse(ids: &str) -> std::result::Result<Self, uuid::Error> {
+        ids.split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(Uuid::parse_str)
+            .collect::<std::result::Result<_, _>>()
+            .map(Admins)
+    }
+
+    pub fn contains(&self, user_id: &Uuid) -> bool {
+        self.0.contains(user_id)
+    }
+}
+
+/// Fails unless the current user is an admin.
+pub async fn require_admin(db: &Db, current_user: &CurrentUser, admins: &Admins) -> Result<()> {
+    let pid = Uuid::parse_str(&current_user.id)
+        .map_err(|_| Error::unauthorized("invalid user id in token"))?;
+
+    User::find()
+        .filter(UserColumn::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| Error::unauthorized("user not found"))?;
+
+    if !admins.contains(&pid) {
+        return Err(AdminError::Forbidden.into_api_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use rapina::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+use crate::api::v1::pagination::PageQuery;
+use crate::jobs::{STATUS_DEAD, STATUS_QUEUED, STATUS_RUNNING, STATUS_SUCCEEDED};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Dead,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => STATUS_QUEUED,
+            JobStatus::Running => STATUS_RUNNING,
+            JobStatus::Succeeded => STATUS_SUCCEEDED,
+            JobStatus::Dead => STATUS_DEAD,
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ListJobsQuery {
+    pub status: Option<JobStatus>,
+    pub kind: Option<String>,
+    pub limit: Option<u64>,
+    pub cursor: Option<String>,
+    pub include_total: Option<bool>,
+}
+
+impl ListJobsQuery {
+    pub fn page(&self) -> PageQuery {
+        PageQuery {
+            limit: self.limit,
+            cursor: self.cursor.clone(),
+            include_total: self.include_total,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct JobResponse {
+    pub id: String,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    /// `queued`, `running`, `succeeded` or `dead`.
+    pub status: String,
+    /// Tries so far, including one that is running.
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Set on recurring jobs, which are queued again after each run.
+    pub repeat_seconds: Option<i64>,
+    /// When the job runs next, if it is queued.
+    pub run_at: String,
+    /// When a running job may be claimed by another worker, if its own has
+    /// not finished it by then.
+    pub locked_until: Option<String>,
+    /// Error of the last failed try.
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub finished_at: Option<String>,
+}
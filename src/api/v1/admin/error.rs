@@ -0,0 +1,58 @@
+use rapina::database::DbError;
+use rapina::prelude::*;
+
+pub enum AdminError {
+    DbError(DbError),
+    NotFound,
+    Forbidden,
+    Conflict(String),
+}
+
+impl IntoApiError for AdminError {
+    fn into_api_error(self) -> Error {
+        match self {
+            AdminError::DbError(e) => e.into_api_error(),
+            AdminError::NotFound => Error::not_found("job not found"),
+            AdminError::Forbidden => Error::forbidden("only admins can manage jobs"),
+            AdminError::Conflict(msg) => Error::conflict(msg),
+        }
+    }
+}
+
+impl DocumentedError for AdminError {
+    fn error_variants() -> Vec<ErrorVariant> {
+        vec![
+            ErrorVariant {
+                status: 400,
+                code: "BAD_REQUEST",
+                description: "Invalid filter or pagination cursor",
+            },
+            ErrorVariant {
+                status: 403,
+                code: "FORBIDDEN",
+                description: "User is not an admin",
+            },
+            ErrorVariant {
+                status: 404,
+                code: "NOT_FOUND",
+                description: "Job not found",
+            },
+            ErrorVariant {
+                status: 409,
+                code: "CONFLICT",
+                description: "Job is running, or has not failed",
+            },
+            ErrorVariant {
+                status: 500,
+                code: "INTERNAL_ERROR",
+                description: "Internal server error",
+            },
+        ]
+    }
+}
+
+impl From<DbError> for AdminError {
+    fn from(e: DbError) -> Self {
+        AdminError::DbError(e)
+    }
+}
@@ -0,0 +1,156 @@
+use rapina::database::{Db, DbError};
+use rapina::prelude::*;
+use rapina::sea_orm::{
+    ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use uuid::Uuid;
+
+use crate::api::v1::pagination::{self, Cursor, Page};
+use crate::db::entities::job::{Column, Entity as JobEntity, Model};
+use crate::jobs::{self, STATUS_DEAD, STATUS_QUEUED, STATUS_RUNNING};
+
+use super::access::{Admins, require_admin};
+use super::dto::{JobResponse, ListJobsQuery};
+use super::error::AdminError;
+
+fn to_response(job: Model) -> JobResponse {
+    JobResponse {
+        id: job.pid.to_string(),
+        kind: job.kind,
+        payload: job.payload,
+        status: job.status,
+        attempts: job.attempts,
+        max_attempts: job.max_attempts,
+        repeat_seconds: job.repeat_seconds,
+        run_at: job.run_at.to_rfc3339(),
+        locked_until: job.locked_until.map(|at| at.to_rfc3339()),
+        last_error: job.last_error,
+        created_at: job.created_at.to_rfc3339(),
+        updated_at: job.updated_at.to_rfc3339(),
+        finished_at: job.finished_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+async fn find_job(db: &Db, id: String) -> Result<Model> {
+    let pid = Uuid::parse_str(&id).map_err(|_| AdminError::NotFound.into_api_error())?;
+
+    JobEntity::find()
+        .filter(Column::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| AdminError::NotFound.into_api_error())
+}
+
+/// Lists jobs of every kind, newest first.
+#[get("/api/v1/admin/jobs")]
+#[errors(AdminError)]
+pub async fn list_jobs(
+    query: Query<ListJobsQuery>,
+    db: Db,
+    current_user: CurrentUser,
+    admins: State<Admins>,
+) -> Result<Json<Page<JobResponse>>> {
+    require_admin(&db, &current_user, &admins.into_inner()).await?;
+
+    let params = query.into_inner();
+    let page = params.page();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = JobEntity::find();
+    if let Some(status) = params.status {
+        q = q.filter(Column::Status.eq(status.as_str()));
+    }
+    if let Some(kind) = params.kind {
+        q = q.filter(Column::Kind.eq(kind));
+    }
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(Column::Id.lt(cursor.id));
+    }
+
+    let mut jobs = q
+        .order_by_desc(Column::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut jobs, limit, |j| Cursor::from_id(j.id));
+
+    Ok(Json(Page {
+        data: jobs.into_iter().map(to_response).collect(),
+        next_cursor,
+        total,
+    }))
+}
+
+#[get("/api/v1/admin/jobs/:id")]
+#[errors(AdminError)]
+pub async fn get_job(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    admins: State<Admins>,
+) -> Result<Json<JobResponse>> {
+    require_admin(&db, &current_user, &admins.into_inner()).await?;
+
+    let job = find_job(&db, id.into_inner()).await?;
+
+    Ok(Json(to_response(job)))
+}
+
+/// Runs a failed job again straight away, with all its attempts: a dead job,
+/// or a queued one waiting to be retried.
+#[post("/api/v1/admin/jobs/:id/retry")]
+#[errors(AdminError)]
+pub async fn retry_job(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    admins: State<Admins>,
+) -> Result<Json<JobResponse>> {
+    require_admin(&db, &current_user, &admins.into_inner()).await?;
+
+    let job = find_job(&db, id.into_inner()).await?;
+    let failed =
+        job.status == STATUS_DEAD || (job.status == STATUS_QUEUED && job.last_error.is_some());
+    if !failed {
+        return Err(
+            AdminError::Conflict(format!("job is {} and has not failed", job.status))
+                .into_api_error(),
+        );
+    }
+
+    let job = jobs::retry(db.conn(), job).await.map_err(DbError)?;
+
+    Ok(Json(to_response(job)))
+}
+
+/// Deletes a job that is not running, such as a dead one that will not be
+/// retried.
+#[delete("/api/v1/admin/jobs/:id")]
+#[errors(AdminError)]
+pub async fn delete_job(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    admins: State<Admins>,
+) -> Result<StatusCode> {
+    require_admin(&db, &current_user, &admins.into_inner()).await?;
+
+    let job = find_job(&db, id.into_inner()).await?;
+    if job.status == STATUS_RUNNING {
+        return Err(AdminError::Conflict("job is running".to_string()).into_api_error());
+    }
+
+    job.delete(db.conn()).await.map_err(DbError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
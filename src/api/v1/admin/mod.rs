@@ -0,0 +1,15 @@
+pub mod access;
+pub mod dto;
+pub mod error;
+pub mod handlers;
+
+use handlers::*;
+use rapina::prelude::*;
+
+pub fn routes() -> Router {
+    Router::new()
+        .get("/jobs", list_jobs)
+        .get("/jobs/:id", get_job)
+        .post("/jobs/:id/retry", retry_job)
+        .delete("/jobs/:id", delete_job)
+}
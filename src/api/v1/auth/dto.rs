@@ -1,5 +1,9 @@
 use rapina::schemars::{self, JsonSchema};
 use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidateEmail, ValidateLength, ValidationError, ValidationErrors};
+
+use crate::api::v1::validation::{self, MIN_PASSWORD_LENGTH};
+use crate::notifications::Frequency;
 
 #[derive(Deserialize, JsonSchema)]
 pub struct RegisterRequest {
@@ -8,6 +12,42 @@ pub struct RegisterRequest {
     pub name: Option<String>,
 }
 
+/// Written out rather than derived: derived rules echo the offending value
+/// back in the error details, which must not happen for the password.
+impl Validate for RegisterRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if !self.email.validate_email() || !self.email.validate_length(None, Some(255), None) {
+            errors.add("email", ValidationError::new("email"));
+        }
+
+        if !self
+            .password
+            .validate_length(Some(MIN_PASSWORD_LENGTH), Some(72), None)
+        {
+            let mut err = ValidationError::new("length");
+            err.add_param("min".into(), &MIN_PASSWORD_LENGTH);
+            err.add_param("max".into(), &72);
+            errors.add("password", err);
+        } else if let Err(err) = validation::password_strength(&self.password) {
+            errors.add("password", err);
+        }
+
+        if let Some(ref name) = self.name
+            && !name.validate_length(Some(1), Some(255), None)
+        {
+            errors.add("name", ValidationError::new("length"));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
 #[derive(Deserialize, JsonSchema)]
 pub struct LoginRequest {
     pub email: String,
@@ -28,3 +68,17 @@ pub struct UserResponse {
     pub name: Option<String>,
     pub avatar_url: Option<String>,
 }
+
+#[derive(Serialize, JsonSchema)]
+pub struct NotificationPreferencesResponse {
+    /// How new form submissions to the user's projects are emailed.
+    pub submissions: Frequency,
+    /// When the last digest was sent, or would have been if there had been
+    /// submissions.
+    pub digest_sent_at: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct UpdateNotificationPreferencesRequest {
+    pub submissions: Option<Frequency>,
+}
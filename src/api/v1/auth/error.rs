@@ -32,6 +32,11 @@ impl DocumentedError for AuthError {
                 code: "CONFLICT",
                 description: "Email already registered",
             },
+            ErrorVariant {
+                status: 422,
+                code: "VALIDATION_ERROR",
+                description: "Request body failed validation; details list each invalid field",
+            },
             ErrorVariant {
                 status: 500,
                 code: "INTERNAL_ERROR",
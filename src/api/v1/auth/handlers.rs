@@ -1,11 +1,18 @@
+use chrono::Utc;
 use rapina::database::{Db, DbError};
 use rapina::prelude::*;
-use rapina::sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, Set,
+};
 use uuid::Uuid;
 
-use crate::db::entities::user::{ActiveModel, Column, Entity as User};
+use crate::db::entities::user::{ActiveModel, Column, Entity as User, Model};
+use crate::notifications::Frequency;
 
-use super::dto::{AuthResponse, LoginRequest, RegisterRequest, UserResponse};
+use super::dto::{
+    AuthResponse, LoginRequest, NotificationPreferencesResponse, RegisterRequest,
+    UpdateNotificationPreferencesRequest, UserResponse,
+};
 use super::error::AuthError;
 
 #[public]
@@ -14,9 +21,9 @@ use super::error::AuthError;
 pub async fn register(
     db: Db,
     auth: State<AuthConfig>,
-    body: Json<RegisterRequest>,
+    body: Validated<Json<RegisterRequest>>,
 ) -> Result<Json<AuthResponse>> {
-    let req = body.into_inner();
+    let req = body.into_inner().into_inner();
     let auth_config = auth.into_inner();
 
     let existing = User::find()
@@ -108,20 +115,68 @@ pub async fn login(
 #[get("/api/v1/auth/me")]
 #[errors(AuthError)]
 pub async fn me(db: Db, current_user: CurrentUser) -> Result<Json<UserResponse>> {
+    let user = find_current_user(&db, &current_user).await?;
+
+    Ok(Json(UserResponse {
+        id: user.pid.to_string(),
+        email: user.email,
+        name: user.name,
+        avatar_url: user.avatar_url,
+    }))
+}
+
+async fn find_current_user(db: &Db, current_user: &CurrentUser) -> Result<Model> {
     let pid = Uuid::parse_str(&current_user.id)
         .map_err(|_| Error::unauthorized("invalid user id in token"))?;
 
-    let user = User::find()
+    User::find()
         .filter(Column::Pid.eq(pid))
         .one(db.conn())
         .await
         .map_err(DbError)?
-        .ok_or_else(|| Error::not_found("user not found"))?;
+        .ok_or_else(|| Error::not_found("user not found"))
+}
 
-    Ok(Json(UserResponse {
-        id: user.pid.to_string(),
-        email: user.email,
-        name: user.name,
-        avatar_url: user.avatar_url,
-    }))
+fn to_preferences(user: &Model) -> NotificationPreferencesResponse {
+    NotificationPreferencesResponse {
+        submissions: Frequency::parse(&user.submission_notifications).unwrap_or_default(),
+        digest_sent_at: user.digest_sent_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+#[get("/api/v1/auth/me/notifications")]
+#[errors(AuthError)]
+pub async fn get_notification_preferences(
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<NotificationPreferencesResponse>> {
+    let user = find_current_user(&db, &current_user).await?;
+
+    Ok(Json(to_preferences(&user)))
+}
+
+#[put("/api/v1/auth/me/notifications")]
+#[errors(AuthError)]
+pub async fn update_notification_preferences(
+    db: Db,
+    current_user: CurrentUser,
+    body: Json<UpdateNotificationPreferencesRequest>,
+) -> Result<Json<NotificationPreferencesResponse>> {
+    let user = find_current_user(&db, &current_user).await?;
+    let req = body.into_inner();
+
+    let Some(submissions) = req.submissions else {
+        return Ok(Json(to_preferences(&user)));
+    };
+    let was_digest = Frequency::parse(&user.submission_notifications) == Some(Frequency::Digest);
+    let mut active = user.into_active_model();
+    active.submission_notifications = Set(submissions.as_str().to_string());
+    // The first digest covers what arrives from now on, not submissions the
+    // user was already emailed about.
+    if submissions == Frequency::Digest && !was_digest {
+        active.digest_sent_at = Set(Some(Utc::now().fixed_offset()));
+    }
+    let user = active.update(db.conn()).await.map_err(DbError)?;
+
+    Ok(Json(to_preferences(&user)))
 }
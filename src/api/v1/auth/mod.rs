@@ -15,4 +15,6 @@ pub fn routes() -> Router {
         .post("/register", register)
         .post("/login", login)
         .get("/me", me)
+        .get("/me/notifications", get_notification_preferences)
+        .put("/me/notifications", update_notification_preferences)
 }
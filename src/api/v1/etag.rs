@@ -0,0 +1,134 @@
+//! Optimistic concurrency for resources with an `updated_at`.
+//!
+//! Handlers that return a single resource wrap it in [`Json`], which sends
+//! an `ETag` derived from its `updated_at`. Writes that send `If-Match` only go through if the resource
+//! still has that `updated_at`, so two clients editing the same resource
+//! cannot silently overwrite each other.
+
+use rapina::database::DbError;
+use rapina::http::header::{ETAG, IF_MATCH};
+use rapina::http::{HeaderMap, HeaderValue, Response};
+use rapina::prelude::*;
+use rapina::response::BoxBody;
+use rapina::sea_orm::prelude::DateTimeWithTimeZone;
+use rapina::sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter,
+};
+
+pub enum PreconditionError {
+    Failed,
+}
+
+impl IntoApiError for PreconditionError {
+    fn into_api_error(self) -> Error {
+        match self {
+            PreconditionError::Failed => Error::new(
+                412,
+                "PRECONDITION_FAILED",
+                "the resource was modified since it was fetched",
+            ),
+        }
+    }
+}
+
+/// The `ETag` of a resource last updated at `updated_at`.
+pub fn etag(updated_at: &DateTimeWithTimeZone) -> String {
+    format!("\"{}\"", updated_at.timestamp_micros())
+}
+
+/// Checks the request's `If-Match` against the resource's `updated_at`.
+///
+/// Returns the `updated_at` the write has to find unchanged, or `None` when
+/// the request is unconditional (no `If-Match`, or `If-Match: *`).
+pub fn if_match(
+    headers: &HeaderMap,
+    updated_at: DateTimeWithTimeZone,
+) -> Result<Option<DateTimeWithTimeZone>> {
+    let values: Vec<&str> = headers
+        .get_all(IF_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect();
+
+    if values.is_empty() || values.contains(&"*") {
+        return Ok(None);
+    }
+
+    let current = etag(&updated_at);
+    if values.iter().any(|v| *v == current) {
+        Ok(Some(updated_at))
+    } else {
+        Err(PreconditionError::Failed.into_api_error())
+    }
+}
+
+/// Saves `active`, but only if `column` still equals `guard` when one is
+/// given. Losing that race to another write fails the precondition.
+pub async fn update<A, C>(
+    conn: &C,
+    active: A,
+    column: <A::Entity as EntityTrait>::Column,
+    guard: Option<DateTimeWithTimeZone>,
+) -> Result<<A::Entity as EntityTrait>::Model>
+where
+    A: ActiveModelTrait + ActiveModelBehavior + Send,
+    C: ConnectionTrait,
+    <A::Entity as EntityTrait>::Model: IntoActiveModel<A>,
+{
+    let active = active.before_save(conn, false).await.map_err(DbError)?;
+
+    let mut query = A::Entity::update(active);
+    if let Some(updated_at) = guard {
+        query = query.filter(column.eq(updated_at));
+    }
+
+    match query.exec(conn).await {
+        Ok(model) => Ok(model),
+        Err(DbErr::RecordNotUpdated) if guard.is_some() => {
+            Err(PreconditionError::Failed.into_api_error())
+        }
+        Err(e) => Err(DbError(e).into_api_error()),
+    }
+}
+
+/// A single resource as JSON, sent with its `ETag`.
+///
+/// Named `Json` so the route macros still document `T` as the response body.
+pub struct Json<T> {
+    status: StatusCode,
+    etag: String,
+    body: T,
+}
+
+impl<T> Json<T> {
+    /// `body`, a resource last updated at `updated_at`, with `200 OK`.
+    pub fn new(updated_at: DateTimeWithTimeZone, body: T) -> Self {
+        Json {
+            status: StatusCode::OK,
+            etag: etag(&updated_at),
+            body,
+        }
+    }
+
+    /// `body`, a resource just created at `updated_at`, with `201 Created`.
+    pub fn created(updated_at: DateTimeWithTimeZone, body: T) -> Self {
+        Json {
+            status: StatusCode::CREATED,
+            ..Json::new(updated_at, body)
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response<BoxBody> {
+        let mut res = (self.status, rapina::extract::Json(self.body)).into_response();
+        if let Ok(value) = HeaderValue::from_str(&self.etag) {
+            res.headers_mut().insert(ETAG, value);
+        }
+        res
+    }
+}
@@ -0,0 +1,101 @@
+use rapina::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::api::v1::validation::MEDIA_URL;
+use crate::incentives::MAX_CODE_LENGTH;
+
+/// Most codes added in one request.
+const MAX_CODES_PER_REQUEST: u64 = 10_000;
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct AddIncentiveCodesRequest {
+    /// Codes to hand out, one per approved submission. Surrounding
+    /// whitespace is trimmed; blank codes and codes the pool already has are
+    /// skipped.
+    #[validate(
+        length(min = 1, max = "MAX_CODES_PER_REQUEST"),
+        custom(function = "code_lengths")
+    )]
+    pub codes: Vec<String>,
+}
+
+fn code_lengths(codes: &[String]) -> std::result::Result<(), ValidationError> {
+    if codes
+        .iter()
+        .all(|code| code.trim().len() <= MAX_CODE_LENGTH)
+    {
+        Ok(())
+    } else {
+        Err(ValidationError::new("length")
+            .with_message(format!("codes must be at most {MAX_CODE_LENGTH} bytes long").into()))
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct IncentivePoolResponse {
+    pub form_id: String,
+    /// Codes left to issue.
+    pub available: u64,
+    pub issued: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AddIncentiveCodesResponse {
+    pub added: u64,
+    /// Blank codes, and codes the pool already had.
+    pub skipped: u64,
+    #[serde(flatten)]
+    pub pool: IncentivePoolResponse,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ThankYouEmailResponse {
+    pub id: String,
+    pub form_id: String,
+    pub testimonial_id: String,
+    /// Where the thank-you was sent.
+    pub email: String,
+    /// The incentive it offered, as the form described it at the time.
+    pub incentive: Option<String>,
+    /// The code issued from the form's pool, if it has one.
+    pub code: Option<String>,
+    pub created_at: String,
+    /// When the email went out; null while it is waiting to be sent again.
+    pub sent_at: Option<String>,
+}
+
+/// A testimonial left through a form's public page.
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct SubmitFormRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub author_name: String,
+    /// Where the thank-you is sent once the testimonial is approved.
+    #[validate(email, length(max = 255))]
+    pub author_email: Option<String>,
+    #[validate(length(max = 255))]
+    pub author_title: Option<String>,
+    #[validate(length(max = 255))]
+    pub author_company: Option<String>,
+    #[validate(url)]
+    pub author_url: Option<String>,
+    /// Required unless a video is given, if the form takes text.
+    #[validate(length(max = 10_000))]
+    pub content: Option<String>,
+    /// Required if the form asks for one.
+    #[validate(range(min = 1, max = 5))]
+    pub rating: Option<i16>,
+    /// Only if the form takes videos.
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
+    pub video_url: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FormSubmissionResponse {
+    /// Id of the testimonial, which waits for the owner's approval.
+    pub id: String,
+    pub thank_you_title: Option<String>,
+    pub thank_you_message: Option<String>,
+    pub thank_you_cta_text: Option<String>,
+    pub thank_you_cta_url: Option<String>,
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use rapina::database::DbError;
+use rapina::prelude::*;
+
+pub enum FormError {
+    DbError(DbError),
+    NotFound,
+    Forbidden,
+    /// The submission does not fit what the form takes.
+    InvalidSubmission(String),
+    /// Too many submissions from one address or to one form; holds how long
+    /// until another is taken.
+    TooManySubmissions(Duration),
+}
+
+impl IntoApiError for FormError {
+    fn into_api_error(self) -> Error {
+        match self {
+            FormError::DbError(e) => e.into_api_error(),
+            FormError::NotFound => Error::not_found("form not found"),
+            FormError::Forbidden => Error::forbidden("you do not own this project"),
+            FormError::InvalidSubmission(msg) => Error::validation(msg),
+            FormError::TooManySubmissions(wait) => Error::rate_limited(format!(
+                "too many submissions, try again in {} seconds",
+                wait.as_secs() + 1
+            )),
+        }
+    }
+}
+
+impl DocumentedError for FormError {
+    fn error_variants() -> Vec<ErrorVariant> {
+        vec![
+            ErrorVariant {
+                status: 400,
+                code: "BAD_REQUEST",
+                description: "Invalid pagination cursor",
+            },
+            ErrorVariant {
+                status: 404,
+                code: "NOT_FOUND",
+                description: "Form not found, or no longer taking submissions",
+            },
+            ErrorVariant {
+                status: 403,
+                code: "FORBIDDEN",
+                description: "User does not own the form's project",
+            },
+            ErrorVariant {
+                status: 422,
+                code: "VALIDATION_ERROR",
+                description: "Request body failed validation, or the submission does not fit the form",
+            },
+            ErrorVariant {
+                status: 429,
+                code: "RATE_LIMITED",
+                description: "Too many submissions from this address or to this form",
+            },
+            ErrorVariant {
+                status: 500,
+                code: "INTERNAL_ERROR",
+                description: "Internal server error",
+            },
+        ]
+    }
+}
+
+impl From<DbError> for FormError {
+    fn from(e: DbError) -> Self {
+        FormError::DbError(e)
+    }
+}
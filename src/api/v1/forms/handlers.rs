@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use rapina::database::{Db, DbError};
+use rapina::prelude::*;
+use rapina::sea_orm::sea_query::Expr;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::api::v1::pagination::{self, Cursor, Page, PageQuery};
+use crate::api::v1::testimonials::dto::CreateTestimonialRequest;
+use crate::api::v1::testimonials::events;
+use crate::api::v1::testimonials::handlers::{new_testimonial, queue_jobs};
+use crate::db::entities::form::{Column, Entity as Form, Model};
+use crate::db::entities::incentive_code::{Column as CodeColumn, Entity as IncentiveCode};
+use crate::db::entities::project::{Column as ProjectColumn, Entity as Project};
+use crate::db::entities::testimonial::{Column as TestimonialColumn, Entity as Testimonial};
+use crate::db::entities::thank_you_email::{
+    Column as ThankYouColumn, Entity as ThankYouEmail, Model as ThankYouModel,
+};
+use crate::db::entities::user::{Column as UserColumn, Entity as User};
+use crate::incentives::{self, Pool};
+use crate::net::Destinations;
+use crate::notifications::FORM_SOURCE;
+use crate::sentiment;
+use crate::video;
+
+use super::dto::{
+    AddIncentiveCodesRequest, AddIncentiveCodesResponse, FormSubmissionResponse,
+    IncentivePoolResponse, SubmitFormRequest, ThankYouEmailResponse,
+};
+use super::error::FormError;
+use super::throttle::SubmissionThrottle;
+
+fn to_pool_response(pool: Pool, form_pid: &Uuid) -> IncentivePoolResponse {
+    IncentivePoolResponse {
+        form_id: form_pid.to_string(),
+        available: pool.available,
+        issued: pool.issued,
+    }
+}
+
+fn to_thank_you_response(
+    thank_you: ThankYouModel,
+    form_pid: &Uuid,
+    testimonial_pid: Option<&Uuid>,
+    code: Option<&String>,
+) -> ThankYouEmailResponse {
+    ThankYouEmailResponse {
+        id: thank_you.pid.to_string(),
+        form_id: form_pid.to_string(),
+        testimonial_id: testimonial_pid.map(Uuid::to_string).unwrap_or_default(),
+        email: thank_you.email,
+        incentive: thank_you.incentive,
+        code: code.cloned(),
+        created_at: thank_you.created_at.to_rfc3339(),
+        sent_at: thank_you.sent_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+async fn resolve_user_id(db: &Db, current_user: &CurrentUser) -> Result<i32> {
+    let pid = Uuid::parse_str(&current_user.id)
+        .map_err(|_| Error::unauthorized("invalid user id in token"))?;
+
+    let user = User::find()
+        .filter(UserColumn::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| Error::unauthorized("user not found"))?;
+
+    Ok(user.id)
+}
+
+/// The form with public id `id`, if the user owns its project.
+async fn find_owned_form(db: &Db, id: String, user_id: i32) -> Result<Model> {
+    let pid = Uuid::parse_str(&id).map_err(|_| FormError::NotFound.into_api_error())?;
+
+    let form = Form::find()
+        .filter(Column::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| FormError::NotFound.into_api_error())?;
+
+    let project = Project::find_by_id(form.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| FormError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(FormError::Forbidden.into_api_error());
+    }
+
+    Ok(form)
+}
+
+/// Checks a submission against what the form takes.
+fn check_submission(form: &Model, req: &SubmitFormRequest) -> Result<()> {
+    let invalid = |msg: &str| Err(FormError::InvalidSubmission(msg.to_string()).into_api_error());
+    let has_content = req.content.as_deref().is_some_and(|c| !c.trim().is_empty());
+
+    if has_content && !form.allow_text {
+        return invalid("this form does not take text testimonials");
+    }
+    if req.video_url.is_some() && !form.allow_video {
+        return invalid("this form does not take video testimonials");
+    }
+    if !has_content && req.video_url.is_none() {
+        return invalid("a testimonial needs content or a video");
+    }
+    if form.require_rating && req.rating.is_none() {
+        return invalid("this form asks for a rating");
+    }
+    Ok(())
+}
+
+/// Takes a testimonial submitted through the form with slug `slug`. It waits
+/// for the owner's approval, and the project's members are notified as their
+/// preferences say. Submissions are throttled per client address and per
+/// form, as [`SubmissionThrottle`] describes.
+#[public]
+#[post("/api/v1/forms/:slug/submissions")]
+#[errors(FormError)]
+pub async fn submit_form(
+    slug: Path<String>,
+    db: Db,
+    destinations: State<Destinations>,
+    throttle: State<SubmissionThrottle>,
+    headers: Headers,
+    body: Validated<Json<SubmitFormRequest>>,
+) -> Result<(StatusCode, Json<FormSubmissionResponse>)> {
+    let throttle = throttle.into_inner();
+    let too_many = |wait| FormError::TooManySubmissions(wait).into_api_error();
+    throttle.check_address(&headers.0).map_err(too_many)?;
+    let form = Form::find()
+        .filter(Column::Slug.eq(slug.into_inner()))
+        .filter(Column::IsActive.eq(true))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| FormError::NotFound.into_api_error())?;
+    let project = Project::find_by_id(form.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| FormError::NotFound.into_api_error())?;
+    throttle.check_form(form.id).map_err(too_many)?;
+
+    let req = body.into_inner().into_inner();
+    check_submission(&form, &req)?;
+    // Videos are fetched to be probed, so they have to be public like
+    // webhook URLs.
+    if let Some(video_url) = req.video_url.as_deref().filter(|url| !url.starts_with('/')) {
+        destinations
+            .into_inner()
+            .check(video_url)
+            .map_err(|e| FormError::InvalidSubmission(e.to_string()).into_api_error())?;
+    }
+
+    let testimonial_type = if req.video_url.is_some() {
+        "video"
+    } else {
+        "text"
+    };
+    let mut testimonial = new_testimonial(
+        project.id,
+        CreateTestimonialRequest {
+            author_name: req.author_name,
+            testimonial_type: Some(testimonial_type.to_string()),
+            content: req.content.filter(|c| !c.trim().is_empty()),
+            rating: req.rating,
+            author_email: req.author_email,
+            author_title: req.author_title,
+            author_avatar_url: None,
+            author_company: req.author_company,
+            author_url: req.author_url,
+            video_url: req.video_url,
+            video_thumbnail_url: None,
+            video_duration_seconds: None,
+            transcription: None,
+            source: Some(FORM_SOURCE.to_string()),
+            source_platform: None,
+            source_url: None,
+            source_id: Some(form.pid.to_string()),
+            sentiment: None,
+            sentiment_score: None,
+            language: None,
+        },
+    );
+    testimonial.form_id = Set(Some(form.id));
+    let has_video = video::queue(None, &mut testimonial);
+    let analyze = sentiment::queue(None, &mut testimonial);
+
+    let txn = db.conn().begin().await.map_err(DbError)?;
+    let testimonial = testimonial.insert(&txn).await.map_err(DbError)?;
+    Form::update_many()
+        .col_expr(
+            Column::SubmissionCount,
+            Expr::col(Column::SubmissionCount).add(1),
+        )
+        .filter(Column::Id.eq(form.id))
+        .exec(&txn)
+        .await
+        .map_err(DbError)?;
+    queue_jobs(&txn, testimonial.id, has_video, analyze).await?;
+    events::emit(&txn, None, &testimonial, &project.pid).await?;
+    txn.commit().await.map_err(DbError)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(FormSubmissionResponse {
+            id: testimonial.pid.to_string(),
+            thank_you_title: form.thank_you_title,
+            thank_you_message: form.thank_you_message,
+            thank_you_cta_text: form.thank_you_cta_text,
+            thank_you_cta_url: form.thank_you_cta_url,
+        }),
+    ))
+}
+
+/// How many codes of the form's incentive pool are left and how many were
+/// issued.
+#[get("/api/v1/forms/:id/incentive-codes")]
+#[errors(FormError)]
+pub async fn get_incentive_pool(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<IncentivePoolResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let form = find_owned_form(&db, id.into_inner(), user_id).await?;
+
+    let pool = incentives::pool(db.conn(), form.id)
+        .await
+        .map_err(DbError)?;
+
+    Ok(Json(to_pool_response(pool, &form.pid)))
+}
+
+/// Adds codes to the form's incentive pool. Each approved submission whose
+/// author left an email is sent one, while the form's incentive is enabled.
+#[post("/api/v1/forms/:id/incentive-codes")]
+#[errors(FormError)]
+pub async fn add_incentive_codes(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    body: Validated<Json<AddIncentiveCodesRequest>>,
+) -> Result<Json<AddIncentiveCodesResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let form = find_owned_form(&db, id.into_inner(), user_id).await?;
+
+    let req = body.into_inner().into_inner();
+    let added = incentives::add_codes(db.conn(), form.id, &req.codes)
+        .await
+        .map_err(DbError)?;
+    let pool = incentives::pool(db.conn(), form.id)
+        .await
+        .map_err(DbError)?;
+
+    Ok(Json(AddIncentiveCodesResponse {
+        added,
+        skipped: req.codes.len() as u64 - added,
+        pool: to_pool_response(pool, &form.pid),
+    }))
+}
+
+/// Removes the codes of the form's incentive pool that were not issued yet.
+/// Issued codes stay, with the thank-yous that carried them.
+#[delete("/api/v1/forms/:id/incentive-codes")]
+#[errors(FormError)]
+pub async fn clear_incentive_codes(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<StatusCode> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let form = find_owned_form(&db, id.into_inner(), user_id).await?;
+
+    incentives::clear_codes(db.conn(), form.id)
+        .await
+        .map_err(DbError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The thank-yous sent to the form's submitters, with the incentive each
+/// carried, newest first.
+#[get("/api/v1/forms/:id/thank-you-emails")]
+#[errors(FormError)]
+pub async fn list_thank_you_emails(
+    id: Path<String>,
+    query: Query<PageQuery>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<Page<ThankYouEmailResponse>>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let form = find_owned_form(&db, id.into_inner(), user_id).await?;
+
+    let page = query.into_inner();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = ThankYouEmail::find().filter(ThankYouColumn::FormId.eq(form.id));
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(ThankYouColumn::Id.lt(cursor.id));
+    }
+
+    let mut thank_yous = q
+        .order_by_desc(ThankYouColumn::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut thank_yous, limit, |t| Cursor::from_id(t.id));
+
+    let testimonial_ids: Vec<i32> = thank_yous.iter().map(|t| t.testimonial_id).collect();
+    let testimonials: HashMap<i32, Uuid> = Testimonial::find()
+        .filter(TestimonialColumn::Id.is_in(testimonial_ids))
+        .all(db.conn())
+        .await
+        .map_err(DbError)?
+        .into_iter()
+        .map(|t| (t.id, t.pid))
+        .collect();
+    let code_ids: Vec<i32> = thank_yous.iter().filter_map(|t| t.code_id).collect();
+    let codes: HashMap<i32, String> = IncentiveCode::find()
+        .filter(CodeColumn::Id.is_in(code_ids))
+        .all(db.conn())
+        .await
+        .map_err(DbError)?
+        .into_iter()
+        .map(|c| (c.id, c.code))
+        .collect();
+
+    Ok(Json(Page {
+        data: thank_yous
+            .into_iter()
+            .map(|t| {
+                let testimonial = testimonials.get(&t.testimonial_id);
+                let code = t.code_id.and_then(|id| codes.get(&id));
+                to_thank_you_response(t, &form.pid, testimonial, code)
+            })
+            .collect(),
+        next_cursor,
+        total,
+    }))
+}
@@ -0,0 +1,19 @@
+pub mod dto;
+pub mod error;
+pub mod handlers;
+pub mod throttle;
+
+use handlers::*;
+use rapina::prelude::*;
+
+/// Forms are filled in by visitors, so submissions are taken without auth.
+pub const PUBLIC_ROUTES: &[(&str, &str)] = &[("POST", "/api/v1/forms/:slug/submissions")];
+
+pub fn routes() -> Router {
+    Router::new()
+        .post("/:slug/submissions", submit_form)
+        .get("/:id/incentive-codes", get_incentive_pool)
+        .post("/:id/incentive-codes", add_incentive_codes)
+        .delete("/:id/incentive-codes", clear_incentive_codes)
+        .get("/:id/thank-you-emails", list_thank_you_emails)
+}
@@ -0,0 +1,117 @@
+//! Limits on how often testimonials can be submitted through forms.
+//!
+//! Submissions need no account, so without a limit one client could fill a
+//! project with testimonials and its members' inboxes with notifications.
+//! Each client address gets a few submissions in a row and then one a
+//! minute; each form gets more, so a flood spread over many addresses still
+//! stops at the form it targets.
+//!
+//! The server only sees its proxy's address, so a client's address is the
+//! last `X-Forwarded-For` entry, the one the proxy appended; entries before it
+//! are whatever the client sent. Without that header `X-Real-IP` is used, and
+//! requests with neither share one allowance.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rapina::http::HeaderMap;
+
+/// Buckets kept before idle ones are dropped.
+const MAX_TRACKED: usize = 10_000;
+
+/// How many submissions may arrive in a row, and how soon another is allowed
+/// after that.
+#[derive(Clone, Copy, Debug)]
+pub struct Limit {
+    pub burst: u32,
+    pub every: Duration,
+}
+
+/// Submission allowances per client address and per form, shared as app
+/// state.
+#[derive(Clone)]
+pub struct SubmissionThrottle {
+    addresses: Arc<Mutex<Buckets<String>>>,
+    forms: Arc<Mutex<Buckets<i32>>>,
+}
+
+impl Default for SubmissionThrottle {
+    fn default() -> Self {
+        SubmissionThrottle::new(
+            Limit {
+                burst: 10,
+                every: Duration::from_secs(60),
+            },
+            Limit {
+                burst: 30,
+                every: Duration::from_secs(2),
+            },
+        )
+    }
+}
+
+impl SubmissionThrottle {
+    pub fn new(per_address: Limit, per_form: Limit) -> Self {
+        SubmissionThrottle {
+            addresses: Arc::new(Mutex::new(Buckets::new(per_address))),
+            forms: Arc::new(Mutex::new(Buckets::new(per_form))),
+        }
+    }
+
+    /// Takes a submission from the client that sent `headers`, or says how
+    /// long it has to wait.
+    pub fn check_address(&self, headers: &HeaderMap) -> Result<(), Duration> {
+        let address = client_address(headers).unwrap_or_default();
+        self.addresses.lock().unwrap().take(address)
+    }
+
+    /// Takes a submission for a form, or says how long it has to wait.
+    pub fn check_form(&self, form_id: i32) -> Result<(), Duration> {
+        self.forms.lock().unwrap().take(form_id)
+    }
+}
+
+fn client_address(headers: &HeaderMap) -> Option<String> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    };
+    header("x-forwarded-for")
+        .and_then(|forwarded| forwarded.rsplit(',').next().map(|s| s.trim().to_owned()))
+        .or_else(|| header("x-real-ip").map(|s| s.trim().to_owned()))
+        .filter(|address| !address.is_empty())
+}
+
+/// When each key may next submit, as in GCRA: a key is allowed while it is
+/// no more than `burst - 1` intervals ahead of now.
+struct Buckets<K> {
+    limit: Limit,
+    next: HashMap<K, Instant>,
+}
+
+impl<K: std::hash::Hash + Eq> Buckets<K> {
+    fn new(limit: Limit) -> Self {
+        Buckets {
+            limit,
+            next: HashMap::new(),
+        }
+    }
+
+    fn take(&mut self, key: K) -> Result<(), Duration> {
+        let now = Instant::now();
+        if self.next.len() >= MAX_TRACKED {
+            self.next.retain(|_, next| *next > now);
+        }
+
+        let next = self.next.get(&key).map_or(now, |next| (*next).max(now));
+        let ahead = self.limit.every * self.limit.burst.saturating_sub(1);
+        if next - now > ahead {
+            return Err(next - now - ahead);
+        }
+        self.next.insert(key, next + self.limit.every);
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use rapina::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::connectors::Platform;
+
+/// Sync intervals range from an hour to 30 days.
+pub const MAX_SYNC_INTERVAL_HOURS: i32 = 720;
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct CreateImportSourceRequest {
+    pub platform: Platform,
+    /// What the platform's connector needs, e.g. `api_key` and
+    /// `business_unit_id` for Trustpilot. Stored encrypted and never
+    /// returned by the API.
+    pub credentials: serde_json::Value,
+    pub auto_sync: Option<bool>,
+    #[validate(range(min = 1, max = MAX_SYNC_INTERVAL_HOURS))]
+    pub sync_interval_hours: Option<i32>,
+}
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct UpdateImportSourceRequest {
+    pub credentials: Option<serde_json::Value>,
+    pub auto_sync: Option<bool>,
+    #[validate(range(min = 1, max = MAX_SYNC_INTERVAL_HOURS))]
+    pub sync_interval_hours: Option<i32>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImportSourceResponse {
+    pub id: String,
+    pub project_id: String,
+    pub platform: String,
+    /// Whether credentials are stored; they are never returned.
+    pub has_credentials: bool,
+    /// Each credential field masked down to at most its last few characters,
+    /// to tell which credentials are stored.
+    pub credential_hints: Option<serde_json::Value>,
+    pub auto_sync: bool,
+    pub sync_interval_hours: i32,
+    pub last_synced_at: Option<String>,
+    /// When auto-sync next runs; absent until it is first scheduled.
+    pub next_sync_at: Option<String>,
+    /// Syncs that failed since the last successful one. The scheduler backs
+    /// off while this grows.
+    pub consecutive_failures: i32,
+    pub created_at: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SyncResponse {
+    pub fetched: usize,
+    pub created: usize,
+    /// Reviews the project already had.
+    pub skipped: usize,
+    pub last_synced_at: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SyncRunResponse {
+    pub id: String,
+    /// `manual` or `scheduled`.
+    pub trigger: String,
+    /// `running`, `succeeded` or `failed`.
+    pub status: String,
+    pub fetched: i32,
+    pub created: i32,
+    pub skipped: i32,
+    pub error: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
@@ -0,0 +1,91 @@
+use rapina::database::DbError;
+use rapina::prelude::*;
+
+use crate::connectors::ConnectorError;
+
+pub enum ImportSourceError {
+    DbError(DbError),
+    NotFound,
+    Forbidden,
+    InvalidCredentials(String),
+    SyncFailed(String),
+    SyncRunning,
+    Internal(String),
+}
+
+impl IntoApiError for ImportSourceError {
+    fn into_api_error(self) -> Error {
+        match self {
+            ImportSourceError::DbError(e) => e.into_api_error(),
+            ImportSourceError::NotFound => Error::not_found("import source not found"),
+            ImportSourceError::Forbidden => Error::forbidden("you do not own this project"),
+            ImportSourceError::InvalidCredentials(msg) => Error::bad_request(msg),
+            ImportSourceError::SyncFailed(msg) => Error::new(502, "BAD_GATEWAY", msg),
+            ImportSourceError::SyncRunning => {
+                Error::conflict("a sync of this import source is already running")
+            }
+            ImportSourceError::Internal(msg) => Error::internal(msg),
+        }
+    }
+}
+
+impl DocumentedError for ImportSourceError {
+    fn error_variants() -> Vec<ErrorVariant> {
+        vec![
+            ErrorVariant {
+                status: 400,
+                code: "BAD_REQUEST",
+                description: "Credentials do not fit the platform, or invalid pagination cursor",
+            },
+            ErrorVariant {
+                status: 404,
+                code: "NOT_FOUND",
+                description: "Project or import source not found",
+            },
+            ErrorVariant {
+                status: 403,
+                code: "FORBIDDEN",
+                description: "User does not own this project",
+            },
+            ErrorVariant {
+                status: 409,
+                code: "CONFLICT",
+                description: "Another sync of the import source is running",
+            },
+            ErrorVariant {
+                status: 422,
+                code: "VALIDATION_ERROR",
+                description: "Request body failed validation; details list each invalid field",
+            },
+            ErrorVariant {
+                status: 500,
+                code: "INTERNAL_ERROR",
+                description: "Internal server error",
+            },
+            ErrorVariant {
+                status: 502,
+                code: "BAD_GATEWAY",
+                description: "The platform could not be reached or rejected the request",
+            },
+        ]
+    }
+}
+
+impl From<DbError> for ImportSourceError {
+    fn from(e: DbError) -> Self {
+        ImportSourceError::DbError(e)
+    }
+}
+
+impl From<ConnectorError> for ImportSourceError {
+    fn from(e: ConnectorError) -> Self {
+        match e {
+            ConnectorError::InvalidCredentials(msg) => {
+                ImportSourceError::InvalidCredentials(format!("invalid credentials: {msg}"))
+            }
+            ConnectorError::Secret(e) => ImportSourceError::Internal(e.to_string()),
+            ConnectorError::Db(e) => ImportSourceError::DbError(DbError(e)),
+            e => ImportSourceError::SyncFailed(e.to_string()),
+        }
+    }
+}
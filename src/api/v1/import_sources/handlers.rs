@@ -0,0 +1,372 @@
+use rapina::database::{Db, DbError};
+use rapina::prelude::*;
+use rapina::sea_orm::sea_query::Condition;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use uuid::Uuid;
+
+use crate::api::v1::pagination::{self, Cursor, Page, PageQuery};
+use crate::connectors::{self, Connectors, Trigger};
+use crate::db::entities::import_source::{ActiveModel, Column, Entity as ImportSource, Model};
+use crate::db::entities::import_sync_run::{
+    Column as SyncRunColumn, Entity as SyncRun, Model as SyncRunModel,
+};
+use crate::db::entities::project::{
+    Column as ProjectColumn, Entity as Project, Model as ProjectModel,
+};
+use crate::db::entities::user::{Column as UserColumn, Entity as User};
+use crate::secrets::Keyring;
+
+use super::dto::{
+    CreateImportSourceRequest, ImportSourceResponse, SyncResponse, SyncRunResponse,
+    UpdateImportSourceRequest,
+};
+use super::error::ImportSourceError;
+
+fn to_run_response(run: SyncRunModel) -> SyncRunResponse {
+    SyncRunResponse {
+        id: run.pid.to_string(),
+        trigger: run.trigger,
+        status: run.status,
+        fetched: run.fetched_count,
+        created: run.created_count,
+        skipped: run.skipped_count,
+        error: run.error,
+        started_at: run.started_at.to_rfc3339(),
+        finished_at: run.finished_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+fn to_response(source: Model, project_pid: &Uuid) -> ImportSourceResponse {
+    ImportSourceResponse {
+        id: source.pid.to_string(),
+        project_id: project_pid.to_string(),
+        platform: source.platform,
+        has_credentials: source.credentials.is_some(),
+        credential_hints: source.credential_hints,
+        auto_sync: source.auto_sync,
+        sync_interval_hours: source.sync_interval_hours,
+        last_synced_at: source.last_synced_at.map(|at| at.to_rfc3339()),
+        next_sync_at: source.next_sync_at.map(|at| at.to_rfc3339()),
+        consecutive_failures: source.consecutive_failures,
+        created_at: source.created_at.to_rfc3339(),
+    }
+}
+
+async fn resolve_user_id(db: &Db, current_user: &CurrentUser) -> Result<i32> {
+    let pid = Uuid::parse_str(&current_user.id)
+        .map_err(|_| Error::unauthorized("invalid user id in token"))?;
+
+    let user = User::find()
+        .filter(UserColumn::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| Error::unauthorized("user not found"))?;
+
+    Ok(user.id)
+}
+
+async fn find_owned_project(db: &Db, id: String, user_id: i32) -> Result<ProjectModel> {
+    let pid = Uuid::parse_str(&id).map_err(|_| ImportSourceError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ImportSourceError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(ImportSourceError::Forbidden.into_api_error());
+    }
+
+    Ok(project)
+}
+
+/// The source with public id `id` and its project, if the user owns it.
+async fn find_owned_source(db: &Db, id: String, user_id: i32) -> Result<(Model, ProjectModel)> {
+    let pid = Uuid::parse_str(&id).map_err(|_| ImportSourceError::NotFound.into_api_error())?;
+
+    let source = ImportSource::find()
+        .filter(Column::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ImportSourceError::NotFound.into_api_error())?;
+
+    let project = Project::find_by_id(source.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ImportSourceError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(ImportSourceError::Forbidden.into_api_error());
+    }
+
+    Ok((source, project))
+}
+
+#[get("/api/v1/projects/:id/import-sources")]
+#[errors(ImportSourceError)]
+pub async fn list_import_sources(
+    id: Path<String>,
+    query: Query<PageQuery>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<Page<ImportSourceResponse>>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let project = find_owned_project(&db, id.into_inner(), user_id).await?;
+
+    let page = query.into_inner();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = ImportSource::find().filter(Column::ProjectId.eq(project.id));
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(Column::Id.lt(cursor.id));
+    }
+
+    let mut sources = q
+        .order_by_desc(Column::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut sources, limit, |s| Cursor::from_id(s.id));
+
+    Ok(Json(Page {
+        data: sources
+            .into_iter()
+            .map(|s| to_response(s, &project.pid))
+            .collect(),
+        next_cursor,
+        total,
+    }))
+}
+
+#[post("/api/v1/projects/:id/import-sources")]
+#[errors(ImportSourceError)]
+pub async fn create_import_source(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    connectors: State<Connectors>,
+    keyring: State<Keyring>,
+    body: Validated<Json<CreateImportSourceRequest>>,
+) -> Result<(StatusCode, Json<ImportSourceResponse>)> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let project = find_owned_project(&db, id.into_inner(), user_id).await?;
+
+    let req = body.into_inner().into_inner();
+    connectors
+        .into_inner()
+        .connector(req.platform)
+        .validate(&req.credentials)
+        .map_err(|e| ImportSourceError::from(e).into_api_error())?;
+
+    let (credentials, hints) =
+        connectors::seal_credentials(&keyring.into_inner(), &req.credentials)
+            .map_err(|e| ImportSourceError::Internal(e.to_string()).into_api_error())?;
+    let mut source = ActiveModel {
+        pid: Set(Uuid::new_v4()),
+        project_id: Set(project.id),
+        platform: Set(req.platform.as_str().to_string()),
+        credentials: Set(Some(credentials)),
+        credential_hints: Set(Some(hints)),
+        ..Default::default()
+    };
+    if let Some(auto_sync) = req.auto_sync {
+        source.auto_sync = Set(auto_sync);
+    }
+    if let Some(hours) = req.sync_interval_hours {
+        source.sync_interval_hours = Set(hours);
+    }
+
+    let source = source.insert(db.conn()).await.map_err(DbError)?;
+
+    Ok((StatusCode::CREATED, Json(to_response(source, &project.pid))))
+}
+
+#[get("/api/v1/import-sources/:id")]
+#[errors(ImportSourceError)]
+pub async fn get_import_source(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<ImportSourceResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (source, project) = find_owned_source(&db, id.into_inner(), user_id).await?;
+
+    Ok(Json(to_response(source, &project.pid)))
+}
+
+#[put("/api/v1/import-sources/:id")]
+#[errors(ImportSourceError)]
+pub async fn update_import_source(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    connectors: State<Connectors>,
+    keyring: State<Keyring>,
+    body: Validated<Json<UpdateImportSourceRequest>>,
+) -> Result<Json<ImportSourceResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (source, project) = find_owned_source(&db, id.into_inner(), user_id).await?;
+
+    let req = body.into_inner().into_inner();
+    let platform = source.platform.clone();
+    let last_synced_at = source.last_synced_at;
+    let failing = source.consecutive_failures > 0;
+    let mut active = source.into_active_model();
+
+    if let Some(hours) = req.sync_interval_hours {
+        active.sync_interval_hours = Set(hours);
+        // Reschedule from the last sync, unless a failing source is backing off.
+        if !failing {
+            active.next_sync_at =
+                Set(last_synced_at.map(|at| at + chrono::Duration::hours(hours.into())));
+        }
+    }
+    if let Some(credentials) = req.credentials {
+        if let Some(platform) = connectors::Platform::parse(&platform) {
+            connectors
+                .into_inner()
+                .connector(platform)
+                .validate(&credentials)
+                .map_err(|e| ImportSourceError::from(e).into_api_error())?;
+        }
+        let (credentials, hints) =
+            connectors::seal_credentials(&keyring.into_inner(), &credentials)
+                .map_err(|e| ImportSourceError::Internal(e.to_string()).into_api_error())?;
+        active.credentials = Set(Some(credentials));
+        active.credential_hints = Set(Some(hints));
+        // New credentials are tried straight away rather than after the
+        // backoff the old ones earned.
+        active.consecutive_failures = Set(0);
+        active.next_sync_at = Set(None);
+    }
+    if let Some(auto_sync) = req.auto_sync {
+        active.auto_sync = Set(auto_sync);
+    }
+
+    let source = active.update(db.conn()).await.map_err(DbError)?;
+
+    Ok(Json(to_response(source, &project.pid)))
+}
+
+#[delete("/api/v1/import-sources/:id")]
+#[errors(ImportSourceError)]
+pub async fn delete_import_source(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<StatusCode> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (source, _) = find_owned_source(&db, id.into_inner(), user_id).await?;
+
+    source.delete(db.conn()).await.map_err(DbError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Fetches the platform's reviews now and creates testimonials for the ones
+/// the project does not have yet. Refused while another sync of the source
+/// runs.
+#[post("/api/v1/import-sources/:id/sync")]
+#[errors(ImportSourceError)]
+pub async fn sync_import_source(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    connectors: State<Connectors>,
+    keyring: State<Keyring>,
+) -> Result<Json<SyncResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (source, _) = find_owned_source(&db, id.into_inner(), user_id).await?;
+    let source_id = source.id;
+
+    if !connectors::claim(db.conn(), source_id, Condition::all())
+        .await
+        .map_err(DbError)?
+    {
+        return Err(ImportSourceError::SyncRunning.into_api_error());
+    }
+    let counts = connectors::sync(
+        db.conn(),
+        &connectors.into_inner(),
+        &keyring.into_inner(),
+        source,
+        Trigger::Manual,
+    )
+    .await
+    .map_err(|e| ImportSourceError::from(e).into_api_error())?;
+
+    let last_synced_at = ImportSource::find_by_id(source_id)
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .and_then(|s| s.last_synced_at);
+
+    Ok(Json(SyncResponse {
+        fetched: counts.fetched,
+        created: counts.created,
+        skipped: counts.skipped,
+        last_synced_at: last_synced_at.map(|at| at.to_rfc3339()),
+    }))
+}
+
+/// The source's sync runs, newest first.
+#[get("/api/v1/import-sources/:id/runs")]
+#[errors(ImportSourceError)]
+pub async fn list_sync_runs(
+    id: Path<String>,
+    query: Query<PageQuery>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<Page<SyncRunResponse>>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (source, _) = find_owned_source(&db, id.into_inner(), user_id).await?;
+
+    let page = query.into_inner();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = SyncRun::find().filter(SyncRunColumn::ImportSourceId.eq(source.id));
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(SyncRunColumn::Id.lt(cursor.id));
+    }
+
+    let mut runs = q
+        .order_by_desc(SyncRunColumn::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut runs, limit, |r| Cursor::from_id(r.id));
+
+    Ok(Json(Page {
+        data: runs.into_iter().map(to_run_response).collect(),
+        next_cursor,
+        total,
+    }))
+}
@@ -0,0 +1,21 @@
+pub mod dto;
+pub mod error;
+pub mod handlers;
+
+use handlers::*;
+use rapina::prelude::*;
+
+pub fn project_routes() -> Router {
+    Router::new()
+        .get("/:id/import-sources", list_import_sources)
+        .post("/:id/import-sources", create_import_source)
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .get("/:id", get_import_source)
+        .put("/:id", update_import_source)
+        .delete("/:id", delete_import_source)
+        .post("/:id/sync", sync_import_source)
+        .get("/:id/runs", list_sync_runs)
+}
@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use rapina::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+/// How the columns of an uploaded CSV map onto testimonial fields. Sent as
+/// the JSON `mapping` part of the upload, next to the `file` part.
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ImportMapping {
+    /// CSV header → field of a create testimonial request, by its API name
+    /// (e.g. `"Name": "author_name"`). Unmapped columns are ignored.
+    pub columns: BTreeMap<String, String>,
+    /// Column of comma-separated tag names. Tags the project does not have yet
+    /// are created.
+    pub tag_column: Option<String>,
+}
+
+/// Why one row of the file was rejected.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ImportRowError {
+    /// Line of the file the row starts on; the header is line 1.
+    pub line: u64,
+    /// Each invalid field with what was wrong with it, in the same shape as
+    /// the `details` of a 422.
+    pub errors: serde_json::Value,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImportPreviewResponse {
+    pub total_rows: i32,
+    /// Rows that would be created.
+    pub valid_count: i32,
+    /// Blank rows, and rows whose `source_id` the project already has.
+    pub skipped_count: i32,
+    pub failed_count: i32,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImportResponse {
+    pub id: String,
+    pub project_id: String,
+    /// `processing`, `completed` or `failed`.
+    pub status: String,
+    pub filename: Option<String>,
+    pub mapping: ImportMapping,
+    pub total_rows: i32,
+    pub created_count: i32,
+    pub skipped_count: i32,
+    pub failed_count: i32,
+    pub errors: Vec<ImportRowError>,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+}
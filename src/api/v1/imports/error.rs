@@ -0,0 +1,58 @@
+use rapina::database::DbError;
+use rapina::prelude::*;
+
+pub enum ImportError {
+    DbError(DbError),
+    NotFound,
+    Forbidden,
+    InvalidUpload(String),
+}
+
+impl IntoApiError for ImportError {
+    fn into_api_error(self) -> Error {
+        match self {
+            ImportError::DbError(e) => e.into_api_error(),
+            ImportError::NotFound => Error::not_found("import not found"),
+            ImportError::Forbidden => Error::forbidden("you do not own this project"),
+            ImportError::InvalidUpload(msg) => Error::bad_request(msg),
+        }
+    }
+}
+
+impl DocumentedError for ImportError {
+    fn error_variants() -> Vec<ErrorVariant> {
+        vec![
+            ErrorVariant {
+                status: 400,
+                code: "BAD_REQUEST",
+                description: "Invalid upload, CSV, column mapping or pagination cursor",
+            },
+            ErrorVariant {
+                status: 404,
+                code: "NOT_FOUND",
+                description: "Project or import not found",
+            },
+            ErrorVariant {
+                status: 403,
+                code: "FORBIDDEN",
+                description: "User does not own this project",
+            },
+            ErrorVariant {
+                status: 413,
+                code: "PAYLOAD_TOO_LARGE",
+                description: "Upload exceeds the size limit",
+            },
+            ErrorVariant {
+                status: 500,
+                code: "INTERNAL_ERROR",
+                description: "Internal server error",
+            },
+        ]
+    }
+}
+
+impl From<DbError> for ImportError {
+    fn from(e: DbError) -> Self {
+        ImportError::DbError(e)
+    }
+}
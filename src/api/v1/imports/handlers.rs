@@ -0,0 +1,263 @@
+use rapina::database::{Db, DbError};
+use rapina::hyper::body::Bytes;
+use rapina::prelude::*;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::api::v1::multipart::Multipart;
+use crate::api::v1::pagination::{self, Cursor, Page, PageQuery};
+use crate::db::entities::import::{ActiveModel, Column, Entity as Import, Model};
+use crate::db::entities::project::{
+    Column as ProjectColumn, Entity as Project, Model as ProjectModel,
+};
+use crate::db::entities::user::{Column as UserColumn, Entity as User};
+use crate::jobs;
+
+use super::dto::{ImportMapping, ImportPreviewResponse, ImportResponse};
+use super::error::ImportError;
+use super::parse::{self, Row};
+use super::run::{RunImport, skip_existing};
+
+pub const STATUS_PROCESSING: &str = "processing";
+pub const STATUS_COMPLETED: &str = "completed";
+pub const STATUS_FAILED: &str = "failed";
+
+fn to_response(import: Model, project_pid: &Uuid) -> ImportResponse {
+    ImportResponse {
+        id: import.pid.to_string(),
+        project_id: project_pid.to_string(),
+        status: import.status,
+        filename: import.filename,
+        mapping: serde_json::from_value(import.mapping).unwrap_or_default(),
+        total_rows: import.total_rows,
+        created_count: import.created_count,
+        skipped_count: import.skipped_count,
+        failed_count: import.failed_count,
+        errors: serde_json::from_value(import.errors).unwrap_or_default(),
+        created_at: import.created_at.to_rfc3339(),
+        completed_at: import.completed_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+async fn resolve_user_id(db: &Db, current_user: &CurrentUser) -> Result<i32> {
+    let pid = Uuid::parse_str(&current_user.id)
+        .map_err(|_| Error::unauthorized("invalid user id in token"))?;
+
+    let user = User::find()
+        .filter(UserColumn::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| Error::unauthorized("user not found"))?;
+
+    Ok(user.id)
+}
+
+async fn find_owned_project(db: &Db, id: String, user_id: i32) -> Result<ProjectModel> {
+    let pid = Uuid::parse_str(&id).map_err(|_| ImportError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ImportError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(ImportError::Forbidden.into_api_error());
+    }
+
+    Ok(project)
+}
+
+/// An uploaded CSV file and how its columns map to testimonial fields.
+struct Upload {
+    filename: Option<String>,
+    mapping: ImportMapping,
+    data: Bytes,
+}
+
+fn read_upload(mut form: Multipart) -> Result<Upload> {
+    let file = form.take("file").ok_or_else(|| {
+        ImportError::InvalidUpload("the file part is required".to_string()).into_api_error()
+    })?;
+    let mapping: ImportMapping = form
+        .text("mapping")
+        .ok_or_else(|| "the mapping part is required".to_string())
+        .and_then(|m| serde_json::from_str(&m).map_err(|e| format!("invalid mapping: {e}")))
+        .map_err(|msg| ImportError::InvalidUpload(msg).into_api_error())?;
+
+    Ok(Upload {
+        filename: file.filename,
+        mapping,
+        data: file.data,
+    })
+}
+
+/// The rows of `upload`, already checked against the project's existing
+/// testimonials.
+async fn read_rows(db: &Db, project_id: i32, upload: &Upload) -> Result<Vec<Row>> {
+    let mut rows = parse::parse(&upload.data, &upload.mapping)?;
+    skip_existing(db.conn(), project_id, &mut rows)
+        .await
+        .map_err(DbError)?;
+    Ok(rows)
+}
+
+#[get("/api/v1/projects/:id/imports")]
+#[errors(ImportError)]
+pub async fn list_imports(
+    id: Path<String>,
+    query: Query<PageQuery>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<Page<ImportResponse>>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let project = find_owned_project(&db, id.into_inner(), user_id).await?;
+
+    let page = query.into_inner();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = Import::find().filter(Column::ProjectId.eq(project.id));
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(Column::Id.lt(cursor.id));
+    }
+
+    let mut imports = q
+        .order_by_desc(Column::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut imports, limit, |i| Cursor::from_id(i.id));
+
+    Ok(Json(Page {
+        data: imports
+            .into_iter()
+            .map(|i| to_response(i, &project.pid))
+            .collect(),
+        next_cursor,
+        total,
+    }))
+}
+
+/// Validates a CSV upload without importing anything.
+#[post("/api/v1/projects/:id/imports/dry-run")]
+#[errors(ImportError)]
+pub async fn dry_run_import(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    form: Multipart,
+) -> Result<Json<ImportPreviewResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let project = find_owned_project(&db, id.into_inner(), user_id).await?;
+
+    let upload = read_upload(form)?;
+    let rows = read_rows(&db, project.id, &upload).await?;
+    let (valid_count, skipped_count, failed_count) = parse::counts(&rows);
+
+    Ok(Json(ImportPreviewResponse {
+        total_rows: rows.len() as i32,
+        valid_count,
+        skipped_count,
+        failed_count,
+        errors: parse::row_errors(&rows),
+    }))
+}
+
+/// Queues an import of a CSV upload, which is checked straight away. A
+/// [`RunImport`] job then creates the valid rows together; failed and skipped
+/// rows are counted and do not stop the others. Poll the import to see how it
+/// went.
+#[post("/api/v1/projects/:id/imports")]
+#[errors(ImportError)]
+pub async fn create_import(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    form: Multipart,
+) -> Result<(StatusCode, Json<ImportResponse>)> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let project = find_owned_project(&db, id.into_inner(), user_id).await?;
+
+    let upload = read_upload(form)?;
+    let rows = read_rows(&db, project.id, &upload).await?;
+    let (_, skipped_count, failed_count) = parse::counts(&rows);
+
+    let txn = db.conn().begin().await.map_err(DbError)?;
+    let import = ActiveModel {
+        pid: Set(Uuid::new_v4()),
+        project_id: Set(project.id),
+        user_id: Set(Some(user_id)),
+        filename: Set(upload.filename),
+        status: Set(STATUS_PROCESSING.to_string()),
+        mapping: Set(serde_json::to_value(&upload.mapping).unwrap_or_default()),
+        total_rows: Set(rows.len() as i32),
+        skipped_count: Set(skipped_count),
+        failed_count: Set(failed_count),
+        errors: Set(serde_json::to_value(parse::row_errors(&rows)).unwrap_or_default()),
+        file: Set(Some(upload.data.to_vec())),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(DbError)?;
+    jobs::enqueue(
+        &txn,
+        &RunImport {
+            import_id: import.id,
+        },
+    )
+    .await
+    .map_err(DbError)?;
+    txn.commit().await.map_err(DbError)?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(to_response(import, &project.pid)),
+    ))
+}
+
+#[get("/api/v1/imports/:id")]
+#[errors(ImportError)]
+pub async fn get_import(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<ImportResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let pid =
+        Uuid::parse_str(&id.into_inner()).map_err(|_| ImportError::NotFound.into_api_error())?;
+
+    let import = Import::find()
+        .filter(Column::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ImportError::NotFound.into_api_error())?;
+
+    let project = Project::find_by_id(import.project_id)
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ImportError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(ImportError::Forbidden.into_api_error());
+    }
+
+    Ok(Json(to_response(import, &project.pid)))
+}
@@ -0,0 +1,19 @@
+pub mod dto;
+pub mod error;
+pub mod handlers;
+pub mod parse;
+pub mod run;
+
+use handlers::*;
+use rapina::prelude::*;
+
+pub fn project_routes() -> Router {
+    Router::new()
+        .get("/:id/imports", list_imports)
+        .post("/:id/imports", create_import)
+        .post("/:id/imports/dry-run", dry_run_import)
+}
+
+pub fn routes() -> Router {
+    Router::new().get("/:id", get_import)
+}
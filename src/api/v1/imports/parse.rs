@@ -0,0 +1,297 @@
+//! Turning an uploaded CSV into testimonial requests.
+//!
+//! Every row is mapped onto a [`CreateTestimonialRequest`] and validated the
+//! same way the create endpoint validates a body, so a dry run reports exactly
+//! the rows a real import would reject.
+
+use std::collections::{HashMap, HashSet};
+
+use csv::{ReaderBuilder, StringRecord, Trim};
+use rapina::prelude::*;
+use serde_json::{Map, Value, json};
+use validator::{ValidationError, ValidationErrors};
+
+use crate::api::v1::testimonials::dto::CreateTestimonialRequest;
+
+use super::dto::{ImportMapping, ImportRowError};
+use super::error::ImportError;
+
+/// Upper bound on the number of rows in one file.
+pub const MAX_ROWS: usize = 5000;
+
+/// `source` of imported testimonials that do not map one.
+pub const DEFAULT_SOURCE: &str = "csv";
+
+/// Fields a column can be mapped to, by API name.
+pub const FIELDS: &[&str] = &[
+    "type",
+    "content",
+    "rating",
+    "author_name",
+    "author_email",
+    "author_title",
+    "author_avatar_url",
+    "author_company",
+    "author_url",
+    "video_url",
+    "video_thumbnail_url",
+    "video_duration_seconds",
+    "transcription",
+    "source",
+    "source_platform",
+    "source_url",
+    "source_id",
+    "sentiment",
+    "sentiment_score",
+    "language",
+];
+
+const MAX_TAG_NAME_LENGTH: usize = 100;
+
+pub enum Outcome {
+    Valid {
+        request: Box<CreateTestimonialRequest>,
+        tags: Vec<String>,
+    },
+    Skipped,
+    Failed(ValidationErrors),
+}
+
+pub struct Row {
+    /// Line of the file the row starts on.
+    pub line: u64,
+    pub outcome: Outcome,
+}
+
+/// Mapped fields, each with the index of its column.
+type Columns = Vec<(usize, &'static str)>;
+
+fn invalid(msg: impl Into<String>) -> Error {
+    ImportError::InvalidUpload(msg.into()).into_api_error()
+}
+
+/// Column index of each mapped field, and of the tag column.
+fn resolve_columns(
+    headers: &StringRecord,
+    mapping: &ImportMapping,
+) -> Result<(Columns, Option<usize>)> {
+    let index_of = |column: &str| {
+        headers
+            .iter()
+            .position(|h| h == column)
+            .ok_or_else(|| invalid(format!("column {column} is not in the file")))
+    };
+
+    let mut columns = Vec::with_capacity(mapping.columns.len());
+    for (column, field) in &mapping.columns {
+        let field = FIELDS
+            .iter()
+            .find(|f| *f == field)
+            .ok_or_else(|| invalid(format!("cannot map a column to unknown field {field}")))?;
+        if columns.iter().any(|(_, f)| f == field) {
+            return Err(invalid(format!(
+                "more than one column is mapped to {field}"
+            )));
+        }
+        columns.push((index_of(column)?, *field));
+    }
+
+    if !columns.iter().any(|(_, f)| *f == "author_name") {
+        return Err(invalid("a column must be mapped to author_name"));
+    }
+
+    let tag_column = mapping.tag_column.as_deref().map(index_of).transpose()?;
+
+    Ok((columns, tag_column))
+}
+
+fn number_error(field: &'static str, errors: &mut ValidationErrors) {
+    errors.add(
+        field,
+        ValidationError::new("number").with_message("must be a number".into()),
+    );
+}
+
+/// The JSON value of one cell, or `None` if it cannot hold one.
+fn cell_value(field: &'static str, cell: &str) -> Option<Value> {
+    match field {
+        "rating" => cell.parse::<i16>().ok().map(Value::from),
+        "video_duration_seconds" => cell.parse::<i32>().ok().map(Value::from),
+        "sentiment_score" => cell
+            .parse::<f32>()
+            .ok()
+            .filter(|score| score.is_finite())
+            .map(Value::from),
+        _ => Some(Value::String(cell.to_string())),
+    }
+}
+
+fn tag_names(cell: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    cell.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter(|name| seen.insert(name.to_lowercase()))
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_row(
+    record: &StringRecord,
+    columns: &[(usize, &'static str)],
+    tag_column: Option<usize>,
+) -> Outcome {
+    let cell = |i: usize| record.get(i).unwrap_or("");
+
+    let blank = columns.iter().all(|(i, _)| cell(*i).is_empty())
+        && tag_column.is_none_or(|i| cell(i).is_empty());
+    if blank {
+        return Outcome::Skipped;
+    }
+
+    let mut errors = ValidationErrors::new();
+    // An empty name is reported by validation rather than failing to parse.
+    let mut body = Map::from_iter([("author_name".to_string(), json!(""))]);
+    for (i, field) in columns {
+        let cell = cell(*i);
+        if cell.is_empty() {
+            continue;
+        }
+        match cell_value(field, cell) {
+            Some(value) => {
+                body.insert(field.to_string(), value);
+            }
+            None => number_error(field, &mut errors),
+        }
+    }
+    body.entry("source")
+        .or_insert_with(|| json!(DEFAULT_SOURCE));
+
+    let tags = tag_column.map(|i| tag_names(cell(i))).unwrap_or_default();
+    if tags
+        .iter()
+        .any(|name| name.chars().count() > MAX_TAG_NAME_LENGTH)
+    {
+        errors.add(
+            "tags",
+            ValidationError::new("length")
+                .with_message("tag names must be at most 100 characters".into()),
+        );
+    }
+
+    let request = match serde_json::from_value::<CreateTestimonialRequest>(Value::Object(body)) {
+        Ok(request) => request,
+        Err(e) => {
+            errors.add(
+                "__all__",
+                ValidationError::new("invalid").with_message(e.to_string().into()),
+            );
+            return Outcome::Failed(errors);
+        }
+    };
+    if let Err(invalid) = request.validate() {
+        errors.errors_mut().extend(invalid.into_errors());
+    }
+
+    if errors.is_empty() {
+        Outcome::Valid {
+            request: Box::new(request),
+            tags,
+        }
+    } else {
+        Outcome::Failed(errors)
+    }
+}
+
+/// Parses and validates every row of `data` under `mapping`. Problems with
+/// the file or the mapping fail the whole upload; problems with a row only
+/// fail that row.
+pub fn parse(data: &[u8], mapping: &ImportMapping) -> Result<Vec<Row>> {
+    let mut reader = ReaderBuilder::new()
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(data);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| invalid(format!("invalid CSV header: {e}")))?
+        .clone();
+    let (columns, tag_column) = resolve_columns(&headers, mapping)?;
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| invalid(format!("invalid CSV: {e}")))?;
+        if rows.len() == MAX_ROWS {
+            return Err(invalid(format!(
+                "a file must not have more than {MAX_ROWS} rows"
+            )));
+        }
+
+        rows.push(Row {
+            line: record.position().map_or(0, |p| p.line()),
+            outcome: parse_row(&record, &columns, tag_column),
+        });
+    }
+
+    Ok(rows)
+}
+
+/// The `(source, source_id)` a request would be deduplicated on.
+pub fn source_key(request: &CreateTestimonialRequest) -> Option<(String, String)> {
+    let source = request.source.clone().unwrap_or_default();
+    request.source_id.clone().map(|id| (source, id))
+}
+
+/// Skips rows whose `(source, source_id)` is in `known`, or repeats an
+/// earlier row of the same file.
+pub fn skip_known(rows: &mut [Row], mut known: HashSet<(String, String)>) {
+    for row in rows {
+        if let Outcome::Valid { request, .. } = &row.outcome
+            && let Some(key) = source_key(request)
+            && !known.insert(key)
+        {
+            row.outcome = Outcome::Skipped;
+        }
+    }
+}
+
+/// Number of valid, skipped and failed rows.
+pub fn counts(rows: &[Row]) -> (i32, i32, i32) {
+    let mut counts = (0, 0, 0);
+    for row in rows {
+        match row.outcome {
+            Outcome::Valid { .. } => counts.0 += 1,
+            Outcome::Skipped => counts.1 += 1,
+            Outcome::Failed(_) => counts.2 += 1,
+        }
+    }
+    counts
+}
+
+pub fn row_errors(rows: &[Row]) -> Vec<ImportRowError> {
+    rows.iter()
+        .filter_map(|row| match &row.outcome {
+            Outcome::Failed(errors) => Some(ImportRowError {
+                line: row.line,
+                errors: serde_json::to_value(errors).unwrap_or_default(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Tag names used by valid rows, keyed by their lowercase form. The first
+/// spelling of a name wins.
+pub fn tag_names_used(rows: &[Row]) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    for row in rows {
+        if let Outcome::Valid { tags, .. } = &row.outcome {
+            for name in tags {
+                names
+                    .entry(name.to_lowercase())
+                    .or_insert_with(|| name.clone());
+            }
+        }
+    }
+    names
+}
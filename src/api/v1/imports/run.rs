@@ -0,0 +1,175 @@
+//! Running imports in the background.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use rapina::prelude::tracing;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::v1::testimonials::handlers::new_testimonial;
+use crate::connectors;
+use crate::db::entities::import::{ActiveModel, Column, Entity as Import};
+use crate::db::entities::tag::{ActiveModel as TagActiveModel, Column as TagColumn, Entity as Tag};
+use crate::db::entities::testimonial::{Column as TestimonialColumn, Entity as Testimonial};
+use crate::db::entities::testimonial_tag::ActiveModel as TestimonialTagActiveModel;
+use crate::jobs::{self, Job, JobError};
+use crate::sentiment::{self, AnalyzeSentiment};
+
+use super::dto::ImportMapping;
+use super::handlers::{STATUS_COMPLETED, STATUS_FAILED, STATUS_PROCESSING};
+use super::parse::{self, Outcome, Row};
+
+/// Skips the rows whose `source_id` the project `project_id` already has.
+pub async fn skip_existing(
+    conn: &impl ConnectionTrait,
+    project_id: i32,
+    rows: &mut [Row],
+) -> Result<(), DbErr> {
+    let source_ids: Vec<String> = rows
+        .iter()
+        .filter_map(|row| match &row.outcome {
+            Outcome::Valid { request, .. } => request.source_id.clone(),
+            _ => None,
+        })
+        .collect();
+    let known: HashSet<(String, String)> = if source_ids.is_empty() {
+        HashSet::new()
+    } else {
+        Testimonial::find()
+            .filter(TestimonialColumn::ProjectId.eq(project_id))
+            .filter(TestimonialColumn::SourceId.is_in(source_ids))
+            .all(conn)
+            .await?
+            .into_iter()
+            .filter_map(|t| Some((t.source.unwrap_or_default(), t.source_id?)))
+            .collect()
+    };
+    parse::skip_known(rows, known);
+    Ok(())
+}
+
+/// Creates the valid rows and any tags they name, returning how many
+/// testimonials were created. Their sentiment is analyzed by jobs.
+async fn create_rows(
+    conn: &impl ConnectionTrait,
+    project_id: i32,
+    rows: Vec<Row>,
+) -> Result<i32, DbErr> {
+    let mut tag_ids: HashMap<String, i32> = Tag::find()
+        .filter(TagColumn::ProjectId.eq(project_id))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|t| (t.name.to_lowercase(), t.id))
+        .collect();
+
+    for (key, name) in parse::tag_names_used(&rows) {
+        if tag_ids.contains_key(&key) {
+            continue;
+        }
+        let tag = TagActiveModel {
+            pid: Set(Uuid::new_v4()),
+            project_id: Set(project_id),
+            name: Set(name),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+        tag_ids.insert(key, tag.id);
+    }
+
+    let mut created = 0;
+    for row in rows {
+        let Outcome::Valid { request, tags } = row.outcome else {
+            continue;
+        };
+
+        let mut testimonial = new_testimonial(project_id, *request);
+        let analyze = sentiment::queue(None, &mut testimonial);
+        // Rows whose review a connector imported meanwhile are left out.
+        let Some(testimonial) = connectors::insert_unless_known(conn, testimonial).await? else {
+            continue;
+        };
+        if analyze {
+            jobs::enqueue(
+                conn,
+                &AnalyzeSentiment {
+                    testimonial_id: testimonial.id,
+                },
+            )
+            .await?;
+        }
+        for name in tags {
+            TestimonialTagActiveModel {
+                testimonial_id: Set(testimonial.id),
+                tag_id: Set(tag_ids[&name.to_lowercase()]),
+            }
+            .insert(conn)
+            .await?;
+        }
+        created += 1;
+    }
+
+    Ok(created)
+}
+
+/// Job that creates the testimonials of an import.
+#[derive(Serialize, Deserialize)]
+pub struct RunImport {
+    pub import_id: i32,
+}
+
+impl Job for RunImport {
+    const KIND: &'static str = "imports.run";
+}
+
+impl RunImport {
+    /// Creates the valid rows of the import's file together and records how
+    /// it went, unless the import is done already. Rows are checked against
+    /// the project's testimonials again, since it may have gained some since
+    /// the upload. A failed try is rolled back and retried.
+    pub async fn run(self, conn: &DatabaseConnection) -> Result<(), JobError> {
+        let import = Import::find_by_id(self.import_id)
+            .filter(Column::Status.eq(STATUS_PROCESSING))
+            .one(conn)
+            .await?;
+        let Some(import) = import else {
+            return Ok(());
+        };
+
+        let mapping = serde_json::from_value::<ImportMapping>(import.mapping.clone());
+        let rows = match (import.file.as_deref(), mapping) {
+            (Some(data), Ok(mapping)) => parse::parse(data, &mapping).ok(),
+            _ => None,
+        };
+        let project_id = import.project_id;
+        let mut active: ActiveModel = import.into();
+        active.file = Set(None);
+        active.completed_at = Set(Some(Utc::now().fixed_offset()));
+
+        // The file was checked when it was uploaded.
+        let Some(mut rows) = rows else {
+            tracing::warn!(import = self.import_id, "import file can no longer be read");
+            active.status = Set(STATUS_FAILED.to_string());
+            active.update(conn).await?;
+            return Ok(());
+        };
+        skip_existing(conn, project_id, &mut rows).await?;
+        let (_, skipped_count, failed_count) = parse::counts(&rows);
+        active.skipped_count = Set(skipped_count);
+        active.failed_count = Set(failed_count);
+        active.errors = Set(serde_json::to_value(parse::row_errors(&rows)).unwrap_or_default());
+
+        let txn = conn.begin().await?;
+        active.created_count = Set(create_rows(&txn, project_id, rows).await?);
+        active.status = Set(STATUS_COMPLETED.to_string());
+        active.update(&txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use rapina::http::header::{ACCEPT_RANGES, CACHE_CONTROL, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use rapina::http::{HeaderValue, Response};
+use rapina::hyper::body::Bytes;
+use rapina::prelude::*;
+use rapina::response::BoxBody;
+
+use crate::storage::{self, MediaStorage, Object};
+
+/// Generated media is rewritten in place when what it belongs to changes, so
+/// clients only cache it briefly.
+const CACHE: &str = "public, max-age=300";
+
+/// Most bytes of a file sent in one response. Larger files are sent a range
+/// at a time, which is how browsers play video anyway.
+pub const MAX_RESPONSE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// What a request asks for of a file.
+#[derive(Debug, PartialEq, Eq)]
+enum Requested {
+    Whole,
+    /// From the first byte to the last, inclusive.
+    Range(u64, u64),
+    Unsatisfiable,
+}
+
+/// What the `Range` header `header` asks for of a file of `size` bytes. Only
+/// single byte ranges are supported; other ranges are ignored, as HTTP
+/// allows, and the file is sent whole.
+fn requested(header: Option<&str>, size: u64) -> Requested {
+    let Some(spec) = header.and_then(|h| h.trim().strip_prefix("bytes=")) else {
+        return Requested::Whole;
+    };
+    if spec.contains(',') {
+        return Requested::Whole;
+    }
+    let Some((first, last)) = spec.split_once('-') else {
+        return Requested::Whole;
+    };
+    let (first, last) = (first.trim(), last.trim());
+
+    // A suffix, "-n", is the last n bytes.
+    if first.is_empty() {
+        return match last.parse::<u64>() {
+            Ok(0) => Requested::Unsatisfiable,
+            Ok(_) if size == 0 => Requested::Unsatisfiable,
+            Ok(n) => Requested::Range(size.saturating_sub(n), size - 1),
+            Err(_) => Requested::Whole,
+        };
+    }
+    let Ok(first) = first.parse::<u64>() else {
+        return Requested::Whole;
+    };
+    let last = if last.is_empty() {
+        None
+    } else {
+        match last.parse::<u64>() {
+            Ok(last) if last >= first => Some(last),
+            _ => return Requested::Whole,
+        }
+    };
+    if first >= size {
+        return Requested::Unsatisfiable;
+    }
+    Requested::Range(first, last.map_or(size - 1, |last| last.min(size - 1)))
+}
+
+fn read_error(e: storage::StorageError) -> Error {
+    Error::internal(format!("could not read media: {e}"))
+}
+
+fn not_found() -> Error {
+    Error::not_found("media not found")
+}
+
+/// A response with `object`'s bytes and the headers every media response has.
+fn respond(object: Object) -> Response<BoxBody> {
+    let mut res = Response::new(BoxBody::new(Bytes::from(object.bytes)));
+    let headers = res.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&object.content_type) {
+        headers.insert(CONTENT_TYPE, value);
+    }
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static(CACHE));
+    headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    res
+}
+
+fn content_range(value: String) -> HeaderValue {
+    HeaderValue::from_str(&value).expect("content ranges are ASCII")
+}
+
+/// Serves a stored file, or the range of it the `Range` header asks for.
+/// Responses carry at most [`MAX_RESPONSE_BYTES`]: a longer range is cut
+/// short, and a larger file asked for whole gets its first part as a `206`,
+/// which clients that play media continue with ranged requests. Large
+/// deployments should still point `MEDIA_BASE_URL` at a CDN or public bucket.
+#[public]
+#[get("/api/v1/media/:name")]
+pub async fn get_media(
+    name: Path<String>,
+    headers: Headers,
+    storage: State<MediaStorage>,
+) -> Result<Response<BoxBody>> {
+    let name = name.into_inner();
+    if !storage::is_valid_key(&name) {
+        return Err(not_found());
+    }
+    let storage = storage.into_inner();
+    let size = storage
+        .size(&name)
+        .await
+        .map_err(read_error)?
+        .ok_or_else(not_found)?;
+    let range = headers.0.get(RANGE).and_then(|v| v.to_str().ok());
+
+    let (first, last) = match requested(range, size) {
+        Requested::Whole if size <= MAX_RESPONSE_BYTES => {
+            let object = storage
+                .get(&name)
+                .await
+                .map_err(read_error)?
+                .ok_or_else(not_found)?;
+            return Ok(respond(object));
+        }
+        Requested::Whole => (0, size - 1),
+        Requested::Range(first, last) => (first, last),
+        Requested::Unsatisfiable => {
+            let mut res = Response::new(BoxBody::new(Bytes::new()));
+            *res.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+            res.headers_mut()
+                .insert(CONTENT_RANGE, content_range(format!("bytes */{size}")));
+            return Ok(res);
+        }
+    };
+
+    let len = (last - first + 1).min(MAX_RESPONSE_BYTES);
+    let object = storage
+        .get_range(&name, first, len)
+        .await
+        .map_err(read_error)?
+        .ok_or_else(not_found)?;
+    // The file may have been replaced with a shorter one in the meantime.
+    if object.bytes.is_empty() {
+        return Err(not_found());
+    }
+    let last = first + object.bytes.len() as u64 - 1;
+
+    let mut res = respond(object);
+    *res.status_mut() = StatusCode::PARTIAL_CONTENT;
+    res.headers_mut().insert(
+        CONTENT_RANGE,
+        content_range(format!("bytes {first}-{last}/{size}")),
+    );
+    Ok(res)
+}
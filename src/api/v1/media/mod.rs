@@ -0,0 +1,11 @@
+pub mod handlers;
+
+use handlers::*;
+use rapina::prelude::*;
+
+/// Media is embedded in public widgets, so it is served without auth.
+pub const PUBLIC_ROUTES: &[(&str, &str)] = &[("GET", "/api/v1/media/:name")];
+
+pub fn routes() -> Router {
+    Router::new().get("/:name", get_media)
+}
@@ -0,0 +1,168 @@
+//! JSON Merge Patch (RFC 7396) support.
+//!
+//! In a merge patch a member that is absent leaves the field unchanged, an
+//! explicit `null` clears it, and any other value replaces it. `Option<T>`
+//! cannot tell the first two apart, so patch requests use [`Patch<T>`].
+
+use std::borrow::Cow;
+
+use rapina::handler::Handler;
+use rapina::prelude::*;
+use rapina::schemars::{JsonSchema, Schema, SchemaGenerator};
+use rapina::sea_orm::{ActiveValue, Set, Value};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use validator::AsRegex;
+use validator::{ValidateEmail, ValidateLength, ValidateRange, ValidateRegex, ValidateUrl};
+
+/// Media type of merge patch request bodies.
+pub const MEDIA_TYPE: &str = "application/merge-patch+json";
+
+/// Route macro for handlers mounted with [`PatchRoutes::patch`]. Rapina has
+/// no `#[patch]`, and its route macros only name the handler, so this is
+/// `#[put]` under the name of the method the handler serves.
+pub use rapina::prelude::put as patch;
+
+/// One member of a merge patch. Fields of type `Patch<T>` need
+/// `#[serde(default)]` so that absent members deserialize to `Missing`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum Patch<T> {
+    #[default]
+    Missing,
+    Null,
+    Value(T),
+}
+
+impl<T> Patch<T> {
+    /// Applies the member to a nullable column.
+    pub fn apply(self, column: &mut ActiveValue<Option<T>>)
+    where
+        Option<T>: Into<Value>,
+    {
+        match self {
+            Patch::Missing => {}
+            Patch::Null => *column = Set(None),
+            Patch::Value(value) => *column = Set(Some(value)),
+        }
+    }
+
+    /// The new value of a column that cannot be null, or `None` if the member
+    /// was absent. Clearing it is an error naming `field`.
+    pub fn required(self, field: &str) -> std::result::Result<Option<T>, String> {
+        match self {
+            Patch::Missing => Ok(None),
+            Patch::Null => Err(format!("{field} cannot be null")),
+            Patch::Value(value) => Ok(Some(value)),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(match Option::<T>::deserialize(deserializer)? {
+            Some(value) => Patch::Value(value),
+            None => Patch::Null,
+        })
+    }
+}
+
+impl<T: Serialize> Serialize for Patch<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Patch::Value(value) => value.serialize(serializer),
+            Patch::Missing | Patch::Null => serializer.serialize_none(),
+        }
+    }
+}
+
+// Validation rules only apply to a value being set; leaving a field alone or
+// clearing it is always valid.
+
+impl<T: ValidateLength<u64>> ValidateLength<u64> for Patch<T> {
+    fn length(&self) -> Option<u64> {
+        match self {
+            Patch::Value(value) => value.length(),
+            Patch::Missing | Patch::Null => None,
+        }
+    }
+}
+
+impl<T: ValidateRange<T>> ValidateRange<T> for Patch<T> {
+    fn greater_than(&self, max: T) -> Option<bool> {
+        match self {
+            Patch::Value(value) => value.greater_than(max),
+            Patch::Missing | Patch::Null => None,
+        }
+    }
+
+    fn less_than(&self, min: T) -> Option<bool> {
+        match self {
+            Patch::Value(value) => value.less_than(min),
+            Patch::Missing | Patch::Null => None,
+        }
+    }
+}
+
+impl<T: ValidateUrl> ValidateUrl for Patch<T> {
+    fn as_url_string(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Patch::Value(value) => value.as_url_string(),
+            Patch::Missing | Patch::Null => None,
+        }
+    }
+}
+
+impl<T: ValidateEmail> ValidateEmail for Patch<T> {
+    fn as_email_string(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Patch::Value(value) => value.as_email_string(),
+            Patch::Missing | Patch::Null => None,
+        }
+    }
+}
+
+impl<T: ValidateRegex> ValidateRegex for Patch<T> {
+    fn validate_regex(&self, regex: impl AsRegex) -> bool {
+        match self {
+            Patch::Value(value) => value.validate_regex(regex),
+            Patch::Missing | Patch::Null => true,
+        }
+    }
+}
+
+/// Documented as the nullable `T`; the member is optional because patch
+/// structs default absent fields.
+impl<T: JsonSchema> JsonSchema for Patch<T> {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        format!("Patch_{}", T::schema_name()).into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        Option::<T>::json_schema(generator)
+    }
+}
+
+/// `PATCH` routes, for handlers declared with [`patch`]. Rapina's spec
+/// leaves them out; [`openapi`](super::openapi) documents them.
+pub trait PatchRoutes {
+    fn patch<H: Handler>(self, pattern: &str, handler: H) -> Self;
+}
+
+impl PatchRoutes for Router {
+    fn patch<H: Handler>(self, pattern: &str, handler: H) -> Self {
+        self.route_named(
+            Method::PATCH,
+            pattern,
+            H::NAME,
+            H::response_schema(),
+            H::error_responses(),
+            move |req, params, state| {
+                let h = handler.clone();
+                async move { h.call(req, params, state).await }
+            },
+        )
+    }
+}
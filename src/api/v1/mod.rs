@@ -1,4 +1,18 @@
+pub mod admin;
 pub mod auth;
+pub mod etag;
+pub mod forms;
+pub mod import_sources;
+pub mod imports;
+pub mod media;
+pub mod merge_patch;
+pub mod multipart;
+pub mod openapi;
+pub mod pagination;
 pub mod projects;
 pub mod tags;
 pub mod testimonials;
+pub mod uploads;
+pub mod validation;
+pub mod webhooks;
+pub mod widgets;
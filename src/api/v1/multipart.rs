@@ -0,0 +1,86 @@
+//! `multipart/form-data` request bodies.
+//!
+//! Rapina only ships JSON and urlencoded extractors, so uploads are read with
+//! [`Multipart`], which buffers every part of a size-limited body.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use http_body_util::BodyDataStream;
+use multer::{Constraints, SizeLimit};
+use rapina::extract::{FromRequest, PathParams};
+use rapina::http::header::CONTENT_TYPE;
+use rapina::hyper::Request;
+use rapina::hyper::body::{Bytes, Incoming};
+use rapina::prelude::*;
+use rapina::state::AppState;
+
+/// Upper bound on the size of a whole multipart body.
+pub const MAX_BODY_BYTES: u64 = 10 * 1024 * 1024;
+
+pub struct Part {
+    pub filename: Option<String>,
+    pub data: Bytes,
+}
+
+/// The parts of a multipart body, by field name. A repeated field keeps its
+/// last part.
+pub struct Multipart(pub HashMap<String, Part>);
+
+impl Multipart {
+    pub fn take(&mut self, name: &str) -> Option<Part> {
+        self.0.remove(name)
+    }
+
+    /// A text field, or `None` if it was not sent or is not UTF-8.
+    pub fn text(&mut self, name: &str) -> Option<String> {
+        self.take(name)
+            .and_then(|part| String::from_utf8(part.data.to_vec()).ok())
+    }
+}
+
+fn invalid(e: multer::Error) -> Error {
+    match e {
+        multer::Error::StreamSizeExceeded { limit } => Error::new(
+            413,
+            "PAYLOAD_TOO_LARGE",
+            format!("request body must not exceed {limit} bytes"),
+        ),
+        e => Error::bad_request(format!("invalid multipart body: {e}")),
+    }
+}
+
+impl FromRequest for Multipart {
+    async fn from_request(
+        req: Request<Incoming>,
+        _params: &PathParams,
+        _state: &Arc<AppState>,
+    ) -> std::result::Result<Self, Error> {
+        let boundary = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| multer::parse_boundary(v).ok())
+            .ok_or_else(|| Error::bad_request("expected a multipart/form-data body"))?;
+
+        let constraints =
+            Constraints::new().size_limit(SizeLimit::new().whole_stream(MAX_BODY_BYTES));
+        let mut multipart = multer::Multipart::with_constraints(
+            BodyDataStream::new(req.into_body()),
+            boundary,
+            constraints,
+        );
+
+        let mut parts = HashMap::new();
+        while let Some(field) = multipart.next_field().await.map_err(invalid)? {
+            let Some(name) = field.name().map(str::to_string) else {
+                continue;
+            };
+            let filename = field.file_name().map(str::to_string);
+            let data = field.bytes().await.map_err(invalid)?;
+            parts.insert(name, Part { filename, data });
+        }
+
+        Ok(Multipart(parts))
+    }
+}
@@ -0,0 +1,105 @@
+//! The OpenAPI document, served at [`PATH`].
+//!
+//! Rapina builds the document from the mounted routes but leaves out `PATCH`
+//! operations, so they are added here with their request bodies: a JSON
+//! Merge Patch, or the raw chunk of a resumable upload.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use rapina::handler::Handler;
+use rapina::introspection::RouteInfo;
+use rapina::openapi::{MediaType, RequestBody, Schema, build_openapi_spec};
+use rapina::prelude::*;
+use rapina::schemars::schema_for;
+use serde_json::{Value, json};
+
+use super::merge_patch;
+use super::projects::dto::PatchProjectRequest;
+use super::projects::handlers::patch_project;
+use super::testimonials::dto::PatchTestimonialRequest;
+use super::testimonials::handlers::patch_testimonial;
+use super::uploads::handlers::upload_chunk;
+
+pub const PATH: &str = "/__rapina/openapi.json";
+
+/// Request bodies of the `PATCH` operations, by handler name.
+fn patch_bodies() -> BTreeMap<&'static str, RequestBody> {
+    BTreeMap::from([
+        (
+            patch_project::NAME,
+            request_body(
+                merge_patch::MEDIA_TYPE,
+                schema_for!(PatchProjectRequest).to_value(),
+            ),
+        ),
+        (
+            patch_testimonial::NAME,
+            request_body(
+                merge_patch::MEDIA_TYPE,
+                schema_for!(PatchTestimonialRequest).to_value(),
+            ),
+        ),
+        (
+            upload_chunk::NAME,
+            request_body(
+                "application/octet-stream",
+                json!({ "type": "string", "format": "binary" }),
+            ),
+        ),
+    ])
+}
+
+fn request_body(media_type: &str, schema: Value) -> RequestBody {
+    RequestBody {
+        description: None,
+        required: true,
+        content: BTreeMap::from([(
+            media_type.to_string(),
+            MediaType {
+                schema: Schema::Inline(schema),
+            },
+        )]),
+    }
+}
+
+/// The API's OpenAPI document.
+#[derive(Clone)]
+pub struct ApiSpec(Arc<Value>);
+
+impl ApiSpec {
+    pub fn build(title: &str, version: &str, routes: &[RouteInfo]) -> Self {
+        let mut spec = serde_json::to_value(build_openapi_spec(title, version, routes))
+            .expect("the OpenAPI document serializes");
+
+        let bodies = patch_bodies();
+        for route in routes.iter().filter(|route| route.method == "PATCH") {
+            // Documented the way Rapina documents a PUT, plus the body.
+            let as_put = RouteInfo {
+                method: "PUT".to_string(),
+                ..route.clone()
+            };
+            let document = build_openapi_spec(title, version, &[as_put]);
+            let Some((path, item)) = document.paths.into_iter().next() else {
+                continue;
+            };
+            let Some(mut operation) = item.put else {
+                continue;
+            };
+            operation.request_body = bodies.get(route.handler_name.as_str()).cloned();
+            spec["paths"][&path]["patch"] =
+                serde_json::to_value(operation).expect("operations serialize");
+        }
+
+        ApiSpec(Arc::new(spec))
+    }
+
+    pub fn document(&self) -> &Value {
+        &self.0
+    }
+}
+
+#[get("/__rapina/openapi.json")]
+pub async fn openapi_spec(spec: State<ApiSpec>) -> Json<Value> {
+    Json(spec.into_inner().document().clone())
+}
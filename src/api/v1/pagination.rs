@@ -0,0 +1,97 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rapina::prelude::*;
+use rapina::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_LIMIT: u64 = 50;
+pub const MAX_LIMIT: u64 = 100;
+
+pub enum PaginationError {
+    InvalidCursor,
+}
+
+impl IntoApiError for PaginationError {
+    fn into_api_error(self) -> Error {
+        match self {
+            PaginationError::InvalidCursor => Error::bad_request("invalid pagination cursor"),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct PageQuery {
+    pub limit: Option<u64>,
+    pub cursor: Option<String>,
+    pub include_total: Option<bool>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+/// Position of the last row of a page. Listings are keyed on `id` by default;
+/// sorted listings also carry the sort column and its value for that row.
+#[derive(Serialize, Deserialize)]
+pub struct Cursor {
+    pub id: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub value: serde_json::Value,
+}
+
+impl Cursor {
+    pub fn from_id(id: i32) -> Self {
+        Cursor {
+            id,
+            sort: None,
+            value: serde_json::Value::Null,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(raw: &str) -> std::result::Result<Self, PaginationError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(raw)
+            .map_err(|_| PaginationError::InvalidCursor)?;
+        serde_json::from_slice(&bytes).map_err(|_| PaginationError::InvalidCursor)
+    }
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> u64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    pub fn include_total(&self) -> bool {
+        self.include_total.unwrap_or(false)
+    }
+
+    pub fn cursor(&self) -> std::result::Result<Option<Cursor>, PaginationError> {
+        self.cursor.as_deref().map(Cursor::decode).transpose()
+    }
+}
+
+/// Trims rows fetched with `limit + 1` back to `limit` and returns the cursor
+/// for the next page. The extra row only signals that another page exists.
+pub fn next_cursor<M>(
+    rows: &mut Vec<M>,
+    limit: u64,
+    cursor_of: impl Fn(&M) -> Cursor,
+) -> Option<String> {
+    if rows.len() as u64 <= limit {
+        return None;
+    }
+
+    rows.truncate(limit as usize);
+    rows.last().map(|m| cursor_of(m).encode())
+}
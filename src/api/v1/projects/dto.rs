@@ -1,29 +1,113 @@
 use rapina::schemars::{self, JsonSchema};
 use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidateEmail, ValidationError};
 
-#[derive(Deserialize, JsonSchema)]
+use crate::api::v1::merge_patch::Patch;
+use crate::api::v1::validation::{MEDIA_URL, SLUG};
+use crate::images::ImageVariant;
+
+#[derive(Deserialize, JsonSchema, Validate)]
 pub struct CreateProjectRequest {
+    #[validate(length(min = 1, max = 255))]
     pub name: String,
+    #[validate(length(min = 1, max = 255), regex(path = *SLUG))]
     pub slug: String,
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
     pub logo_url: Option<String>,
+    #[validate(url)]
     pub website_url: Option<String>,
 }
 
-#[derive(Deserialize, JsonSchema)]
+#[derive(Deserialize, JsonSchema, Validate)]
 pub struct UpdateProjectRequest {
+    #[validate(length(min = 1, max = 255))]
     pub name: Option<String>,
+    #[validate(length(min = 1, max = 255), regex(path = *SLUG))]
     pub slug: Option<String>,
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
     pub logo_url: Option<String>,
+    #[validate(url)]
     pub website_url: Option<String>,
 }
 
+/// A JSON Merge Patch: `null` clears `logo_url` or `website_url`.
+#[derive(Default, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct PatchProjectRequest {
+    #[schemars(with = "String")]
+    #[validate(length(min = 1, max = 255))]
+    pub name: Patch<String>,
+    #[schemars(with = "String")]
+    #[validate(length(min = 1, max = 255), regex(path = *SLUG))]
+    pub slug: Patch<String>,
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
+    pub logo_url: Patch<String>,
+    #[validate(url)]
+    pub website_url: Patch<String>,
+}
+
 #[derive(Serialize, JsonSchema)]
 pub struct ProjectResponse {
     pub id: String,
     pub name: String,
     pub slug: String,
     pub logo_url: Option<String>,
+    /// Resized copies of the logo, smallest first, when it is an uploaded
+    /// image.
+    pub logo_variants: Vec<ImageVariant>,
     pub website_url: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// When the project was moved to the trash.
+    pub deleted_at: Option<String>,
+}
+
+/// Most members a project can have besides its owner, invitations included.
+const MAX_MEMBERS: u64 = 100;
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct SetProjectMembersRequest {
+    /// Emails of the users invited to hear about the project's form
+    /// submissions along with its owner, once they accept. Members and
+    /// invitations missing from the list are removed.
+    #[validate(length(max = "MAX_MEMBERS"), custom(function = "member_emails"))]
+    pub emails: Vec<String>,
+}
+
+fn member_emails(emails: &[String]) -> std::result::Result<(), ValidationError> {
+    if emails.iter().all(|email| email.validate_email()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("email").with_message("emails must be email addresses".into()))
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ProjectMemberResponse {
+    pub user_id: String,
+    pub email: String,
+    pub name: Option<String>,
+    pub is_owner: bool,
+    /// Whether the user accepted the invitation. Only members who have hear
+    /// about form submissions.
+    pub accepted: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ProjectMembersResponse {
+    /// The owner first, then the users they invited.
+    pub members: Vec<ProjectMemberResponse>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ProjectInvitationResponse {
+    pub project_id: String,
+    pub project_name: String,
+    pub invited_at: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ProjectInvitationsResponse {
+    /// Oldest first.
+    pub invitations: Vec<ProjectInvitationResponse>,
 }
@@ -6,6 +6,10 @@ pub enum ProjectError {
     NotFound,
     Forbidden,
     SlugTaken,
+    InvalidPatch(String),
+    /// An email is not a user's, so they cannot be invited. Which one is not
+    /// said, so the error does not tell who has an account.
+    UnknownMember,
 }
 
 impl IntoApiError for ProjectError {
@@ -15,6 +19,10 @@ impl IntoApiError for ProjectError {
             ProjectError::NotFound => Error::not_found("project not found"),
             ProjectError::Forbidden => Error::forbidden("you do not own this project"),
             ProjectError::SlugTaken => Error::conflict("slug already taken"),
+            ProjectError::InvalidPatch(msg) => Error::bad_request(msg),
+            ProjectError::UnknownMember => {
+                Error::validation("members must be invited by the email of their account")
+            }
         }
     }
 }
@@ -22,6 +30,11 @@ impl IntoApiError for ProjectError {
 impl DocumentedError for ProjectError {
     fn error_variants() -> Vec<ErrorVariant> {
         vec![
+            ErrorVariant {
+                status: 400,
+                code: "BAD_REQUEST",
+                description: "Invalid pagination cursor or patch",
+            },
             ErrorVariant {
                 status: 404,
                 code: "NOT_FOUND",
@@ -37,6 +50,16 @@ impl DocumentedError for ProjectError {
                 code: "CONFLICT",
                 description: "Slug already taken",
             },
+            ErrorVariant {
+                status: 412,
+                code: "PRECONDITION_FAILED",
+                description: "If-Match does not match the current ETag",
+            },
+            ErrorVariant {
+                status: 422,
+                code: "VALIDATION_ERROR",
+                description: "Request body failed validation, or a member email is not a user's",
+            },
             ErrorVariant {
                 status: 500,
                 code: "INTERNAL_ERROR",
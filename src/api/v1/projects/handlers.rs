@@ -1,12 +1,30 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
 use rapina::database::{Db, DbError};
 use rapina::prelude::*;
-use rapina::sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Select, Set, TransactionTrait,
+};
 use uuid::Uuid;
 
-use crate::db::entities::project::{ActiveModel, Column, Entity as Project};
+use crate::api::v1::etag;
+use crate::api::v1::merge_patch::patch;
+use crate::api::v1::pagination::{self, Cursor, Page, PageQuery};
+use crate::db::entities::project::{ActiveModel, Column, Entity as Project, Model};
+use crate::db::entities::project_member::{
+    ActiveModel as MemberActiveModel, Column as MemberColumn, Entity as ProjectMember,
+    Model as MemberModel,
+};
 use crate::db::entities::user::{Column as UserColumn, Entity as User};
+use crate::images;
 
-use super::dto::{CreateProjectRequest, ProjectResponse, UpdateProjectRequest};
+use super::dto::{
+    CreateProjectRequest, PatchProjectRequest, ProjectInvitationResponse,
+    ProjectInvitationsResponse, ProjectMemberResponse, ProjectMembersResponse, ProjectResponse,
+    SetProjectMembersRequest, UpdateProjectRequest,
+};
 use super::error::ProjectError;
 
 fn to_response(p: crate::db::entities::project::Model) -> ProjectResponse {
@@ -15,9 +33,11 @@ fn to_response(p: crate::db::entities::project::Model) -> ProjectResponse {
         name: p.name,
         slug: p.slug,
         logo_url: p.logo_url,
+        logo_variants: images::parse(p.logo_variants),
         website_url: p.website_url,
         created_at: p.created_at.to_rfc3339(),
         updated_at: p.updated_at.to_rfc3339(),
+        deleted_at: p.deleted_at.map(|at| at.to_rfc3339()),
     }
 }
 
@@ -35,22 +55,69 @@ async fn resolve_user_id(db: &Db, current_user: &CurrentUser) -> Result<i32> {
     Ok(user.id)
 }
 
+async fn paginate(
+    db: &Db,
+    mut q: Select<Project>,
+    page: PageQuery,
+) -> Result<Page<ProjectResponse>> {
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(Column::Id.lt(cursor.id));
+    }
+
+    let mut projects = q
+        .order_by_desc(Column::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut projects, limit, |p| Cursor::from_id(p.id));
+
+    Ok(Page {
+        data: projects.into_iter().map(to_response).collect(),
+        next_cursor,
+        total,
+    })
+}
+
 #[get("/api/v1/projects")]
 #[errors(ProjectError)]
 pub async fn list_projects(
+    query: Query<PageQuery>,
     db: Db,
     current_user: CurrentUser,
-) -> Result<Json<Vec<ProjectResponse>>> {
+) -> Result<Json<Page<ProjectResponse>>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
 
-    let projects = Project::find()
+    let q = Project::find()
         .filter(Column::UserId.eq(user_id))
-        .all(db.conn())
-        .await
-        .map_err(DbError)?;
+        .filter(Column::DeletedAt.is_null());
+
+    Ok(Json(paginate(&db, q, query.into_inner()).await?))
+}
+
+#[get("/api/v1/projects/trash")]
+#[errors(ProjectError)]
+pub async fn list_trashed_projects(
+    query: Query<PageQuery>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<Page<ProjectResponse>>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+
+    let q = Project::find()
+        .filter(Column::UserId.eq(user_id))
+        .filter(Column::DeletedAt.is_not_null());
 
-    let response: Vec<ProjectResponse> = projects.into_iter().map(to_response).collect();
-    Ok(Json(response))
+    Ok(Json(paginate(&db, q, query.into_inner()).await?))
 }
 
 #[post("/api/v1/projects")]
@@ -58,10 +125,10 @@ pub async fn list_projects(
 pub async fn create_project(
     db: Db,
     current_user: CurrentUser,
-    body: Json<CreateProjectRequest>,
-) -> Result<(StatusCode, Json<ProjectResponse>)> {
+    body: Validated<Json<CreateProjectRequest>>,
+) -> Result<etag::Json<ProjectResponse>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
-    let req = body.into_inner();
+    let req = body.into_inner().into_inner();
 
     let existing = Project::find()
         .filter(Column::Slug.eq(&req.slug))
@@ -87,7 +154,10 @@ pub async fn create_project(
 
     let project = new_project.insert(db.conn()).await.map_err(DbError)?;
 
-    Ok((StatusCode::CREATED, Json(to_response(project))))
+    Ok(etag::Json::created(
+        project.updated_at,
+        to_response(project),
+    ))
 }
 
 #[get("/api/v1/projects/:id")]
@@ -96,13 +166,14 @@ pub async fn get_project(
     id: Path<String>,
     db: Db,
     current_user: CurrentUser,
-) -> Result<Json<ProjectResponse>> {
+) -> Result<etag::Json<ProjectResponse>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid =
         Uuid::parse_str(&id.into_inner()).map_err(|_| ProjectError::NotFound.into_api_error())?;
 
     let project = Project::find()
         .filter(Column::Pid.eq(pid))
+        .filter(Column::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -112,7 +183,22 @@ pub async fn get_project(
         return Err(ProjectError::Forbidden.into_api_error());
     }
 
-    Ok(Json(to_response(project)))
+    Ok(etag::Json::new(project.updated_at, to_response(project)))
+}
+
+async fn ensure_slug_free(db: &Db, slug: &str, project_id: i32) -> Result<()> {
+    let slug_taken = Project::find()
+        .filter(Column::Slug.eq(slug))
+        .filter(Column::Id.ne(project_id))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?;
+
+    if slug_taken.is_some() {
+        return Err(ProjectError::SlugTaken.into_api_error());
+    }
+
+    Ok(())
 }
 
 #[put("/api/v1/projects/:id")]
@@ -121,14 +207,16 @@ pub async fn update_project(
     id: Path<String>,
     db: Db,
     current_user: CurrentUser,
-    body: Json<UpdateProjectRequest>,
-) -> Result<Json<ProjectResponse>> {
+    headers: Headers,
+    body: Validated<Json<UpdateProjectRequest>>,
+) -> Result<etag::Json<ProjectResponse>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid =
         Uuid::parse_str(&id.into_inner()).map_err(|_| ProjectError::NotFound.into_api_error())?;
 
     let project = Project::find()
         .filter(Column::Pid.eq(pid))
+        .filter(Column::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -138,21 +226,14 @@ pub async fn update_project(
         return Err(ProjectError::Forbidden.into_api_error());
     }
 
-    let req = body.into_inner();
+    let guard = etag::if_match(&headers.0, project.updated_at)?;
+    let req = body.into_inner().into_inner();
 
     if let Some(ref slug) = req.slug {
-        let slug_taken = Project::find()
-            .filter(Column::Slug.eq(slug))
-            .filter(Column::Id.ne(project.id))
-            .one(db.conn())
-            .await
-            .map_err(DbError)?;
-
-        if slug_taken.is_some() {
-            return Err(ProjectError::SlugTaken.into_api_error());
-        }
+        ensure_slug_free(&db, slug, project.id).await?;
     }
 
+    let before = project.clone();
     let mut active: ActiveModel = project.into();
 
     if let Some(name) = req.name {
@@ -167,10 +248,88 @@ pub async fn update_project(
     if let Some(website_url) = req.website_url {
         active.website_url = Set(Some(website_url));
     }
+    images::link(
+        db.conn(),
+        before.id,
+        Some(&before.logo_url),
+        &active.logo_url,
+        &mut active.logo_variants,
+    )
+    .await
+    .map_err(DbError)?;
+
+    let updated = etag::update(db.conn(), active, Column::UpdatedAt, guard).await?;
+
+    Ok(etag::Json::new(updated.updated_at, to_response(updated)))
+}
 
-    let updated = active.update(db.conn()).await.map_err(DbError)?;
+/// Applies a JSON Merge Patch: absent fields are left alone and `null`
+/// clears `logo_url` or `website_url`.
+#[patch("/api/v1/projects/:id")]
+#[errors(ProjectError)]
+pub async fn patch_project(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    headers: Headers,
+    body: Validated<Json<PatchProjectRequest>>,
+) -> Result<etag::Json<ProjectResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let pid =
+        Uuid::parse_str(&id.into_inner()).map_err(|_| ProjectError::NotFound.into_api_error())?;
 
-    Ok(Json(to_response(updated)))
+    let project = Project::find()
+        .filter(Column::Pid.eq(pid))
+        .filter(Column::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ProjectError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(ProjectError::Forbidden.into_api_error());
+    }
+
+    let guard = etag::if_match(&headers.0, project.updated_at)?;
+    let patch = body.into_inner().into_inner();
+
+    let name = patch
+        .name
+        .required("name")
+        .map_err(|msg| ProjectError::InvalidPatch(msg).into_api_error())?;
+    let slug = patch
+        .slug
+        .required("slug")
+        .map_err(|msg| ProjectError::InvalidPatch(msg).into_api_error())?;
+
+    if let Some(ref slug) = slug {
+        ensure_slug_free(&db, slug, project.id).await?;
+    }
+
+    let before = project.clone();
+    let mut active: ActiveModel = project.into();
+
+    if let Some(name) = name {
+        active.name = Set(name);
+    }
+    if let Some(slug) = slug {
+        active.slug = Set(slug);
+    }
+    patch.logo_url.apply(&mut active.logo_url);
+    patch.website_url.apply(&mut active.website_url);
+    images::link(
+        db.conn(),
+        before.id,
+        Some(&before.logo_url),
+        &active.logo_url,
+        &mut active.logo_variants,
+    )
+    .await
+    .map_err(DbError)?;
+
+    let updated = etag::update(db.conn(), active, Column::UpdatedAt, guard).await?;
+
+    Ok(etag::Json::new(updated.updated_at, to_response(updated)))
 }
 
 #[delete("/api/v1/projects/:id")]
@@ -179,6 +338,7 @@ pub async fn delete_project(
     id: Path<String>,
     db: Db,
     current_user: CurrentUser,
+    headers: Headers,
 ) -> Result<StatusCode> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid =
@@ -186,6 +346,7 @@ pub async fn delete_project(
 
     let project = Project::find()
         .filter(Column::Pid.eq(pid))
+        .filter(Column::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -195,10 +356,285 @@ pub async fn delete_project(
         return Err(ProjectError::Forbidden.into_api_error());
     }
 
-    Project::delete_by_id(project.id)
-        .exec(db.conn())
+    let guard = etag::if_match(&headers.0, project.updated_at)?;
+
+    let mut active: ActiveModel = project.into();
+    active.deleted_at = Set(Some(Utc::now().fixed_offset()));
+    etag::update(db.conn(), active, Column::UpdatedAt, guard).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[post("/api/v1/projects/:id/restore")]
+#[errors(ProjectError)]
+pub async fn restore_project(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<etag::Json<ProjectResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let pid =
+        Uuid::parse_str(&id.into_inner()).map_err(|_| ProjectError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(Column::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ProjectError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(ProjectError::Forbidden.into_api_error());
+    }
+
+    if project.deleted_at.is_none() {
+        return Ok(etag::Json::new(project.updated_at, to_response(project)));
+    }
+
+    let mut active: ActiveModel = project.into();
+    active.deleted_at = Set(None);
+    let restored = active.update(db.conn()).await.map_err(DbError)?;
+
+    Ok(etag::Json::new(restored.updated_at, to_response(restored)))
+}
+
+/// The project with public id `id`, if the user owns it.
+async fn find_owned_project(db: &Db, id: String, user_id: i32) -> Result<Model> {
+    let pid = Uuid::parse_str(&id).map_err(|_| ProjectError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(Column::Pid.eq(pid))
+        .filter(Column::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ProjectError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(ProjectError::Forbidden.into_api_error());
+    }
+
+    Ok(project)
+}
+
+async fn members_response(db: &Db, project: &Model) -> Result<ProjectMembersResponse> {
+    let invited = ProjectMember::find()
+        .filter(MemberColumn::ProjectId.eq(project.id))
+        .filter(MemberColumn::UserId.ne(project.user_id))
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let accepted: HashSet<i32> = invited
+        .iter()
+        .filter(|m| m.accepted_at.is_some())
+        .map(|m| m.user_id)
+        .collect();
+    let mut users = User::find_by_id(project.user_id)
+        .all(db.conn())
         .await
         .map_err(DbError)?;
+    users.extend(
+        User::find()
+            .filter(UserColumn::Id.is_in(invited.iter().map(|m| m.user_id)))
+            .order_by_asc(UserColumn::Id)
+            .all(db.conn())
+            .await
+            .map_err(DbError)?,
+    );
+
+    Ok(ProjectMembersResponse {
+        members: users
+            .into_iter()
+            .map(|user| {
+                let is_owner = user.id == project.user_id;
+                ProjectMemberResponse {
+                    user_id: user.pid.to_string(),
+                    accepted: is_owner || accepted.contains(&user.id),
+                    is_owner,
+                    email: user.email,
+                    name: user.name,
+                }
+            })
+            .collect(),
+    })
+}
+
+/// The project's members: its owner and the users they invited, who hear
+/// about its form submissions once they accept.
+#[get("/api/v1/projects/:id/members")]
+#[errors(ProjectError)]
+pub async fn list_project_members(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<ProjectMembersResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let project = find_owned_project(&db, id.into_inner(), user_id).await?;
+
+    Ok(Json(members_response(&db, &project).await?))
+}
+
+/// Replaces the users invited to the project. Users new to the list are
+/// invited, and only become members once they accept; those already in it
+/// keep their place. Only the owner manages the project; members hear about
+/// its form submissions as their own notification preferences say.
+#[put("/api/v1/projects/:id/members")]
+#[errors(ProjectError)]
+pub async fn set_project_members(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    body: Validated<Json<SetProjectMembersRequest>>,
+) -> Result<Json<ProjectMembersResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let project = find_owned_project(&db, id.into_inner(), user_id).await?;
+    let req = body.into_inner().into_inner();
+
+    let mut member_ids = Vec::new();
+    for email in &req.emails {
+        let user = User::find()
+            .filter(UserColumn::Email.eq(email))
+            .one(db.conn())
+            .await
+            .map_err(DbError)?
+            .ok_or_else(|| ProjectError::UnknownMember.into_api_error())?;
+        // The owner is a member anyway.
+        if user.id != project.user_id && !member_ids.contains(&user.id) {
+            member_ids.push(user.id);
+        }
+    }
+
+    let txn = db.conn().begin().await.map_err(DbError)?;
+    ProjectMember::delete_many()
+        .filter(MemberColumn::ProjectId.eq(project.id))
+        .filter(MemberColumn::UserId.is_not_in(member_ids.clone()))
+        .exec(&txn)
+        .await
+        .map_err(DbError)?;
+    let existing: Vec<i32> = ProjectMember::find()
+        .select_only()
+        .column(MemberColumn::UserId)
+        .filter(MemberColumn::ProjectId.eq(project.id))
+        .into_tuple()
+        .all(&txn)
+        .await
+        .map_err(DbError)?;
+    for member_id in member_ids {
+        if existing.contains(&member_id) {
+            continue;
+        }
+        MemberActiveModel {
+            project_id: Set(project.id),
+            user_id: Set(member_id),
+            accepted_at: Set(None),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .map_err(DbError)?;
+    }
+    txn.commit().await.map_err(DbError)?;
 
+    Ok(Json(members_response(&db, &project).await?))
+}
+
+/// The projects the current user is invited to and has not yet accepted.
+#[get("/api/v1/projects/invitations")]
+#[errors(ProjectError)]
+pub async fn list_project_invitations(
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<ProjectInvitationsResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+
+    let invitations = ProjectMember::find()
+        .filter(MemberColumn::UserId.eq(user_id))
+        .filter(MemberColumn::AcceptedAt.is_null())
+        .order_by_asc(MemberColumn::Id)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let projects: HashMap<i32, Model> = Project::find()
+        .filter(Column::Id.is_in(invitations.iter().map(|m| m.project_id)))
+        .filter(Column::DeletedAt.is_null())
+        .all(db.conn())
+        .await
+        .map_err(DbError)?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+
+    Ok(Json(ProjectInvitationsResponse {
+        invitations: invitations
+            .into_iter()
+            .filter_map(|member| {
+                let project = projects.get(&member.project_id)?;
+                Some(ProjectInvitationResponse {
+                    project_id: project.pid.to_string(),
+                    project_name: project.name.clone(),
+                    invited_at: member.created_at.to_rfc3339(),
+                })
+            })
+            .collect(),
+    }))
+}
+
+/// The current user's membership of the project with public id `id`, or
+/// their invitation to it.
+async fn find_own_membership(db: &Db, id: String, user_id: i32) -> Result<MemberModel> {
+    let pid = Uuid::parse_str(&id).map_err(|_| ProjectError::NotFound.into_api_error())?;
+    let project = Project::find()
+        .filter(Column::Pid.eq(pid))
+        .filter(Column::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ProjectError::NotFound.into_api_error())?;
+
+    ProjectMember::find()
+        .filter(MemberColumn::ProjectId.eq(project.id))
+        .filter(MemberColumn::UserId.eq(user_id))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| ProjectError::NotFound.into_api_error())
+}
+
+/// Accepts the current user's invitation to the project, after which they
+/// hear about its form submissions.
+#[post("/api/v1/projects/:id/members/me")]
+#[errors(ProjectError)]
+pub async fn accept_project_invitation(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<StatusCode> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let member = find_own_membership(&db, id.into_inner(), user_id).await?;
+
+    if member.accepted_at.is_none() {
+        let mut active: MemberActiveModel = member.into();
+        active.accepted_at = Set(Some(Utc::now().fixed_offset()));
+        active.update(db.conn()).await.map_err(DbError)?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Declines the current user's invitation to the project, or leaves it if
+/// they accepted. Owners cannot leave their own projects.
+#[delete("/api/v1/projects/:id/members/me")]
+#[errors(ProjectError)]
+pub async fn leave_project(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<StatusCode> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let member = find_own_membership(&db, id.into_inner(), user_id).await?;
+
+    ProjectMember::delete_by_id(member.id)
+        .exec(db.conn())
+        .await
+        .map_err(DbError)?;
     Ok(StatusCode::NO_CONTENT)
 }
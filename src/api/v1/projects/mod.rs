@@ -5,11 +5,21 @@ pub mod handlers;
 use handlers::*;
 use rapina::prelude::*;
 
+use crate::api::v1::merge_patch::PatchRoutes;
+
 pub fn routes() -> Router {
     Router::new()
         .get("/", list_projects)
         .post("/", create_project)
+        .get("/trash", list_trashed_projects)
+        .get("/invitations", list_project_invitations)
         .get("/:id", get_project)
         .put("/:id", update_project)
+        .patch("/:id", patch_project)
         .delete("/:id", delete_project)
+        .post("/:id/restore", restore_project)
+        .get("/:id/members", list_project_members)
+        .put("/:id/members", set_project_members)
+        .post("/:id/members/me", accept_project_invitation)
+        .delete("/:id/members/me", leave_project)
 }
@@ -1,15 +1,22 @@
 use rapina::schemars::{self, JsonSchema};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
-#[derive(Deserialize, JsonSchema)]
+use crate::api::v1::validation::HEX_COLOR;
+
+#[derive(Deserialize, JsonSchema, Validate)]
 pub struct CreateTagRequest {
+    #[validate(length(min = 1, max = 100))]
     pub name: String,
+    #[validate(regex(path = *HEX_COLOR))]
     pub color: Option<String>,
 }
 
-#[derive(Deserialize, JsonSchema)]
+#[derive(Deserialize, JsonSchema, Validate)]
 pub struct UpdateTagRequest {
+    #[validate(length(min = 1, max = 100))]
     pub name: Option<String>,
+    #[validate(regex(path = *HEX_COLOR))]
     pub color: Option<String>,
 }
 
@@ -19,6 +26,8 @@ pub struct TagResponse {
     pub project_id: String,
     pub name: String,
     pub color: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
 }
 
 #[derive(Deserialize, JsonSchema)]
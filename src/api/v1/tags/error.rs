@@ -24,6 +24,11 @@ impl IntoApiError for TagError {
 impl DocumentedError for TagError {
     fn error_variants() -> Vec<ErrorVariant> {
         vec![
+            ErrorVariant {
+                status: 400,
+                code: "BAD_REQUEST",
+                description: "Invalid pagination cursor",
+            },
             ErrorVariant {
                 status: 404,
                 code: "NOT_FOUND",
@@ -39,6 +44,16 @@ impl DocumentedError for TagError {
                 code: "CONFLICT",
                 description: "Tag name already exists in this project",
             },
+            ErrorVariant {
+                status: 412,
+                code: "PRECONDITION_FAILED",
+                description: "If-Match does not match the current ETag",
+            },
+            ErrorVariant {
+                status: 422,
+                code: "VALIDATION_ERROR",
+                description: "Request body failed validation; details list each invalid field",
+            },
             ErrorVariant {
                 status: 500,
                 code: "INTERNAL_ERROR",
@@ -1,8 +1,13 @@
 use rapina::database::{Db, DbError};
 use rapina::prelude::*;
-use rapina::sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
 use uuid::Uuid;
 
+use crate::api::v1::etag;
+use crate::api::v1::pagination::{self, Cursor, Page, PageQuery};
 use crate::db::entities::project::{Column as ProjectColumn, Entity as Project};
 use crate::db::entities::tag::{ActiveModel, Column, Entity as Tag};
 use crate::db::entities::testimonial::{Column as TestimonialColumn, Entity as Testimonial};
@@ -24,6 +29,8 @@ fn to_response(tag: crate::db::entities::tag::Model, project_pid: &Uuid) -> TagR
         project_id: project_pid.to_string(),
         name: tag.name,
         color: tag.color,
+        created_at: tag.created_at.to_rfc3339(),
+        updated_at: tag.updated_at.to_rfc3339(),
     }
 }
 
@@ -51,13 +58,13 @@ pub fn to_tag_responses(
 }
 
 pub async fn load_tags_for_testimonial(
-    db: &Db,
+    conn: &impl ConnectionTrait,
     testimonial_id: i32,
     project_pid: &Uuid,
 ) -> Result<Vec<TagResponse>> {
     let links = TestimonialTag::find()
         .filter(TestimonialTagColumn::TestimonialId.eq(testimonial_id))
-        .all(db.conn())
+        .all(conn)
         .await
         .map_err(DbError)?;
 
@@ -68,7 +75,7 @@ pub async fn load_tags_for_testimonial(
     let tag_ids: Vec<i32> = links.iter().map(|l| l.tag_id).collect();
     let tags = Tag::find()
         .filter(Column::Id.is_in(tag_ids))
-        .all(db.conn())
+        .all(conn)
         .await
         .map_err(DbError)?;
 
@@ -123,14 +130,16 @@ pub async fn load_tags_for_testimonials(
 #[errors(TagError)]
 pub async fn list_tags(
     id: Path<String>,
+    query: Query<PageQuery>,
     db: Db,
     current_user: CurrentUser,
-) -> Result<Json<Vec<TagResponse>>> {
+) -> Result<Json<Page<TagResponse>>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner()).map_err(|_| TagError::NotFound.into_api_error())?;
 
     let project = Project::find()
         .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -140,14 +149,35 @@ pub async fn list_tags(
         return Err(TagError::Forbidden.into_api_error());
     }
 
-    let tags = Tag::find()
-        .filter(Column::ProjectId.eq(project.id))
+    let page = query.into_inner();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = Tag::find().filter(Column::ProjectId.eq(project.id));
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(Column::Id.lt(cursor.id));
+    }
+
+    let mut tags = q
+        .order_by_desc(Column::Id)
+        .limit(limit + 1)
         .all(db.conn())
         .await
         .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut tags, limit, |t| Cursor::from_id(t.id));
 
-    let response = to_tag_responses(tags, &project.pid);
-    Ok(Json(response))
+    Ok(Json(Page {
+        data: to_tag_responses(tags, &project.pid),
+        next_cursor,
+        total,
+    }))
 }
 
 #[post("/api/v1/projects/:id/tags")]
@@ -156,13 +186,14 @@ pub async fn create_tag(
     id: Path<String>,
     db: Db,
     current_user: CurrentUser,
-    body: Json<CreateTagRequest>,
-) -> Result<(StatusCode, Json<TagResponse>)> {
+    body: Validated<Json<CreateTagRequest>>,
+) -> Result<etag::Json<TagResponse>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner()).map_err(|_| TagError::NotFound.into_api_error())?;
 
     let project = Project::find()
         .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -172,7 +203,7 @@ pub async fn create_tag(
         return Err(TagError::Forbidden.into_api_error());
     }
 
-    let req = body.into_inner();
+    let req = body.into_inner().into_inner();
 
     let existing = Tag::find()
         .filter(Column::ProjectId.eq(project.id))
@@ -195,7 +226,10 @@ pub async fn create_tag(
 
     let tag = new_tag.insert(db.conn()).await.map_err(DbError)?;
 
-    Ok((StatusCode::CREATED, Json(to_response(tag, &project.pid))))
+    Ok(etag::Json::created(
+        tag.updated_at,
+        to_response(tag, &project.pid),
+    ))
 }
 
 #[put("/api/v1/tags/:id")]
@@ -204,8 +238,9 @@ pub async fn update_tag(
     id: Path<String>,
     db: Db,
     current_user: CurrentUser,
-    body: Json<UpdateTagRequest>,
-) -> Result<Json<TagResponse>> {
+    headers: Headers,
+    body: Validated<Json<UpdateTagRequest>>,
+) -> Result<etag::Json<TagResponse>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner()).map_err(|_| TagError::NotFound.into_api_error())?;
 
@@ -217,6 +252,7 @@ pub async fn update_tag(
         .ok_or_else(|| TagError::NotFound.into_api_error())?;
 
     let project = Project::find_by_id(tag.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -226,7 +262,8 @@ pub async fn update_tag(
         return Err(TagError::Forbidden.into_api_error());
     }
 
-    let req = body.into_inner();
+    let guard = etag::if_match(&headers.0, tag.updated_at)?;
+    let req = body.into_inner().into_inner();
 
     if let Some(ref name) = req.name {
         let name_taken = Tag::find()
@@ -251,14 +288,22 @@ pub async fn update_tag(
         active.color = Set(Some(color));
     }
 
-    let updated = active.update(db.conn()).await.map_err(DbError)?;
+    let updated = etag::update(db.conn(), active, Column::UpdatedAt, guard).await?;
 
-    Ok(Json(to_response(updated, &project.pid)))
+    Ok(etag::Json::new(
+        updated.updated_at,
+        to_response(updated, &project.pid),
+    ))
 }
 
 #[delete("/api/v1/tags/:id")]
 #[errors(TagError)]
-pub async fn delete_tag(id: Path<String>, db: Db, current_user: CurrentUser) -> Result<StatusCode> {
+pub async fn delete_tag(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    headers: Headers,
+) -> Result<StatusCode> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner()).map_err(|_| TagError::NotFound.into_api_error())?;
 
@@ -270,6 +315,7 @@ pub async fn delete_tag(id: Path<String>, db: Db, current_user: CurrentUser) ->
         .ok_or_else(|| TagError::NotFound.into_api_error())?;
 
     let project = Project::find_by_id(tag.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -279,10 +325,17 @@ pub async fn delete_tag(id: Path<String>, db: Db, current_user: CurrentUser) ->
         return Err(TagError::Forbidden.into_api_error());
     }
 
-    Tag::delete_by_id(tag.id)
-        .exec(db.conn())
-        .await
-        .map_err(DbError)?;
+    let guard = etag::if_match(&headers.0, tag.updated_at)?;
+
+    let mut delete = Tag::delete_many().filter(Column::Id.eq(tag.id));
+    if let Some(updated_at) = guard {
+        delete = delete.filter(Column::UpdatedAt.eq(updated_at));
+    }
+    let result = delete.exec(db.conn()).await.map_err(DbError)?;
+
+    if result.rows_affected == 0 && guard.is_some() {
+        return Err(etag::PreconditionError::Failed.into_api_error());
+    }
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -300,12 +353,14 @@ pub async fn set_testimonial_tags(
 
     let testimonial = Testimonial::find()
         .filter(TestimonialColumn::Pid.eq(pid))
+        .filter(TestimonialColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
         .ok_or_else(|| TagError::NotFound.into_api_error())?;
 
     let project = Project::find_by_id(testimonial.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use rapina::database::{Db, DbError};
+use rapina::prelude::*;
+use rapina::sea_orm::sea_query::Expr;
+use rapina::sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect, Set};
+use uuid::Uuid;
+
+use crate::db::entities::project::{Column as ProjectColumn, Entity as Project};
+use crate::db::entities::tag::{Column as TagColumn, Entity as Tag};
+use crate::db::entities::testimonial::{Column, Entity as Testimonial, Model};
+use crate::db::entities::testimonial_tag::{
+    ActiveModel as TestimonialTagActiveModel, Column as TestimonialTagColumn,
+    Entity as TestimonialTag,
+};
+
+use super::dto::{BulkAction, ModerationStatus, TestimonialFilters};
+use super::error::TestimonialError;
+use super::{filters, moderation};
+
+/// Upper bound on the number of testimonials a single bulk request touches.
+pub const MAX_ITEMS: usize = 1000;
+
+/// A bulk action with its ids resolved and validated against the project.
+pub enum Prepared {
+    Moderate(ModerationStatus, Option<String>),
+    Feature(bool),
+    AddTags(Vec<i32>),
+    RemoveTags(Vec<i32>),
+    Delete,
+    Move(i32),
+}
+
+fn invalid(msg: &str) -> Error {
+    TestimonialError::InvalidFilter(msg.to_string()).into_api_error()
+}
+
+async fn resolve_tags(db: &Db, project_id: i32, tag_ids: &[String]) -> Result<Vec<i32>> {
+    let mut pids = tag_ids
+        .iter()
+        .map(|id| Uuid::parse_str(id))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| invalid("tag_ids must be tag ids"))?;
+    pids.sort();
+    pids.dedup();
+
+    if pids.is_empty() {
+        return Err(invalid("tag_ids must not be empty"));
+    }
+
+    let tags = Tag::find()
+        .filter(TagColumn::ProjectId.eq(project_id))
+        .filter(TagColumn::Pid.is_in(pids.clone()))
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+
+    if tags.len() != pids.len() {
+        return Err(invalid("tag_ids must be tags of this project"));
+    }
+
+    Ok(tags.into_iter().map(|t| t.id).collect())
+}
+
+pub async fn prepare(
+    db: &Db,
+    project_id: i32,
+    user_id: i32,
+    action: BulkAction,
+) -> Result<Prepared> {
+    Ok(match action {
+        BulkAction::Approve => Prepared::Moderate(ModerationStatus::Approved, None),
+        BulkAction::Reject { reason } => Prepared::Moderate(ModerationStatus::Rejected, reason),
+        BulkAction::Feature => Prepared::Feature(true),
+        BulkAction::Unfeature => Prepared::Feature(false),
+        BulkAction::AddTags { tag_ids } => {
+            Prepared::AddTags(resolve_tags(db, project_id, &tag_ids).await?)
+        }
+        BulkAction::RemoveTags { tag_ids } => {
+            Prepared::RemoveTags(resolve_tags(db, project_id, &tag_ids).await?)
+        }
+        BulkAction::Delete => Prepared::Delete,
+        BulkAction::Move { project_id: target } => {
+            let pid = Uuid::parse_str(&target)
+                .map_err(|_| TestimonialError::NotFound.into_api_error())?;
+
+            let target = Project::find()
+                .filter(ProjectColumn::Pid.eq(pid))
+                .filter(ProjectColumn::DeletedAt.is_null())
+                .one(db.conn())
+                .await
+                .map_err(DbError)?
+                .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
+
+            if target.user_id != user_id {
+                return Err(TestimonialError::Forbidden.into_api_error());
+            }
+
+            Prepared::Move(target.id)
+        }
+    })
+}
+
+/// The testimonials a bulk request applies to, each paired with the id it was
+/// requested as. Ids that cannot be acted on carry the reason instead.
+pub async fn targets(
+    db: &Db,
+    project_id: i32,
+    user_id: i32,
+    ids: Option<Vec<String>>,
+    filter: Option<TestimonialFilters>,
+) -> Result<Vec<(String, std::result::Result<Model, String>)>> {
+    let targets = match (ids, filter) {
+        (Some(mut ids), None) => {
+            let mut seen = HashSet::new();
+            ids.retain(|id| seen.insert(id.clone()));
+            if ids.len() > MAX_ITEMS {
+                return Err(invalid("too many testimonials in one bulk request"));
+            }
+
+            let pids: Vec<Uuid> = ids.iter().filter_map(|id| id.parse().ok()).collect();
+            let found: HashMap<Uuid, Model> = Testimonial::find()
+                .filter(Column::Pid.is_in(pids))
+                .filter(Column::DeletedAt.is_null())
+                .all(db.conn())
+                .await
+                .map_err(DbError)?
+                .into_iter()
+                .map(|t| (t.pid, t))
+                .collect();
+
+            let project_ids: Vec<i32> = found.values().map(|t| t.project_id).collect();
+            let owners: HashMap<i32, i32> = Project::find()
+                .filter(ProjectColumn::Id.is_in(project_ids))
+                .all(db.conn())
+                .await
+                .map_err(DbError)?
+                .into_iter()
+                .map(|p| (p.id, p.user_id))
+                .collect();
+
+            ids.into_iter()
+                .map(|id| {
+                    let t = id.parse::<Uuid>().ok().and_then(|pid| found.get(&pid));
+                    let result = match t {
+                        None => Err("testimonial not found".to_string()),
+                        Some(t) if owners.get(&t.project_id) != Some(&user_id) => {
+                            Err("you do not own this project".to_string())
+                        }
+                        Some(t) if t.project_id != project_id => {
+                            Err("testimonial is not in this project".to_string())
+                        }
+                        Some(t) => Ok(t.clone()),
+                    };
+                    (id, result)
+                })
+                .collect()
+        }
+        (None, Some(filter)) => {
+            // One row past the limit is enough to tell the filter matches too many.
+            let matched = filters::filtered(db, project_id, &filter)
+                .await?
+                .limit(MAX_ITEMS as u64 + 1)
+                .all(db.conn())
+                .await
+                .map_err(DbError)?;
+            if matched.len() > MAX_ITEMS {
+                return Err(invalid("too many testimonials in one bulk request"));
+            }
+
+            matched
+                .into_iter()
+                .map(|t| (t.pid.to_string(), Ok(t)))
+                .collect()
+        }
+        _ => return Err(invalid("provide either ids or filter")),
+    };
+
+    Ok(targets)
+}
+
+/// Whether `action` can set off webhook events, so the testimonials it changed
+/// have to be compared before and after.
+pub fn has_events(action: &Prepared) -> bool {
+    matches!(
+        action,
+        Prepared::Moderate(ModerationStatus::Approved, _) | Prepared::Feature(true)
+    )
+}
+
+pub async fn apply(
+    conn: &impl ConnectionTrait,
+    t: Model,
+    action: &Prepared,
+    user_id: i32,
+) -> Result<()> {
+    let now = Utc::now().fixed_offset();
+
+    match action {
+        Prepared::Moderate(to, reason) => {
+            moderation::transition(conn, t, *to, user_id, reason.clone()).await?;
+        }
+        Prepared::Feature(featured) => {
+            Testimonial::update_many()
+                .col_expr(Column::IsFeatured, Expr::value(*featured))
+                .col_expr(Column::UpdatedAt, Expr::value(now))
+                .filter(Column::Id.eq(t.id))
+                .exec(conn)
+                .await
+                .map_err(DbError)?;
+        }
+        Prepared::AddTags(tag_ids) => {
+            TestimonialTag::delete_many()
+                .filter(TestimonialTagColumn::TestimonialId.eq(t.id))
+                .filter(TestimonialTagColumn::TagId.is_in(tag_ids.clone()))
+                .exec(conn)
+                .await
+                .map_err(DbError)?;
+            TestimonialTag::insert_many(tag_ids.iter().map(|tag_id| TestimonialTagActiveModel {
+                testimonial_id: Set(t.id),
+                tag_id: Set(*tag_id),
+            }))
+            .exec(conn)
+            .await
+            .map_err(DbError)?;
+        }
+        Prepared::RemoveTags(tag_ids) => {
+            TestimonialTag::delete_many()
+                .filter(TestimonialTagColumn::TestimonialId.eq(t.id))
+                .filter(TestimonialTagColumn::TagId.is_in(tag_ids.clone()))
+                .exec(conn)
+                .await
+                .map_err(DbError)?;
+        }
+        Prepared::Delete => {
+            Testimonial::update_many()
+                .col_expr(Column::DeletedAt, Expr::value(now))
+                .col_expr(Column::UpdatedAt, Expr::value(now))
+                .filter(Column::Id.eq(t.id))
+                .exec(conn)
+                .await
+                .map_err(DbError)?;
+        }
+        Prepared::Move(project_id) => {
+            // Tags are scoped to a project, so they stay behind.
+            TestimonialTag::delete_many()
+                .filter(TestimonialTagColumn::TestimonialId.eq(t.id))
+                .exec(conn)
+                .await
+                .map_err(DbError)?;
+            Testimonial::update_many()
+                .col_expr(Column::ProjectId, Expr::value(*project_id))
+                .col_expr(Column::UpdatedAt, Expr::value(now))
+                .filter(Column::Id.eq(t.id))
+                .exec(conn)
+                .await
+                .map_err(DbError)?;
+        }
+    }
+
+    Ok(())
+}
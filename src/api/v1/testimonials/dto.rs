@@ -1,60 +1,164 @@
 use rapina::schemars::{self, JsonSchema};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
+use crate::api::v1::merge_patch::Patch;
+use crate::api::v1::pagination::PageQuery;
 use crate::api::v1::tags::dto::TagResponse;
+use crate::api::v1::validation::{JsonNames, LANGUAGE, MEDIA_URL, TESTIMONIAL_TYPE};
+use crate::images::ImageVariant;
+use crate::json_names;
 
-#[derive(Deserialize, JsonSchema)]
+#[derive(Deserialize, JsonSchema, Validate)]
+#[validate(context = JsonNames)]
 pub struct CreateTestimonialRequest {
+    #[validate(length(min = 1, max = 255))]
     pub author_name: String,
     #[serde(rename = "type")]
+    #[validate(regex(path = *TESTIMONIAL_TYPE))]
     pub testimonial_type: Option<String>,
     pub content: Option<String>,
+    #[validate(range(min = 1, max = 5))]
     pub rating: Option<i16>,
+    #[validate(email, length(max = 255))]
     pub author_email: Option<String>,
+    #[validate(length(max = 255))]
     pub author_title: Option<String>,
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
     pub author_avatar_url: Option<String>,
+    #[validate(length(max = 255))]
     pub author_company: Option<String>,
+    #[validate(url)]
     pub author_url: Option<String>,
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
     pub video_url: Option<String>,
+    #[validate(url)]
     pub video_thumbnail_url: Option<String>,
+    #[validate(range(min = 0))]
     pub video_duration_seconds: Option<i32>,
     pub transcription: Option<String>,
+    /// `form` for submissions through a collection form.
+    #[validate(length(max = 50))]
     pub source: Option<String>,
+    #[validate(length(max = 50))]
     pub source_platform: Option<String>,
+    #[validate(url)]
     pub source_url: Option<String>,
+    /// For form submissions, the id of the form.
+    #[validate(length(max = 255))]
     pub source_id: Option<String>,
+    #[validate(length(max = 20))]
     pub sentiment: Option<String>,
+    #[validate(range(min = -1.0, max = 1.0))]
     pub sentiment_score: Option<f32>,
+    #[validate(length(max = 10), regex(path = *LANGUAGE))]
     pub language: Option<String>,
 }
 
-#[derive(Deserialize, JsonSchema)]
+json_names!(CreateTestimonialRequest { testimonial_type => "type" });
+
+#[derive(Deserialize, JsonSchema, Validate)]
+#[validate(context = JsonNames)]
 pub struct UpdateTestimonialRequest {
     #[serde(rename = "type")]
+    #[validate(regex(path = *TESTIMONIAL_TYPE))]
     pub testimonial_type: Option<String>,
     pub content: Option<String>,
+    #[validate(range(min = 1, max = 5))]
     pub rating: Option<i16>,
+    #[validate(length(min = 1, max = 255))]
     pub author_name: Option<String>,
+    #[validate(email, length(max = 255))]
     pub author_email: Option<String>,
+    #[validate(length(max = 255))]
     pub author_title: Option<String>,
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
     pub author_avatar_url: Option<String>,
+    #[validate(length(max = 255))]
     pub author_company: Option<String>,
+    #[validate(url)]
     pub author_url: Option<String>,
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
     pub video_url: Option<String>,
+    #[validate(url)]
     pub video_thumbnail_url: Option<String>,
+    #[validate(range(min = 0))]
     pub video_duration_seconds: Option<i32>,
     pub transcription: Option<String>,
+    #[validate(length(max = 50))]
     pub source: Option<String>,
+    #[validate(length(max = 50))]
     pub source_platform: Option<String>,
+    #[validate(url)]
     pub source_url: Option<String>,
+    #[validate(length(max = 255))]
     pub source_id: Option<String>,
+    #[validate(length(max = 20))]
     pub sentiment: Option<String>,
+    #[validate(range(min = -1.0, max = 1.0))]
     pub sentiment_score: Option<f32>,
+    #[validate(length(max = 10), regex(path = *LANGUAGE))]
     pub language: Option<String>,
     pub is_approved: Option<bool>,
     pub is_featured: Option<bool>,
 }
 
+json_names!(UpdateTestimonialRequest { testimonial_type => "type" });
+
+/// A JSON Merge Patch: `null` clears a nullable field such as `author_email`.
+#[derive(Default, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+#[validate(context = JsonNames)]
+pub struct PatchTestimonialRequest {
+    #[serde(rename = "type")]
+    #[schemars(with = "String")]
+    #[validate(regex(path = *TESTIMONIAL_TYPE))]
+    pub testimonial_type: Patch<String>,
+    pub content: Patch<String>,
+    #[validate(range(min = 1, max = 5))]
+    pub rating: Patch<i16>,
+    #[schemars(with = "String")]
+    #[validate(length(min = 1, max = 255))]
+    pub author_name: Patch<String>,
+    #[validate(email, length(max = 255))]
+    pub author_email: Patch<String>,
+    #[validate(length(max = 255))]
+    pub author_title: Patch<String>,
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
+    pub author_avatar_url: Patch<String>,
+    #[validate(length(max = 255))]
+    pub author_company: Patch<String>,
+    #[validate(url)]
+    pub author_url: Patch<String>,
+    #[validate(regex(path = *MEDIA_URL, code = "url"))]
+    pub video_url: Patch<String>,
+    #[validate(url)]
+    pub video_thumbnail_url: Patch<String>,
+    #[validate(range(min = 0))]
+    pub video_duration_seconds: Patch<i32>,
+    pub transcription: Patch<String>,
+    #[validate(length(max = 50))]
+    pub source: Patch<String>,
+    #[validate(length(max = 50))]
+    pub source_platform: Patch<String>,
+    #[validate(url)]
+    pub source_url: Patch<String>,
+    #[validate(length(max = 255))]
+    pub source_id: Patch<String>,
+    #[validate(length(max = 20))]
+    pub sentiment: Patch<String>,
+    #[validate(range(min = -1.0, max = 1.0))]
+    pub sentiment_score: Patch<f32>,
+    #[validate(length(max = 10), regex(path = *LANGUAGE))]
+    pub language: Patch<String>,
+    #[schemars(with = "bool")]
+    pub is_approved: Patch<bool>,
+    #[schemars(with = "bool")]
+    pub is_featured: Patch<bool>,
+}
+
+json_names!(PatchTestimonialRequest { testimonial_type => "type" });
+
 #[derive(Serialize, JsonSchema)]
 pub struct TestimonialResponse {
     pub id: String,
@@ -62,17 +166,28 @@ pub struct TestimonialResponse {
     #[serde(rename = "type")]
     pub testimonial_type: String,
     pub content: Option<String>,
+    /// Content as originally submitted.
+    pub original_content: Option<String>,
+    /// Whether `content` was edited after submission.
+    pub content_edited: bool,
     pub rating: Option<i16>,
     pub author_name: String,
     pub author_email: Option<String>,
     pub author_title: Option<String>,
     pub author_avatar_url: Option<String>,
+    /// Resized copies of the avatar, smallest first, when it is an uploaded
+    /// image.
+    pub author_avatar_variants: Vec<ImageVariant>,
     pub author_company: Option<String>,
     pub author_url: Option<String>,
     pub video_url: Option<String>,
     pub video_thumbnail_url: Option<String>,
     pub video_duration_seconds: Option<i32>,
     pub transcription: Option<String>,
+    /// `processing`, `ready` or `failed` once a video was set.
+    pub video_status: Option<String>,
+    /// Why video processing failed.
+    pub video_error: Option<String>,
     pub source: Option<String>,
     pub source_platform: Option<String>,
     pub source_url: Option<String>,
@@ -80,15 +195,415 @@ pub struct TestimonialResponse {
     pub sentiment: Option<String>,
     pub sentiment_score: Option<f32>,
     pub language: Option<String>,
+    /// Mirrors `status == "approved"`; kept for older clients.
     pub is_approved: bool,
     pub is_featured: bool,
+    pub status: ModerationStatus,
+    pub rejection_reason: Option<String>,
+    /// Id of the user who last changed `status`.
+    pub moderated_by: Option<String>,
+    pub moderated_at: Option<String>,
     pub tags: Vec<TagResponse>,
     pub created_at: String,
     pub updated_at: String,
+    /// When the testimonial was moved to the trash.
+    pub deleted_at: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationStatus {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+    Archived,
+}
+
+impl ModerationStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModerationStatus::Pending => "pending",
+            ModerationStatus::Approved => "approved",
+            ModerationStatus::Rejected => "rejected",
+            ModerationStatus::Archived => "archived",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(ModerationStatus::Pending),
+            "approved" => Some(ModerationStatus::Approved),
+            "rejected" => Some(ModerationStatus::Rejected),
+            "archived" => Some(ModerationStatus::Archived),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ModerationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RejectTestimonialRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    CreatedAt,
+    Rating,
+    SentimentScore,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
+
+#[derive(Clone, Default, Deserialize, JsonSchema)]
+pub struct TestimonialFilters {
+    pub status: Option<ModerationStatus>,
+    pub is_approved: Option<bool>,
+    pub is_featured: Option<bool>,
+    #[serde(rename = "type")]
+    pub testimonial_type: Option<String>,
+    pub min_rating: Option<i16>,
+    pub max_rating: Option<i16>,
+    /// Tag ids to match; see `tag_match`.
+    #[serde(default)]
+    pub tag_ids: Vec<String>,
+    /// `any` (default) keeps testimonials with at least one of `tag_ids`,
+    /// `all` only those carrying every tag.
+    pub tag_match: Option<TagMatch>,
+    pub source: Option<String>,
+    pub source_platform: Option<String>,
+    /// Comma-separated language tags, such as `en,pt`. A bare language also
+    /// matches its regional variants.
+    pub language: Option<String>,
+    pub sentiment: Option<String>,
+    /// RFC 3339 timestamp, inclusive.
+    pub created_after: Option<String>,
+    /// RFC 3339 timestamp, exclusive.
+    pub created_before: Option<String>,
+    pub has_video: Option<bool>,
+}
+
+fn split_ids(ids: Option<&str>) -> Vec<String> {
+    ids.map(|ids| {
+        ids.split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(String::from)
+            .collect()
+    })
+    .unwrap_or_default()
 }
 
 #[derive(Deserialize, JsonSchema)]
 pub struct ListTestimonialsQuery {
+    pub status: Option<ModerationStatus>,
+    pub is_approved: Option<bool>,
+    pub is_featured: Option<bool>,
+    #[serde(rename = "type")]
+    pub testimonial_type: Option<String>,
+    pub min_rating: Option<i16>,
+    pub max_rating: Option<i16>,
+    /// Comma-separated tag ids.
+    pub tag_ids: Option<String>,
+    pub tag_match: Option<TagMatch>,
+    pub source: Option<String>,
+    pub source_platform: Option<String>,
+    /// Comma-separated language tags, such as `en,pt`. A bare language also
+    /// matches its regional variants.
+    pub language: Option<String>,
+    pub sentiment: Option<String>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub has_video: Option<bool>,
+    pub sort: Option<SortField>,
+    pub order: Option<SortOrder>,
+    pub limit: Option<u64>,
+    pub cursor: Option<String>,
+    pub include_total: Option<bool>,
+}
+
+impl ListTestimonialsQuery {
+    pub fn filters(&self) -> TestimonialFilters {
+        TestimonialFilters {
+            status: self.status,
+            is_approved: self.is_approved,
+            is_featured: self.is_featured,
+            testimonial_type: self.testimonial_type.clone(),
+            min_rating: self.min_rating,
+            max_rating: self.max_rating,
+            tag_ids: split_ids(self.tag_ids.as_deref()),
+            tag_match: self.tag_match,
+            source: self.source.clone(),
+            source_platform: self.source_platform.clone(),
+            language: self.language.clone(),
+            sentiment: self.sentiment.clone(),
+            created_after: self.created_after.clone(),
+            created_before: self.created_before.clone(),
+            has_video: self.has_video,
+        }
+    }
+
+    pub fn page(&self) -> PageQuery {
+        PageQuery {
+            limit: self.limit,
+            cursor: self.cursor.clone(),
+            include_total: self.include_total,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    #[default]
+    Json,
+    Ndjson,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ExportTestimonialsQuery {
+    /// `csv`, `json` (default) or `ndjson`.
+    pub format: Option<ExportFormat>,
+    pub status: Option<ModerationStatus>,
     pub is_approved: Option<bool>,
     pub is_featured: Option<bool>,
+    #[serde(rename = "type")]
+    pub testimonial_type: Option<String>,
+    pub min_rating: Option<i16>,
+    pub max_rating: Option<i16>,
+    /// Comma-separated tag ids.
+    pub tag_ids: Option<String>,
+    pub tag_match: Option<TagMatch>,
+    pub source: Option<String>,
+    pub source_platform: Option<String>,
+    pub language: Option<String>,
+    pub sentiment: Option<String>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub has_video: Option<bool>,
+    pub sort: Option<SortField>,
+    pub order: Option<SortOrder>,
+    /// The `X-Next-Cursor` of the previous page.
+    pub cursor: Option<String>,
+}
+
+impl ExportTestimonialsQuery {
+    pub fn filters(&self) -> TestimonialFilters {
+        TestimonialFilters {
+            status: self.status,
+            is_approved: self.is_approved,
+            is_featured: self.is_featured,
+            testimonial_type: self.testimonial_type.clone(),
+            min_rating: self.min_rating,
+            max_rating: self.max_rating,
+            tag_ids: split_ids(self.tag_ids.as_deref()),
+            tag_match: self.tag_match,
+            source: self.source.clone(),
+            source_platform: self.source_platform.clone(),
+            language: self.language.clone(),
+            sentiment: self.sentiment.clone(),
+            created_after: self.created_after.clone(),
+            created_before: self.created_before.clone(),
+            has_video: self.has_video,
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SearchTestimonialsQuery {
+    /// Search terms in web search syntax: `"quoted phrases"`, `or`, `-excluded`.
+    pub q: String,
+    pub status: Option<ModerationStatus>,
+    pub is_approved: Option<bool>,
+    pub is_featured: Option<bool>,
+    #[serde(rename = "type")]
+    pub testimonial_type: Option<String>,
+    pub min_rating: Option<i16>,
+    pub max_rating: Option<i16>,
+    /// Comma-separated tag ids.
+    pub tag_ids: Option<String>,
+    pub tag_match: Option<TagMatch>,
+    pub source: Option<String>,
+    pub source_platform: Option<String>,
+    pub language: Option<String>,
+    pub sentiment: Option<String>,
+    pub created_after: Option<String>,
+    pub created_before: Option<String>,
+    pub has_video: Option<bool>,
+    pub limit: Option<u64>,
+    pub cursor: Option<String>,
+    pub include_total: Option<bool>,
+}
+
+impl SearchTestimonialsQuery {
+    pub fn filters(&self) -> TestimonialFilters {
+        TestimonialFilters {
+            status: self.status,
+            is_approved: self.is_approved,
+            is_featured: self.is_featured,
+            testimonial_type: self.testimonial_type.clone(),
+            min_rating: self.min_rating,
+            max_rating: self.max_rating,
+            tag_ids: split_ids(self.tag_ids.as_deref()),
+            tag_match: self.tag_match,
+            source: self.source.clone(),
+            source_platform: self.source_platform.clone(),
+            language: self.language.clone(),
+            sentiment: self.sentiment.clone(),
+            created_after: self.created_after.clone(),
+            created_before: self.created_before.clone(),
+            has_video: self.has_video,
+        }
+    }
+
+    pub fn page(&self) -> PageQuery {
+        PageQuery {
+            limit: self.limit,
+            cursor: self.cursor.clone(),
+            include_total: self.include_total,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SearchHitResponse {
+    pub testimonial: TestimonialResponse,
+    pub rank: f32,
+    /// Excerpt of the content (or transcription) with matches wrapped in
+    /// `<mark>`. The surrounding text is HTML-escaped.
+    pub snippet: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BulkAction {
+    Approve,
+    Reject {
+        reason: Option<String>,
+    },
+    Feature,
+    Unfeature,
+    AddTags {
+        tag_ids: Vec<String>,
+    },
+    RemoveTags {
+        tag_ids: Vec<String>,
+    },
+    /// Moves to the trash, like deleting a single testimonial.
+    Delete,
+    /// Moves testimonials to another project owned by the same user. Tags do
+    /// not carry over since they belong to the source project.
+    Move {
+        project_id: String,
+    },
+}
+
+/// Targets either an explicit list of testimonial `ids` or every testimonial
+/// of the project matching `filter`, never both.
+#[derive(Deserialize, JsonSchema)]
+pub struct BulkTestimonialsRequest {
+    pub ids: Option<Vec<String>>,
+    pub filter: Option<TestimonialFilters>,
+    #[serde(flatten)]
+    pub action: BulkAction,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct BulkItemResult {
+    pub id: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct BulkTestimonialsResponse {
+    pub results: Vec<BulkItemResult>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct RevisionResponse {
+    pub id: String,
+    /// `update`, `revert` or `merge`.
+    pub action: String,
+    /// Changed fields, each as `{ "before": .., "after": .. }`.
+    pub changes: serde_json::Value,
+    /// Id of the user who made the change.
+    pub user_id: Option<String>,
+    /// For reverts, the revision that was rolled back to.
+    pub reverted_revision_id: Option<String>,
+    /// For merges, the testimonials merged into this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_testimonial_ids: Option<Vec<String>>,
+    pub created_at: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RevertTestimonialRequest {
+    /// Revision to undo. The testimonial is restored to how it was right
+    /// before this revision, undoing it and every later one; reverting the
+    /// first revision restores the original submission.
+    pub revision_id: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AnalyzeSentimentResponse {
+    /// Testimonials analyzed.
+    pub analyzed: u64,
+    /// Testimonials whose sentiment changed.
+    pub updated: u64,
+    /// Testimonials that could not be analyzed. Their sentiment is left as is.
+    pub failed: u64,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DuplicatesQuery {
+    /// Most groups to return. Defaults to 50, at most 100.
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DuplicateGroupResponse {
+    /// The likely duplicates, newest first.
+    pub testimonials: Vec<TestimonialResponse>,
+    /// Highest content similarity between two of them, from 0 to 1.
+    pub similarity: f32,
+    /// Why they were matched: `same_email`, `same_name`, `similar_content`.
+    pub reasons: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DuplicatesResponse {
+    pub data: Vec<DuplicateGroupResponse>,
+    /// Testimonials compared. Only the newest ones of large projects are.
+    pub scanned: u64,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct MergeTestimonialsRequest {
+    /// Testimonials to merge into this one. Fields this one lacks are filled
+    /// from them, their tags are added to it, and they are moved to the trash.
+    pub duplicate_ids: Vec<String>,
 }
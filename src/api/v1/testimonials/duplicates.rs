@@ -0,0 +1,198 @@
+//! Detection of likely duplicate testimonials.
+//!
+//! Imports and resubmitted forms leave near-identical testimonials behind.
+//! Two testimonials are likely duplicates when they have the same author and
+//! say much the same thing, or say nearly exactly the same thing whoever wrote
+//! them. Authors are matched on their normalized email or name, and content on
+//! the Jaccard similarity of its character trigrams. Matching pairs are
+//! grouped transitively, so a testimonial and two re-imports of it form one
+//! group.
+//!
+//! Only pairs that share an author or a run of words are compared, which
+//! keeps a scan of a large project close to linear.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::db::entities::testimonial::Model;
+
+pub const REASON_SAME_EMAIL: &str = "same_email";
+pub const REASON_SAME_NAME: &str = "same_name";
+pub const REASON_SIMILAR_CONTENT: &str = "similar_content";
+
+/// Content similarity above which testimonials by the same author are
+/// duplicates.
+const SAME_AUTHOR_SIMILARITY: f32 = 0.6;
+
+/// Content similarity above which testimonials are duplicates whoever wrote
+/// them.
+const SIMILAR_CONTENT: f32 = 0.85;
+
+/// Words per shingle used to find testimonials worth comparing.
+const SHINGLE_WORDS: usize = 3;
+
+/// Keys shared by more testimonials than this are too common to point at
+/// duplicates, and are not used to find candidates.
+const MAX_POSTINGS: usize = 50;
+
+/// A set of testimonials that are likely duplicates of each other.
+pub struct Group {
+    /// Indexes into the scanned testimonials, in scan order.
+    pub members: Vec<usize>,
+    /// Highest content similarity between two members, from 0 to 1.
+    pub similarity: f32,
+    pub reasons: BTreeSet<&'static str>,
+}
+
+/// Lowercased `email`, without a `+tag` on the local part.
+pub fn normalize_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    let local = local.split('+').next().unwrap_or_default();
+    if local.is_empty() || domain.is_empty() {
+        return None;
+    }
+    Some(format!("{local}@{domain}"))
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Lowercased words of `name`, ignoring punctuation and spacing.
+pub fn normalize_name(name: &str) -> Option<String> {
+    let words = words(name);
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+fn trigrams(words: &[String]) -> HashSet<[char; 3]> {
+    let padded: Vec<char> = format!(" {} ", words.join(" ")).chars().collect();
+    padded.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+/// Jaccard similarity of two trigram sets; 0 when either is empty.
+fn similarity(a: &HashSet<[char; 3]>, b: &HashSet<[char; 3]>) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.intersection(b).count();
+    shared as f32 / (a.len() + b.len() - shared) as f32
+}
+
+struct Features {
+    email: Option<String>,
+    name: Option<String>,
+    trigrams: HashSet<[char; 3]>,
+    shingles: HashSet<String>,
+}
+
+fn features(t: &Model) -> Features {
+    let text = t
+        .content
+        .as_deref()
+        .or(t.transcription.as_deref())
+        .unwrap_or_default();
+    let words = words(text);
+
+    Features {
+        email: t.author_email.as_deref().and_then(normalize_email),
+        name: normalize_name(&t.author_name),
+        trigrams: if words.is_empty() {
+            HashSet::new()
+        } else {
+            trigrams(&words)
+        },
+        shingles: words.windows(SHINGLE_WORDS).map(|w| w.join(" ")).collect(),
+    }
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Groups of likely duplicates among `testimonials`, most similar first.
+pub fn find(testimonials: &[Model]) -> Vec<Group> {
+    let features: Vec<Features> = testimonials.iter().map(features).collect();
+
+    let mut postings: HashMap<(u8, &str), Vec<usize>> = HashMap::new();
+    for (i, f) in features.iter().enumerate() {
+        if let Some(email) = &f.email {
+            postings.entry((0, email)).or_default().push(i);
+        }
+        if let Some(name) = &f.name {
+            postings.entry((1, name)).or_default().push(i);
+        }
+        for shingle in &f.shingles {
+            postings.entry((2, shingle)).or_default().push(i);
+        }
+    }
+
+    let mut candidates = HashSet::new();
+    for ids in postings.values().filter(|ids| ids.len() <= MAX_POSTINGS) {
+        for (n, &a) in ids.iter().enumerate() {
+            for &b in &ids[n + 1..] {
+                candidates.insert((a, b));
+            }
+        }
+    }
+
+    let mut parents: Vec<usize> = (0..testimonials.len()).collect();
+    let mut matches = Vec::new();
+    for (a, b) in candidates {
+        let (fa, fb) = (&features[a], &features[b]);
+        let score = similarity(&fa.trigrams, &fb.trigrams);
+
+        let mut reasons = Vec::new();
+        if fa.email.is_some() && fa.email == fb.email {
+            reasons.push(REASON_SAME_EMAIL);
+        }
+        if fa.name.is_some() && fa.name == fb.name {
+            reasons.push(REASON_SAME_NAME);
+        }
+        let duplicate = if reasons.is_empty() {
+            score >= SIMILAR_CONTENT
+        } else {
+            score >= SAME_AUTHOR_SIMILARITY
+        };
+        if !duplicate {
+            continue;
+        }
+        reasons.push(REASON_SIMILAR_CONTENT);
+
+        let (ra, rb) = (find_root(&mut parents, a), find_root(&mut parents, b));
+        parents[ra.max(rb)] = ra.min(rb);
+        matches.push((a, score, reasons));
+    }
+
+    let mut groups: HashMap<usize, Group> = HashMap::new();
+    for (i, score, reasons) in matches {
+        let root = find_root(&mut parents, i);
+        let group = groups.entry(root).or_insert_with(|| Group {
+            members: Vec::new(),
+            similarity: 0.0,
+            reasons: BTreeSet::new(),
+        });
+        group.similarity = group.similarity.max(score);
+        group.reasons.extend(reasons);
+    }
+    for i in 0..testimonials.len() {
+        let root = find_root(&mut parents, i);
+        if let Some(group) = groups.get_mut(&root) {
+            group.members.push(i);
+        }
+    }
+
+    let mut groups: Vec<Group> = groups.into_values().collect();
+    groups.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then(a.members[0].cmp(&b.members[0]))
+    });
+    groups
+}
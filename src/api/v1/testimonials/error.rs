@@ -1,10 +1,22 @@
 use rapina::database::DbError;
 use rapina::prelude::*;
 
+use super::dto::{BulkItemResult, ModerationStatus};
+
 pub enum TestimonialError {
     DbError(DbError),
     NotFound,
+    RevisionNotFound,
     Forbidden,
+    InvalidFilter(String),
+    InvalidPatch(String),
+    NoVideo,
+    DuplicateSource,
+    InvalidTransition {
+        from: ModerationStatus,
+        to: ModerationStatus,
+    },
+    BulkFailed(Vec<BulkItemResult>),
 }
 
 impl IntoApiError for TestimonialError {
@@ -12,7 +24,24 @@ impl IntoApiError for TestimonialError {
         match self {
             TestimonialError::DbError(e) => e.into_api_error(),
             TestimonialError::NotFound => Error::not_found("testimonial not found"),
+            TestimonialError::RevisionNotFound => Error::not_found("revision not found"),
             TestimonialError::Forbidden => Error::forbidden("you do not own this project"),
+            TestimonialError::InvalidFilter(msg) => Error::bad_request(msg),
+            TestimonialError::InvalidPatch(msg) => Error::bad_request(msg),
+            TestimonialError::NoVideo => Error::bad_request("testimonial has no video"),
+            TestimonialError::DuplicateSource => Error::conflict(
+                "the project already has a testimonial with this source_platform and source_id",
+            ),
+            TestimonialError::InvalidTransition { from, to } => {
+                Error::conflict(format!("cannot move a {from} testimonial to {to}"))
+            }
+            TestimonialError::BulkFailed(results) => {
+                let failed = results.iter().filter(|r| !r.ok).count();
+                Error::conflict(format!(
+                    "{failed} testimonial(s) could not be updated; no changes were applied"
+                ))
+                .with_details(serde_json::json!({ "results": results }))
+            }
         }
     }
 }
@@ -20,16 +49,36 @@ impl IntoApiError for TestimonialError {
 impl DocumentedError for TestimonialError {
     fn error_variants() -> Vec<ErrorVariant> {
         vec![
+            ErrorVariant {
+                status: 400,
+                code: "BAD_REQUEST",
+                description: "Invalid filter, sort, pagination cursor or patch, or no video to process",
+            },
             ErrorVariant {
                 status: 404,
                 code: "NOT_FOUND",
-                description: "Testimonial not found",
+                description: "Testimonial or revision not found",
             },
             ErrorVariant {
                 status: 403,
                 code: "FORBIDDEN",
                 description: "User does not own this project",
             },
+            ErrorVariant {
+                status: 409,
+                code: "CONFLICT",
+                description: "Invalid moderation transition, a bulk operation was rolled back, or the source is taken",
+            },
+            ErrorVariant {
+                status: 412,
+                code: "PRECONDITION_FAILED",
+                description: "If-Match does not match the current ETag",
+            },
+            ErrorVariant {
+                status: 422,
+                code: "VALIDATION_ERROR",
+                description: "Request body failed validation; details list each invalid field",
+            },
             ErrorVariant {
                 status: 500,
                 code: "INTERNAL_ERROR",
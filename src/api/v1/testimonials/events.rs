@@ -0,0 +1,83 @@
+//! Webhook events, notifications and thank-yous about testimonials.
+
+use rapina::database::DbError;
+use rapina::prelude::*;
+use rapina::sea_orm::DatabaseTransaction;
+use uuid::Uuid;
+
+use crate::db::entities::testimonial::Model;
+use crate::incentives;
+use crate::notifications;
+use crate::webhooks::{self, Event};
+
+use super::dto::ModerationStatus;
+use super::handlers::render;
+use super::moderation;
+
+/// The events of a testimonial going from `before`, or nothing if it is new,
+/// to `after`.
+pub fn changes(before: Option<&Model>, after: &Model) -> Vec<Event> {
+    let mut events = Vec::new();
+
+    let Some(before) = before else {
+        events.push(Event::TestimonialCreated);
+        if after.form_id.is_some() {
+            events.push(Event::FormSubmitted);
+        }
+        return events;
+    };
+
+    if moderation::status_of(after) == ModerationStatus::Approved
+        && moderation::status_of(before) != ModerationStatus::Approved
+    {
+        events.push(Event::TestimonialApproved);
+    }
+    if after.is_featured && !before.is_featured {
+        events.push(Event::TestimonialFeatured);
+    }
+    events
+}
+
+/// Queues webhook deliveries for the events of a change to a testimonial of
+/// the project `project_pid`, the project members' notifications if it is a
+/// form submission, and its author's thank-you if it is an approved one.
+///
+/// `txn` is the transaction that writes the change, so that everything is
+/// queued if and only if it commits, and the payload is rendered from what it
+/// wrote. The payload is only rendered if a webhook is subscribed to one of
+/// the events.
+pub async fn emit(
+    txn: &DatabaseTransaction,
+    before: Option<&Model>,
+    after: &Model,
+    project_pid: &Uuid,
+) -> Result<()> {
+    let changes = changes(before, after);
+    if changes.contains(&Event::FormSubmitted) {
+        notifications::queue_submission(txn, after)
+            .await
+            .map_err(DbError)?;
+    }
+    if changes.contains(&Event::TestimonialApproved) {
+        incentives::queue_thank_you(txn, after)
+            .await
+            .map_err(DbError)?;
+    }
+
+    let events = webhooks::subscribed(txn, after.project_id, &changes)
+        .await
+        .map_err(DbError)?;
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let response = render(txn, after.clone(), project_pid).await?;
+    let data = serde_json::to_value(response)
+        .map_err(|e| Error::internal(format!("could not serialize testimonial: {e}")))?;
+    for event in events {
+        webhooks::dispatch(txn, after.project_id, event, &data)
+            .await
+            .map_err(DbError)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,189 @@
+//! Encoding testimonials for export.
+//!
+//! Testimonials are read in keyset batches of [`BATCH_SIZE`] and encoded one
+//! batch at a time, so only a single batch of rows is held in memory. The
+//! encoded file itself is buffered: Rapina response bodies are `Full<Bytes>`,
+//! so it is sent as one body rather than streamed. To bound that buffer
+//! exports are paged: a response holds at most [`PAGE_SIZE`] testimonials,
+//! and names the cursor of the next page in [`NEXT_CURSOR_HEADER`]. Each page
+//! is a complete file, so CSV pages all start with the header row.
+
+use rapina::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE, HeaderName};
+use rapina::http::{HeaderValue, Response};
+use rapina::hyper::body::Bytes;
+use rapina::prelude::*;
+use rapina::response::BoxBody;
+
+use super::dto::{ExportFormat, TestimonialResponse};
+
+/// Testimonials read from the database per query.
+pub const BATCH_SIZE: u64 = 500;
+
+/// Most testimonials one page of an export holds.
+pub const PAGE_SIZE: u64 = 5_000;
+
+/// Response header with the cursor of the export's next page, if it has one.
+pub const NEXT_CURSOR_HEADER: HeaderName = HeaderName::from_static("x-next-cursor");
+
+/// CSV columns. Field columns use the same names as CSV import mappings;
+/// `tags` holds comma-separated tag names.
+pub const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "type",
+    "status",
+    "content",
+    "original_content",
+    "rating",
+    "author_name",
+    "author_email",
+    "author_title",
+    "author_company",
+    "author_url",
+    "author_avatar_url",
+    "video_url",
+    "video_thumbnail_url",
+    "video_duration_seconds",
+    "transcription",
+    "source",
+    "source_platform",
+    "source_url",
+    "source_id",
+    "sentiment",
+    "sentiment_score",
+    "language",
+    "is_approved",
+    "is_featured",
+    "tags",
+    "created_at",
+    "updated_at",
+];
+
+fn text<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn csv_record(t: &TestimonialResponse) -> Vec<String> {
+    let tags: Vec<&str> = t.tags.iter().map(|tag| tag.name.as_str()).collect();
+
+    vec![
+        t.id.clone(),
+        t.testimonial_type.clone(),
+        t.status.to_string(),
+        text(t.content.as_ref()),
+        text(t.original_content.as_ref()),
+        text(t.rating),
+        t.author_name.clone(),
+        text(t.author_email.as_ref()),
+        text(t.author_title.as_ref()),
+        text(t.author_company.as_ref()),
+        text(t.author_url.as_ref()),
+        text(t.author_avatar_url.as_ref()),
+        text(t.video_url.as_ref()),
+        text(t.video_thumbnail_url.as_ref()),
+        text(t.video_duration_seconds),
+        text(t.transcription.as_ref()),
+        text(t.source.as_ref()),
+        text(t.source_platform.as_ref()),
+        text(t.source_url.as_ref()),
+        text(t.source_id.as_ref()),
+        text(t.sentiment.as_ref()),
+        text(t.sentiment_score),
+        text(t.language.as_ref()),
+        t.is_approved.to_string(),
+        t.is_featured.to_string(),
+        tags.join(", "),
+        t.created_at.clone(),
+        t.updated_at.clone(),
+    ]
+}
+
+fn encode_error(e: impl std::fmt::Display) -> Error {
+    Error::internal(format!("failed to encode export: {e}"))
+}
+
+/// Accumulates an export file batch by batch.
+pub struct Exporter {
+    format: ExportFormat,
+    out: Vec<u8>,
+    written: u64,
+}
+
+impl Exporter {
+    pub fn new(format: ExportFormat) -> Result<Self> {
+        let mut exporter = Exporter {
+            format,
+            out: Vec::new(),
+            written: 0,
+        };
+
+        match format {
+            ExportFormat::Csv => exporter.write_csv(CSV_COLUMNS)?,
+            ExportFormat::Json => exporter.out.push(b'['),
+            ExportFormat::Ndjson => {}
+        }
+
+        Ok(exporter)
+    }
+
+    fn write_csv<I, T>(&mut self, record: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut writer = csv::Writer::from_writer(&mut self.out);
+        writer.write_record(record).map_err(encode_error)?;
+        writer.flush().map_err(encode_error)
+    }
+
+    pub fn write(&mut self, batch: &[TestimonialResponse]) -> Result<()> {
+        for t in batch {
+            match self.format {
+                ExportFormat::Csv => self.write_csv(csv_record(t))?,
+                ExportFormat::Json => {
+                    if self.written > 0 {
+                        self.out.push(b',');
+                    }
+                    serde_json::to_writer(&mut self.out, t).map_err(encode_error)?;
+                }
+                ExportFormat::Ndjson => {
+                    serde_json::to_writer(&mut self.out, t).map_err(encode_error)?;
+                    self.out.push(b'\n');
+                }
+            }
+            self.written += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Testimonials written so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// The finished file as a download named `{name}.{extension}`, with the
+    /// cursor of the next page if there is one.
+    pub fn finish(mut self, name: &str, next_cursor: Option<String>) -> Response<BoxBody> {
+        let (content_type, extension) = match self.format {
+            ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+            ExportFormat::Json => {
+                self.out.push(b']');
+                ("application/json", "json")
+            }
+            ExportFormat::Ndjson => ("application/x-ndjson", "ndjson"),
+        };
+
+        let mut res = Response::new(BoxBody::new(Bytes::from(self.out)));
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        if let Ok(value) =
+            HeaderValue::from_str(&format!("attachment; filename=\"{name}.{extension}\""))
+        {
+            res.headers_mut().insert(CONTENT_DISPOSITION, value);
+        }
+        if let Some(value) = next_cursor.and_then(|c| HeaderValue::from_str(&c).ok()) {
+            res.headers_mut().insert(NEXT_CURSOR_HEADER, value);
+        }
+        res
+    }
+}
@@ -0,0 +1,262 @@
+use chrono::{DateTime, FixedOffset};
+use rapina::database::{Db, DbError};
+use rapina::prelude::*;
+use rapina::sea_orm::sea_query::{
+    Expr, Func, NullOrdering, Order, Query as SeaQuery, SelectStatement,
+};
+use rapina::sea_orm::{
+    ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Select, Value,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::api::v1::pagination::{Cursor, PaginationError};
+use crate::api::v1::validation::LANGUAGE;
+use crate::db::entities::tag::{Column as TagColumn, Entity as Tag};
+use crate::db::entities::testimonial::{Column, Entity as Testimonial, Model};
+use crate::db::entities::testimonial_tag::{
+    Column as TestimonialTagColumn, Entity as TestimonialTag,
+};
+
+use super::dto::{SortField, SortOrder, TagMatch, TestimonialFilters};
+use super::error::TestimonialError;
+
+fn parse_timestamp(field: &str, raw: &str) -> Result<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(raw).map_err(|_| {
+        TestimonialError::InvalidFilter(format!("{field} must be an RFC 3339 timestamp"))
+            .into_api_error()
+    })
+}
+
+/// Testimonials in any of the comma-separated language tags of `languages`,
+/// compared case-insensitively. A bare language such as `pt` also matches
+/// its regional variants such as `pt-BR`.
+fn in_languages(languages: &str) -> Result<Condition> {
+    let mut any = Condition::any();
+    for tag in languages
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+    {
+        if !LANGUAGE.is_match(tag) {
+            return Err(TestimonialError::InvalidFilter(
+                "language must be comma-separated language tags".to_string(),
+            )
+            .into_api_error());
+        }
+        let tag = tag.to_lowercase();
+        let language = Expr::expr(Func::lower(Expr::col((Testimonial, Column::Language))));
+        any = any.add(language.clone().eq(tag.as_str()));
+        if !tag.contains('-') {
+            any = any.add(language.like(format!("{tag}-%")));
+        }
+    }
+    Ok(any)
+}
+
+fn tagged_with(tag_ids: Vec<i32>) -> SelectStatement {
+    SeaQuery::select()
+        .column(TestimonialTagColumn::TestimonialId)
+        .from(TestimonialTag)
+        .and_where(TestimonialTagColumn::TagId.is_in(tag_ids))
+        .to_owned()
+}
+
+/// Testimonials of a project narrowed down by `filters`, trash excluded. Tag
+/// ids are resolved within the project, so tags from other projects never
+/// match.
+pub async fn filtered(
+    db: &Db,
+    project_id: i32,
+    filters: &TestimonialFilters,
+) -> Result<Select<Testimonial>> {
+    let mut q = Testimonial::find()
+        .filter(Column::ProjectId.eq(project_id))
+        .filter(Column::DeletedAt.is_null());
+
+    if let Some(status) = filters.status {
+        q = q.filter(Column::Status.eq(status.as_str()));
+    }
+    if let Some(approved) = filters.is_approved {
+        q = q.filter(Column::IsApproved.eq(approved));
+    }
+    if let Some(featured) = filters.is_featured {
+        q = q.filter(Column::IsFeatured.eq(featured));
+    }
+    if let Some(ref testimonial_type) = filters.testimonial_type {
+        q = q.filter(Column::TestimonialType.eq(testimonial_type));
+    }
+    if let Some(min_rating) = filters.min_rating {
+        q = q.filter(Column::Rating.gte(min_rating));
+    }
+    if let Some(max_rating) = filters.max_rating {
+        q = q.filter(Column::Rating.lte(max_rating));
+    }
+    if let Some(ref source) = filters.source {
+        q = q.filter(Column::Source.eq(source));
+    }
+    if let Some(ref source_platform) = filters.source_platform {
+        q = q.filter(Column::SourcePlatform.eq(source_platform));
+    }
+    if let Some(ref languages) = filters.language {
+        q = q.filter(in_languages(languages)?);
+    }
+    if let Some(ref sentiment) = filters.sentiment {
+        q = q.filter(Column::Sentiment.eq(sentiment));
+    }
+    if let Some(ref created_after) = filters.created_after {
+        q = q.filter(Column::CreatedAt.gte(parse_timestamp("created_after", created_after)?));
+    }
+    if let Some(ref created_before) = filters.created_before {
+        q = q.filter(Column::CreatedAt.lt(parse_timestamp("created_before", created_before)?));
+    }
+    if let Some(has_video) = filters.has_video {
+        q = if has_video {
+            q.filter(Column::VideoUrl.is_not_null())
+        } else {
+            q.filter(Column::VideoUrl.is_null())
+        };
+    }
+
+    if !filters.tag_ids.is_empty() {
+        let mut tag_pids = filters
+            .tag_ids
+            .iter()
+            .map(|id| Uuid::parse_str(id))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| {
+                TestimonialError::InvalidFilter("tag_ids must be tag ids".to_string())
+                    .into_api_error()
+            })?;
+        tag_pids.sort();
+        tag_pids.dedup();
+
+        let tag_ids: Vec<i32> = Tag::find()
+            .filter(TagColumn::ProjectId.eq(project_id))
+            .filter(TagColumn::Pid.is_in(tag_pids.clone()))
+            .all(db.conn())
+            .await
+            .map_err(DbError)?
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+
+        match filters.tag_match.unwrap_or_default() {
+            TagMatch::Any => {
+                q = q.filter(Column::Id.in_subquery(tagged_with(tag_ids)));
+            }
+            TagMatch::All => {
+                // A tag that does not exist in this project can never be matched.
+                if tag_ids.len() < tag_pids.len() {
+                    q = q.filter(Expr::value(false));
+                }
+                for tag_id in tag_ids {
+                    q = q.filter(Column::Id.in_subquery(tagged_with(vec![tag_id])));
+                }
+            }
+        }
+    }
+
+    Ok(q)
+}
+
+fn sort_column(sort: SortField) -> Column {
+    match sort {
+        SortField::CreatedAt => Column::CreatedAt,
+        SortField::Rating => Column::Rating,
+        SortField::SentimentScore => Column::SentimentScore,
+    }
+}
+
+fn sort_key(sort: SortField) -> &'static str {
+    match sort {
+        SortField::CreatedAt => "created_at",
+        SortField::Rating => "rating",
+        SortField::SentimentScore => "sentiment_score",
+    }
+}
+
+fn cursor_value(
+    sort: SortField,
+    value: &serde_json::Value,
+) -> std::result::Result<Option<Value>, PaginationError> {
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    let value = match sort {
+        SortField::CreatedAt => value
+            .as_str()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(Value::from),
+        SortField::Rating => value
+            .as_i64()
+            .and_then(|r| i16::try_from(r).ok())
+            .map(Value::from),
+        SortField::SentimentScore => value.as_f64().map(|s| Value::from(s as f32)),
+    };
+
+    value.map(Some).ok_or(PaginationError::InvalidCursor)
+}
+
+pub fn cursor_for(t: &Model, sort: SortField) -> Cursor {
+    let value = match sort {
+        SortField::CreatedAt => json!(t.created_at.to_rfc3339()),
+        SortField::Rating => json!(t.rating),
+        SortField::SentimentScore => json!(t.sentiment_score),
+    };
+
+    Cursor {
+        id: t.id,
+        sort: Some(sort_key(sort).to_string()),
+        value,
+    }
+}
+
+/// Orders by the sort column (nulls last) with `id` as tie-breaker, and skips
+/// everything up to and including the cursor row.
+pub fn sorted(
+    mut q: Select<Testimonial>,
+    sort: SortField,
+    order: SortOrder,
+    cursor: Option<&Cursor>,
+) -> std::result::Result<Select<Testimonial>, PaginationError> {
+    let column = sort_column(sort);
+
+    if let Some(cursor) = cursor {
+        if cursor.sort.as_deref() != Some(sort_key(sort)) {
+            return Err(PaginationError::InvalidCursor);
+        }
+
+        let past_id = match order {
+            SortOrder::Asc => Column::Id.gt(cursor.id),
+            SortOrder::Desc => Column::Id.lt(cursor.id),
+        };
+
+        let condition = match cursor_value(sort, &cursor.value)? {
+            Some(value) => {
+                let past_value = match order {
+                    SortOrder::Asc => column.gt(value.clone()),
+                    SortOrder::Desc => column.lt(value.clone()),
+                };
+                Condition::any()
+                    .add(past_value)
+                    .add(Condition::all().add(column.eq(value)).add(past_id))
+                    .add(column.is_null())
+            }
+            None => Condition::all().add(column.is_null()).add(past_id),
+        };
+
+        q = q.filter(condition);
+    }
+
+    let order = match order {
+        SortOrder::Asc => Order::Asc,
+        SortOrder::Desc => Order::Desc,
+    };
+
+    Ok(
+        q.order_by_with_nulls(column, order.clone(), NullOrdering::Last)
+            .order_by(Column::Id, order),
+    )
+}
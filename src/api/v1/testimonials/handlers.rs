@@ -1,40 +1,87 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
 use rapina::database::{Db, DbError};
+use rapina::http::Response;
 use rapina::prelude::*;
-use rapina::sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use rapina::response::BoxBody;
+use rapina::sea_orm::sea_query::{Expr, Order};
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
 use uuid::Uuid;
 
+use crate::api::v1::etag;
+use crate::api::v1::merge_patch::patch;
+use crate::api::v1::pagination::{self, Cursor, Page, PageQuery};
 use crate::api::v1::tags::dto::TagResponse;
 use crate::api::v1::tags::handlers::{load_tags_for_testimonial, load_tags_for_testimonials};
 use crate::db::entities::project::{Column as ProjectColumn, Entity as Project};
-use crate::db::entities::testimonial::{ActiveModel, Column, Entity as Testimonial};
+use crate::db::entities::testimonial::{ActiveModel, Column, Entity as Testimonial, Model};
+use crate::db::entities::testimonial_revision::{Column as RevisionColumn, Entity as Revision};
+use crate::db::entities::testimonial_tag::{
+    ActiveModel as TestimonialTagActiveModel, Column as TestimonialTagColumn,
+    Entity as TestimonialTag,
+};
 use crate::db::entities::user::{Column as UserColumn, Entity as User};
+use crate::images;
+use crate::jobs;
+use crate::language;
+use crate::sentiment::{self, AnalyzeSentiment, SentimentAnalyzer};
+use crate::video::{self, ProcessVideo};
 
 use super::dto::{
-    CreateTestimonialRequest, ListTestimonialsQuery, TestimonialResponse, UpdateTestimonialRequest,
+    AnalyzeSentimentResponse, BulkItemResult, BulkTestimonialsRequest, BulkTestimonialsResponse,
+    CreateTestimonialRequest, DuplicateGroupResponse, DuplicatesQuery, DuplicatesResponse,
+    ExportTestimonialsQuery, ListTestimonialsQuery, MergeTestimonialsRequest, ModerationStatus,
+    PatchTestimonialRequest, RejectTestimonialRequest, RevertTestimonialRequest, RevisionResponse,
+    SearchHitResponse, SearchTestimonialsQuery, TestimonialResponse, UpdateTestimonialRequest,
 };
 use super::error::TestimonialError;
+use super::export::{self, Exporter};
+use super::{bulk, duplicates, events, filters, moderation, revisions, search};
+
+/// Most testimonials of a project compared when looking for duplicates,
+/// newest first.
+const DUPLICATE_SCAN_LIMIT: u64 = 5000;
+
+/// Most duplicates merged into a testimonial at once.
+const MAX_MERGED: usize = 100;
+
+/// Testimonials loaded at a time when analyzing a project's sentiment.
+const SENTIMENT_BATCH_SIZE: u64 = 500;
 
 fn to_response(
-    t: crate::db::entities::testimonial::Model,
+    t: Model,
     project_pid: &Uuid,
     tags: Vec<TagResponse>,
+    moderators: &HashMap<i32, Uuid>,
 ) -> TestimonialResponse {
+    let status = moderation::status_of(&t);
+    let content_edited = t.content != t.original_content;
+
     TestimonialResponse {
         id: t.pid.to_string(),
         project_id: project_pid.to_string(),
         testimonial_type: t.testimonial_type,
         content: t.content,
+        original_content: t.original_content,
+        content_edited,
         rating: t.rating,
         author_name: t.author_name,
         author_email: t.author_email,
         author_title: t.author_title,
         author_avatar_url: t.author_avatar_url,
+        author_avatar_variants: images::parse(t.author_avatar_variants),
         author_company: t.author_company,
         author_url: t.author_url,
         video_url: t.video_url,
         video_thumbnail_url: t.video_thumbnail_url,
         video_duration_seconds: t.video_duration_seconds,
         transcription: t.transcription,
+        video_status: t.video_status,
+        video_error: t.video_error,
         source: t.source,
         source_platform: t.source_platform,
         source_url: t.source_url,
@@ -44,12 +91,112 @@ fn to_response(
         language: t.language,
         is_approved: t.is_approved,
         is_featured: t.is_featured,
+        status,
+        rejection_reason: t.rejection_reason,
+        moderated_by: t
+            .moderated_by
+            .and_then(|id| moderators.get(&id))
+            .map(Uuid::to_string),
+        moderated_at: t.moderated_at.map(|at| at.to_rfc3339()),
         tags,
         created_at: t.created_at.to_rfc3339(),
         updated_at: t.updated_at.to_rfc3339(),
+        deleted_at: t.deleted_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+/// Refuses to save `active` if another testimonial of `project_id` has its
+/// `source_platform` and `source_id`, which must be unique together.
+async fn check_source(
+    conn: &impl ConnectionTrait,
+    project_id: i32,
+    active: &ActiveModel,
+) -> Result<()> {
+    let platform = active.source_platform.try_as_ref().cloned().flatten();
+    let source_id = active.source_id.try_as_ref().cloned().flatten();
+    let (Some(platform), Some(source_id)) = (platform, source_id) else {
+        return Ok(());
+    };
+
+    let mut q = Testimonial::find()
+        .filter(Column::ProjectId.eq(project_id))
+        .filter(Column::SourcePlatform.eq(platform))
+        .filter(Column::SourceId.eq(source_id));
+    if let Some(id) = active.id.try_as_ref() {
+        q = q.filter(Column::Id.ne(*id));
+    }
+    if q.one(conn).await.map_err(DbError)?.is_some() {
+        return Err(TestimonialError::DuplicateSource.into_api_error());
+    }
+    Ok(())
+}
+
+/// Queues processing the new video of the testimonial `testimonial_id` and
+/// analyzing its sentiment, as its save calls for, in the save's transaction.
+pub(crate) async fn queue_jobs(
+    txn: &impl ConnectionTrait,
+    testimonial_id: i32,
+    new_video: bool,
+    analyze: bool,
+) -> Result<()> {
+    if new_video {
+        jobs::enqueue(txn, &ProcessVideo { testimonial_id })
+            .await
+            .map_err(DbError)?;
+    }
+    if analyze {
+        jobs::enqueue(txn, &AnalyzeSentiment { testimonial_id })
+            .await
+            .map_err(DbError)?;
+    }
+    Ok(())
+}
+
+/// A new testimonial in `project_id`, as requested. Without a `language`, the
+/// language of its text is detected.
+pub fn new_testimonial(project_id: i32, req: CreateTestimonialRequest) -> ActiveModel {
+    let language = req
+        .language
+        .or_else(|| language::of(req.content.as_deref(), req.transcription.as_deref()));
+
+    ActiveModel {
+        pid: Set(Uuid::new_v4()),
+        project_id: Set(project_id),
+        testimonial_type: Set(req.testimonial_type.unwrap_or_else(|| "text".to_string())),
+        original_content: Set(req.content.clone()),
+        content: Set(req.content),
+        rating: Set(req.rating),
+        author_name: Set(req.author_name),
+        author_email: Set(req.author_email),
+        author_title: Set(req.author_title),
+        author_avatar_url: Set(req.author_avatar_url),
+        author_company: Set(req.author_company),
+        author_url: Set(req.author_url),
+        video_url: Set(req.video_url),
+        video_thumbnail_url: Set(req.video_thumbnail_url),
+        video_duration_seconds: Set(req.video_duration_seconds),
+        transcription: Set(req.transcription),
+        source: Set(req.source),
+        source_platform: Set(req.source_platform),
+        source_url: Set(req.source_url),
+        source_id: Set(req.source_id),
+        sentiment: Set(req.sentiment),
+        sentiment_score: Set(req.sentiment_score),
+        language: Set(language),
+        ..Default::default()
     }
 }
 
+pub(super) async fn render(
+    conn: &impl ConnectionTrait,
+    t: Model,
+    project_pid: &Uuid,
+) -> Result<TestimonialResponse> {
+    let tags = load_tags_for_testimonial(conn, t.id, project_pid).await?;
+    let moderators = moderation::load_moderators(conn, std::slice::from_ref(&t)).await?;
+    Ok(to_response(t, project_pid, tags, &moderators))
+}
+
 async fn resolve_user_id(db: &Db, current_user: &CurrentUser) -> Result<i32> {
     let pid = Uuid::parse_str(&current_user.id)
         .map_err(|_| Error::unauthorized("invalid user id in token"))?;
@@ -71,13 +218,14 @@ pub async fn list_testimonials(
     query: Query<ListTestimonialsQuery>,
     db: Db,
     current_user: CurrentUser,
-) -> Result<Json<Vec<TestimonialResponse>>> {
+) -> Result<Json<Page<TestimonialResponse>>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner())
         .map_err(|_| TestimonialError::NotFound.into_api_error())?;
 
     let project = Project::find()
         .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -88,45 +236,66 @@ pub async fn list_testimonials(
     }
 
     let params = query.into_inner();
-    let mut q = Testimonial::find().filter(Column::ProjectId.eq(project.id));
+    let page = params.page();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+    let sort = params.sort.unwrap_or_default();
+    let order = params.order.unwrap_or_default();
 
-    if let Some(approved) = params.is_approved {
-        q = q.filter(Column::IsApproved.eq(approved));
-    }
-    if let Some(featured) = params.is_featured {
-        q = q.filter(Column::IsFeatured.eq(featured));
-    }
+    let q = filters::filtered(&db, project.id, &params.filters()).await?;
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
 
-    let testimonials = q.all(db.conn()).await.map_err(DbError)?;
+    let mut testimonials = filters::sorted(q, sort, order, cursor.as_ref())?
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor =
+        pagination::next_cursor(&mut testimonials, limit, |t| filters::cursor_for(t, sort));
 
     let testimonial_ids: Vec<i32> = testimonials.iter().map(|t| t.id).collect();
     let mut tags_map = load_tags_for_testimonials(&db, &testimonial_ids, &project.pid).await?;
+    let moderators = moderation::load_moderators(db.conn(), &testimonials).await?;
 
-    let response: Vec<TestimonialResponse> = testimonials
+    let data: Vec<TestimonialResponse> = testimonials
         .into_iter()
         .map(|t| {
             let tags = tags_map.remove(&t.id).unwrap_or_default();
-            to_response(t, &project.pid, tags)
+            to_response(t, &project.pid, tags, &moderators)
         })
         .collect();
 
-    Ok(Json(response))
+    Ok(Json(Page {
+        data,
+        next_cursor,
+        total,
+    }))
 }
 
-#[post("/api/v1/projects/:id/testimonials")]
+/// Downloads the testimonials matching the listing filters, with their tags
+/// and media URLs, as CSV, JSON or NDJSON. Each response is one page of at
+/// most [`export::PAGE_SIZE`] testimonials; while more match, the
+/// `X-Next-Cursor` header holds the `cursor` that fetches the next page.
+#[get("/api/v1/projects/:id/testimonials/export")]
 #[errors(TestimonialError)]
-pub async fn create_testimonial(
+pub async fn export_testimonials(
     id: Path<String>,
+    query: Query<ExportTestimonialsQuery>,
     db: Db,
     current_user: CurrentUser,
-    body: Json<CreateTestimonialRequest>,
-) -> Result<(StatusCode, Json<TestimonialResponse>)> {
+) -> Result<Response<BoxBody>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner())
         .map_err(|_| TestimonialError::NotFound.into_api_error())?;
 
     let project = Project::find()
         .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -136,61 +305,316 @@ pub async fn create_testimonial(
         return Err(TestimonialError::Forbidden.into_api_error());
     }
 
-    let req = body.into_inner();
+    let params = query.into_inner();
+    let sort = params.sort.unwrap_or_default();
+    let order = params.order.unwrap_or_default();
+    let mut cursor = params.cursor.as_deref().map(Cursor::decode).transpose()?;
+    let q = filters::filtered(&db, project.id, &params.filters()).await?;
 
-    let new_testimonial = ActiveModel {
-        pid: Set(Uuid::new_v4()),
-        project_id: Set(project.id),
-        testimonial_type: Set(req.testimonial_type.unwrap_or_else(|| "text".to_string())),
-        content: Set(req.content),
-        rating: Set(req.rating),
-        author_name: Set(req.author_name),
-        author_email: Set(req.author_email),
-        author_title: Set(req.author_title),
-        author_avatar_url: Set(req.author_avatar_url),
-        author_company: Set(req.author_company),
-        author_url: Set(req.author_url),
-        video_url: Set(req.video_url),
-        video_thumbnail_url: Set(req.video_thumbnail_url),
-        video_duration_seconds: Set(req.video_duration_seconds),
-        transcription: Set(req.transcription),
-        source: Set(req.source),
-        source_platform: Set(req.source_platform),
-        source_url: Set(req.source_url),
-        source_id: Set(req.source_id),
-        sentiment: Set(req.sentiment),
-        sentiment_score: Set(req.sentiment_score),
-        language: Set(req.language),
-        ..Default::default()
+    let mut exporter = Exporter::new(params.format.unwrap_or_default())?;
+    let mut full = true;
+    while full && exporter.written() < export::PAGE_SIZE {
+        let batch_size = export::BATCH_SIZE.min(export::PAGE_SIZE - exporter.written());
+        let testimonials = filters::sorted(q.clone(), sort, order, cursor.as_ref())?
+            .limit(batch_size)
+            .all(db.conn())
+            .await
+            .map_err(DbError)?;
+        let Some(last) = testimonials.last() else {
+            break;
+        };
+        cursor = Some(filters::cursor_for(last, sort));
+        full = testimonials.len() as u64 == batch_size;
+
+        let testimonial_ids: Vec<i32> = testimonials.iter().map(|t| t.id).collect();
+        let mut tags_map = load_tags_for_testimonials(&db, &testimonial_ids, &project.pid).await?;
+        let moderators = moderation::load_moderators(db.conn(), &testimonials).await?;
+
+        let batch: Vec<TestimonialResponse> = testimonials
+            .into_iter()
+            .map(|t| {
+                let tags = tags_map.remove(&t.id).unwrap_or_default();
+                to_response(t, &project.pid, tags, &moderators)
+            })
+            .collect();
+        exporter.write(&batch)?;
+    }
+
+    // A full page only has a next one if anything follows its last row.
+    let next_cursor = match cursor {
+        Some(cursor) if full => filters::sorted(q, sort, order, Some(&cursor))?
+            .limit(1)
+            .one(db.conn())
+            .await
+            .map_err(DbError)?
+            .map(|_| cursor.encode()),
+        _ => None,
     };
 
-    let testimonial = new_testimonial.insert(db.conn()).await.map_err(DbError)?;
+    Ok(exporter.finish(&format!("testimonials-{}", project.slug), next_cursor))
+}
 
-    Ok((
-        StatusCode::CREATED,
-        Json(to_response(testimonial, &project.pid, vec![])),
-    ))
+#[get("/api/v1/projects/:id/testimonials/search")]
+#[errors(TestimonialError)]
+pub async fn search_testimonials(
+    id: Path<String>,
+    query: Query<SearchTestimonialsQuery>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<Page<SearchHitResponse>>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let pid = Uuid::parse_str(&id.into_inner())
+        .map_err(|_| TestimonialError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(TestimonialError::Forbidden.into_api_error());
+    }
+
+    let params = query.into_inner();
+    let terms = params.q.trim();
+    if terms.is_empty() {
+        return Err(
+            TestimonialError::InvalidFilter("q must not be empty".to_string()).into_api_error(),
+        );
+    }
+
+    let page = params.page();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = filters::filtered(&db, project.id, &params.filters())
+        .await?
+        .filter(search::matches(terms));
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(ref cursor) = cursor {
+        q = q.filter(search::after(terms, cursor)?);
+    }
+
+    let mut hits: Vec<(i32, f32, String)> = q
+        .select_only()
+        .column(Column::Id)
+        .column_as(search::rank(terms), "rank")
+        .column_as(search::snippet(terms), "snippet")
+        .order_by(search::rank(terms), Order::Desc)
+        .order_by(Column::Id, Order::Desc)
+        .limit(limit + 1)
+        .into_tuple()
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut hits, limit, |(id, rank, _)| {
+        search::cursor_for(*id, *rank)
+    });
+
+    let testimonial_ids: Vec<i32> = hits.iter().map(|(id, _, _)| *id).collect();
+    let mut testimonials: HashMap<i32, _> = Testimonial::find()
+        .filter(Column::Id.is_in(testimonial_ids.clone()))
+        .all(db.conn())
+        .await
+        .map_err(DbError)?
+        .into_iter()
+        .map(|t| (t.id, t))
+        .collect();
+    let mut tags_map = load_tags_for_testimonials(&db, &testimonial_ids, &project.pid).await?;
+    let moderators = moderation::load_moderators(
+        db.conn(),
+        &testimonials.values().cloned().collect::<Vec<_>>(),
+    )
+    .await?;
+
+    let data: Vec<SearchHitResponse> = hits
+        .into_iter()
+        .filter_map(|(id, rank, snippet)| {
+            let t = testimonials.remove(&id)?;
+            let tags = tags_map.remove(&id).unwrap_or_default();
+            Some(SearchHitResponse {
+                testimonial: to_response(t, &project.pid, tags, &moderators),
+                rank,
+                snippet,
+            })
+        })
+        .collect();
+
+    Ok(Json(Page {
+        data,
+        next_cursor,
+        total,
+    }))
 }
 
-#[get("/api/v1/testimonials/:id")]
+#[get("/api/v1/projects/:id/testimonials/trash")]
 #[errors(TestimonialError)]
-pub async fn get_testimonial(
+pub async fn list_trashed_testimonials(
     id: Path<String>,
+    query: Query<PageQuery>,
     db: Db,
     current_user: CurrentUser,
-) -> Result<Json<TestimonialResponse>> {
+) -> Result<Json<Page<TestimonialResponse>>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner())
         .map_err(|_| TestimonialError::NotFound.into_api_error())?;
 
-    let testimonial = Testimonial::find()
-        .filter(Column::Pid.eq(pid))
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
         .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
 
-    let project = Project::find_by_id(testimonial.project_id)
+    if project.user_id != user_id {
+        return Err(TestimonialError::Forbidden.into_api_error());
+    }
+
+    let page = query.into_inner();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = Testimonial::find()
+        .filter(Column::ProjectId.eq(project.id))
+        .filter(Column::DeletedAt.is_not_null());
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(Column::Id.lt(cursor.id));
+    }
+
+    let mut testimonials = q
+        .order_by_desc(Column::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut testimonials, limit, |t| Cursor::from_id(t.id));
+
+    let testimonial_ids: Vec<i32> = testimonials.iter().map(|t| t.id).collect();
+    let mut tags_map = load_tags_for_testimonials(&db, &testimonial_ids, &project.pid).await?;
+    let moderators = moderation::load_moderators(db.conn(), &testimonials).await?;
+
+    let data: Vec<TestimonialResponse> = testimonials
+        .into_iter()
+        .map(|t| {
+            let tags = tags_map.remove(&t.id).unwrap_or_default();
+            to_response(t, &project.pid, tags, &moderators)
+        })
+        .collect();
+
+    Ok(Json(Page {
+        data,
+        next_cursor,
+        total,
+    }))
+}
+
+#[post("/api/v1/projects/:id/testimonials/bulk")]
+#[errors(TestimonialError)]
+pub async fn bulk_testimonials(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    body: Json<BulkTestimonialsRequest>,
+) -> Result<Json<BulkTestimonialsResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let pid = Uuid::parse_str(&id.into_inner())
+        .map_err(|_| TestimonialError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(TestimonialError::Forbidden.into_api_error());
+    }
+
+    let req = body.into_inner();
+    let targets = bulk::targets(&db, project.id, user_id, req.ids, req.filter).await?;
+    let action = bulk::prepare(&db, project.id, user_id, req.action).await?;
+
+    // All or nothing: any item that fails rolls back the whole batch.
+    let txn = db.conn().begin().await.map_err(DbError)?;
+    let mut results = Vec::with_capacity(targets.len());
+    let mut changed = Vec::new();
+    for (id, target) in targets {
+        let outcome = match target {
+            Ok(t) => match bulk::apply(&txn, t.clone(), &action, user_id).await {
+                Ok(()) if bulk::has_events(&action) => {
+                    changed.push(t);
+                    Ok(())
+                }
+                Ok(()) => Ok(()),
+                Err(e) if e.status < 500 => Err(e.message),
+                Err(e) => return Err(e),
+            },
+            Err(reason) => Err(reason),
+        };
+        results.push(BulkItemResult {
+            id,
+            ok: outcome.is_ok(),
+            error: outcome.err(),
+        });
+    }
+
+    if results.iter().any(|r| !r.ok) {
+        txn.rollback().await.map_err(DbError)?;
+        return Err(TestimonialError::BulkFailed(results).into_api_error());
+    }
+
+    let mut updated: HashMap<i32, Model> = Testimonial::find()
+        .filter(Column::Id.is_in(changed.iter().map(|t| t.id)))
+        .all(&txn)
+        .await
+        .map_err(DbError)?
+        .into_iter()
+        .map(|t| (t.id, t))
+        .collect();
+    for before in &changed {
+        if let Some(after) = updated.remove(&before.id) {
+            events::emit(&txn, Some(before), &after, &project.pid).await?;
+        }
+    }
+    txn.commit().await.map_err(DbError)?;
+
+    Ok(Json(BulkTestimonialsResponse { results }))
+}
+
+#[post("/api/v1/projects/:id/testimonials")]
+#[errors(TestimonialError)]
+pub async fn create_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    body: Validated<Json<CreateTestimonialRequest>>,
+) -> Result<etag::Json<TestimonialResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let pid = Uuid::parse_str(&id.into_inner())
+        .map_err(|_| TestimonialError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -200,30 +624,54 @@ pub async fn get_testimonial(
         return Err(TestimonialError::Forbidden.into_api_error());
     }
 
-    let tags = load_tags_for_testimonial(&db, testimonial.id, &project.pid).await?;
-    Ok(Json(to_response(testimonial, &project.pid, tags)))
+    let req = body.into_inner().into_inner();
+
+    let mut testimonial = new_testimonial(project.id, req);
+    check_source(db.conn(), project.id, &testimonial).await?;
+    let has_video = video::queue(None, &mut testimonial);
+    let analyze = sentiment::queue(None, &mut testimonial);
+    images::link(
+        db.conn(),
+        project.id,
+        None,
+        &testimonial.author_avatar_url,
+        &mut testimonial.author_avatar_variants,
+    )
+    .await
+    .map_err(DbError)?;
+    let txn = db.conn().begin().await.map_err(DbError)?;
+    let testimonial = testimonial.insert(&txn).await.map_err(DbError)?;
+    queue_jobs(&txn, testimonial.id, has_video, analyze).await?;
+    events::emit(&txn, None, &testimonial, &project.pid).await?;
+    txn.commit().await.map_err(DbError)?;
+
+    Ok(etag::Json::created(
+        testimonial.updated_at,
+        to_response(testimonial, &project.pid, vec![], &HashMap::new()),
+    ))
 }
 
-#[put("/api/v1/testimonials/:id")]
+#[get("/api/v1/testimonials/:id")]
 #[errors(TestimonialError)]
-pub async fn update_testimonial(
+pub async fn get_testimonial(
     id: Path<String>,
     db: Db,
     current_user: CurrentUser,
-    body: Json<UpdateTestimonialRequest>,
-) -> Result<Json<TestimonialResponse>> {
+) -> Result<etag::Json<TestimonialResponse>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner())
         .map_err(|_| TestimonialError::NotFound.into_api_error())?;
 
     let testimonial = Testimonial::find()
         .filter(Column::Pid.eq(pid))
+        .filter(Column::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
         .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
 
     let project = Project::find_by_id(testimonial.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -233,80 +681,212 @@ pub async fn update_testimonial(
         return Err(TestimonialError::Forbidden.into_api_error());
     }
 
-    let req = body.into_inner();
+    Ok(etag::Json::new(
+        testimonial.updated_at,
+        render(db.conn(), testimonial, &project.pid).await?,
+    ))
+}
+
+#[put("/api/v1/testimonials/:id")]
+#[errors(TestimonialError)]
+pub async fn update_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    headers: Headers,
+    body: Validated<Json<UpdateTestimonialRequest>>,
+) -> Result<etag::Json<TestimonialResponse>> {
+    let req = body.into_inner().into_inner();
+
+    save_edit(id, db, current_user, headers, move |active| {
+        if let Some(testimonial_type) = req.testimonial_type {
+            active.testimonial_type = Set(testimonial_type);
+        }
+        if let Some(content) = req.content {
+            active.content = Set(Some(content));
+        }
+        if let Some(rating) = req.rating {
+            active.rating = Set(Some(rating));
+        }
+        if let Some(author_name) = req.author_name {
+            active.author_name = Set(author_name);
+        }
+        if let Some(author_email) = req.author_email {
+            active.author_email = Set(Some(author_email));
+        }
+        if let Some(author_title) = req.author_title {
+            active.author_title = Set(Some(author_title));
+        }
+        if let Some(author_avatar_url) = req.author_avatar_url {
+            active.author_avatar_url = Set(Some(author_avatar_url));
+        }
+        if let Some(author_company) = req.author_company {
+            active.author_company = Set(Some(author_company));
+        }
+        if let Some(author_url) = req.author_url {
+            active.author_url = Set(Some(author_url));
+        }
+        if let Some(video_url) = req.video_url {
+            active.video_url = Set(Some(video_url));
+        }
+        if let Some(video_thumbnail_url) = req.video_thumbnail_url {
+            active.video_thumbnail_url = Set(Some(video_thumbnail_url));
+        }
+        if let Some(video_duration_seconds) = req.video_duration_seconds {
+            active.video_duration_seconds = Set(Some(video_duration_seconds));
+        }
+        if let Some(transcription) = req.transcription {
+            active.transcription = Set(Some(transcription));
+        }
+        if let Some(source) = req.source {
+            active.source = Set(Some(source));
+        }
+        if let Some(source_platform) = req.source_platform {
+            active.source_platform = Set(Some(source_platform));
+        }
+        if let Some(source_url) = req.source_url {
+            active.source_url = Set(Some(source_url));
+        }
+        if let Some(source_id) = req.source_id {
+            active.source_id = Set(Some(source_id));
+        }
+        if let Some(sentiment) = req.sentiment {
+            active.sentiment = Set(Some(sentiment));
+        }
+        if let Some(sentiment_score) = req.sentiment_score {
+            active.sentiment_score = Set(Some(sentiment_score));
+        }
+        if let Some(language) = req.language {
+            active.language = Set(Some(language));
+        }
+        if let Some(is_featured) = req.is_featured {
+            active.is_featured = Set(is_featured);
+        }
+        Ok(req.is_approved)
+    })
+    .await
+}
+
+/// Applies a JSON Merge Patch: absent fields are left alone and `null` clears
+/// nullable ones such as `author_email`.
+#[patch("/api/v1/testimonials/:id")]
+#[errors(TestimonialError)]
+pub async fn patch_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    headers: Headers,
+    body: Validated<Json<PatchTestimonialRequest>>,
+) -> Result<etag::Json<TestimonialResponse>> {
+    let patch = body.into_inner().into_inner();
+
+    save_edit(id, db, current_user, headers, move |active| {
+        let invalid = |msg| TestimonialError::InvalidPatch(msg).into_api_error();
+        let testimonial_type = patch.testimonial_type.required("type").map_err(invalid)?;
+        let author_name = patch.author_name.required("author_name").map_err(invalid)?;
+        let is_approved = patch.is_approved.required("is_approved").map_err(invalid)?;
+        let is_featured = patch.is_featured.required("is_featured").map_err(invalid)?;
+
+        if let Some(testimonial_type) = testimonial_type {
+            active.testimonial_type = Set(testimonial_type);
+        }
+        if let Some(author_name) = author_name {
+            active.author_name = Set(author_name);
+        }
+        if let Some(is_featured) = is_featured {
+            active.is_featured = Set(is_featured);
+        }
+        patch.content.apply(&mut active.content);
+        patch.rating.apply(&mut active.rating);
+        patch.author_email.apply(&mut active.author_email);
+        patch.author_title.apply(&mut active.author_title);
+        patch.author_avatar_url.apply(&mut active.author_avatar_url);
+        patch.author_company.apply(&mut active.author_company);
+        patch.author_url.apply(&mut active.author_url);
+        patch.video_url.apply(&mut active.video_url);
+        patch
+            .video_thumbnail_url
+            .apply(&mut active.video_thumbnail_url);
+        patch
+            .video_duration_seconds
+            .apply(&mut active.video_duration_seconds);
+        patch.transcription.apply(&mut active.transcription);
+        patch.source.apply(&mut active.source);
+        patch.source_platform.apply(&mut active.source_platform);
+        patch.source_url.apply(&mut active.source_url);
+        patch.source_id.apply(&mut active.source_id);
+        patch.sentiment.apply(&mut active.sentiment);
+        patch.sentiment_score.apply(&mut active.sentiment_score);
+        patch.language.apply(&mut active.language);
+        Ok(is_approved)
+    })
+    .await
+}
+
+/// Edits the testimonial with public id `id`, if the user owns it and it
+/// still matches `If-Match`. `apply` sets the edited columns and returns the
+/// legacy `is_approved` flag: true approves, false sends the testimonial back
+/// to pending.
+///
+/// The edit is saved together with its revision, any moderation transition,
+/// and the jobs that process a new video and analyze the sentiment again if
+/// the text changed.
+async fn save_edit(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    headers: Headers,
+    apply: impl FnOnce(&mut ActiveModel) -> Result<Option<bool>>,
+) -> Result<etag::Json<TestimonialResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (testimonial, project) = find_owned_testimonial(&db, id.into_inner(), user_id).await?;
+
+    let guard = etag::if_match(&headers.0, testimonial.updated_at)?;
+    let before = testimonial.clone();
     let mut active: ActiveModel = testimonial.into();
+    let is_approved = apply(&mut active)?;
+    check_source(db.conn(), before.project_id, &active).await?;
 
-    if let Some(testimonial_type) = req.testimonial_type {
-        active.testimonial_type = Set(testimonial_type);
-    }
-    if let Some(content) = req.content {
-        active.content = Set(Some(content));
-    }
-    if let Some(rating) = req.rating {
-        active.rating = Set(Some(rating));
-    }
-    if let Some(author_name) = req.author_name {
-        active.author_name = Set(author_name);
-    }
-    if let Some(author_email) = req.author_email {
-        active.author_email = Set(Some(author_email));
-    }
-    if let Some(author_title) = req.author_title {
-        active.author_title = Set(Some(author_title));
-    }
-    if let Some(author_avatar_url) = req.author_avatar_url {
-        active.author_avatar_url = Set(Some(author_avatar_url));
-    }
-    if let Some(author_company) = req.author_company {
-        active.author_company = Set(Some(author_company));
-    }
-    if let Some(author_url) = req.author_url {
-        active.author_url = Set(Some(author_url));
-    }
-    if let Some(video_url) = req.video_url {
-        active.video_url = Set(Some(video_url));
-    }
-    if let Some(video_thumbnail_url) = req.video_thumbnail_url {
-        active.video_thumbnail_url = Set(Some(video_thumbnail_url));
-    }
-    if let Some(video_duration_seconds) = req.video_duration_seconds {
-        active.video_duration_seconds = Set(Some(video_duration_seconds));
-    }
-    if let Some(transcription) = req.transcription {
-        active.transcription = Set(Some(transcription));
-    }
-    if let Some(source) = req.source {
-        active.source = Set(Some(source));
-    }
-    if let Some(source_platform) = req.source_platform {
-        active.source_platform = Set(Some(source_platform));
-    }
-    if let Some(source_url) = req.source_url {
-        active.source_url = Set(Some(source_url));
-    }
-    if let Some(source_id) = req.source_id {
-        active.source_id = Set(Some(source_id));
-    }
-    if let Some(sentiment) = req.sentiment {
-        active.sentiment = Set(Some(sentiment));
-    }
-    if let Some(sentiment_score) = req.sentiment_score {
-        active.sentiment_score = Set(Some(sentiment_score));
-    }
-    if let Some(language) = req.language {
-        active.language = Set(Some(language));
-    }
-    if let Some(is_approved) = req.is_approved {
-        active.is_approved = Set(is_approved);
-    }
-    if let Some(is_featured) = req.is_featured {
-        active.is_featured = Set(is_featured);
-    }
+    let new_video = video::queue(Some(&before), &mut active);
+    images::link(
+        db.conn(),
+        before.project_id,
+        Some(&before.author_avatar_url),
+        &active.author_avatar_url,
+        &mut active.author_avatar_variants,
+    )
+    .await
+    .map_err(DbError)?;
+    let analyze = sentiment::queue(Some(&before), &mut active);
 
-    let updated = active.update(db.conn()).await.map_err(DbError)?;
+    let txn = db.conn().begin().await.map_err(DbError)?;
+    let mut updated = etag::update(&txn, active, Column::UpdatedAt, guard).await?;
+    revisions::record(
+        &txn,
+        &before,
+        &updated,
+        user_id,
+        revisions::ACTION_UPDATE,
+        None,
+    )
+    .await?;
+    // A transition that is not allowed rolls the edit back with it.
+    if let Some(is_approved) = is_approved {
+        let to = if is_approved {
+            ModerationStatus::Approved
+        } else {
+            ModerationStatus::Pending
+        };
+        updated = moderation::transition(&txn, updated, to, user_id, None).await?;
+    }
+    queue_jobs(&txn, updated.id, new_video, analyze).await?;
+    events::emit(&txn, Some(&before), &updated, &project.pid).await?;
+    txn.commit().await.map_err(DbError)?;
 
-    let tags = load_tags_for_testimonial(&db, updated.id, &project.pid).await?;
-    Ok(Json(to_response(updated, &project.pid, tags)))
+    Ok(etag::Json::new(
+        updated.updated_at,
+        render(db.conn(), updated, &project.pid).await?,
+    ))
 }
 
 #[delete("/api/v1/testimonials/:id")]
@@ -315,6 +895,7 @@ pub async fn delete_testimonial(
     id: Path<String>,
     db: Db,
     current_user: CurrentUser,
+    headers: Headers,
 ) -> Result<StatusCode> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner())
@@ -322,12 +903,14 @@ pub async fn delete_testimonial(
 
     let testimonial = Testimonial::find()
         .filter(Column::Pid.eq(pid))
+        .filter(Column::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
         .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
 
     let project = Project::find_by_id(testimonial.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -337,21 +920,22 @@ pub async fn delete_testimonial(
         return Err(TestimonialError::Forbidden.into_api_error());
     }
 
-    Testimonial::delete_by_id(testimonial.id)
-        .exec(db.conn())
-        .await
-        .map_err(DbError)?;
+    let guard = etag::if_match(&headers.0, testimonial.updated_at)?;
+
+    let mut active: ActiveModel = testimonial.into();
+    active.deleted_at = Set(Some(Utc::now().fixed_offset()));
+    etag::update(db.conn(), active, Column::UpdatedAt, guard).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
-#[post("/api/v1/testimonials/:id/approve")]
+#[post("/api/v1/testimonials/:id/restore")]
 #[errors(TestimonialError)]
-pub async fn approve_testimonial(
+pub async fn restore_testimonial(
     id: Path<String>,
     db: Db,
     current_user: CurrentUser,
-) -> Result<Json<TestimonialResponse>> {
+) -> Result<etag::Json<TestimonialResponse>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner())
         .map_err(|_| TestimonialError::NotFound.into_api_error())?;
@@ -364,6 +948,7 @@ pub async fn approve_testimonial(
         .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
 
     let project = Project::find_by_id(testimonial.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -373,36 +958,498 @@ pub async fn approve_testimonial(
         return Err(TestimonialError::Forbidden.into_api_error());
     }
 
-    let new_value = !testimonial.is_approved;
-    let testimonial_id = testimonial.id;
+    let restored = if testimonial.deleted_at.is_some() {
+        let mut active: ActiveModel = testimonial.into();
+        active.deleted_at = Set(None);
+        active.update(db.conn()).await.map_err(DbError)?
+    } else {
+        testimonial
+    };
+
+    Ok(etag::Json::new(
+        restored.updated_at,
+        render(db.conn(), restored, &project.pid).await?,
+    ))
+}
+
+#[get("/api/v1/testimonials/:id/revisions")]
+#[errors(TestimonialError)]
+pub async fn list_revisions(
+    id: Path<String>,
+    query: Query<PageQuery>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<Page<RevisionResponse>>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (testimonial, _) = find_owned_testimonial(&db, id.into_inner(), user_id).await?;
+
+    let page = query.into_inner();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = Revision::find().filter(RevisionColumn::TestimonialId.eq(testimonial.id));
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(RevisionColumn::Id.lt(cursor.id));
+    }
+
+    let mut revisions = q
+        .order_by_desc(RevisionColumn::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut revisions, limit, |r| Cursor::from_id(r.id));
+
+    let users = revisions::load_user_pids(
+        db.conn(),
+        revisions.iter().filter_map(|r| r.user_id).collect(),
+    )
+    .await?;
+    let reverted_ids: Vec<i32> = revisions
+        .iter()
+        .filter_map(|r| r.reverted_revision_id)
+        .collect();
+    let reverted: HashMap<i32, Uuid> = Revision::find()
+        .filter(RevisionColumn::Id.is_in(reverted_ids))
+        .all(db.conn())
+        .await
+        .map_err(DbError)?
+        .into_iter()
+        .map(|r| (r.id, r.pid))
+        .collect();
+
+    let data = revisions
+        .into_iter()
+        .map(|r| RevisionResponse {
+            id: r.pid.to_string(),
+            action: r.action,
+            changes: r.changes,
+            user_id: r.user_id.and_then(|id| users.get(&id)).map(Uuid::to_string),
+            reverted_revision_id: r
+                .reverted_revision_id
+                .and_then(|id| reverted.get(&id))
+                .map(Uuid::to_string),
+            merged_testimonial_ids: r
+                .merged_testimonial_ids
+                .and_then(|ids| serde_json::from_value(ids).ok()),
+            created_at: r.created_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(Json(Page {
+        data,
+        next_cursor,
+        total,
+    }))
+}
+
+#[post("/api/v1/testimonials/:id/revert")]
+#[errors(TestimonialError)]
+pub async fn revert_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    body: Json<RevertTestimonialRequest>,
+) -> Result<etag::Json<TestimonialResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (testimonial, project) = find_owned_testimonial(&db, id.into_inner(), user_id).await?;
+
+    let revision_pid = Uuid::parse_str(&body.into_inner().revision_id)
+        .map_err(|_| TestimonialError::RevisionNotFound.into_api_error())?;
+
+    let target = Revision::find()
+        .filter(RevisionColumn::Pid.eq(revision_pid))
+        .filter(RevisionColumn::TestimonialId.eq(testimonial.id))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| TestimonialError::RevisionNotFound.into_api_error())?;
+
+    let txn = db.conn().begin().await.map_err(DbError)?;
+
+    // Walk back from the newest revision to the target, so each field ends up
+    // with the value it had right before the target was applied.
+    let later = Revision::find()
+        .filter(RevisionColumn::TestimonialId.eq(testimonial.id))
+        .filter(RevisionColumn::Id.gte(target.id))
+        .order_by_desc(RevisionColumn::Id)
+        .all(&txn)
+        .await
+        .map_err(DbError)?;
+
+    let mut restored = serde_json::Map::new();
+    for revision in later {
+        if let serde_json::Value::Object(changes) = revision.changes {
+            for (field, change) in changes {
+                restored.insert(field, change["before"].clone());
+            }
+        }
+    }
+
+    let mut active: ActiveModel = testimonial.clone().into();
+    for (field, value) in restored {
+        revisions::assign(&mut active, &field, value)?;
+    }
+    let new_video = video::queue(Some(&testimonial), &mut active);
+    images::link(
+        &txn,
+        testimonial.project_id,
+        Some(&testimonial.author_avatar_url),
+        &active.author_avatar_url,
+        &mut active.author_avatar_variants,
+    )
+    .await
+    .map_err(DbError)?;
+
+    let updated = active.update(&txn).await.map_err(DbError)?;
+    revisions::record(
+        &txn,
+        &testimonial,
+        &updated,
+        user_id,
+        revisions::ACTION_REVERT,
+        Some(target.id),
+    )
+    .await?;
+    queue_jobs(&txn, updated.id, new_video, false).await?;
+    txn.commit().await.map_err(DbError)?;
+
+    Ok(etag::Json::new(
+        updated.updated_at,
+        render(db.conn(), updated, &project.pid).await?,
+    ))
+}
+
+/// Processes the testimonial's video again, for example after it failed.
+/// Only what the testimonial is missing is filled in, so clear the thumbnail,
+/// duration or transcription first to have them made again.
+#[post("/api/v1/testimonials/:id/video/process")]
+#[errors(TestimonialError)]
+pub async fn process_video(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<(StatusCode, Json<TestimonialResponse>)> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (testimonial, project) = find_owned_testimonial(&db, id.into_inner(), user_id).await?;
+
+    if testimonial.video_url.is_none() {
+        return Err(TestimonialError::NoVideo.into_api_error());
+    }
+
     let mut active: ActiveModel = testimonial.into();
-    active.is_approved = Set(new_value);
+    active.video_status = Set(Some(video::STATUS_PROCESSING.to_string()));
+    active.video_error = Set(None);
+    let txn = db.conn().begin().await.map_err(DbError)?;
+    let updated = active.update(&txn).await.map_err(DbError)?;
+    queue_jobs(&txn, updated.id, true, false).await?;
+    txn.commit().await.map_err(DbError)?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(render(db.conn(), updated, &project.pid).await?),
+    ))
+}
 
-    let updated = active.update(db.conn()).await.map_err(DbError)?;
+/// Analyzes the sentiment of every live testimonial in the project again,
+/// replacing what they had, including sentiment set by clients.
+#[post("/api/v1/projects/:id/testimonials/sentiment")]
+#[errors(TestimonialError)]
+pub async fn analyze_sentiment(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    analyzer: State<SentimentAnalyzer>,
+) -> Result<Json<AnalyzeSentimentResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let pid = Uuid::parse_str(&id.into_inner())
+        .map_err(|_| TestimonialError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(TestimonialError::Forbidden.into_api_error());
+    }
+
+    let analyzer = analyzer.into_inner();
+    let mut counts = AnalyzeSentimentResponse {
+        analyzed: 0,
+        updated: 0,
+        failed: 0,
+    };
+    let mut after = 0;
+    loop {
+        let batch = Testimonial::find()
+            .filter(Column::ProjectId.eq(project.id))
+            .filter(Column::DeletedAt.is_null())
+            .filter(Column::Id.gt(after))
+            .order_by_asc(Column::Id)
+            .limit(SENTIMENT_BATCH_SIZE)
+            .all(db.conn())
+            .await
+            .map_err(DbError)?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        after = last.id;
+
+        for t in batch {
+            counts.analyzed += 1;
+            let sentiment = match analyzer
+                .of(
+                    t.content.as_deref(),
+                    t.transcription.as_deref(),
+                    t.language.as_deref(),
+                )
+                .await
+            {
+                Ok(sentiment) => sentiment,
+                Err(e) => {
+                    tracing::warn!(testimonial = %t.pid, error = %e, "could not analyze testimonial sentiment");
+                    counts.failed += 1;
+                    continue;
+                }
+            };
+
+            let label = sentiment.map(|s| s.label.as_str().to_string());
+            let score = sentiment.map(|s| s.score);
+            if label == t.sentiment && score == t.sentiment_score {
+                continue;
+            }
+            let mut active: ActiveModel = t.into();
+            active.sentiment = Set(label);
+            active.sentiment_score = Set(score);
+            active.update(db.conn()).await.map_err(DbError)?;
+            counts.updated += 1;
+        }
+    }
 
-    let tags = load_tags_for_testimonial(&db, testimonial_id, &project.pid).await?;
-    Ok(Json(to_response(updated, &project.pid, tags)))
+    Ok(Json(counts))
 }
 
-#[post("/api/v1/testimonials/:id/feature")]
+/// Groups of live testimonials in the project that are likely duplicates of
+/// each other, most similar first.
+#[get("/api/v1/projects/:id/testimonials/duplicates")]
 #[errors(TestimonialError)]
-pub async fn feature_testimonial(
+pub async fn list_duplicates(
     id: Path<String>,
+    query: Query<DuplicatesQuery>,
     db: Db,
     current_user: CurrentUser,
-) -> Result<Json<TestimonialResponse>> {
+) -> Result<Json<DuplicatesResponse>> {
     let user_id = resolve_user_id(&db, &current_user).await?;
     let pid = Uuid::parse_str(&id.into_inner())
         .map_err(|_| TestimonialError::NotFound.into_api_error())?;
 
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(TestimonialError::Forbidden.into_api_error());
+    }
+
+    let limit = query
+        .into_inner()
+        .limit
+        .unwrap_or(pagination::DEFAULT_LIMIT)
+        .clamp(1, pagination::MAX_LIMIT) as usize;
+
+    let testimonials = Testimonial::find()
+        .filter(Column::ProjectId.eq(project.id))
+        .filter(Column::DeletedAt.is_null())
+        .order_by_desc(Column::Id)
+        .limit(DUPLICATE_SCAN_LIMIT)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+
+    let mut groups = duplicates::find(&testimonials);
+    groups.truncate(limit);
+
+    let members: Vec<Model> = groups
+        .iter()
+        .flat_map(|g| g.members.iter().map(|&i| testimonials[i].clone()))
+        .collect();
+    let member_ids: Vec<i32> = members.iter().map(|t| t.id).collect();
+    let mut tags_map = load_tags_for_testimonials(&db, &member_ids, &project.pid).await?;
+    let moderators = moderation::load_moderators(db.conn(), &members).await?;
+
+    let mut members = members.into_iter();
+    let data = groups
+        .into_iter()
+        .map(|group| DuplicateGroupResponse {
+            testimonials: members
+                .by_ref()
+                .take(group.members.len())
+                .map(|t| {
+                    let tags = tags_map.remove(&t.id).unwrap_or_default();
+                    to_response(t, &project.pid, tags, &moderators)
+                })
+                .collect(),
+            similarity: group.similarity,
+            reasons: group.reasons.into_iter().map(str::to_string).collect(),
+        })
+        .collect();
+
+    Ok(Json(DuplicatesResponse {
+        data,
+        scanned: testimonials.len() as u64,
+    }))
+}
+
+/// Merges duplicates into this testimonial. Fields it lacks are filled from
+/// the first duplicate that has them, it gains their tags, and they are moved
+/// to the trash. Its moderation status is left as is.
+#[post("/api/v1/testimonials/:id/merge")]
+#[errors(TestimonialError)]
+pub async fn merge_testimonials(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    body: Json<MergeTestimonialsRequest>,
+) -> Result<etag::Json<TestimonialResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (testimonial, project) = find_owned_testimonial(&db, id.into_inner(), user_id).await?;
+
+    let mut pids: Vec<Uuid> = Vec::new();
+    for raw in body.into_inner().duplicate_ids {
+        let pid = Uuid::parse_str(&raw).map_err(|_| TestimonialError::NotFound.into_api_error())?;
+        if !pids.contains(&pid) {
+            pids.push(pid);
+        }
+    }
+    let invalid = |msg: String| TestimonialError::InvalidFilter(msg).into_api_error();
+    if pids.is_empty() {
+        return Err(invalid("duplicate_ids must not be empty".to_string()));
+    }
+    if pids.len() > MAX_MERGED {
+        return Err(invalid(format!(
+            "at most {MAX_MERGED} testimonials can be merged at once"
+        )));
+    }
+    if pids.contains(&testimonial.pid) {
+        return Err(invalid(
+            "a testimonial cannot be merged into itself".to_string(),
+        ));
+    }
+
+    let txn = db.conn().begin().await.map_err(DbError)?;
+
+    let mut merged = Testimonial::find()
+        .filter(Column::Pid.is_in(pids.clone()))
+        .filter(Column::ProjectId.eq(testimonial.project_id))
+        .filter(Column::DeletedAt.is_null())
+        .all(&txn)
+        .await
+        .map_err(DbError)?;
+    if merged.len() != pids.len() {
+        return Err(TestimonialError::NotFound.into_api_error());
+    }
+    merged.sort_by_key(|d| pids.iter().position(|pid| *pid == d.pid));
+
+    let snapshots: Vec<_> = merged.iter().map(revisions::snapshot).collect();
+    let mut active: ActiveModel = testimonial.clone().into();
+    for (field, value) in revisions::snapshot(&testimonial) {
+        if !value.is_null() {
+            continue;
+        }
+        if let Some(fill) = snapshots
+            .iter()
+            .find_map(|s| s.get(&field).filter(|v| !v.is_null()))
+        {
+            revisions::assign(&mut active, &field, fill.clone())?;
+        }
+    }
+    images::link(
+        &txn,
+        testimonial.project_id,
+        Some(&testimonial.author_avatar_url),
+        &active.author_avatar_url,
+        &mut active.author_avatar_variants,
+    )
+    .await
+    .map_err(DbError)?;
+    let updated = active.update(&txn).await.map_err(DbError)?;
+
+    let duplicate_ids: Vec<i32> = merged.iter().map(|d| d.id).collect();
+    let mut tag_ids: HashSet<i32> = TestimonialTag::find()
+        .filter(TestimonialTagColumn::TestimonialId.eq(testimonial.id))
+        .all(&txn)
+        .await
+        .map_err(DbError)?
+        .into_iter()
+        .map(|link| link.tag_id)
+        .collect();
+    let merged_links = TestimonialTag::find()
+        .filter(TestimonialTagColumn::TestimonialId.is_in(duplicate_ids.clone()))
+        .all(&txn)
+        .await
+        .map_err(DbError)?;
+    for link in merged_links {
+        if tag_ids.insert(link.tag_id) {
+            TestimonialTagActiveModel {
+                testimonial_id: Set(testimonial.id),
+                tag_id: Set(link.tag_id),
+            }
+            .insert(&txn)
+            .await
+            .map_err(DbError)?;
+        }
+    }
+
+    let now = Utc::now().fixed_offset();
+    Testimonial::update_many()
+        .col_expr(Column::DeletedAt, Expr::value(now))
+        .col_expr(Column::UpdatedAt, Expr::value(now))
+        .filter(Column::Id.is_in(duplicate_ids))
+        .exec(&txn)
+        .await
+        .map_err(DbError)?;
+
+    revisions::record_merge(&txn, &testimonial, &updated, user_id, &pids).await?;
+    txn.commit().await.map_err(DbError)?;
+
+    Ok(etag::Json::new(
+        updated.updated_at,
+        render(db.conn(), updated, &project.pid).await?,
+    ))
+}
+
+async fn find_owned_testimonial(
+    db: &Db,
+    id: String,
+    user_id: i32,
+) -> Result<(Model, crate::db::entities::project::Model)> {
+    let pid = Uuid::parse_str(&id).map_err(|_| TestimonialError::NotFound.into_api_error())?;
+
     let testimonial = Testimonial::find()
         .filter(Column::Pid.eq(pid))
+        .filter(Column::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
         .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
 
     let project = Project::find_by_id(testimonial.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
         .one(db.conn())
         .await
         .map_err(DbError)?
@@ -412,13 +1459,117 @@ pub async fn feature_testimonial(
         return Err(TestimonialError::Forbidden.into_api_error());
     }
 
-    let new_value = !testimonial.is_featured;
-    let testimonial_id = testimonial.id;
-    let mut active: ActiveModel = testimonial.into();
-    active.is_featured = Set(new_value);
+    Ok((testimonial, project))
+}
+
+async fn moderate(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    to: ModerationStatus,
+    reason: Option<String>,
+) -> Result<etag::Json<TestimonialResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (testimonial, project) = find_owned_testimonial(&db, id.into_inner(), user_id).await?;
+
+    let before = testimonial.clone();
+    let txn = db.conn().begin().await.map_err(DbError)?;
+    let updated = moderation::transition(&txn, testimonial, to, user_id, reason).await?;
+    events::emit(&txn, Some(&before), &updated, &project.pid).await?;
+    txn.commit().await.map_err(DbError)?;
+
+    Ok(etag::Json::new(
+        updated.updated_at,
+        render(db.conn(), updated, &project.pid).await?,
+    ))
+}
+
+#[post("/api/v1/testimonials/:id/approve")]
+#[errors(TestimonialError)]
+pub async fn approve_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<etag::Json<TestimonialResponse>> {
+    moderate(id, db, current_user, ModerationStatus::Approved, None).await
+}
 
-    let updated = active.update(db.conn()).await.map_err(DbError)?;
+#[post("/api/v1/testimonials/:id/reject")]
+#[errors(TestimonialError)]
+pub async fn reject_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    body: Json<RejectTestimonialRequest>,
+) -> Result<etag::Json<TestimonialResponse>> {
+    let reason = body.into_inner().reason;
+    moderate(id, db, current_user, ModerationStatus::Rejected, reason).await
+}
 
-    let tags = load_tags_for_testimonial(&db, testimonial_id, &project.pid).await?;
-    Ok(Json(to_response(updated, &project.pid, tags)))
+#[post("/api/v1/testimonials/:id/archive")]
+#[errors(TestimonialError)]
+pub async fn archive_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<etag::Json<TestimonialResponse>> {
+    moderate(id, db, current_user, ModerationStatus::Archived, None).await
+}
+
+#[post("/api/v1/testimonials/:id/reopen")]
+#[errors(TestimonialError)]
+pub async fn reopen_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<etag::Json<TestimonialResponse>> {
+    moderate(id, db, current_user, ModerationStatus::Pending, None).await
+}
+
+async fn set_featured(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    featured: bool,
+) -> Result<etag::Json<TestimonialResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (testimonial, project) = find_owned_testimonial(&db, id.into_inner(), user_id).await?;
+
+    let updated = if testimonial.is_featured == featured {
+        testimonial
+    } else {
+        let before = testimonial.clone();
+        let mut active: ActiveModel = testimonial.into();
+        active.is_featured = Set(featured);
+        let txn = db.conn().begin().await.map_err(DbError)?;
+        let updated = active.update(&txn).await.map_err(DbError)?;
+        events::emit(&txn, Some(&before), &updated, &project.pid).await?;
+        txn.commit().await.map_err(DbError)?;
+        updated
+    };
+
+    Ok(etag::Json::new(
+        updated.updated_at,
+        render(db.conn(), updated, &project.pid).await?,
+    ))
+}
+
+#[post("/api/v1/testimonials/:id/feature")]
+#[errors(TestimonialError)]
+pub async fn feature_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<etag::Json<TestimonialResponse>> {
+    set_featured(id, db, current_user, true).await
+}
+
+#[delete("/api/v1/testimonials/:id/feature")]
+#[errors(TestimonialError)]
+pub async fn unfeature_testimonial(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<etag::Json<TestimonialResponse>> {
+    set_featured(id, db, current_user, false).await
 }
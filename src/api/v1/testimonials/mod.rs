@@ -1,21 +1,47 @@
+pub mod bulk;
 pub mod dto;
+pub mod duplicates;
 pub mod error;
+pub mod events;
+pub mod export;
+pub mod filters;
 pub mod handlers;
+pub mod moderation;
+pub mod revisions;
+pub mod search;
 
 use handlers::*;
 use rapina::prelude::*;
 
+use crate::api::v1::merge_patch::PatchRoutes;
+
 pub fn project_routes() -> Router {
     Router::new()
         .get("/:id/testimonials", list_testimonials)
+        .get("/:id/testimonials/search", search_testimonials)
+        .get("/:id/testimonials/export", export_testimonials)
+        .get("/:id/testimonials/trash", list_trashed_testimonials)
+        .get("/:id/testimonials/duplicates", list_duplicates)
         .post("/:id/testimonials", create_testimonial)
+        .post("/:id/testimonials/bulk", bulk_testimonials)
+        .post("/:id/testimonials/sentiment", analyze_sentiment)
 }
 
 pub fn routes() -> Router {
     Router::new()
         .get("/:id", get_testimonial)
         .put("/:id", update_testimonial)
+        .patch("/:id", patch_testimonial)
         .delete("/:id", delete_testimonial)
+        .post("/:id/restore", restore_testimonial)
+        .get("/:id/revisions", list_revisions)
+        .post("/:id/revert", revert_testimonial)
+        .post("/:id/merge", merge_testimonials)
+        .post("/:id/video/process", process_video)
         .post("/:id/approve", approve_testimonial)
+        .post("/:id/reject", reject_testimonial)
+        .post("/:id/archive", archive_testimonial)
+        .post("/:id/reopen", reopen_testimonial)
         .post("/:id/feature", feature_testimonial)
+        .delete("/:id/feature", unfeature_testimonial)
 }
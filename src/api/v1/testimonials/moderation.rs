@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use rapina::database::DbError;
+use rapina::prelude::*;
+use rapina::sea_orm::sea_query::Expr;
+use rapina::sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use crate::db::entities::testimonial::{Column, Entity as Testimonial, Model};
+
+use super::dto::ModerationStatus;
+use super::error::TestimonialError;
+use super::revisions;
+
+impl ModerationStatus {
+    /// Archived testimonials have to be reopened (back to pending) before they
+    /// can be approved or rejected again.
+    pub fn can_move_to(self, to: ModerationStatus) -> bool {
+        use ModerationStatus::*;
+
+        match self {
+            Pending => matches!(to, Approved | Rejected | Archived),
+            Approved => matches!(to, Pending | Rejected | Archived),
+            Rejected => matches!(to, Pending | Approved | Archived),
+            Archived => matches!(to, Pending),
+        }
+    }
+}
+
+pub fn status_of(t: &Model) -> ModerationStatus {
+    ModerationStatus::parse(&t.status).unwrap_or_default()
+}
+
+/// Moves `testimonial` to `to`. Moving to the state it is already in is a
+/// no-op, so repeated or concurrent requests for the same outcome all succeed.
+///
+/// The update only applies if the status is still the one the transition was
+/// checked against; if another moderator got there first, the transition is
+/// re-evaluated against the fresh row.
+pub async fn transition(
+    conn: &impl ConnectionTrait,
+    mut testimonial: Model,
+    to: ModerationStatus,
+    moderator_id: i32,
+    reason: Option<String>,
+) -> Result<Model> {
+    loop {
+        let from = status_of(&testimonial);
+        if from == to {
+            return Ok(testimonial);
+        }
+        if !from.can_move_to(to) {
+            return Err(TestimonialError::InvalidTransition { from, to }.into_api_error());
+        }
+
+        let now = Utc::now().fixed_offset();
+        let reason = if to == ModerationStatus::Rejected {
+            reason.clone()
+        } else {
+            None
+        };
+
+        let result = Testimonial::update_many()
+            .col_expr(Column::Status, Expr::value(to.as_str()))
+            .col_expr(
+                Column::IsApproved,
+                Expr::value(to == ModerationStatus::Approved),
+            )
+            .col_expr(Column::RejectionReason, Expr::value(reason))
+            .col_expr(Column::ModeratedBy, Expr::value(moderator_id))
+            .col_expr(Column::ModeratedAt, Expr::value(now))
+            .col_expr(Column::UpdatedAt, Expr::value(now))
+            .filter(Column::Id.eq(testimonial.id))
+            .filter(Column::Status.eq(from.as_str()))
+            .exec(conn)
+            .await
+            .map_err(DbError)?;
+
+        testimonial = Testimonial::find_by_id(testimonial.id)
+            .one(conn)
+            .await
+            .map_err(DbError)?
+            .ok_or_else(|| TestimonialError::NotFound.into_api_error())?;
+
+        if result.rows_affected > 0 {
+            return Ok(testimonial);
+        }
+    }
+}
+
+/// Public ids of the users who moderated `testimonials`, keyed by user id.
+pub async fn load_moderators(
+    conn: &impl ConnectionTrait,
+    testimonials: &[Model],
+) -> Result<HashMap<i32, Uuid>> {
+    let ids = testimonials.iter().filter_map(|t| t.moderated_by).collect();
+    revisions::load_user_pids(conn, ids).await
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use rapina::database::DbError;
+use rapina::prelude::*;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set,
+};
+use serde_json::{Map, Value, json};
+use uuid::Uuid;
+
+use crate::db::entities::testimonial::{ActiveModel, Model};
+use crate::db::entities::testimonial_revision::ActiveModel as RevisionActiveModel;
+use crate::db::entities::user::{Column as UserColumn, Entity as User};
+
+use super::error::TestimonialError;
+
+pub const ACTION_UPDATE: &str = "update";
+pub const ACTION_REVERT: &str = "revert";
+pub const ACTION_MERGE: &str = "merge";
+
+/// Fields whose edits are recorded, by their API name. Moderation and
+/// featuring have their own audit trail and are not revisions.
+pub const TRACKED_FIELDS: &[&str] = &[
+    "type",
+    "content",
+    "rating",
+    "author_name",
+    "author_email",
+    "author_title",
+    "author_avatar_url",
+    "author_company",
+    "author_url",
+    "video_url",
+    "video_thumbnail_url",
+    "video_duration_seconds",
+    "transcription",
+    "source",
+    "source_platform",
+    "source_url",
+    "source_id",
+    "sentiment",
+    "sentiment_score",
+    "language",
+];
+
+/// The tracked fields of `t`, keyed by API name.
+pub fn snapshot(t: &Model) -> Map<String, Value> {
+    let Ok(Value::Object(mut fields)) = serde_json::to_value(t) else {
+        return Map::new();
+    };
+    if let Some(testimonial_type) = fields.remove("testimonial_type") {
+        fields.insert("type".to_string(), testimonial_type);
+    }
+
+    fields
+        .into_iter()
+        .filter(|(field, _)| TRACKED_FIELDS.contains(&field.as_str()))
+        .collect()
+}
+
+/// Field-level `{ "field": { "before": .., "after": .. } }` for everything that
+/// differs between `before` and `after`.
+pub fn diff(before: &Model, after: &Model) -> Map<String, Value> {
+    let before = snapshot(before);
+    let mut after = snapshot(after);
+
+    before
+        .into_iter()
+        .filter_map(|(field, old)| {
+            let new = after.remove(&field)?;
+            (old != new).then(|| (field, json!({ "before": old, "after": new })))
+        })
+        .collect()
+}
+
+fn parse<T: serde::de::DeserializeOwned>(field: &str, value: Value) -> Result<T> {
+    serde_json::from_value(value).map_err(|_| {
+        TestimonialError::InvalidFilter(format!("invalid value for {field}")).into_api_error()
+    })
+}
+
+/// Sets one tracked field on `active` from its JSON value.
+pub fn assign(active: &mut ActiveModel, field: &str, value: Value) -> Result<()> {
+    match field {
+        "type" => active.testimonial_type = Set(parse(field, value)?),
+        "content" => active.content = Set(parse(field, value)?),
+        "rating" => active.rating = Set(parse(field, value)?),
+        "author_name" => active.author_name = Set(parse(field, value)?),
+        "author_email" => active.author_email = Set(parse(field, value)?),
+        "author_title" => active.author_title = Set(parse(field, value)?),
+        "author_avatar_url" => active.author_avatar_url = Set(parse(field, value)?),
+        "author_company" => active.author_company = Set(parse(field, value)?),
+        "author_url" => active.author_url = Set(parse(field, value)?),
+        "video_url" => active.video_url = Set(parse(field, value)?),
+        "video_thumbnail_url" => active.video_thumbnail_url = Set(parse(field, value)?),
+        "video_duration_seconds" => active.video_duration_seconds = Set(parse(field, value)?),
+        "transcription" => active.transcription = Set(parse(field, value)?),
+        "source" => active.source = Set(parse(field, value)?),
+        "source_platform" => active.source_platform = Set(parse(field, value)?),
+        "source_url" => active.source_url = Set(parse(field, value)?),
+        "source_id" => active.source_id = Set(parse(field, value)?),
+        "sentiment" => active.sentiment = Set(parse(field, value)?),
+        "sentiment_score" => active.sentiment_score = Set(parse(field, value)?),
+        "language" => active.language = Set(parse(field, value)?),
+        _ => {
+            return Err(
+                TestimonialError::InvalidFilter(format!("unknown field {field}")).into_api_error(),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores a revision for the change from `before` to `after`, unless nothing
+/// tracked changed.
+pub async fn record(
+    conn: &impl ConnectionTrait,
+    before: &Model,
+    after: &Model,
+    user_id: i32,
+    action: &str,
+    reverted_revision_id: Option<i32>,
+) -> Result<()> {
+    let changes = diff(before, after);
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    RevisionActiveModel {
+        pid: Set(Uuid::new_v4()),
+        testimonial_id: Set(after.id),
+        user_id: Set(Some(user_id)),
+        action: Set(action.to_string()),
+        changes: Set(Value::Object(changes)),
+        reverted_revision_id: Set(reverted_revision_id),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+    .map_err(DbError)?;
+
+    Ok(())
+}
+
+/// Stores the revision for merging the `merged` testimonials into `after`.
+/// Unlike [`record`], it is stored even when no tracked field changed, so the
+/// merge always shows up in the history.
+pub async fn record_merge(
+    conn: &impl ConnectionTrait,
+    before: &Model,
+    after: &Model,
+    user_id: i32,
+    merged: &[Uuid],
+) -> Result<()> {
+    RevisionActiveModel {
+        pid: Set(Uuid::new_v4()),
+        testimonial_id: Set(after.id),
+        user_id: Set(Some(user_id)),
+        action: Set(ACTION_MERGE.to_string()),
+        changes: Set(Value::Object(diff(before, after))),
+        merged_testimonial_ids: Set(Some(json!(merged))),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+    .map_err(DbError)?;
+
+    Ok(())
+}
+
+/// Public ids of the given users, keyed by user id.
+pub async fn load_user_pids(
+    conn: &impl ConnectionTrait,
+    mut ids: Vec<i32>,
+) -> Result<HashMap<i32, Uuid>> {
+    ids.sort();
+    ids.dedup();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let users = User::find()
+        .filter(UserColumn::Id.is_in(ids))
+        .all(conn)
+        .await
+        .map_err(DbError)?;
+
+    Ok(users.into_iter().map(|u| (u.id, u.pid)).collect())
+}
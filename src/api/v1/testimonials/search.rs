@@ -0,0 +1,78 @@
+use rapina::sea_orm::sea_query::{Expr, SimpleExpr};
+use rapina::sea_orm::{ColumnTrait, Condition};
+use serde_json::json;
+
+use crate::api::v1::pagination::{Cursor, PaginationError};
+use crate::db::entities::testimonial::Column;
+
+const SORT_KEY: &str = "rank";
+
+/// Parses `terms` with the search configuration of each row's language, so a
+/// query is stemmed the same way as the testimonial it is matched against.
+const QUERY: &str =
+    r#"websearch_to_tsquery(testimonials_search_config("testimonials"."language"), $1)"#;
+
+/// The terms parsed with every search configuration, which is the same for
+/// every row and so lets the GIN index on `search_vector` find candidates.
+const INDEXED_QUERY: &str = "testimonials_search_query($1)";
+
+/// Finds candidates through the index, then keeps those that match the query
+/// parsed with their own language's configuration.
+pub fn matches(terms: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!(
+            r#""testimonials"."search_vector" @@ {INDEXED_QUERY}
+                AND "testimonials"."search_vector" @@ {QUERY}"#
+        ),
+        [terms],
+    )
+}
+
+pub fn rank(terms: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!(r#"ts_rank_cd("testimonials"."search_vector", {QUERY})"#),
+        [terms],
+    )
+}
+
+pub fn snippet(terms: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!(
+            r#"ts_headline(
+                testimonials_search_config("testimonials"."language"),
+                replace(replace(replace(
+                    coalesce("testimonials"."content", "testimonials"."transcription", ''),
+                    '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+                {QUERY},
+                'StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15, MaxFragments=2'
+            )"#
+        ),
+        [terms],
+    )
+}
+
+pub fn cursor_for(id: i32, rank: f32) -> Cursor {
+    Cursor {
+        id,
+        sort: Some(SORT_KEY.to_string()),
+        value: json!(rank),
+    }
+}
+
+/// Skips every hit ranked above the cursor, and those tied with it up to and
+/// including the cursor row.
+pub fn after(terms: &str, cursor: &Cursor) -> Result<Condition, PaginationError> {
+    if cursor.sort.as_deref() != Some(SORT_KEY) {
+        return Err(PaginationError::InvalidCursor);
+    }
+    let value = cursor
+        .value
+        .as_f64()
+        .ok_or(PaginationError::InvalidCursor)? as f32;
+
+    Ok(Condition::any().add(Expr::expr(rank(terms)).lt(value)).add(
+        Condition::all()
+            .add(Expr::expr(rank(terms)).eq(value))
+            .add(Column::Id.lt(cursor.id)),
+    ))
+}
@@ -0,0 +1,57 @@
+//! Chunks of a resumable upload.
+//!
+//! A chunk is a raw request body holding the next bytes of the file, sent
+//! with an `Upload-Offset` header saying where in the file it starts.
+
+use std::sync::Arc;
+
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use rapina::extract::{FromRequest, PathParams};
+use rapina::hyper::Request;
+use rapina::hyper::body::{Bytes, Incoming};
+use rapina::prelude::*;
+use rapina::state::AppState;
+
+/// Header giving the byte of the file a chunk starts at.
+pub const UPLOAD_OFFSET: &str = "upload-offset";
+
+/// Upper bound on the size of one chunk.
+pub const MAX_CHUNK_BYTES: usize = 64 * 1024 * 1024;
+
+pub struct Chunk {
+    pub offset: u64,
+    pub data: Bytes,
+}
+
+impl FromRequest for Chunk {
+    async fn from_request(
+        req: Request<Incoming>,
+        _params: &PathParams,
+        _state: &Arc<AppState>,
+    ) -> std::result::Result<Self, Error> {
+        let offset = req
+            .headers()
+            .get(UPLOAD_OFFSET)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .ok_or_else(|| Error::bad_request("Upload-Offset header must be a byte offset"))?;
+
+        let data = Limited::new(req.into_body(), MAX_CHUNK_BYTES)
+            .collect()
+            .await
+            .map_err(|e| {
+                if e.is::<LengthLimitError>() {
+                    Error::new(
+                        413,
+                        "PAYLOAD_TOO_LARGE",
+                        format!("chunks must not exceed {MAX_CHUNK_BYTES} bytes"),
+                    )
+                } else {
+                    Error::bad_request("failed to read request body")
+                }
+            })?
+            .to_bytes();
+
+        Ok(Chunk { offset, data })
+    }
+}
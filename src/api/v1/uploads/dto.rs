@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+use rapina::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::images::ImageVariant;
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct CreateUploadRequest {
+    /// Name of the file on the client, kept for reference.
+    #[validate(length(max = 255))]
+    pub filename: Option<String>,
+    /// MIME type of the file, such as `video/mp4` or `image/png`.
+    pub content_type: String,
+    /// Size of the whole file in bytes.
+    pub size: u64,
+}
+
+/// A request that uploads the whole file straight to storage.
+#[derive(Serialize, JsonSchema)]
+pub struct PresignedUploadResponse {
+    pub method: String,
+    pub url: String,
+    /// Headers the request must be sent with.
+    pub headers: BTreeMap<String, String>,
+    pub expires_at: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct UploadResponse {
+    pub id: String,
+    pub project_id: String,
+    /// `pending` until all bytes arrived, then `complete`.
+    pub status: String,
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub size: u64,
+    /// Bytes received so far; the next chunk starts here.
+    pub offset: u64,
+    /// Size of the chunks to send. Every chunk but the last must be at least
+    /// 5 MiB.
+    pub chunk_size: u64,
+    /// Where the file is served once the upload is complete. Use it as a
+    /// testimonial's `video_url` or `author_avatar_url`, or a project's
+    /// `logo_url`.
+    pub url: Option<String>,
+    /// Set when the storage accepts uploads from clients directly. Send the
+    /// whole file with it, then complete the upload.
+    pub presigned: Option<PresignedUploadResponse>,
+    pub created_at: String,
+    /// When a pending upload is discarded.
+    pub expires_at: String,
+    pub completed_at: Option<String>,
+    /// Resized copies of a complete image upload, smallest first.
+    pub variants: Vec<ImageVariant>,
+}
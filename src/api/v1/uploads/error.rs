@@ -0,0 +1,111 @@
+use rapina::database::DbError;
+use rapina::prelude::*;
+
+use crate::images::ImageError;
+use crate::storage::StorageError;
+
+pub enum UploadError {
+    DbError(DbError),
+    Storage(StorageError),
+    NotFound,
+    Forbidden,
+    Invalid(String),
+    TooLarge(String),
+    UnsupportedType(String),
+    OffsetMismatch { offset: u64 },
+    Conflict(String),
+    Expired,
+}
+
+impl IntoApiError for UploadError {
+    fn into_api_error(self) -> Error {
+        match self {
+            UploadError::DbError(e) => e.into_api_error(),
+            UploadError::Storage(e) => Error::internal(e.to_string()),
+            UploadError::NotFound => Error::not_found("upload not found"),
+            UploadError::Forbidden => Error::forbidden("you do not own this project"),
+            UploadError::Invalid(msg) => Error::bad_request(msg),
+            UploadError::TooLarge(msg) => Error::new(413, "PAYLOAD_TOO_LARGE", msg),
+            UploadError::UnsupportedType(msg) => Error::new(415, "UNSUPPORTED_MEDIA_TYPE", msg),
+            UploadError::OffsetMismatch { offset } => {
+                Error::conflict(format!("the next chunk starts at byte {offset}"))
+                    .with_details(serde_json::json!({ "offset": offset }))
+            }
+            UploadError::Conflict(msg) => Error::conflict(msg),
+            UploadError::Expired => Error::new(410, "GONE", "upload expired"),
+        }
+    }
+}
+
+impl DocumentedError for UploadError {
+    fn error_variants() -> Vec<ErrorVariant> {
+        vec![
+            ErrorVariant {
+                status: 400,
+                code: "BAD_REQUEST",
+                description: "Invalid upload, chunk or Upload-Offset header",
+            },
+            ErrorVariant {
+                status: 404,
+                code: "NOT_FOUND",
+                description: "Project or upload not found",
+            },
+            ErrorVariant {
+                status: 403,
+                code: "FORBIDDEN",
+                description: "User does not own this project",
+            },
+            ErrorVariant {
+                status: 409,
+                code: "CONFLICT",
+                description: "Chunk does not start at the current offset, or the upload is not finished",
+            },
+            ErrorVariant {
+                status: 410,
+                code: "GONE",
+                description: "Upload expired before it was completed",
+            },
+            ErrorVariant {
+                status: 413,
+                code: "PAYLOAD_TOO_LARGE",
+                description: "File or chunk exceeds the size limit",
+            },
+            ErrorVariant {
+                status: 415,
+                code: "UNSUPPORTED_MEDIA_TYPE",
+                description: "File type is not accepted, or the file is not a valid file of its type",
+            },
+            ErrorVariant {
+                status: 422,
+                code: "VALIDATION_ERROR",
+                description: "Request body failed validation",
+            },
+            ErrorVariant {
+                status: 500,
+                code: "INTERNAL_ERROR",
+                description: "Internal server error",
+            },
+        ]
+    }
+}
+
+impl From<DbError> for UploadError {
+    fn from(e: DbError) -> Self {
+        UploadError::DbError(e)
+    }
+}
+
+impl From<StorageError> for UploadError {
+    fn from(e: StorageError) -> Self {
+        UploadError::Storage(e)
+    }
+}
+
+impl From<ImageError> for UploadError {
+    fn from(e: ImageError) -> Self {
+        match e {
+            ImageError::Invalid(msg) => UploadError::UnsupportedType(msg),
+            ImageError::Storage(e) => UploadError::Storage(e),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Discarding uploads that were never completed.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use rapina::prelude::tracing;
+use rapina::sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+
+use crate::db::entities::upload::{Column, Entity as Upload, Model};
+use crate::jobs::{Job, JobError};
+use crate::storage::{MediaStorage, StorageError};
+
+use super::handlers::STATUS_PENDING;
+
+/// How often expired uploads are discarded.
+pub const EXPIRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Removes what a pending upload stored so far.
+pub async fn discard(storage: &MediaStorage, upload: &Model) -> Result<(), StorageError> {
+    if let Some(multipart_id) = &upload.multipart_id {
+        storage.abort_multipart(&upload.key, multipart_id).await?;
+    }
+    // A presigned upload may have stored the file without being completed.
+    storage.delete(&upload.key).await
+}
+
+/// Discards every pending upload past its expiry, and returns how many there
+/// were.
+pub async fn expire(conn: &DatabaseConnection, storage: &MediaStorage) -> Result<u64, DbErr> {
+    let expired = Upload::find()
+        .filter(Column::Status.eq(STATUS_PENDING))
+        .filter(Column::ExpiresAt.lt(Utc::now().fixed_offset()))
+        .all(conn)
+        .await?;
+
+    let mut count = 0;
+    for upload in expired {
+        if let Err(e) = discard(storage, &upload).await {
+            tracing::warn!(upload = %upload.pid, error = %e, "could not discard expired upload");
+            continue;
+        }
+        Upload::delete_by_id(upload.id).exec(conn).await?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Job that runs [`expire`].
+#[derive(Serialize, Deserialize)]
+pub struct ExpireUploads;
+
+impl Job for ExpireUploads {
+    const KIND: &'static str = "uploads.expire";
+}
+
+impl ExpireUploads {
+    pub async fn run(
+        self,
+        conn: &DatabaseConnection,
+        storage: &MediaStorage,
+    ) -> Result<(), JobError> {
+        let count = expire(conn, storage).await?;
+        if count > 0 {
+            tracing::info!(count, "discarded expired uploads");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,466 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use rapina::database::{Db, DbError};
+use rapina::prelude::*;
+use rapina::sea_orm::sea_query::Expr;
+use rapina::sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, Set};
+use uuid::Uuid;
+
+use crate::api::v1::merge_patch::patch;
+use crate::db::entities::project::{
+    Column as ProjectColumn, Entity as Project, Model as ProjectModel,
+};
+use crate::db::entities::upload::{ActiveModel, Column, Entity as Upload, Model};
+use crate::db::entities::user::{Column as UserColumn, Entity as User};
+use crate::images::{self, ImageError};
+use crate::storage::{MIN_PART_BYTES, MediaStorage, PresignedRequest, UploadedPart};
+
+use super::chunk::Chunk;
+use super::dto::{CreateUploadRequest, PresignedUploadResponse, UploadResponse};
+use super::error::UploadError;
+use super::limits::{self, Kind, UploadLimits};
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_COMPLETE: &str = "complete";
+
+/// How long a pending upload is kept before it is discarded.
+const UPLOAD_TTL: chrono::Duration = chrono::Duration::hours(24);
+
+/// How long presigned upload requests stay valid.
+const PRESIGN_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Size of the chunks clients are asked to send.
+const CHUNK_SIZE: u64 = 2 * MIN_PART_BYTES;
+
+fn to_response(
+    upload: Model,
+    project_pid: &Uuid,
+    storage: &MediaStorage,
+    presigned: Option<PresignedRequest>,
+) -> UploadResponse {
+    let complete = upload.status == STATUS_COMPLETE;
+    UploadResponse {
+        id: upload.pid.to_string(),
+        project_id: project_pid.to_string(),
+        url: complete.then(|| storage.url(&upload.key)),
+        status: upload.status,
+        filename: upload.filename,
+        content_type: upload.content_type,
+        size: upload.size as u64,
+        offset: upload.received as u64,
+        chunk_size: CHUNK_SIZE,
+        presigned: presigned.map(|p| PresignedUploadResponse {
+            method: p.method.to_string(),
+            url: p.url,
+            headers: p.headers.into_iter().collect(),
+            expires_at: p.expires_at.to_rfc3339(),
+        }),
+        created_at: upload.created_at.to_rfc3339(),
+        expires_at: upload.expires_at.to_rfc3339(),
+        completed_at: upload.completed_at.map(|at| at.to_rfc3339()),
+        variants: images::parse(upload.variants),
+    }
+}
+
+/// A presigned request for a pending upload nothing was sent to yet, so
+/// clients can choose between uploading directly and in chunks.
+fn presign(upload: &Model, storage: &MediaStorage) -> Option<PresignedRequest> {
+    if upload.status != STATUS_PENDING || upload.received > 0 {
+        return None;
+    }
+    let left = (upload.expires_at.to_utc() - Utc::now()).to_std().ok()?;
+    storage.presign_put(&upload.key, &upload.content_type, PRESIGN_TTL.min(left))
+}
+
+async fn resolve_user_id(db: &Db, current_user: &CurrentUser) -> Result<i32> {
+    let pid = Uuid::parse_str(&current_user.id)
+        .map_err(|_| Error::unauthorized("invalid user id in token"))?;
+
+    let user = User::find()
+        .filter(UserColumn::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| Error::unauthorized("user not found"))?;
+
+    Ok(user.id)
+}
+
+async fn find_owned_upload(db: &Db, id: String, user_id: i32) -> Result<(Model, ProjectModel)> {
+    let pid = Uuid::parse_str(&id).map_err(|_| UploadError::NotFound.into_api_error())?;
+
+    let upload = Upload::find()
+        .filter(Column::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| UploadError::NotFound.into_api_error())?;
+
+    let project = Project::find_by_id(upload.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| UploadError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(UploadError::Forbidden.into_api_error());
+    }
+
+    Ok((upload, project))
+}
+
+/// Fails unless `upload` is still pending and has not expired.
+fn ensure_pending(upload: &Model) -> Result<()> {
+    if upload.status != STATUS_PENDING {
+        return Err(
+            UploadError::Conflict("upload is already complete".to_string()).into_api_error(),
+        );
+    }
+    if upload.expires_at < Utc::now().fixed_offset() {
+        return Err(UploadError::Expired.into_api_error());
+    }
+    Ok(())
+}
+
+fn parts_of(upload: &Model) -> Vec<UploadedPart> {
+    serde_json::from_value(upload.parts.clone()).unwrap_or_default()
+}
+
+/// Marks `upload` complete, once its file is in storage. Images are
+/// processed first; one that turns out not to be a valid image is discarded
+/// together with its upload.
+async fn mark_complete(db: &Db, storage: &MediaStorage, upload: Model) -> Result<Model> {
+    let mut variants = None;
+    if let Some((Kind::Image, _)) = limits::accepted(&upload.content_type) {
+        match images::process_stored(storage, &upload.key, &upload.content_type).await {
+            Ok(processed) => variants = Some(serde_json::json!(processed)),
+            Err(ImageError::Invalid(msg)) => {
+                storage
+                    .delete(&upload.key)
+                    .await
+                    .map_err(|e| UploadError::from(e).into_api_error())?;
+                Upload::delete_by_id(upload.id)
+                    .exec(db.conn())
+                    .await
+                    .map_err(DbError)?;
+                return Err(UploadError::UnsupportedType(msg).into_api_error());
+            }
+            Err(e) => return Err(UploadError::from(e).into_api_error()),
+        }
+    }
+
+    let mut active: ActiveModel = upload.into();
+    active.variants = Set(variants);
+    active.status = Set(STATUS_COMPLETE.to_string());
+    active.completed_at = Set(Some(Utc::now().fixed_offset()));
+    Ok(active.update(db.conn()).await.map_err(DbError)?)
+}
+
+/// Joins the chunks of `upload`, all of which have arrived, into its file.
+async fn finish_chunks(db: &Db, storage: &MediaStorage, upload: Model) -> Result<Model> {
+    if let Some(multipart_id) = &upload.multipart_id {
+        storage
+            .complete_multipart(&upload.key, multipart_id, &parts_of(&upload))
+            .await
+            .map_err(|e| UploadError::from(e).into_api_error())?;
+    }
+    mark_complete(db, storage, upload).await
+}
+
+/// Starts uploading a file to the project's media storage.
+///
+/// Send the file in chunks to the upload with `PATCH`, each starting at the
+/// current `offset`; if the connection drops, fetch the upload to learn the
+/// offset and carry on from there. When `presigned` is set, the whole file
+/// can instead be sent straight to storage, followed by `complete`.
+#[post("/api/v1/projects/:id/uploads")]
+#[errors(UploadError)]
+pub async fn create_upload(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    storage: State<MediaStorage>,
+    limits: State<UploadLimits>,
+    body: Validated<Json<CreateUploadRequest>>,
+) -> Result<(StatusCode, Json<UploadResponse>)> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let pid =
+        Uuid::parse_str(&id.into_inner()).map_err(|_| UploadError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| UploadError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(UploadError::Forbidden.into_api_error());
+    }
+
+    let req = body.into_inner().into_inner();
+    let content_type = req.content_type.trim().to_ascii_lowercase();
+    let (kind, extension) = limits::accepted(&content_type).ok_or_else(|| {
+        UploadError::UnsupportedType(format!(
+            "{content_type} files are not accepted; upload one of {}",
+            limits::accepted_list()
+        ))
+        .into_api_error()
+    })?;
+
+    let max = limits.into_inner().max_bytes(kind);
+    if req.size == 0 {
+        return Err(UploadError::Invalid("size must be at least 1".to_string()).into_api_error());
+    }
+    if req.size > max {
+        let kind = match kind {
+            Kind::Image => "images",
+            Kind::Video => "videos",
+        };
+        return Err(
+            UploadError::TooLarge(format!("{kind} must not exceed {max} bytes")).into_api_error(),
+        );
+    }
+
+    let pid = Uuid::new_v4();
+    let now = Utc::now().fixed_offset();
+    let upload = ActiveModel {
+        pid: Set(pid),
+        project_id: Set(project.id),
+        user_id: Set(Some(user_id)),
+        key: Set(format!("{}.{extension}", pid.simple())),
+        filename: Set(req.filename),
+        content_type: Set(content_type),
+        size: Set(req.size as i64),
+        received: Set(0),
+        parts: Set(serde_json::json!([])),
+        status: Set(STATUS_PENDING.to_string()),
+        created_at: Set(now),
+        expires_at: Set(now + UPLOAD_TTL),
+        ..Default::default()
+    }
+    .insert(db.conn())
+    .await
+    .map_err(DbError)?;
+
+    let storage = storage.into_inner();
+    let presigned = presign(&upload, &storage);
+    Ok((
+        StatusCode::CREATED,
+        Json(to_response(upload, &project.pid, &storage, presigned)),
+    ))
+}
+
+/// The upload and how far it got.
+#[get("/api/v1/uploads/:id")]
+#[errors(UploadError)]
+pub async fn get_upload(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    storage: State<MediaStorage>,
+) -> Result<Json<UploadResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (upload, project) = find_owned_upload(&db, id.into_inner(), user_id).await?;
+
+    let storage = storage.into_inner();
+    let presigned = presign(&upload, &storage);
+    Ok(Json(to_response(upload, &project.pid, &storage, presigned)))
+}
+
+/// Appends the request body to the file. The `Upload-Offset` header must
+/// equal the upload's current `offset`. The chunk that brings the file to its
+/// full size completes the upload.
+#[patch("/api/v1/uploads/:id")]
+#[errors(UploadError)]
+pub async fn upload_chunk(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    storage: State<MediaStorage>,
+    chunk: Chunk,
+) -> Result<Json<UploadResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (upload, project) = find_owned_upload(&db, id.into_inner(), user_id).await?;
+    ensure_pending(&upload)?;
+
+    let received = upload.received as u64;
+    let size = upload.size as u64;
+    let len = chunk.data.len() as u64;
+    if chunk.offset != received {
+        return Err(UploadError::OffsetMismatch { offset: received }.into_api_error());
+    }
+    if len == 0 {
+        return Err(UploadError::Invalid("chunk must not be empty".to_string()).into_api_error());
+    }
+    if received + len > size {
+        return Err(UploadError::TooLarge(format!(
+            "chunk would take the file past its declared size of {size} bytes"
+        ))
+        .into_api_error());
+    }
+    let last = received + len == size;
+    if !last && len < MIN_PART_BYTES {
+        return Err(UploadError::Invalid(format!(
+            "chunks other than the last must be at least {MIN_PART_BYTES} bytes"
+        ))
+        .into_api_error());
+    }
+    if received == 0 && !limits::looks_like(&upload.content_type, &chunk.data) {
+        return Err(UploadError::UnsupportedType(format!(
+            "file is not a valid {}",
+            upload.content_type
+        ))
+        .into_api_error());
+    }
+
+    let storage = storage.into_inner();
+    let storage_error = |e| UploadError::from(e).into_api_error();
+    let multipart_id = match &upload.multipart_id {
+        Some(multipart_id) => multipart_id.clone(),
+        None => {
+            let multipart_id = storage
+                .start_multipart(&upload.key, &upload.content_type)
+                .await
+                .map_err(storage_error)?;
+            Upload::update_many()
+                .col_expr(Column::MultipartId, Expr::value(multipart_id.clone()))
+                .filter(Column::Id.eq(upload.id))
+                .exec(db.conn())
+                .await
+                .map_err(DbError)?;
+            multipart_id
+        }
+    };
+
+    let mut parts = parts_of(&upload);
+    let part = storage
+        .put_part(
+            &upload.key,
+            &multipart_id,
+            parts.len() as u32 + 1,
+            chunk.data.to_vec(),
+        )
+        .await
+        .map_err(storage_error)?;
+    parts.push(part);
+
+    // Only count the chunk if no other chunk for this offset got in first.
+    let result = Upload::update_many()
+        .col_expr(Column::Received, Expr::value((received + len) as i64))
+        .col_expr(Column::Parts, Expr::value(serde_json::json!(parts)))
+        .filter(Column::Id.eq(upload.id))
+        .filter(Column::Received.eq(upload.received))
+        .exec(db.conn())
+        .await
+        .map_err(DbError)?;
+    if result.rows_affected == 0 {
+        let current = Upload::find_by_id(upload.id)
+            .one(db.conn())
+            .await
+            .map_err(DbError)?
+            .map_or(received, |u| u.received as u64);
+        return Err(UploadError::OffsetMismatch { offset: current }.into_api_error());
+    }
+
+    let mut upload = Upload::find_by_id(upload.id)
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| UploadError::NotFound.into_api_error())?;
+    if last {
+        upload = finish_chunks(&db, &storage, upload).await?;
+    }
+
+    Ok(Json(to_response(upload, &project.pid, &storage, None)))
+}
+
+/// Completes an upload whose file was sent with the presigned request, or
+/// whose last chunk arrived but could not be joined. Completing a complete
+/// upload does nothing.
+#[post("/api/v1/uploads/:id/complete")]
+#[errors(UploadError)]
+pub async fn complete_upload(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    storage: State<MediaStorage>,
+) -> Result<Json<UploadResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (upload, project) = find_owned_upload(&db, id.into_inner(), user_id).await?;
+    let storage = storage.into_inner();
+
+    if upload.status == STATUS_COMPLETE {
+        return Ok(Json(to_response(upload, &project.pid, &storage, None)));
+    }
+    ensure_pending(&upload)?;
+
+    let upload = if upload.received == upload.size {
+        finish_chunks(&db, &storage, upload).await?
+    } else if upload.received > 0 {
+        return Err(UploadError::Conflict(format!(
+            "received {} of {} bytes",
+            upload.received, upload.size
+        ))
+        .into_api_error());
+    } else {
+        let stored = storage
+            .size(&upload.key)
+            .await
+            .map_err(|e| UploadError::from(e).into_api_error())?;
+        match stored {
+            None => {
+                return Err(UploadError::Conflict(
+                    "the file has not been uploaded yet".to_string(),
+                )
+                .into_api_error());
+            }
+            Some(stored) if stored != upload.size as u64 => {
+                // Make room for a retry with the right file.
+                storage
+                    .delete(&upload.key)
+                    .await
+                    .map_err(|e| UploadError::from(e).into_api_error())?;
+                return Err(UploadError::Invalid(format!(
+                    "uploaded file is {stored} bytes, but {} were declared",
+                    upload.size
+                ))
+                .into_api_error());
+            }
+            Some(_) => mark_complete(&db, &storage, upload).await?,
+        }
+    };
+
+    Ok(Json(to_response(upload, &project.pid, &storage, None)))
+}
+
+/// Cancels a pending upload and discards what was sent. Complete uploads
+/// cannot be cancelled, since their file may be in use.
+#[delete("/api/v1/uploads/:id")]
+#[errors(UploadError)]
+pub async fn cancel_upload(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    storage: State<MediaStorage>,
+) -> Result<StatusCode> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (upload, _) = find_owned_upload(&db, id.into_inner(), user_id).await?;
+
+    if upload.status != STATUS_PENDING {
+        return Err(
+            UploadError::Conflict("complete uploads cannot be cancelled".to_string())
+                .into_api_error(),
+        );
+    }
+
+    super::expiry::discard(&storage.into_inner(), &upload)
+        .await
+        .map_err(|e| UploadError::from(e).into_api_error())?;
+    upload.delete(db.conn()).await.map_err(DbError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
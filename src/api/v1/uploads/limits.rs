@@ -0,0 +1,84 @@
+//! Which files can be uploaded, and how large they may be.
+
+/// What kind of media a file is; each kind has its own size limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Image,
+    Video,
+}
+
+/// Accepted MIME types, with their kind and the extension files get.
+const TYPES: &[(&str, Kind, &str)] = &[
+    ("image/jpeg", Kind::Image, "jpg"),
+    ("image/png", Kind::Image, "png"),
+    ("image/webp", Kind::Image, "webp"),
+    ("image/gif", Kind::Image, "gif"),
+    ("video/mp4", Kind::Video, "mp4"),
+    ("video/quicktime", Kind::Video, "mov"),
+    ("video/webm", Kind::Video, "webm"),
+];
+
+/// The kind and extension of an accepted MIME type.
+pub fn accepted(content_type: &str) -> Option<(Kind, &'static str)> {
+    TYPES
+        .iter()
+        .find(|(t, _, _)| t.eq_ignore_ascii_case(content_type.trim()))
+        .map(|(_, kind, ext)| (*kind, *ext))
+}
+
+/// The accepted MIME types, for error messages.
+pub fn accepted_list() -> String {
+    TYPES
+        .iter()
+        .map(|(t, _, _)| *t)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether a file starting with `head` looks like `content_type`, going by
+/// the signature formats put at the start of their files.
+pub fn looks_like(content_type: &str, head: &[u8]) -> bool {
+    match content_type.trim().to_ascii_lowercase().as_str() {
+        "image/jpeg" => head.starts_with(&[0xFF, 0xD8, 0xFF]),
+        "image/png" => head.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "image/gif" => head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a"),
+        "image/webp" => head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP",
+        // ISO base media files (MP4, QuickTime) start with a box whose type
+        // follows its 4-byte size; QuickTime files may start with other boxes.
+        "video/mp4" => head.len() >= 8 && &head[4..8] == b"ftyp",
+        "video/quicktime" => {
+            head.len() >= 8
+                && matches!(
+                    &head[4..8],
+                    b"ftyp" | b"moov" | b"mdat" | b"wide" | b"free" | b"skip"
+                )
+        }
+        "video/webm" => head.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]),
+        _ => false,
+    }
+}
+
+/// Largest files accepted, shared as app state.
+#[derive(Clone, Copy, Debug)]
+pub struct UploadLimits {
+    pub max_image_bytes: u64,
+    pub max_video_bytes: u64,
+}
+
+impl Default for UploadLimits {
+    fn default() -> Self {
+        UploadLimits {
+            max_image_bytes: 10 * 1024 * 1024,
+            max_video_bytes: 500 * 1024 * 1024,
+        }
+    }
+}
+
+impl UploadLimits {
+    pub fn max_bytes(&self, kind: Kind) -> u64 {
+        match kind {
+            Kind::Image => self.max_image_bytes,
+            Kind::Video => self.max_video_bytes,
+        }
+    }
+}
@@ -0,0 +1,23 @@
+pub mod chunk;
+pub mod dto;
+pub mod error;
+pub mod expiry;
+pub mod handlers;
+pub mod limits;
+
+use handlers::*;
+use rapina::prelude::*;
+
+use crate::api::v1::merge_patch::PatchRoutes;
+
+pub fn project_routes() -> Router {
+    Router::new().post("/:id/uploads", create_upload)
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .get("/:id", get_upload)
+        .patch("/:id", upload_chunk)
+        .post("/:id/complete", complete_upload)
+        .delete("/:id", cancel_upload)
+}
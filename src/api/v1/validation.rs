@@ -0,0 +1,87 @@
+//! Shared rules for request validation.
+//!
+//! DTOs derive `Validate` and handlers take them as `Validated<Json<T>>`, so
+//! an invalid body is rejected with a 422 whose `details` map each invalid
+//! field to what was wrong with it.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use validator::{ValidationError, ValidationErrors};
+
+/// Lowercase words joined by single hyphens, e.g. `acme-inc`.
+pub static SLUG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z0-9]+(?:-[a-z0-9]+)*$").unwrap());
+
+/// `#rrggbb`.
+pub static HEX_COLOR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^#[0-9a-fA-F]{6}$").unwrap());
+
+pub static TESTIMONIAL_TYPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(text|video)$").unwrap());
+
+/// A BCP 47 language tag such as `en` or `pt-BR`.
+pub static LANGUAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-zA-Z]{2,3}(?:-[a-zA-Z0-9]{2,8})*$").unwrap());
+
+/// An absolute `http(s)` URL, or a path on this host such as the `url` of an
+/// upload when `MEDIA_BASE_URL` is relative.
+pub static MEDIA_URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:(?i:https?)://[^\s/?#]+(?:[/?#]\S*)?|/[^/\s]\S*)$").unwrap());
+
+pub const MIN_PASSWORD_LENGTH: u64 = 8;
+
+/// Passwords need at least one letter and one digit on top of the length.
+pub fn password_strength(password: &str) -> std::result::Result<(), ValidationError> {
+    let has_letter = password.chars().any(char::is_alphabetic);
+    let has_digit = password.chars().any(|c| c.is_ascii_digit());
+
+    if has_letter && has_digit {
+        Ok(())
+    } else {
+        Err(ValidationError::new("password_strength")
+            .with_message("must contain a letter and a digit".into()))
+    }
+}
+
+/// Validation context for DTOs with a field serialized under another name.
+///
+/// Deriving with `#[validate(context = JsonNames)]` leaves out the derived
+/// `Validate` impl; [`json_names!`](crate::json_names) supplies one that
+/// reports errors under the names clients actually send.
+pub struct JsonNames;
+
+/// Moves the errors of each `(field, name)` pair from the Rust field name to
+/// the JSON member name.
+pub fn rename_fields(
+    result: std::result::Result<(), ValidationErrors>,
+    names: &[(&str, &'static str)],
+) -> std::result::Result<(), ValidationErrors> {
+    result.map_err(|mut errors| {
+        let fields = errors.errors_mut();
+        for (field, name) in names {
+            if let Some(kind) = fields.remove(*field) {
+                fields.insert((*name).into(), kind);
+            }
+        }
+        errors
+    })
+}
+
+/// Implements `Validate` for a DTO derived with
+/// `#[validate(context = JsonNames)]`, renaming the given fields.
+#[macro_export]
+macro_rules! json_names {
+    ($ty:ty { $($field:ident => $name:literal),+ $(,)? }) => {
+        impl ::validator::Validate for $ty {
+            fn validate(&self) -> ::std::result::Result<(), ::validator::ValidationErrors> {
+                $crate::api::v1::validation::rename_fields(
+                    ::validator::ValidateArgs::validate_with_args(
+                        self,
+                        &$crate::api::v1::validation::JsonNames,
+                    ),
+                    &[$((stringify!($field), $name)),+],
+                )
+            }
+        }
+    };
+}
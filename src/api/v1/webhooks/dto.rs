@@ -0,0 +1,68 @@
+use rapina::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::webhooks::Event;
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct CreateWebhookRequest {
+    /// Where deliveries are POSTed.
+    #[validate(url, length(max = 2048))]
+    pub url: String,
+    #[validate(length(min = 1))]
+    pub events: Vec<Event>,
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct UpdateWebhookRequest {
+    #[validate(url, length(max = 2048))]
+    pub url: Option<String>,
+    #[validate(length(min = 1))]
+    pub events: Option<Vec<Event>>,
+    /// An empty description clears it.
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub project_id: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub events: Vec<Event>,
+    pub is_active: bool,
+    /// Key deliveries are signed with. Only returned when the webhook is
+    /// created; it is stored encrypted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct WebhookDeliveryResponse {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: String,
+    /// The JSON body that is sent.
+    pub payload: serde_json::Value,
+    /// `pending`, `succeeded` or `failed`. A pending delivery that has
+    /// attempts is waiting to be retried.
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    /// The start of the last response's body.
+    pub response_body: Option<String>,
+    /// Why the last attempt failed, if it did.
+    pub error: Option<String>,
+    pub duration_ms: Option<i32>,
+    /// The delivery this one sends again, if it is a redelivery.
+    pub redelivery_of: Option<String>,
+    pub created_at: String,
+    pub delivered_at: Option<String>,
+}
@@ -0,0 +1,70 @@
+use rapina::database::DbError;
+use rapina::prelude::*;
+
+pub enum WebhookError {
+    DbError(DbError),
+    NotFound,
+    DeliveryNotFound,
+    Forbidden,
+    Pending,
+    /// The url is not `http(s)`, or points to a non-public address.
+    InvalidUrl(String),
+    Internal(String),
+}
+
+impl IntoApiError for WebhookError {
+    fn into_api_error(self) -> Error {
+        match self {
+            WebhookError::DbError(e) => e.into_api_error(),
+            WebhookError::NotFound => Error::not_found("webhook not found"),
+            WebhookError::DeliveryNotFound => Error::not_found("webhook delivery not found"),
+            WebhookError::Forbidden => Error::forbidden("you do not own this project"),
+            WebhookError::Pending => Error::conflict("the delivery has not been attempted yet"),
+            WebhookError::InvalidUrl(msg) => Error::validation(msg),
+            WebhookError::Internal(msg) => Error::internal(msg),
+        }
+    }
+}
+
+impl DocumentedError for WebhookError {
+    fn error_variants() -> Vec<ErrorVariant> {
+        vec![
+            ErrorVariant {
+                status: 400,
+                code: "BAD_REQUEST",
+                description: "Invalid pagination cursor",
+            },
+            ErrorVariant {
+                status: 404,
+                code: "NOT_FOUND",
+                description: "Project, webhook or delivery not found",
+            },
+            ErrorVariant {
+                status: 403,
+                code: "FORBIDDEN",
+                description: "User does not own this project",
+            },
+            ErrorVariant {
+                status: 409,
+                code: "CONFLICT",
+                description: "The delivery is still waiting to be sent",
+            },
+            ErrorVariant {
+                status: 422,
+                code: "VALIDATION_ERROR",
+                description: "Request body failed validation, or the url is not an http(s) URL of a public address",
+            },
+            ErrorVariant {
+                status: 500,
+                code: "INTERNAL_ERROR",
+                description: "Internal server error",
+            },
+        ]
+    }
+}
+
+impl From<DbError> for WebhookError {
+    fn from(e: DbError) -> Self {
+        WebhookError::DbError(e)
+    }
+}
@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+
+use rapina::database::{Db, DbError};
+use rapina::prelude::*;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::api::v1::pagination::{self, Cursor, Page, PageQuery};
+use crate::db::entities::project::{
+    Column as ProjectColumn, Entity as Project, Model as ProjectModel,
+};
+use crate::db::entities::user::{Column as UserColumn, Entity as User};
+use crate::db::entities::webhook::{ActiveModel, Column, Entity as Webhook, Model};
+use crate::db::entities::webhook_delivery::{
+    Column as DeliveryColumn, Entity as Delivery, Model as DeliveryModel,
+};
+use crate::net::Destinations;
+use crate::secrets::Keyring;
+use crate::webhooks::{self, Event};
+
+use super::dto::{
+    CreateWebhookRequest, UpdateWebhookRequest, WebhookDeliveryResponse, WebhookResponse,
+};
+use super::error::WebhookError;
+
+fn to_response(webhook: Model, project_pid: &Uuid, secret: Option<String>) -> WebhookResponse {
+    WebhookResponse {
+        id: webhook.pid.to_string(),
+        project_id: project_pid.to_string(),
+        events: webhooks::events_of(&webhook),
+        url: webhook.url,
+        description: webhook.description,
+        is_active: webhook.is_active,
+        secret,
+        created_at: webhook.created_at.to_rfc3339(),
+        updated_at: webhook.updated_at.to_rfc3339(),
+    }
+}
+
+fn to_delivery_response(
+    delivery: DeliveryModel,
+    webhook_pid: &Uuid,
+    redelivery_of: Option<&Uuid>,
+) -> WebhookDeliveryResponse {
+    WebhookDeliveryResponse {
+        id: delivery.pid.to_string(),
+        webhook_id: webhook_pid.to_string(),
+        event: delivery.event,
+        payload: delivery.payload,
+        status: delivery.status,
+        attempts: delivery.attempts,
+        response_status: delivery.response_status,
+        response_body: delivery.response_body,
+        error: delivery.error,
+        duration_ms: delivery.duration_ms,
+        redelivery_of: redelivery_of.map(Uuid::to_string),
+        created_at: delivery.created_at.to_rfc3339(),
+        delivered_at: delivery.delivered_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+/// Public ids of the deliveries `deliveries` are redeliveries of.
+async fn load_redelivered(db: &Db, deliveries: &[DeliveryModel]) -> Result<HashMap<i32, Uuid>> {
+    let ids: Vec<i32> = deliveries.iter().filter_map(|d| d.redelivery_of).collect();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    Ok(Delivery::find()
+        .filter(DeliveryColumn::Id.is_in(ids))
+        .all(db.conn())
+        .await
+        .map_err(DbError)?
+        .into_iter()
+        .map(|d| (d.id, d.pid))
+        .collect())
+}
+
+/// `events` as stored, without duplicates.
+fn events_json(events: &[Event]) -> serde_json::Value {
+    let mut names: Vec<&str> = events.iter().map(|e| e.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    serde_json::json!(names)
+}
+
+async fn resolve_user_id(db: &Db, current_user: &CurrentUser) -> Result<i32> {
+    let pid = Uuid::parse_str(&current_user.id)
+        .map_err(|_| Error::unauthorized("invalid user id in token"))?;
+
+    let user = User::find()
+        .filter(UserColumn::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| Error::unauthorized("user not found"))?;
+
+    Ok(user.id)
+}
+
+/// Refuses webhook URLs deliveries may not be sent to. Host names are
+/// checked again as each delivery resolves them.
+fn check_url(destinations: Destinations, url: &str) -> Result<()> {
+    destinations
+        .check(url)
+        .map_err(|e| WebhookError::InvalidUrl(e.to_string()).into_api_error())
+}
+
+async fn find_owned_project(db: &Db, id: String, user_id: i32) -> Result<ProjectModel> {
+    let pid = Uuid::parse_str(&id).map_err(|_| WebhookError::NotFound.into_api_error())?;
+
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(pid))
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| WebhookError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(WebhookError::Forbidden.into_api_error());
+    }
+
+    Ok(project)
+}
+
+/// The webhook with id `webhook_id` and its project, if the user owns it.
+async fn find_owned(db: &Db, webhook_id: i32, user_id: i32) -> Result<(Model, ProjectModel)> {
+    let webhook = Webhook::find_by_id(webhook_id)
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| WebhookError::NotFound.into_api_error())?;
+
+    let project = Project::find_by_id(webhook.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| WebhookError::NotFound.into_api_error())?;
+
+    if project.user_id != user_id {
+        return Err(WebhookError::Forbidden.into_api_error());
+    }
+
+    Ok((webhook, project))
+}
+
+/// The webhook with public id `id` and its project, if the user owns it.
+async fn find_owned_webhook(db: &Db, id: String, user_id: i32) -> Result<(Model, ProjectModel)> {
+    let pid = Uuid::parse_str(&id).map_err(|_| WebhookError::NotFound.into_api_error())?;
+
+    let webhook = Webhook::find()
+        .filter(Column::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| WebhookError::NotFound.into_api_error())?;
+
+    find_owned(db, webhook.id, user_id).await
+}
+
+/// The delivery with public id `id` and its webhook, if the user owns it.
+async fn find_owned_delivery(db: &Db, id: String, user_id: i32) -> Result<(DeliveryModel, Model)> {
+    let pid = Uuid::parse_str(&id).map_err(|_| WebhookError::DeliveryNotFound.into_api_error())?;
+
+    let delivery = Delivery::find()
+        .filter(DeliveryColumn::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| WebhookError::DeliveryNotFound.into_api_error())?;
+
+    let (webhook, _) = find_owned(db, delivery.webhook_id, user_id).await?;
+
+    Ok((delivery, webhook))
+}
+
+#[get("/api/v1/projects/:id/webhooks")]
+#[errors(WebhookError)]
+pub async fn list_webhooks(
+    id: Path<String>,
+    query: Query<PageQuery>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<Page<WebhookResponse>>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let project = find_owned_project(&db, id.into_inner(), user_id).await?;
+
+    let page = query.into_inner();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = Webhook::find().filter(Column::ProjectId.eq(project.id));
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(Column::Id.lt(cursor.id));
+    }
+
+    let mut webhooks = q
+        .order_by_desc(Column::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut webhooks, limit, |w| Cursor::from_id(w.id));
+
+    Ok(Json(Page {
+        data: webhooks
+            .into_iter()
+            .map(|w| to_response(w, &project.pid, None))
+            .collect(),
+        next_cursor,
+        total,
+    }))
+}
+
+/// Creates a webhook and returns its signing secret, which is not shown
+/// again. The url must be `http(s)` and, unless the server allows private
+/// networks, must not point to a loopback, private or link-local address.
+#[post("/api/v1/projects/:id/webhooks")]
+#[errors(WebhookError)]
+pub async fn create_webhook(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    keyring: State<Keyring>,
+    destinations: State<Destinations>,
+    body: Validated<Json<CreateWebhookRequest>>,
+) -> Result<(StatusCode, Json<WebhookResponse>)> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let project = find_owned_project(&db, id.into_inner(), user_id).await?;
+
+    let req = body.into_inner().into_inner();
+    check_url(destinations.into_inner(), &req.url)?;
+    let secret = webhooks::generate_secret();
+    let sealed = webhooks::seal_secret(&keyring.into_inner(), &secret)
+        .map_err(|e| WebhookError::Internal(e.to_string()).into_api_error())?;
+    let mut webhook = ActiveModel {
+        pid: Set(Uuid::new_v4()),
+        project_id: Set(project.id),
+        url: Set(req.url),
+        description: Set(req.description.filter(|d| !d.is_empty())),
+        events: Set(events_json(&req.events)),
+        secret: Set(sealed),
+        ..Default::default()
+    };
+    if let Some(is_active) = req.is_active {
+        webhook.is_active = Set(is_active);
+    }
+
+    let webhook = webhook.insert(db.conn()).await.map_err(DbError)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(to_response(webhook, &project.pid, Some(secret))),
+    ))
+}
+
+#[get("/api/v1/webhooks/:id")]
+#[errors(WebhookError)]
+pub async fn get_webhook(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<WebhookResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (webhook, project) = find_owned_webhook(&db, id.into_inner(), user_id).await?;
+
+    Ok(Json(to_response(webhook, &project.pid, None)))
+}
+
+#[put("/api/v1/webhooks/:id")]
+#[errors(WebhookError)]
+pub async fn update_webhook(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    destinations: State<Destinations>,
+    body: Validated<Json<UpdateWebhookRequest>>,
+) -> Result<Json<WebhookResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (webhook, project) = find_owned_webhook(&db, id.into_inner(), user_id).await?;
+
+    let req = body.into_inner().into_inner();
+    let mut active = webhook.into_active_model();
+    if let Some(url) = req.url {
+        check_url(destinations.into_inner(), &url)?;
+        active.url = Set(url);
+    }
+    if let Some(events) = req.events {
+        active.events = Set(events_json(&events));
+    }
+    if let Some(description) = req.description {
+        active.description = Set(Some(description).filter(|d| !d.is_empty()));
+    }
+    if let Some(is_active) = req.is_active {
+        active.is_active = Set(is_active);
+    }
+
+    let webhook = active.update(db.conn()).await.map_err(DbError)?;
+
+    Ok(Json(to_response(webhook, &project.pid, None)))
+}
+
+/// Deletes the webhook with its delivery log. Deliveries still queued are
+/// dropped.
+#[delete("/api/v1/webhooks/:id")]
+#[errors(WebhookError)]
+pub async fn delete_webhook(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<StatusCode> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (webhook, _) = find_owned_webhook(&db, id.into_inner(), user_id).await?;
+
+    webhook.delete(db.conn()).await.map_err(DbError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The webhook's deliveries, newest first.
+#[get("/api/v1/webhooks/:id/deliveries")]
+#[errors(WebhookError)]
+pub async fn list_deliveries(
+    id: Path<String>,
+    query: Query<PageQuery>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<Page<WebhookDeliveryResponse>>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (webhook, _) = find_owned_webhook(&db, id.into_inner(), user_id).await?;
+
+    let page = query.into_inner();
+    let limit = page.limit();
+    let cursor = page.cursor()?;
+
+    let mut q = Delivery::find().filter(DeliveryColumn::WebhookId.eq(webhook.id));
+
+    let total = if page.include_total() {
+        Some(q.clone().count(db.conn()).await.map_err(DbError)?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = cursor {
+        q = q.filter(DeliveryColumn::Id.lt(cursor.id));
+    }
+
+    let mut deliveries = q
+        .order_by_desc(DeliveryColumn::Id)
+        .limit(limit + 1)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+    let next_cursor = pagination::next_cursor(&mut deliveries, limit, |d| Cursor::from_id(d.id));
+    let redelivered = load_redelivered(&db, &deliveries).await?;
+
+    Ok(Json(Page {
+        data: deliveries
+            .into_iter()
+            .map(|d| {
+                let original = d.redelivery_of.and_then(|id| redelivered.get(&id));
+                to_delivery_response(d, &webhook.pid, original)
+            })
+            .collect(),
+        next_cursor,
+        total,
+    }))
+}
+
+#[get("/api/v1/webhook-deliveries/:id")]
+#[errors(WebhookError)]
+pub async fn get_delivery(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<Json<WebhookDeliveryResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (delivery, webhook) = find_owned_delivery(&db, id.into_inner(), user_id).await?;
+
+    let redelivered = load_redelivered(&db, std::slice::from_ref(&delivery)).await?;
+    let original = delivery.redelivery_of.and_then(|id| redelivered.get(&id));
+
+    Ok(Json(to_delivery_response(delivery, &webhook.pid, original)))
+}
+
+/// Sends the delivery's data again as a new delivery, with a fresh id and
+/// all its attempts. Deliveries still being retried cannot be redelivered.
+#[post("/api/v1/webhook-deliveries/:id/redeliver")]
+#[errors(WebhookError)]
+pub async fn redeliver_delivery(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<(StatusCode, Json<WebhookDeliveryResponse>)> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let (delivery, webhook) = find_owned_delivery(&db, id.into_inner(), user_id).await?;
+
+    if delivery.status == webhooks::STATUS_PENDING {
+        return Err(WebhookError::Pending.into_api_error());
+    }
+
+    let txn = db.conn().begin().await.map_err(DbError)?;
+    let redelivery = webhooks::redeliver(&txn, &delivery)
+        .await
+        .map_err(DbError)?;
+    txn.commit().await.map_err(DbError)?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(to_delivery_response(
+            redelivery,
+            &webhook.pid,
+            Some(&delivery.pid),
+        )),
+    ))
+}
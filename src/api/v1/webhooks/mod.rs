@@ -0,0 +1,26 @@
+pub mod dto;
+pub mod error;
+pub mod handlers;
+
+use handlers::*;
+use rapina::prelude::*;
+
+pub fn project_routes() -> Router {
+    Router::new()
+        .get("/:id/webhooks", list_webhooks)
+        .post("/:id/webhooks", create_webhook)
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .get("/:id", get_webhook)
+        .put("/:id", update_webhook)
+        .delete("/:id", delete_webhook)
+        .get("/:id/deliveries", list_deliveries)
+}
+
+pub fn delivery_routes() -> Router {
+    Router::new()
+        .get("/:id", get_delivery)
+        .post("/:id/redeliver", redeliver_delivery)
+}
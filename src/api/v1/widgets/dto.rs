@@ -0,0 +1,72 @@
+use rapina::schemars::{self, JsonSchema};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use crate::api::v1::validation::LANGUAGE;
+use crate::images::ImageVariant;
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct SetWidgetLanguageRequest {
+    /// Comma-separated language tags, such as `en,pt-BR`, the widget shows
+    /// testimonials in. A bare language also matches its regional variants;
+    /// `null` shows every language.
+    #[validate(length(max = 255), custom(function = "language_list"))]
+    pub languages: Option<String>,
+    /// Show testimonials in the languages the visitor's browser asks for,
+    /// falling back to `languages` when it asks for none.
+    #[serde(default)]
+    pub visitor_language: bool,
+}
+
+fn language_list(languages: &str) -> std::result::Result<(), ValidationError> {
+    if languages
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .all(|tag| LANGUAGE.is_match(tag))
+    {
+        Ok(())
+    } else {
+        Err(ValidationError::new("regex")
+            .with_message("languages must be comma-separated language tags".into()))
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct WidgetLanguageResponse {
+    pub widget_id: String,
+    pub languages: Option<String>,
+    pub visitor_language: bool,
+}
+
+/// A testimonial as a widget shows it, without its author's contact details.
+#[derive(Serialize, JsonSchema)]
+pub struct WidgetTestimonialResponse {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub testimonial_type: String,
+    pub content: Option<String>,
+    pub rating: Option<i16>,
+    pub author_name: String,
+    pub author_title: Option<String>,
+    pub author_avatar_url: Option<String>,
+    pub author_avatar_variants: Vec<ImageVariant>,
+    pub author_company: Option<String>,
+    pub author_url: Option<String>,
+    pub video_url: Option<String>,
+    pub video_thumbnail_url: Option<String>,
+    pub source_platform: Option<String>,
+    pub source_url: Option<String>,
+    pub language: Option<String>,
+    pub is_featured: bool,
+    pub created_at: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct WidgetTestimonialsResponse {
+    pub widget_id: String,
+    /// The comma-separated language tags the testimonials were restricted to,
+    /// if any.
+    pub language: Option<String>,
+    pub testimonials: Vec<WidgetTestimonialResponse>,
+}
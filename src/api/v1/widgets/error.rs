@@ -0,0 +1,56 @@
+use rapina::database::DbError;
+use rapina::prelude::*;
+
+pub enum WidgetError {
+    DbError(DbError),
+    NotFound,
+    Forbidden,
+}
+
+impl IntoApiError for WidgetError {
+    fn into_api_error(self) -> Error {
+        match self {
+            WidgetError::DbError(e) => e.into_api_error(),
+            WidgetError::NotFound => Error::not_found("widget not found"),
+            WidgetError::Forbidden => Error::forbidden("you do not own this project"),
+        }
+    }
+}
+
+impl DocumentedError for WidgetError {
+    fn error_variants() -> Vec<ErrorVariant> {
+        vec![
+            ErrorVariant {
+                status: 404,
+                code: "NOT_FOUND",
+                description: "Widget not found",
+            },
+            ErrorVariant {
+                status: 403,
+                code: "FORBIDDEN",
+                description: "User does not own the widget's project",
+            },
+            ErrorVariant {
+                status: 412,
+                code: "PRECONDITION_FAILED",
+                description: "If-Match does not match the current ETag",
+            },
+            ErrorVariant {
+                status: 422,
+                code: "VALIDATION_ERROR",
+                description: "Request body failed validation; details list each invalid field",
+            },
+            ErrorVariant {
+                status: 500,
+                code: "INTERNAL_ERROR",
+                description: "Internal server error",
+            },
+        ]
+    }
+}
+
+impl From<DbError> for WidgetError {
+    fn from(e: DbError) -> Self {
+        WidgetError::DbError(e)
+    }
+}
@@ -0,0 +1,236 @@
+use rapina::database::{Db, DbError};
+use rapina::http::HeaderMap;
+use rapina::http::header::ACCEPT_LANGUAGE;
+use rapina::prelude::*;
+use rapina::sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set};
+use uuid::Uuid;
+
+use crate::api::v1::etag;
+use crate::api::v1::testimonials::dto::{ModerationStatus, TestimonialFilters};
+use crate::api::v1::testimonials::filters;
+use crate::db::entities::project::{Column as ProjectColumn, Entity as Project};
+use crate::db::entities::testimonial::{Column as TestimonialColumn, Model as TestimonialModel};
+use crate::db::entities::user::{Column as UserColumn, Entity as User};
+use crate::db::entities::widget::{ActiveModel, Column, Entity as Widget, Model};
+use crate::images;
+use crate::language;
+
+use super::dto::{
+    SetWidgetLanguageRequest, WidgetLanguageResponse, WidgetTestimonialResponse,
+    WidgetTestimonialsResponse,
+};
+use super::error::WidgetError;
+
+fn to_language_response(widget: Model) -> WidgetLanguageResponse {
+    WidgetLanguageResponse {
+        widget_id: widget.pid.to_string(),
+        languages: widget.languages,
+        visitor_language: widget.visitor_language,
+    }
+}
+
+fn to_testimonial_response(t: TestimonialModel) -> WidgetTestimonialResponse {
+    WidgetTestimonialResponse {
+        id: t.pid.to_string(),
+        testimonial_type: t.testimonial_type,
+        content: t.content,
+        rating: t.rating,
+        author_name: t.author_name,
+        author_title: t.author_title,
+        author_avatar_url: t.author_avatar_url,
+        author_avatar_variants: images::parse(t.author_avatar_variants),
+        author_company: t.author_company,
+        author_url: t.author_url,
+        video_url: t.video_url,
+        video_thumbnail_url: t.video_thumbnail_url,
+        source_platform: t.source_platform,
+        source_url: t.source_url,
+        language: t.language,
+        is_featured: t.is_featured,
+        created_at: t.created_at.to_rfc3339(),
+    }
+}
+
+async fn resolve_user_id(db: &Db, current_user: &CurrentUser) -> Result<i32> {
+    let pid = Uuid::parse_str(&current_user.id)
+        .map_err(|_| Error::unauthorized("invalid user id in token"))?;
+
+    let user = User::find()
+        .filter(UserColumn::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| Error::unauthorized("user not found"))?;
+
+    Ok(user.id)
+}
+
+/// The widget with public id `id`, with its project's owner, if the project
+/// still exists.
+async fn find_widget(db: &Db, id: String) -> Result<(Model, i32)> {
+    let pid = Uuid::parse_str(&id).map_err(|_| WidgetError::NotFound.into_api_error())?;
+
+    let widget = Widget::find()
+        .filter(Column::Pid.eq(pid))
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| WidgetError::NotFound.into_api_error())?;
+
+    let project = Project::find_by_id(widget.project_id)
+        .filter(ProjectColumn::DeletedAt.is_null())
+        .one(db.conn())
+        .await
+        .map_err(DbError)?
+        .ok_or_else(|| WidgetError::NotFound.into_api_error())?;
+
+    Ok((widget, project.user_id))
+}
+
+/// The widget with public id `id`, if the user owns its project.
+async fn find_owned_widget(db: &Db, id: String, user_id: i32) -> Result<Model> {
+    let (widget, owner_id) = find_widget(db, id).await?;
+    if owner_id != user_id {
+        return Err(WidgetError::Forbidden.into_api_error());
+    }
+    Ok(widget)
+}
+
+/// The languages the widget shows testimonials in for this request: the
+/// visitor's, when the widget follows them and the request names any, else
+/// the widget's own.
+fn languages_for(widget: &Model, headers: &HeaderMap) -> Option<String> {
+    if widget.visitor_language {
+        let mut tags = Vec::new();
+        let accepted = headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .map(language::accepted)
+            .unwrap_or_default();
+        for tag in accepted {
+            // `en-US` also takes testimonials only known to be in `en`.
+            let primary = tag.split('-').next().unwrap_or_default().to_string();
+            for tag in [tag, primary] {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+        if !tags.is_empty() {
+            return Some(tags.join(","));
+        }
+    }
+    widget.languages.clone()
+}
+
+/// The tag ids a widget's `tag_filter` lists; anything else in it is ignored.
+fn tag_ids(widget: &Model) -> Vec<String> {
+    widget
+        .tag_filter
+        .as_ref()
+        .and_then(|filter| filter.as_array())
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str())
+                .filter(|id| Uuid::parse_str(id).is_ok())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The approved testimonials a widget shows, newest first, restricted to the
+/// visitor's languages (from `Accept-Language`) when the widget follows them,
+/// or else to the widget's configured languages.
+#[public]
+#[get("/api/v1/widgets/:id/testimonials")]
+#[errors(WidgetError)]
+pub async fn list_widget_testimonials(
+    id: Path<String>,
+    db: Db,
+    headers: Headers,
+) -> Result<Json<WidgetTestimonialsResponse>> {
+    let (widget, _) = find_widget(&db, id.into_inner()).await?;
+
+    let language = languages_for(&widget, &headers.0);
+    let filters = TestimonialFilters {
+        status: Some(ModerationStatus::Approved),
+        is_featured: widget.featured_only.then_some(true),
+        min_rating: widget.min_rating,
+        tag_ids: tag_ids(&widget),
+        language: language.clone(),
+        ..Default::default()
+    };
+
+    let testimonials = filters::filtered(&db, widget.project_id, &filters)
+        .await?
+        .order_by_desc(TestimonialColumn::CreatedAt)
+        .order_by_desc(TestimonialColumn::Id)
+        .limit(widget.max_testimonials.max(0) as u64)
+        .all(db.conn())
+        .await
+        .map_err(DbError)?;
+
+    Ok(Json(WidgetTestimonialsResponse {
+        widget_id: widget.pid.to_string(),
+        language,
+        testimonials: testimonials
+            .into_iter()
+            .map(to_testimonial_response)
+            .collect(),
+    }))
+}
+
+/// Which languages the widget shows testimonials in.
+#[get("/api/v1/widgets/:id/language")]
+#[errors(WidgetError)]
+pub async fn get_widget_language(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+) -> Result<etag::Json<WidgetLanguageResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let widget = find_owned_widget(&db, id.into_inner(), user_id).await?;
+
+    Ok(etag::Json::new(
+        widget.updated_at,
+        to_language_response(widget),
+    ))
+}
+
+/// Restricts the widget to testimonials in the given languages, or in the
+/// visitor's.
+#[put("/api/v1/widgets/:id/language")]
+#[errors(WidgetError)]
+pub async fn set_widget_language(
+    id: Path<String>,
+    db: Db,
+    current_user: CurrentUser,
+    headers: Headers,
+    body: Validated<Json<SetWidgetLanguageRequest>>,
+) -> Result<etag::Json<WidgetLanguageResponse>> {
+    let user_id = resolve_user_id(&db, &current_user).await?;
+    let widget = find_owned_widget(&db, id.into_inner(), user_id).await?;
+    let guard = etag::if_match(&headers.0, widget.updated_at)?;
+    let req = body.into_inner().into_inner();
+
+    let languages = req.languages.map(|languages| {
+        languages
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .collect::<Vec<_>>()
+            .join(",")
+    });
+
+    let mut active: ActiveModel = widget.into();
+    active.languages = Set(languages.filter(|languages| !languages.is_empty()));
+    active.visitor_language = Set(req.visitor_language);
+
+    let updated = etag::update(db.conn(), active, Column::UpdatedAt, guard).await?;
+
+    Ok(etag::Json::new(
+        updated.updated_at,
+        to_language_response(updated),
+    ))
+}
@@ -0,0 +1,17 @@
+pub mod dto;
+pub mod error;
+pub mod handlers;
+
+use handlers::*;
+use rapina::prelude::*;
+
+/// Widgets are embedded on customers' sites, so their testimonials are served
+/// without auth.
+pub const PUBLIC_ROUTES: &[(&str, &str)] = &[("GET", "/api/v1/widgets/:id/testimonials")];
+
+pub fn routes() -> Router {
+    Router::new()
+        .get("/:id/testimonials", list_widget_testimonials)
+        .get("/:id/language", get_widget_language)
+        .put("/:id/language", set_widget_language)
+}
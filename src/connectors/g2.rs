@@ -0,0 +1,125 @@
+//! G2 survey responses (reviews) of a product.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use rapina::sea_orm::prelude::DateTimeWithTimeZone;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{
+    Connector, ConnectorError, ExternalReview, MAX_PAGES, credentials, parse_time, send, stars,
+    take_newer,
+};
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct Credentials {
+    api_token: String,
+    product_id: String,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    data: Vec<Resource>,
+    #[serde(default)]
+    links: Links,
+}
+
+#[derive(Default, Deserialize)]
+struct Links {
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Resource {
+    id: String,
+    attributes: Attributes,
+}
+
+#[derive(Deserialize)]
+struct Attributes {
+    star_rating: Option<f64>,
+    title: Option<String>,
+    user_name: Option<String>,
+    product_name: Option<String>,
+    public_url: Option<String>,
+    submitted_at: Option<String>,
+    #[serde(default)]
+    comment_answers: HashMap<String, Answer>,
+}
+
+#[derive(Deserialize)]
+struct Answer {
+    value: String,
+}
+
+pub struct G2 {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl G2 {
+    pub fn new(client: reqwest::Client, base_url: &str) -> Self {
+        G2 {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+fn to_review(resource: Resource) -> ExternalReview {
+    let mut a = resource.attributes;
+    // "What do you like best?" is the part of a G2 review that reads as a quote.
+    let content = a.comment_answers.remove("love").map(|answer| answer.value);
+
+    ExternalReview {
+        source_id: resource.id,
+        source_url: a.public_url,
+        author_name: a.user_name.unwrap_or_else(|| "G2 reviewer".to_string()),
+        content: content.or(a.title),
+        rating: a.star_rating.and_then(stars),
+        created_at: parse_time(a.submitted_at.as_deref()),
+        author_title: a.product_name.map(|product| format!("{product} user")),
+        ..Default::default()
+    }
+}
+
+#[async_trait]
+impl Connector for G2 {
+    fn validate(&self, credentials: &Value) -> Result<(), ConnectorError> {
+        super::credentials::<Credentials>(credentials).map(|_| ())
+    }
+
+    async fn fetch(
+        &self,
+        creds: &Value,
+        since: Option<DateTimeWithTimeZone>,
+    ) -> Result<Vec<ExternalReview>, ConnectorError> {
+        let creds: Credentials = credentials(creds)?;
+        let url = format!("{}/api/v1/survey-responses", self.base_url);
+
+        let mut reviews = Vec::new();
+        for page in 1..=MAX_PAGES {
+            let request = self
+                .client
+                .get(&url)
+                .header("Authorization", format!("Token token={}", creds.api_token))
+                .query(&[
+                    ("filter[product_id]", creds.product_id.clone()),
+                    ("page[size]", PAGE_SIZE.to_string()),
+                    ("page[number]", page.to_string()),
+                    ("sort", "-submitted_at".to_string()),
+                ]);
+            let body: Page = send(request).await?;
+
+            let reached = take_newer(since, body.data.into_iter().map(to_review), &mut reviews);
+            if reached || body.links.next.is_none() {
+                break;
+            }
+        }
+
+        Ok(reviews)
+    }
+}
@@ -0,0 +1,602 @@
+//! Connectors that pull reviews from external platforms.
+//!
+//! Each row of `import_sources` names a [`Platform`] and holds the credentials
+//! its [`Connector`] needs. Syncing a source fetches the platform's reviews
+//! and creates a testimonial for every review the project does not have yet,
+//! keyed on `source_platform` and `source_id`, which a unique index backs.
+//! A sync first [`claim`]s its source, so two never run at once. Every sync
+//! is recorded as a run, and sources with auto-sync on are synced by the
+//! [`scheduler`].
+
+mod g2;
+mod google_business;
+mod product_hunt;
+mod trustpilot;
+mod twitter;
+
+pub mod scheduler;
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rapina::schemars::{self, JsonSchema};
+use rapina::sea_orm::prelude::DateTimeWithTimeZone;
+use rapina::sea_orm::sea_query::{Condition, Expr, OnConflict};
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, Set, TransactionTrait, TryInsertResult,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::db::entities::import_source::{
+    ActiveModel as ImportSourceActiveModel, Column as ImportSourceColumn,
+    Entity as ImportSourceEntity, Model as ImportSource,
+};
+use crate::db::entities::import_sync_run::ActiveModel as ImportSyncRunActiveModel;
+use crate::db::entities::testimonial::{
+    ActiveModel as TestimonialActiveModel, Column as TestimonialColumn, Entity as Testimonial,
+    Model as TestimonialModel,
+};
+use crate::jobs;
+use crate::language;
+use crate::secrets::{self, Keyring, SecretError};
+use crate::sentiment::{self, AnalyzeSentiment};
+
+pub use g2::G2;
+pub use google_business::GoogleBusiness;
+pub use product_hunt::ProductHunt;
+pub use trustpilot::Trustpilot;
+pub use twitter::Twitter;
+
+/// `source` of testimonials created by a connector.
+pub const SOURCE: &str = "import";
+
+/// Upper bound on the pages a connector reads in one sync.
+pub const MAX_PAGES: usize = 20;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a claimed source is held if its sync never finishes.
+const CLAIM_LEASE_MINUTES: i64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Platform {
+    Trustpilot,
+    G2,
+    GoogleBusiness,
+    ProductHunt,
+    Twitter,
+}
+
+impl Platform {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Platform::Trustpilot => "trustpilot",
+            Platform::G2 => "g2",
+            Platform::GoogleBusiness => "google_business",
+            Platform::ProductHunt => "product_hunt",
+            Platform::Twitter => "twitter",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "trustpilot" => Some(Platform::Trustpilot),
+            "g2" => Some(Platform::G2),
+            "google_business" => Some(Platform::GoogleBusiness),
+            "product_hunt" => Some(Platform::ProductHunt),
+            "twitter" => Some(Platform::Twitter),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A review as fetched from a platform.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExternalReview {
+    /// The platform's id for the review; unique per platform.
+    pub source_id: String,
+    pub source_url: Option<String>,
+    pub author_name: String,
+    pub author_title: Option<String>,
+    pub author_avatar_url: Option<String>,
+    pub author_url: Option<String>,
+    pub content: Option<String>,
+    /// 1 to 5, where the platform rates reviews.
+    pub rating: Option<i16>,
+    pub language: Option<String>,
+    /// When the review was posted.
+    pub created_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Debug)]
+pub enum ConnectorError {
+    /// The source's credentials are missing or do not fit the platform.
+    InvalidCredentials(String),
+    /// The platform could not be reached or answered with an error.
+    Upstream(String),
+    /// The platform answered with something that is not a page of reviews.
+    InvalidResponse(String),
+    /// The stored credentials could not be opened with the keyring.
+    Secret(SecretError),
+    Db(DbErr),
+}
+
+impl std::fmt::Display for ConnectorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectorError::InvalidCredentials(msg) => write!(f, "invalid credentials: {msg}"),
+            ConnectorError::Upstream(msg) => write!(f, "platform request failed: {msg}"),
+            ConnectorError::InvalidResponse(msg) => {
+                write!(f, "unexpected platform response: {msg}")
+            }
+            ConnectorError::Secret(e) => write!(f, "cannot read credentials: {e}"),
+            ConnectorError::Db(e) => write!(f, "database error: {e}"),
+        }
+    }
+}
+
+impl From<SecretError> for ConnectorError {
+    fn from(e: SecretError) -> Self {
+        ConnectorError::Secret(e)
+    }
+}
+
+impl From<DbErr> for ConnectorError {
+    fn from(e: DbErr) -> Self {
+        ConnectorError::Db(e)
+    }
+}
+
+#[async_trait]
+pub trait Connector: Send + Sync {
+    /// Checks that `credentials` have what the platform needs.
+    fn validate(&self, credentials: &Value) -> Result<(), ConnectorError>;
+
+    /// The platform's reviews, newest first, up to [`MAX_PAGES`] pages of
+    /// them. With `since`, paging stops at the first page that reaches back
+    /// past it and older reviews are left out.
+    async fn fetch(
+        &self,
+        credentials: &Value,
+        since: Option<DateTimeWithTimeZone>,
+    ) -> Result<Vec<ExternalReview>, ConnectorError>;
+}
+
+/// Parses a source's credentials into the shape a connector expects.
+fn credentials<T: DeserializeOwned>(credentials: &Value) -> Result<T, ConnectorError> {
+    serde_json::from_value(credentials.clone())
+        .map_err(|e| ConnectorError::InvalidCredentials(e.to_string()))
+}
+
+/// Sends `request` and decodes its JSON body, treating any non-2xx status as
+/// an upstream failure.
+async fn send<T: DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T, ConnectorError> {
+    let res = request
+        .send()
+        .await
+        .map_err(|e| ConnectorError::Upstream(e.to_string()))?;
+
+    let status = res.status();
+    if !status.is_success() {
+        return Err(ConnectorError::Upstream(format!("status {status}")));
+    }
+
+    res.json()
+        .await
+        .map_err(|e| ConnectorError::InvalidResponse(e.to_string()))
+}
+
+/// A star rating rounded onto the 1 to 5 scale testimonials use.
+fn stars(rating: f64) -> Option<i16> {
+    (rating > 0.0).then(|| rating.round().clamp(1.0, 5.0) as i16)
+}
+
+/// Moves the reviews of `page` posted at or after `since` into `reviews`.
+/// Returns whether the page reached back past `since`, so that later pages
+/// hold nothing new.
+fn take_newer(
+    since: Option<DateTimeWithTimeZone>,
+    page: impl IntoIterator<Item = ExternalReview>,
+    reviews: &mut Vec<ExternalReview>,
+) -> bool {
+    let mut reached = false;
+    for review in page {
+        match (since, review.created_at) {
+            (Some(since), Some(at)) if at < since => reached = true,
+            _ => reviews.push(review),
+        }
+    }
+    reached
+}
+
+fn parse_time(at: Option<&str>) -> Option<DateTimeWithTimeZone> {
+    at.and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+}
+
+/// Base URL of each platform's API. Overridable so connectors can be pointed
+/// at a fixture server.
+#[derive(Clone, Debug)]
+pub struct BaseUrls {
+    pub trustpilot: String,
+    pub g2: String,
+    pub google_business: String,
+    pub product_hunt: String,
+    pub twitter: String,
+}
+
+impl Default for BaseUrls {
+    fn default() -> Self {
+        BaseUrls {
+            trustpilot: "https://api.trustpilot.com".to_string(),
+            g2: "https://data.g2.com".to_string(),
+            google_business: "https://mybusiness.googleapis.com".to_string(),
+            product_hunt: "https://api.producthunt.com".to_string(),
+            twitter: "https://api.twitter.com".to_string(),
+        }
+    }
+}
+
+impl BaseUrls {
+    /// Every platform served from one origin, as fixture servers do.
+    pub fn all(base_url: &str) -> Self {
+        BaseUrls {
+            trustpilot: base_url.to_string(),
+            g2: base_url.to_string(),
+            google_business: base_url.to_string(),
+            product_hunt: base_url.to_string(),
+            twitter: base_url.to_string(),
+        }
+    }
+}
+
+/// The connector for each platform, sharing one HTTP client.
+#[derive(Clone)]
+pub struct Connectors {
+    client: reqwest::Client,
+    base_urls: BaseUrls,
+}
+
+impl Connectors {
+    pub fn new(base_urls: BaseUrls) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("reeverb/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("failed to build HTTP client");
+
+        Connectors { client, base_urls }
+    }
+
+    pub fn connector(&self, platform: Platform) -> Box<dyn Connector> {
+        let client = self.client.clone();
+        let urls = &self.base_urls;
+
+        match platform {
+            Platform::Trustpilot => Box::new(Trustpilot::new(client, &urls.trustpilot)),
+            Platform::G2 => Box::new(G2::new(client, &urls.g2)),
+            Platform::GoogleBusiness => {
+                Box::new(GoogleBusiness::new(client, &urls.google_business))
+            }
+            Platform::ProductHunt => Box::new(ProductHunt::new(client, &urls.product_hunt)),
+            Platform::Twitter => Box::new(Twitter::new(client, &urls.twitter)),
+        }
+    }
+}
+
+/// What one sync did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncCounts {
+    pub fetched: usize,
+    pub created: usize,
+    /// Reviews the project already had.
+    pub skipped: usize,
+}
+
+/// Inserts `testimonial` unless its project already has one with the same
+/// `source_platform` and `source_id`, and returns it if it was inserted.
+pub async fn insert_unless_known(
+    conn: &impl ConnectionTrait,
+    testimonial: TestimonialActiveModel,
+) -> Result<Option<TestimonialModel>, DbErr> {
+    let on_conflict = OnConflict::columns([
+        TestimonialColumn::ProjectId,
+        TestimonialColumn::SourcePlatform,
+        TestimonialColumn::SourceId,
+    ])
+    .target_and_where(Expr::col(TestimonialColumn::SourceId).is_not_null())
+    .do_nothing()
+    .to_owned();
+
+    match Testimonial::insert(testimonial)
+        .on_conflict(on_conflict)
+        .do_nothing()
+        .exec_with_returning(conn)
+        .await?
+    {
+        TryInsertResult::Inserted(model) => Ok(Some(model)),
+        TryInsertResult::Empty | TryInsertResult::Conflicted => Ok(None),
+    }
+}
+
+/// Creates a testimonial in `project_id` for each review the project does not
+/// have yet. Imported testimonials start out pending like any other, and get
+/// their sentiment analyzed by a job.
+pub async fn ingest(
+    conn: &impl ConnectionTrait,
+    project_id: i32,
+    platform: Platform,
+    reviews: Vec<ExternalReview>,
+) -> Result<SyncCounts, DbErr> {
+    let source_ids: Vec<String> = reviews.iter().map(|r| r.source_id.clone()).collect();
+    let mut known: HashSet<String> = Testimonial::find()
+        .filter(TestimonialColumn::ProjectId.eq(project_id))
+        .filter(TestimonialColumn::SourcePlatform.eq(platform.as_str()))
+        .filter(TestimonialColumn::SourceId.is_in(source_ids))
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|t| t.source_id)
+        .collect();
+
+    let mut counts = SyncCounts {
+        fetched: reviews.len(),
+        ..Default::default()
+    };
+
+    for review in reviews {
+        if !known.insert(review.source_id.clone()) {
+            counts.skipped += 1;
+            continue;
+        }
+
+        let language = review
+            .language
+            .or_else(|| language::of(review.content.as_deref(), None));
+        let mut testimonial = TestimonialActiveModel {
+            pid: Set(Uuid::new_v4()),
+            project_id: Set(project_id),
+            testimonial_type: Set("text".to_string()),
+            original_content: Set(review.content.clone()),
+            content: Set(review.content),
+            rating: Set(review.rating),
+            author_name: Set(review.author_name),
+            author_title: Set(review.author_title),
+            author_avatar_url: Set(review.author_avatar_url),
+            author_url: Set(review.author_url),
+            source: Set(Some(SOURCE.to_string())),
+            source_platform: Set(Some(platform.as_str().to_string())),
+            source_url: Set(review.source_url),
+            source_id: Set(Some(review.source_id)),
+            language: Set(language),
+            ..Default::default()
+        };
+        if let Some(created_at) = review.created_at {
+            testimonial.created_at = Set(created_at);
+        }
+        let analyze = sentiment::queue(None, &mut testimonial);
+        // Another sync may have imported the review since it was looked up.
+        let Some(testimonial) = insert_unless_known(conn, testimonial).await? else {
+            counts.skipped += 1;
+            continue;
+        };
+        if analyze {
+            jobs::enqueue(
+                conn,
+                &AnalyzeSentiment {
+                    testimonial_id: testimonial.id,
+                },
+            )
+            .await?;
+        }
+        counts.created += 1;
+    }
+
+    Ok(counts)
+}
+
+/// What started a sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    Manual,
+    Scheduled,
+}
+
+impl Trigger {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Trigger::Manual => "manual",
+            Trigger::Scheduled => "scheduled",
+        }
+    }
+}
+
+pub const RUN_RUNNING: &str = "running";
+pub const RUN_SUCCEEDED: &str = "succeeded";
+pub const RUN_FAILED: &str = "failed";
+
+/// Wait before retrying a source whose last sync failed. Doubles with each
+/// failure in a row.
+const RETRY_BASE_MINUTES: i64 = 15;
+
+/// Longest a failing source waits between attempts.
+const MAX_RETRY_MINUTES: i64 = 24 * 60;
+
+/// How long to wait after the `failures`-th failed sync in a row.
+pub fn retry_delay(failures: i32) -> chrono::Duration {
+    let doublings = (failures - 1).clamp(0, 16) as u32;
+    let minutes = RETRY_BASE_MINUTES
+        .saturating_mul(1 << doublings)
+        .min(MAX_RETRY_MINUTES);
+    chrono::Duration::minutes(minutes)
+}
+
+/// Fetches the reviews posted since the source's cursor and ingests them in
+/// one transaction. Returns the counts and the newest posting time seen.
+async fn fetch_and_ingest(
+    conn: &DatabaseConnection,
+    connectors: &Connectors,
+    keyring: &Keyring,
+    source: &ImportSource,
+) -> Result<(SyncCounts, Option<DateTimeWithTimeZone>), ConnectorError> {
+    let platform = Platform::parse(&source.platform).ok_or_else(|| {
+        ConnectorError::InvalidCredentials(format!("unknown platform {}", source.platform))
+    })?;
+    let credentials = match &source.credentials {
+        Some(stored) => keyring.open(stored)?,
+        None => Value::Null,
+    };
+
+    let reviews = connectors
+        .connector(platform)
+        .fetch(&credentials, source.sync_cursor)
+        .await?;
+    let newest = reviews.iter().filter_map(|r| r.created_at).max();
+
+    let txn = conn.begin().await?;
+    let counts = ingest(&txn, source.project_id, platform, reviews).await?;
+    txn.commit().await?;
+
+    Ok((counts, newest))
+}
+
+/// Sources no sync holds a claim on.
+pub fn unclaimed(now: DateTimeWithTimeZone) -> Condition {
+    Condition::any()
+        .add(ImportSourceColumn::SyncClaimedUntil.is_null())
+        .add(ImportSourceColumn::SyncClaimedUntil.lt(now))
+}
+
+/// Claims the source for a sync, if none holds it and it matches `condition`,
+/// and returns whether it did. The claim lapses after a lease, in case the
+/// sync never finishes; [`sync`] releases it.
+pub async fn claim(
+    conn: &DatabaseConnection,
+    source_id: i32,
+    condition: Condition,
+) -> Result<bool, DbErr> {
+    let now = Utc::now().fixed_offset();
+    let claimed = ImportSourceEntity::update_many()
+        .col_expr(
+            ImportSourceColumn::SyncClaimedUntil,
+            Expr::value(now + chrono::Duration::minutes(CLAIM_LEASE_MINUTES)),
+        )
+        .filter(ImportSourceColumn::Id.eq(source_id))
+        .filter(unclaimed(now))
+        .filter(condition)
+        .exec(conn)
+        .await?
+        .rows_affected;
+    Ok(claimed > 0)
+}
+
+/// Syncs `source`, which the caller has [`claim`]ed, records the run and
+/// releases the claim. A successful sync moves the cursor forward and
+/// schedules the next one a sync interval out; a failed one is retried after
+/// [`retry_delay`].
+pub async fn sync(
+    conn: &DatabaseConnection,
+    connectors: &Connectors,
+    keyring: &Keyring,
+    source: ImportSource,
+    trigger: Trigger,
+) -> Result<SyncCounts, ConnectorError> {
+    let run = ImportSyncRunActiveModel {
+        pid: Set(Uuid::new_v4()),
+        import_source_id: Set(source.id),
+        trigger: Set(trigger.as_str().to_string()),
+        status: Set(RUN_RUNNING.to_string()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    let result = fetch_and_ingest(conn, connectors, keyring, &source).await;
+
+    let now = Utc::now().fixed_offset();
+    let mut run = run.into_active_model();
+    run.finished_at = Set(Some(now));
+    let cursor = source.sync_cursor;
+    let failures = source.consecutive_failures;
+    let interval = chrono::Duration::hours(source.sync_interval_hours.into());
+    let mut active = source.into_active_model();
+    active.sync_claimed_until = Set(None);
+
+    match &result {
+        Ok((counts, newest)) => {
+            run.status = Set(RUN_SUCCEEDED.to_string());
+            run.fetched_count = Set(counts.fetched as i32);
+            run.created_count = Set(counts.created as i32);
+            run.skipped_count = Set(counts.skipped as i32);
+            active.last_synced_at = Set(Some(now));
+            active.sync_cursor = Set(cursor.max(*newest));
+            active.consecutive_failures = Set(0);
+            active.next_sync_at = Set(Some(now + interval));
+        }
+        Err(e) => {
+            run.status = Set(RUN_FAILED.to_string());
+            run.error = Set(Some(e.to_string()));
+            active.consecutive_failures = Set(failures + 1);
+            active.next_sync_at = Set(Some(now + retry_delay(failures + 1)));
+        }
+    }
+
+    run.update(conn).await?;
+    active.update(conn).await?;
+
+    result.map(|(counts, _)| counts)
+}
+
+/// The stored form of `credentials`: sealed, with their masked hints.
+pub fn seal_credentials(
+    keyring: &Keyring,
+    credentials: &Value,
+) -> Result<(Value, Value), SecretError> {
+    Ok((keyring.seal(credentials)?, secrets::hints(credentials)))
+}
+
+/// Reseals every source's credentials under the keyring's active key, and
+/// seals any still stored as plaintext. Returns the number of sources
+/// updated. Run after adding a new active key, before retiring the old one.
+pub async fn reseal_credentials(
+    conn: &DatabaseConnection,
+    keyring: &Keyring,
+) -> Result<usize, ConnectorError> {
+    let sources = ImportSourceEntity::find()
+        .filter(ImportSourceColumn::Credentials.is_not_null())
+        .all(conn)
+        .await?;
+
+    let mut updated = 0;
+    for source in sources {
+        let Some(stored) = &source.credentials else {
+            continue;
+        };
+        let hints = (!secrets::is_sealed(stored)).then(|| secrets::hints(stored));
+        let Some(resealed) = keyring.reseal(stored)? else {
+            continue;
+        };
+
+        let mut active: ImportSourceActiveModel = source.into_active_model();
+        active.credentials = Set(Some(resealed));
+        if let Some(hints) = hints {
+            active.credential_hints = Set(Some(hints));
+        }
+        active.update(conn).await?;
+        updated += 1;
+    }
+
+    Ok(updated)
+}
@@ -0,0 +1,165 @@
+//! Product Hunt comments on a launch.
+//!
+//! The public GraphQL API does not expose launch reviews, so the comments on
+//! a post are what is imported. They carry no rating.
+
+use async_trait::async_trait;
+use rapina::sea_orm::prelude::DateTimeWithTimeZone;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{
+    Connector, ConnectorError, ExternalReview, MAX_PAGES, credentials, parse_time, send, take_newer,
+};
+
+const PAGE_SIZE: usize = 50;
+
+const QUERY: &str = "query Comments($slug: String!, $first: Int!, $after: String) {
+  post(slug: $slug) {
+    comments(first: $first, after: $after, order: NEWEST) {
+      edges { node { id body url createdAt user { name headline profileImage url } } }
+      pageInfo { hasNextPage endCursor }
+    }
+  }
+}";
+
+#[derive(Deserialize)]
+struct Credentials {
+    access_token: String,
+    post_slug: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    data: Option<Data>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Data {
+    post: Option<Post>,
+}
+
+#[derive(Deserialize)]
+struct Post {
+    comments: Connection,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection {
+    edges: Vec<Edge>,
+    page_info: PageInfo,
+}
+
+#[derive(Deserialize)]
+struct Edge {
+    node: Comment,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Comment {
+    id: String,
+    body: Option<String>,
+    url: Option<String>,
+    created_at: Option<String>,
+    user: User,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct User {
+    name: String,
+    headline: Option<String>,
+    profile_image: Option<String>,
+    url: Option<String>,
+}
+
+pub struct ProductHunt {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl ProductHunt {
+    pub fn new(client: reqwest::Client, base_url: &str) -> Self {
+        ProductHunt {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+fn to_review(comment: Comment) -> ExternalReview {
+    ExternalReview {
+        source_id: comment.id,
+        source_url: comment.url,
+        author_name: comment.user.name,
+        author_title: comment.user.headline,
+        author_avatar_url: comment.user.profile_image,
+        author_url: comment.user.url,
+        content: comment.body,
+        created_at: parse_time(comment.created_at.as_deref()),
+        ..Default::default()
+    }
+}
+
+#[async_trait]
+impl Connector for ProductHunt {
+    fn validate(&self, credentials: &Value) -> Result<(), ConnectorError> {
+        super::credentials::<Credentials>(credentials).map(|_| ())
+    }
+
+    async fn fetch(
+        &self,
+        creds: &Value,
+        since: Option<DateTimeWithTimeZone>,
+    ) -> Result<Vec<ExternalReview>, ConnectorError> {
+        let creds: Credentials = credentials(creds)?;
+        let url = format!("{}/v2/api/graphql", self.base_url);
+
+        let mut reviews = Vec::new();
+        let mut after: Option<String> = None;
+        for _ in 0..MAX_PAGES {
+            let request = self
+                .client
+                .post(&url)
+                .bearer_auth(&creds.access_token)
+                .json(&json!({
+                    "query": QUERY,
+                    "variables": { "slug": creds.post_slug, "first": PAGE_SIZE, "after": after },
+                }));
+            let body: Response = send(request).await?;
+
+            if let Some(error) = body.errors.into_iter().next() {
+                return Err(ConnectorError::Upstream(error.message));
+            }
+            let post = body.data.and_then(|data| data.post).ok_or_else(|| {
+                ConnectorError::InvalidResponse(format!("post {} not found", creds.post_slug))
+            })?;
+
+            let comments = post.comments;
+            let page = comments.edges.into_iter().map(|edge| to_review(edge.node));
+            let reached = take_newer(since, page, &mut reviews);
+            after = comments.page_info.end_cursor;
+            if reached || !comments.page_info.has_next_page || after.is_none() {
+                break;
+            }
+        }
+
+        Ok(reviews)
+    }
+}
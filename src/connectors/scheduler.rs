@@ -0,0 +1,113 @@
+//! Periodic syncing of import sources that have auto-sync on.
+//!
+//! A source is due once its `next_sync_at` has passed, or straight away if it
+//! has never been scheduled. Before syncing a source the scheduler
+//! [`claim`]s it, so several app instances, or a manual sync, never sync the
+//! same source at once. The sync then sets the next time, backing off while
+//! the source keeps failing.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use rapina::prelude::tracing;
+use rapina::sea_orm::prelude::DateTimeWithTimeZone;
+use rapina::sea_orm::sea_query::{Condition, Expr, Query};
+use rapina::sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::db::entities::import_source::{Column, Entity as ImportSource};
+use crate::db::entities::project::{Column as ProjectColumn, Entity as Project};
+use crate::jobs::{Job, JobError};
+use crate::secrets::Keyring;
+
+use super::{Connectors, Trigger, claim, sync, unclaimed};
+
+/// How often the scheduler looks for due sources.
+pub const SCHEDULE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Upper bound on the sources synced per pass.
+const BATCH_SIZE: u64 = 50;
+
+fn due(now: DateTimeWithTimeZone) -> Condition {
+    Condition::all().add(Column::AutoSync.eq(true)).add(
+        Condition::any()
+            .add(Column::NextSyncAt.is_null())
+            .add(Column::NextSyncAt.lte(now)),
+    )
+}
+
+/// What one pass of the scheduler did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SchedulerCounts {
+    pub synced: usize,
+    pub failed: usize,
+}
+
+/// Syncs every due source of a live project, oldest due first. A failed sync
+/// is recorded on its source and does not stop the pass.
+pub async fn sync_due(
+    conn: &DatabaseConnection,
+    connectors: &Connectors,
+    keyring: &Keyring,
+) -> Result<SchedulerCounts, DbErr> {
+    let now = Utc::now().fixed_offset();
+    let live_projects = Query::select()
+        .column(ProjectColumn::Id)
+        .from(Project)
+        .and_where(Expr::col(ProjectColumn::DeletedAt).is_null())
+        .to_owned();
+
+    let sources = ImportSource::find()
+        .filter(due(now))
+        .filter(unclaimed(now))
+        .filter(Column::ProjectId.in_subquery(live_projects))
+        .order_by_asc(Column::NextSyncAt)
+        .order_by_asc(Column::Id)
+        .limit(BATCH_SIZE)
+        .all(conn)
+        .await?;
+
+    let mut counts = SchedulerCounts::default();
+    for source in sources {
+        if !claim(conn, source.id, due(now)).await? {
+            continue;
+        }
+
+        let id = source.pid;
+        let platform = source.platform.clone();
+        match sync(conn, connectors, keyring, source, Trigger::Scheduled).await {
+            Ok(_) => counts.synced += 1,
+            Err(e) => {
+                tracing::warn!(source = %id, platform, error = %e, "import source sync failed");
+                counts.failed += 1;
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Job that runs [`sync_due`].
+#[derive(Serialize, Deserialize)]
+pub struct SyncDueSources;
+
+impl Job for SyncDueSources {
+    const KIND: &'static str = "import_sources.sync_due";
+}
+
+impl SyncDueSources {
+    pub async fn run(
+        self,
+        conn: &DatabaseConnection,
+        connectors: &Connectors,
+        keyring: &Keyring,
+    ) -> Result<(), JobError> {
+        let SchedulerCounts { synced, failed } = sync_due(conn, connectors, keyring).await?;
+        if synced > 0 || failed > 0 {
+            tracing::info!(synced, failed, "synced import sources");
+        }
+        Ok(())
+    }
+}
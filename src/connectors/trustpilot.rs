@@ -0,0 +1,116 @@
+//! Trustpilot service reviews of a business unit.
+
+use async_trait::async_trait;
+use rapina::sea_orm::prelude::DateTimeWithTimeZone;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{
+    Connector, ConnectorError, ExternalReview, MAX_PAGES, credentials, parse_time, send, stars,
+    take_newer,
+};
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct Credentials {
+    api_key: String,
+    business_unit_id: String,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    reviews: Vec<Review>,
+    #[serde(default)]
+    links: Vec<Link>,
+}
+
+#[derive(Deserialize)]
+struct Link {
+    rel: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Review {
+    id: String,
+    stars: f64,
+    title: Option<String>,
+    text: Option<String>,
+    language: Option<String>,
+    created_at: Option<String>,
+    consumer: Consumer,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Consumer {
+    display_name: String,
+}
+
+pub struct Trustpilot {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Trustpilot {
+    pub fn new(client: reqwest::Client, base_url: &str) -> Self {
+        Trustpilot {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+fn to_review(review: Review) -> ExternalReview {
+    ExternalReview {
+        source_url: Some(format!("https://www.trustpilot.com/reviews/{}", review.id)),
+        source_id: review.id,
+        author_name: review.consumer.display_name,
+        content: review.text.or(review.title),
+        rating: stars(review.stars),
+        language: review.language,
+        created_at: parse_time(review.created_at.as_deref()),
+        ..Default::default()
+    }
+}
+
+#[async_trait]
+impl Connector for Trustpilot {
+    fn validate(&self, credentials: &Value) -> Result<(), ConnectorError> {
+        super::credentials::<Credentials>(credentials).map(|_| ())
+    }
+
+    async fn fetch(
+        &self,
+        creds: &Value,
+        since: Option<DateTimeWithTimeZone>,
+    ) -> Result<Vec<ExternalReview>, ConnectorError> {
+        let creds: Credentials = credentials(creds)?;
+        let url = format!(
+            "{}/v1/business-units/{}/reviews",
+            self.base_url, creds.business_unit_id
+        );
+
+        let mut reviews = Vec::new();
+        for page in 1..=MAX_PAGES {
+            let request = self
+                .client
+                .get(&url)
+                .header("apikey", &creds.api_key)
+                .query(&[
+                    ("perPage", PAGE_SIZE.to_string()),
+                    ("page", page.to_string()),
+                    ("orderBy", "createdat.desc".to_string()),
+                ]);
+            let body: Page = send(request).await?;
+
+            let reached = take_newer(since, body.reviews.into_iter().map(to_review), &mut reviews);
+            if reached || !body.links.iter().any(|link| link.rel == "next-page") {
+                break;
+            }
+        }
+
+        Ok(reviews)
+    }
+}
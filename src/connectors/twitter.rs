@@ -0,0 +1,148 @@
+//! Posts on X (formerly Twitter) matching a search query.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+use rapina::sea_orm::prelude::DateTimeWithTimeZone;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{
+    Connector, ConnectorError, ExternalReview, MAX_PAGES, credentials, parse_time, send, take_newer,
+};
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct Credentials {
+    bearer_token: String,
+    /// A search query such as `@acme -is:retweet`.
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    #[serde(default)]
+    data: Vec<Tweet>,
+    #[serde(default)]
+    includes: Includes,
+    meta: Option<Meta>,
+}
+
+#[derive(Deserialize)]
+struct Tweet {
+    id: String,
+    text: String,
+    author_id: Option<String>,
+    created_at: Option<String>,
+    lang: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct Includes {
+    #[serde(default)]
+    users: Vec<User>,
+}
+
+#[derive(Deserialize)]
+struct User {
+    id: String,
+    name: String,
+    username: String,
+    profile_image_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    next_token: Option<String>,
+}
+
+pub struct Twitter {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Twitter {
+    pub fn new(client: reqwest::Client, base_url: &str) -> Self {
+        Twitter {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+fn to_review(tweet: Tweet, users: &HashMap<String, User>) -> ExternalReview {
+    let user = tweet.author_id.as_ref().and_then(|id| users.get(id));
+    let handle = user.map_or("i", |u| u.username.as_str());
+
+    ExternalReview {
+        source_url: Some(format!("https://x.com/{handle}/status/{}", tweet.id)),
+        source_id: tweet.id,
+        author_name: user.map_or_else(|| "X user".to_string(), |u| u.name.clone()),
+        author_title: user.map(|u| format!("@{}", u.username)),
+        author_avatar_url: user.and_then(|u| u.profile_image_url.clone()),
+        author_url: user.map(|u| format!("https://x.com/{}", u.username)),
+        content: Some(tweet.text),
+        // X reports undetermined text as "und".
+        language: tweet.lang.filter(|lang| lang != "und"),
+        created_at: parse_time(tweet.created_at.as_deref()),
+        ..Default::default()
+    }
+}
+
+#[async_trait]
+impl Connector for Twitter {
+    fn validate(&self, credentials: &Value) -> Result<(), ConnectorError> {
+        super::credentials::<Credentials>(credentials).map(|_| ())
+    }
+
+    async fn fetch(
+        &self,
+        creds: &Value,
+        since: Option<DateTimeWithTimeZone>,
+    ) -> Result<Vec<ExternalReview>, ConnectorError> {
+        let creds: Credentials = credentials(creds)?;
+        let url = format!("{}/2/tweets/search/recent", self.base_url);
+
+        let mut reviews = Vec::new();
+        let mut next_token: Option<String> = None;
+        for _ in 0..MAX_PAGES {
+            let mut request = self
+                .client
+                .get(&url)
+                .bearer_auth(&creds.bearer_token)
+                .query(&[
+                    ("query", creds.query.as_str()),
+                    ("max_results", &PAGE_SIZE.to_string()),
+                    ("expansions", "author_id"),
+                    ("tweet.fields", "created_at,lang"),
+                    ("user.fields", "name,username,profile_image_url"),
+                ]);
+            if let Some(token) = &next_token {
+                request = request.query(&[("next_token", token)]);
+            }
+            if let Some(since) = since {
+                let start_time = since.to_utc().to_rfc3339_opts(SecondsFormat::Secs, true);
+                request = request.query(&[("start_time", start_time)]);
+            }
+            let body: Page = send(request).await?;
+
+            let users: HashMap<String, User> = body
+                .includes
+                .users
+                .into_iter()
+                .map(|u| (u.id.clone(), u))
+                .collect();
+            let page = body.data.into_iter().map(|tweet| to_review(tweet, &users));
+            let reached = take_newer(since, page, &mut reviews);
+
+            next_token = body.meta.and_then(|meta| meta.next_token);
+            if reached || next_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(reviews)
+    }
+}
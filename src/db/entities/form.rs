@@ -0,0 +1,42 @@
+use rapina::sea_orm;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "forms")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub pid: Uuid,
+    pub project_id: i32,
+    pub name: String,
+    #[sea_orm(unique)]
+    pub slug: String,
+    pub headline: Option<String>,
+    pub description: Option<String>,
+    pub questions: Json,
+    pub allow_video: bool,
+    pub allow_text: bool,
+    pub require_rating: bool,
+    pub logo_url: Option<String>,
+    pub accent_color: String,
+    pub background_color: String,
+    pub thank_you_title: Option<String>,
+    pub thank_you_message: Option<String>,
+    pub thank_you_cta_text: Option<String>,
+    pub thank_you_cta_url: Option<String>,
+    pub incentive_enabled: bool,
+    pub incentive_description: Option<String>,
+    pub share_enabled: bool,
+    pub share_message: Option<String>,
+    pub is_active: bool,
+    pub submission_count: i32,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,31 @@
+use rapina::sea_orm;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "imports")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub pid: Uuid,
+    pub project_id: i32,
+    pub user_id: Option<i32>,
+    pub filename: Option<String>,
+    pub status: String,
+    pub mapping: Json,
+    pub total_rows: i32,
+    pub created_count: i32,
+    pub skipped_count: i32,
+    pub failed_count: i32,
+    pub errors: Json,
+    /// The uploaded CSV, kept until the import job is done with it.
+    pub file: Option<Vec<u8>>,
+    pub created_at: DateTimeWithTimeZone,
+    pub completed_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
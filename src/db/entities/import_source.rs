@@ -0,0 +1,37 @@
+use rapina::sea_orm;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "import_sources")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub pid: Uuid,
+    pub project_id: i32,
+    pub platform: String,
+    /// Sealed with [`crate::secrets::Keyring`]; never returned by the API.
+    pub credentials: Option<Json>,
+    /// Masked hints of the credentials, safe to show.
+    pub credential_hints: Option<Json>,
+    pub auto_sync: bool,
+    pub last_synced_at: Option<DateTimeWithTimeZone>,
+    pub sync_interval_hours: i32,
+    pub created_at: DateTimeWithTimeZone,
+    /// Posting time of the newest review seen; later syncs stop paging once
+    /// they reach reviews older than it.
+    pub sync_cursor: Option<DateTimeWithTimeZone>,
+    /// When the scheduler next syncs the source; `None` means as soon as
+    /// auto-sync is on.
+    pub next_sync_at: Option<DateTimeWithTimeZone>,
+    pub consecutive_failures: i32,
+    /// Set while a sync of the source runs; another sync cannot start until
+    /// it is cleared or has passed.
+    pub sync_claimed_until: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
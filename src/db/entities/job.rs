@@ -0,0 +1,32 @@
+use rapina::sea_orm;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "jobs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub pid: Uuid,
+    pub kind: String,
+    pub payload: Json,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    #[sea_orm(unique)]
+    pub schedule: Option<String>,
+    pub repeat_seconds: Option<i64>,
+    pub run_at: DateTimeWithTimeZone,
+    pub locked_until: Option<DateTimeWithTimeZone>,
+    pub locked_by: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+    pub finished_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,5 +1,18 @@
+pub mod form;
+pub mod import;
+pub mod import_source;
+pub mod import_sync_run;
+pub mod incentive_code;
+pub mod job;
 pub mod project;
+pub mod project_member;
 pub mod tag;
 pub mod testimonial;
+pub mod testimonial_revision;
 pub mod testimonial_tag;
+pub mod thank_you_email;
+pub mod upload;
 pub mod user;
+pub mod webhook;
+pub mod webhook_delivery;
+pub mod widget;
@@ -1,4 +1,5 @@
 use rapina::sea_orm;
+use sea_orm::Set;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -14,12 +15,27 @@ pub struct Model {
     #[sea_orm(unique)]
     pub slug: String,
     pub logo_url: Option<String>,
+    pub logo_variants: Option<Json>,
     pub website_url: Option<String>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Every update bumps `updated_at`, which the resource's `ETag` is derived from.
+    async fn before_save<C: ConnectionTrait>(
+        mut self,
+        _db: &C,
+        insert: bool,
+    ) -> Result<Self, DbErr> {
+        if !insert {
+            self.updated_at = Set(chrono::Utc::now().fixed_offset());
+        }
+        Ok(self)
+    }
+}
@@ -1,4 +1,5 @@
 use rapina::sea_orm;
+use sea_orm::Set;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -13,31 +14,55 @@ pub struct Model {
     #[sea_orm(column_name = "type")]
     pub testimonial_type: String,
     pub content: Option<String>,
+    pub original_content: Option<String>,
     pub rating: Option<i16>,
     pub author_name: String,
     pub author_email: Option<String>,
     pub author_title: Option<String>,
     pub author_avatar_url: Option<String>,
+    pub author_avatar_variants: Option<Json>,
     pub author_company: Option<String>,
     pub author_url: Option<String>,
     pub video_url: Option<String>,
     pub video_thumbnail_url: Option<String>,
     pub video_duration_seconds: Option<i32>,
     pub transcription: Option<String>,
+    pub video_status: Option<String>,
+    pub video_error: Option<String>,
     pub source: Option<String>,
     pub source_platform: Option<String>,
     pub source_url: Option<String>,
     pub source_id: Option<String>,
+    /// The collection form it was submitted through.
+    pub form_id: Option<i32>,
     pub sentiment: Option<String>,
     pub sentiment_score: Option<f32>,
     pub language: Option<String>,
     pub is_approved: bool,
     pub is_featured: bool,
+    pub status: String,
+    pub rejection_reason: Option<String>,
+    pub moderated_by: Option<i32>,
+    pub moderated_at: Option<DateTimeWithTimeZone>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Every update bumps `updated_at`, which the resource's `ETag` is derived from.
+    async fn before_save<C: ConnectionTrait>(
+        mut self,
+        _db: &C,
+        insert: bool,
+    ) -> Result<Self, DbErr> {
+        if !insert {
+            self.updated_at = Set(chrono::Utc::now().fixed_offset());
+        }
+        Ok(self)
+    }
+}
@@ -0,0 +1,26 @@
+use rapina::sea_orm;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "thank_you_emails")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub pid: Uuid,
+    pub form_id: i32,
+    #[sea_orm(unique)]
+    pub testimonial_id: i32,
+    pub email: String,
+    pub incentive: Option<String>,
+    #[sea_orm(unique)]
+    pub code_id: Option<i32>,
+    pub created_at: DateTimeWithTimeZone,
+    pub sent_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,32 @@
+use rapina::sea_orm;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "uploads")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub pid: Uuid,
+    pub project_id: i32,
+    pub user_id: Option<i32>,
+    #[sea_orm(unique)]
+    pub key: String,
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub size: i64,
+    pub received: i64,
+    pub multipart_id: Option<String>,
+    pub parts: Json,
+    pub variants: Option<Json>,
+    pub status: String,
+    pub created_at: DateTimeWithTimeZone,
+    pub expires_at: DateTimeWithTimeZone,
+    pub completed_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
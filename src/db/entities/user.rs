@@ -17,6 +17,9 @@ pub struct Model {
     pub avatar_url: Option<String>,
     pub oauth_provider: Option<String>,
     pub oauth_id: Option<String>,
+    /// `immediate`, `digest` or `off`.
+    pub submission_notifications: String,
+    pub digest_sent_at: Option<DateTimeWithTimeZone>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
@@ -0,0 +1,59 @@
+use rapina::sea_orm;
+use sea_orm::Set;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "widgets")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub pid: Uuid,
+    pub project_id: i32,
+    pub name: String,
+    pub widget_type: String,
+    /// Public ids of the tags a testimonial needs one of to be shown.
+    pub tag_filter: Option<Json>,
+    pub min_rating: Option<i16>,
+    pub featured_only: bool,
+    pub max_testimonials: i32,
+    /// Comma-separated language tags the widget shows testimonials in.
+    pub languages: Option<String>,
+    /// Whether the visitor's `Accept-Language` picks the languages, before
+    /// `languages`.
+    pub visitor_language: bool,
+    pub theme: String,
+    pub accent_color: String,
+    pub border_radius: i32,
+    pub font_family: Option<String>,
+    pub custom_css: Option<String>,
+    pub autoplay: bool,
+    pub autoplay_speed: i32,
+    pub show_rating: bool,
+    pub show_avatar: bool,
+    pub show_date: bool,
+    pub show_source: bool,
+    pub view_count: i64,
+    pub click_count: i64,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Every update bumps `updated_at`, which the resource's `ETag` is derived from.
+    async fn before_save<C: ConnectionTrait>(
+        mut self,
+        _db: &C,
+        insert: bool,
+    ) -> Result<Self, DbErr> {
+        if !insert {
+            self.updated_at = Set(chrono::Utc::now().fixed_offset());
+        }
+        Ok(self)
+    }
+}
@@ -0,0 +1,115 @@
+//! Migration: add testimonial search
+//!
+//! Adds a `search_vector` tsvector to testimonials, kept up to date by a
+//! trigger. Content and transcription are stemmed with the text search
+//! configuration matching `testimonials.language` (falling back to `simple`);
+//! author fields are indexed unstemmed.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("ALTER TABLE testimonials ADD COLUMN search_vector tsvector")
+            .await?;
+
+        db.execute_unprepared(
+            r#"
+            CREATE OR REPLACE FUNCTION testimonials_search_config(lang text)
+            RETURNS regconfig
+            LANGUAGE sql IMMUTABLE AS $$
+                SELECT CASE lower(split_part(coalesce(lang, ''), '-', 1))
+                    WHEN 'ar' THEN 'arabic'
+                    WHEN 'da' THEN 'danish'
+                    WHEN 'de' THEN 'german'
+                    WHEN 'el' THEN 'greek'
+                    WHEN 'en' THEN 'english'
+                    WHEN 'es' THEN 'spanish'
+                    WHEN 'fi' THEN 'finnish'
+                    WHEN 'fr' THEN 'french'
+                    WHEN 'hu' THEN 'hungarian'
+                    WHEN 'id' THEN 'indonesian'
+                    WHEN 'it' THEN 'italian'
+                    WHEN 'nl' THEN 'dutch'
+                    WHEN 'no' THEN 'norwegian'
+                    WHEN 'pt' THEN 'portuguese'
+                    WHEN 'ro' THEN 'romanian'
+                    WHEN 'ru' THEN 'russian'
+                    WHEN 'sv' THEN 'swedish'
+                    WHEN 'tr' THEN 'turkish'
+                    ELSE 'simple'
+                END::regconfig
+            $$
+            "#,
+        )
+        .await?;
+
+        db.execute_unprepared(
+            r#"
+            CREATE OR REPLACE FUNCTION testimonials_search_vector_update()
+            RETURNS trigger
+            LANGUAGE plpgsql AS $$
+            DECLARE
+                cfg regconfig := testimonials_search_config(NEW.language);
+            BEGIN
+                NEW.search_vector :=
+                    setweight(to_tsvector(cfg, coalesce(NEW.content, '')), 'A')
+                    || setweight(to_tsvector(cfg, coalesce(NEW.transcription, '')), 'B')
+                    || setweight(
+                        to_tsvector(
+                            'simple',
+                            concat_ws(' ', NEW.author_name, NEW.author_company, NEW.author_title)
+                        ),
+                        'C'
+                    );
+                RETURN NEW;
+            END
+            $$
+            "#,
+        )
+        .await?;
+
+        db.execute_unprepared(
+            r#"
+            CREATE TRIGGER testimonials_search_vector
+            BEFORE INSERT OR UPDATE OF content, transcription, author_name, author_company,
+                author_title, language
+            ON testimonials
+            FOR EACH ROW EXECUTE FUNCTION testimonials_search_vector_update()
+            "#,
+        )
+        .await?;
+
+        // Backfill existing rows through the trigger
+        db.execute_unprepared("UPDATE testimonials SET content = content")
+            .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX idx_testimonials_search ON testimonials USING GIN (search_vector)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("DROP TRIGGER IF EXISTS testimonials_search_vector ON testimonials")
+            .await?;
+        db.execute_unprepared("DROP FUNCTION IF EXISTS testimonials_search_vector_update()")
+            .await?;
+        db.execute_unprepared("DROP FUNCTION IF EXISTS testimonials_search_config(text)")
+            .await?;
+        db.execute_unprepared("ALTER TABLE testimonials DROP COLUMN IF EXISTS search_vector")
+            .await?;
+
+        Ok(())
+    }
+}
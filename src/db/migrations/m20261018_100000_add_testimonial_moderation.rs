@@ -0,0 +1,101 @@
+//! Migration: add testimonial moderation
+//!
+//! Replaces the approve toggle with an explicit moderation status
+//! (pending, approved, rejected, archived) plus who moderated it, when and
+//! why it was rejected. `is_approved` is kept in sync with the status so
+//! existing readers keep working.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+use super::m20260218_000001_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .add_column(
+                        ColumnDef::new(Testimonials::Status)
+                            .string_len(20)
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .add_column(ColumnDef::new(Testimonials::RejectionReason).text())
+                    .add_column(ColumnDef::new(Testimonials::ModeratedBy).integer())
+                    .add_column(
+                        ColumnDef::new(Testimonials::ModeratedAt).timestamp_with_time_zone(),
+                    )
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_testimonials_moderated_by")
+                            .from_tbl(Testimonials::Table)
+                            .from_col(Testimonials::ModeratedBy)
+                            .to_tbl(Users::Table)
+                            .to_col(Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE testimonials SET status = 'approved' WHERE is_approved")
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_testimonials_status")
+                    .table(Testimonials::Table)
+                    .col(Testimonials::ProjectId)
+                    .col(Testimonials::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_testimonials_status")
+                    .table(Testimonials::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .drop_foreign_key(Alias::new("fk_testimonials_moderated_by"))
+                    .drop_column(Testimonials::Status)
+                    .drop_column(Testimonials::RejectionReason)
+                    .drop_column(Testimonials::ModeratedBy)
+                    .drop_column(Testimonials::ModeratedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Testimonials {
+    Table,
+    ProjectId,
+    Status,
+    RejectionReason,
+    ModeratedBy,
+    ModeratedAt,
+}
@@ -0,0 +1,90 @@
+//! Migration: add soft delete
+//!
+//! Adds `deleted_at` to projects and testimonials. Rows with a `deleted_at`
+//! are in the trash: hidden from every listing until restored, and purged for
+//! good once the retention window has passed.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .add_column(ColumnDef::new(Projects::DeletedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .add_column(ColumnDef::new(Testimonials::DeletedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_projects_deleted_at")
+                    .table(Projects::Table)
+                    .col(Projects::DeletedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_testimonials_deleted_at")
+                    .table(Testimonials::Table)
+                    .col(Testimonials::DeletedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .drop_column(Testimonials::DeletedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .drop_column(Projects::DeletedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Projects {
+    Table,
+    DeletedAt,
+}
+
+#[derive(DeriveIden)]
+enum Testimonials {
+    Table,
+    DeletedAt,
+}
@@ -0,0 +1,149 @@
+//! Migration: create testimonial revisions
+//!
+//! Records every edit to a testimonial as a field-level before/after diff, and
+//! keeps the content as originally submitted so edited quotes can be flagged.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+use super::m20260218_000001_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TestimonialRevisions::Table)
+                    .col(
+                        ColumnDef::new(TestimonialRevisions::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TestimonialRevisions::Pid)
+                            .uuid()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TestimonialRevisions::TestimonialId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TestimonialRevisions::UserId).integer())
+                    .col(
+                        ColumnDef::new(TestimonialRevisions::Action)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TestimonialRevisions::Changes)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TestimonialRevisions::RevertedRevisionId).integer())
+                    .col(
+                        ColumnDef::new(TestimonialRevisions::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                TestimonialRevisions::Table,
+                                TestimonialRevisions::TestimonialId,
+                            )
+                            .to(Testimonials::Table, Testimonials::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(TestimonialRevisions::Table, TestimonialRevisions::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                TestimonialRevisions::Table,
+                                TestimonialRevisions::RevertedRevisionId,
+                            )
+                            .to(TestimonialRevisions::Table, TestimonialRevisions::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_testimonial_revisions_testimonial")
+                    .table(TestimonialRevisions::Table)
+                    .col(TestimonialRevisions::TestimonialId)
+                    .col(TestimonialRevisions::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .add_column(ColumnDef::new(Testimonials::OriginalContent).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE testimonials SET original_content = content")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .drop_column(Testimonials::OriginalContent)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(TestimonialRevisions::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TestimonialRevisions {
+    Table,
+    Id,
+    Pid,
+    TestimonialId,
+    UserId,
+    Action,
+    Changes,
+    RevertedRevisionId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Testimonials {
+    Table,
+    Id,
+    OriginalContent,
+}
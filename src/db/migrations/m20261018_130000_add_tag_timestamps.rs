@@ -0,0 +1,59 @@
+//! Migration: add tag timestamps
+//!
+//! Tags were created without `created_at`/`updated_at`. `updated_at` is what
+//! their `ETag` is derived from, so renames can be guarded with `If-Match`
+//! like projects and testimonials.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tags::Table)
+                    .add_column(
+                        ColumnDef::new(Tags::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .add_column(
+                        ColumnDef::new(Tags::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tags::Table)
+                    .drop_column(Tags::CreatedAt)
+                    .drop_column(Tags::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tags {
+    Table,
+    CreatedAt,
+    UpdatedAt,
+}
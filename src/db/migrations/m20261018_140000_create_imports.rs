@@ -0,0 +1,121 @@
+//! Migration: create imports
+//!
+//! Tracks CSV imports of testimonials: the column mapping used, how many rows
+//! were created, skipped or failed, and why each failed row was rejected.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+use super::m20260218_000001_create_users::Users;
+use super::m20260218_000002_create_projects::Projects;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Imports::Table)
+                    .col(
+                        ColumnDef::new(Imports::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Imports::Pid).uuid().not_null().unique_key())
+                    .col(ColumnDef::new(Imports::ProjectId).integer().not_null())
+                    .col(ColumnDef::new(Imports::UserId).integer())
+                    .col(ColumnDef::new(Imports::Filename).string_len(255))
+                    .col(ColumnDef::new(Imports::Status).string_len(20).not_null())
+                    .col(ColumnDef::new(Imports::Mapping).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(Imports::TotalRows)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Imports::CreatedCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Imports::SkippedCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Imports::FailedCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Imports::Errors).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(Imports::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Imports::CompletedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Imports::Table, Imports::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Imports::Table, Imports::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_imports_project")
+                    .table(Imports::Table)
+                    .col(Imports::ProjectId)
+                    .col(Imports::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Imports::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Imports {
+    Table,
+    Id,
+    Pid,
+    ProjectId,
+    UserId,
+    Filename,
+    Status,
+    Mapping,
+    TotalRows,
+    CreatedCount,
+    SkippedCount,
+    FailedCount,
+    Errors,
+    CreatedAt,
+    CompletedAt,
+}
@@ -0,0 +1,181 @@
+//! Migration: add import source sync
+//!
+//! Scheduling state for auto-synced import sources: the cursor incremental
+//! syncs resume from, when the next sync is due and how many syncs in a row
+//! have failed. Each sync is recorded in `import_sync_runs`, with its counts
+//! and the error it failed with.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportSources::Table)
+                    .add_column(
+                        ColumnDef::new(ImportSources::SyncCursor).timestamp_with_time_zone(),
+                    )
+                    .add_column(
+                        ColumnDef::new(ImportSources::NextSyncAt).timestamp_with_time_zone(),
+                    )
+                    .add_column(
+                        ColumnDef::new(ImportSources::ConsecutiveFailures)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_import_sources_due")
+                    .table(ImportSources::Table)
+                    .col(ImportSources::NextSyncAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImportSyncRuns::Table)
+                    .col(
+                        ColumnDef::new(ImportSyncRuns::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ImportSyncRuns::Pid)
+                            .uuid()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ImportSyncRuns::ImportSourceId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ImportSyncRuns::Trigger)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ImportSyncRuns::Status)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ImportSyncRuns::FetchedCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ImportSyncRuns::CreatedCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ImportSyncRuns::SkippedCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(ImportSyncRuns::Error).text())
+                    .col(
+                        ColumnDef::new(ImportSyncRuns::StartedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ImportSyncRuns::FinishedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ImportSyncRuns::Table, ImportSyncRuns::ImportSourceId)
+                            .to(ImportSources::Table, ImportSources::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_import_sync_runs_source")
+                    .table(ImportSyncRuns::Table)
+                    .col(ImportSyncRuns::ImportSourceId)
+                    .col(ImportSyncRuns::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImportSyncRuns::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_import_sources_due")
+                    .table(ImportSources::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportSources::Table)
+                    .drop_column(ImportSources::SyncCursor)
+                    .drop_column(ImportSources::NextSyncAt)
+                    .drop_column(ImportSources::ConsecutiveFailures)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ImportSources {
+    Table,
+    Id,
+    SyncCursor,
+    NextSyncAt,
+    ConsecutiveFailures,
+}
+
+#[derive(DeriveIden)]
+enum ImportSyncRuns {
+    Table,
+    Id,
+    Pid,
+    ImportSourceId,
+    Trigger,
+    Status,
+    FetchedCount,
+    CreatedCount,
+    SkippedCount,
+    Error,
+    StartedAt,
+    FinishedAt,
+}
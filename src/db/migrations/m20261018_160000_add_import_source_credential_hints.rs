@@ -0,0 +1,41 @@
+//! Migration: add import source credential hints
+//!
+//! Credentials are now stored sealed, so the masked hints shown in responses
+//! are kept alongside them rather than derived from the plaintext on read.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportSources::Table)
+                    .add_column(ColumnDef::new(ImportSources::CredentialHints).json_binary())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportSources::Table)
+                    .drop_column(ImportSources::CredentialHints)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ImportSources {
+    Table,
+    CredentialHints,
+}
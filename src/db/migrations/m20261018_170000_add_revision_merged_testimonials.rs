@@ -0,0 +1,43 @@
+//! Migration: add merged testimonials to revisions
+//!
+//! Merging duplicates records a revision on the testimonial that was kept,
+//! listing the testimonials merged into it.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TestimonialRevisions::Table)
+                    .add_column(
+                        ColumnDef::new(TestimonialRevisions::MergedTestimonialIds).json_binary(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TestimonialRevisions::Table)
+                    .drop_column(TestimonialRevisions::MergedTestimonialIds)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TestimonialRevisions {
+    Table,
+    MergedTestimonialIds,
+}
@@ -0,0 +1,64 @@
+//! Migration: add video processing state to testimonials
+//!
+//! Video testimonials are probed and transcribed in the background after
+//! their video is set. `video_status` is `processing`, `ready` or `failed`,
+//! and `video_error` says why processing failed.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .add_column(ColumnDef::new(Testimonials::VideoStatus).string_len(20))
+                    .add_column(ColumnDef::new(Testimonials::VideoError).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_testimonials_video_status")
+                    .table(Testimonials::Table)
+                    .col(Testimonials::VideoStatus)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_testimonials_video_status")
+                    .table(Testimonials::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .drop_column(Testimonials::VideoStatus)
+                    .drop_column(Testimonials::VideoError)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Testimonials {
+    Table,
+    VideoStatus,
+    VideoError,
+}
@@ -0,0 +1,122 @@
+//! Migration: create uploads
+//!
+//! Tracks files being uploaded to media storage: what was declared up front,
+//! how many bytes have arrived, and the parts stored so far, so an
+//! interrupted upload can resume where it stopped.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+use super::m20260218_000001_create_users::Users;
+use super::m20260218_000002_create_projects::Projects;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Uploads::Table)
+                    .col(
+                        ColumnDef::new(Uploads::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Uploads::Pid).uuid().not_null().unique_key())
+                    .col(ColumnDef::new(Uploads::ProjectId).integer().not_null())
+                    .col(ColumnDef::new(Uploads::UserId).integer())
+                    .col(
+                        ColumnDef::new(Uploads::Key)
+                            .string_len(255)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Uploads::Filename).string_len(255))
+                    .col(
+                        ColumnDef::new(Uploads::ContentType)
+                            .string_len(100)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Uploads::Size).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Uploads::Received)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Uploads::MultipartId).string_len(1024))
+                    .col(ColumnDef::new(Uploads::Parts).json_binary().not_null())
+                    .col(ColumnDef::new(Uploads::Status).string_len(20).not_null())
+                    .col(
+                        ColumnDef::new(Uploads::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Uploads::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Uploads::CompletedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Uploads::Table, Uploads::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Uploads::Table, Uploads::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_uploads_pending_expiry")
+                    .table(Uploads::Table)
+                    .col(Uploads::Status)
+                    .col(Uploads::ExpiresAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Uploads::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Uploads {
+    Table,
+    Id,
+    Pid,
+    ProjectId,
+    UserId,
+    Key,
+    Filename,
+    ContentType,
+    Size,
+    Received,
+    MultipartId,
+    Parts,
+    Status,
+    CreatedAt,
+    ExpiresAt,
+    CompletedAt,
+}
@@ -0,0 +1,90 @@
+//! Migration: add resized variants of uploaded images
+//!
+//! Images uploaded as avatars or logos are stored in a few standard sizes.
+//! `uploads.variants` lists them, and testimonials and projects copy the
+//! list of the upload their avatar or logo points at.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Uploads::Table)
+                    .add_column(ColumnDef::new(Uploads::Variants).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .add_column(ColumnDef::new(Testimonials::AuthorAvatarVariants).json_binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .add_column(ColumnDef::new(Projects::LogoVariants).json_binary())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .drop_column(Projects::LogoVariants)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .drop_column(Testimonials::AuthorAvatarVariants)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Uploads::Table)
+                    .drop_column(Uploads::Variants)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Uploads {
+    Table,
+    Variants,
+}
+
+#[derive(DeriveIden)]
+enum Testimonials {
+    Table,
+    AuthorAvatarVariants,
+}
+
+#[derive(DeriveIden)]
+enum Projects {
+    Table,
+    LogoVariants,
+}
@@ -0,0 +1,105 @@
+//! Migration: create jobs
+//!
+//! The background job queue. Workers claim due jobs with `FOR UPDATE SKIP
+//! LOCKED`, so any number of app instances can share it; a job that keeps
+//! failing is retried with backoff and then kept as `dead` for inspection.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Jobs::Table)
+                    .col(
+                        ColumnDef::new(Jobs::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Jobs::Pid).uuid().not_null().unique_key())
+                    .col(ColumnDef::new(Jobs::Kind).string_len(100).not_null())
+                    .col(ColumnDef::new(Jobs::Payload).json_binary().not_null())
+                    .col(ColumnDef::new(Jobs::Status).string_len(20).not_null())
+                    .col(
+                        ColumnDef::new(Jobs::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Jobs::MaxAttempts).integer().not_null())
+                    .col(ColumnDef::new(Jobs::Schedule).string_len(100).unique_key())
+                    .col(ColumnDef::new(Jobs::RepeatSeconds).big_integer())
+                    .col(
+                        ColumnDef::new(Jobs::RunAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Jobs::LockedUntil).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Jobs::LockedBy).string_len(100))
+                    .col(ColumnDef::new(Jobs::LastError).text())
+                    .col(
+                        ColumnDef::new(Jobs::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Jobs::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Jobs::FinishedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_jobs_status_run_at")
+                    .table(Jobs::Table)
+                    .col(Jobs::Status)
+                    .col(Jobs::RunAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Jobs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Jobs {
+    Table,
+    Id,
+    Pid,
+    Kind,
+    Payload,
+    Status,
+    Attempts,
+    MaxAttempts,
+    Schedule,
+    RepeatSeconds,
+    RunAt,
+    LockedUntil,
+    LockedBy,
+    LastError,
+    CreatedAt,
+    UpdatedAt,
+    FinishedAt,
+}
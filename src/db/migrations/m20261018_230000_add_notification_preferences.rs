@@ -0,0 +1,50 @@
+//! Migration: add notification preferences
+//!
+//! Users choose how they hear about new form submissions: an email for each,
+//! a daily digest, or nothing. `digest_sent_at` marks where the next digest
+//! picks up.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(
+                        ColumnDef::new(Users::SubmissionNotifications)
+                            .string_len(20)
+                            .not_null()
+                            .default("immediate"),
+                    )
+                    .add_column(ColumnDef::new(Users::DigestSentAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::SubmissionNotifications)
+                    .drop_column(Users::DigestSentAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    SubmissionNotifications,
+    DigestSentAt,
+}
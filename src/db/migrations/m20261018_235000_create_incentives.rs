@@ -0,0 +1,184 @@
+//! Migration: create incentives
+//!
+//! Pools of coupon codes a form hands out to the people whose submissions
+//! get approved, and a log of the thank-you emails sent to them with the
+//! incentive each one carried.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IncentiveCodes::Table)
+                    .col(
+                        ColumnDef::new(IncentiveCodes::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(IncentiveCodes::FormId).integer().not_null())
+                    .col(
+                        ColumnDef::new(IncentiveCodes::Code)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(IncentiveCodes::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(IncentiveCodes::IssuedAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(IncentiveCodes::Table, IncentiveCodes::FormId)
+                            .to(Forms::Table, Forms::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_incentive_codes_form_id_code")
+                    .table(IncentiveCodes::Table)
+                    .col(IncentiveCodes::FormId)
+                    .col(IncentiveCodes::Code)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ThankYouEmails::Table)
+                    .col(
+                        ColumnDef::new(ThankYouEmails::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ThankYouEmails::Pid)
+                            .uuid()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(ThankYouEmails::FormId).integer().not_null())
+                    .col(
+                        ColumnDef::new(ThankYouEmails::TestimonialId)
+                            .integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ThankYouEmails::Email)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ThankYouEmails::Incentive).text())
+                    .col(
+                        ColumnDef::new(ThankYouEmails::CodeId)
+                            .integer()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ThankYouEmails::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(ThankYouEmails::SentAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ThankYouEmails::Table, ThankYouEmails::FormId)
+                            .to(Forms::Table, Forms::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ThankYouEmails::Table, ThankYouEmails::TestimonialId)
+                            .to(Testimonials::Table, Testimonials::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ThankYouEmails::Table, ThankYouEmails::CodeId)
+                            .to(IncentiveCodes::Table, IncentiveCodes::Id)
+                            .on_delete(ForeignKeyAction::Restrict),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_thank_you_emails_form_id")
+                    .table(ThankYouEmails::Table)
+                    .col(ThankYouEmails::FormId)
+                    .col(ThankYouEmails::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ThankYouEmails::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(IncentiveCodes::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum IncentiveCodes {
+    Table,
+    Id,
+    FormId,
+    Code,
+    CreatedAt,
+    IssuedAt,
+}
+
+#[derive(DeriveIden)]
+enum ThankYouEmails {
+    Table,
+    Id,
+    Pid,
+    FormId,
+    TestimonialId,
+    Email,
+    Incentive,
+    CodeId,
+    CreatedAt,
+    SentAt,
+}
+
+#[derive(DeriveIden)]
+enum Forms {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Testimonials {
+    Table,
+    Id,
+}
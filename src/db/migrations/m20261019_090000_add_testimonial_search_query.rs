@@ -0,0 +1,58 @@
+//! Migration: add testimonial search query
+//!
+//! Adds `testimonials_search_query(terms)`, the terms parsed with every
+//! configuration `testimonials_search_config` can return and OR-ed together.
+//! Unlike a query parsed with each row's own configuration, it is the same
+//! for every row, so matching `search_vector` against it can use the GIN
+//! index.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                CREATE OR REPLACE FUNCTION testimonials_search_query(terms text)
+                RETURNS tsquery
+                LANGUAGE sql IMMUTABLE AS $$
+                    SELECT websearch_to_tsquery('simple', terms)
+                        || websearch_to_tsquery('arabic', terms)
+                        || websearch_to_tsquery('danish', terms)
+                        || websearch_to_tsquery('german', terms)
+                        || websearch_to_tsquery('greek', terms)
+                        || websearch_to_tsquery('english', terms)
+                        || websearch_to_tsquery('spanish', terms)
+                        || websearch_to_tsquery('finnish', terms)
+                        || websearch_to_tsquery('french', terms)
+                        || websearch_to_tsquery('hungarian', terms)
+                        || websearch_to_tsquery('indonesian', terms)
+                        || websearch_to_tsquery('italian', terms)
+                        || websearch_to_tsquery('dutch', terms)
+                        || websearch_to_tsquery('norwegian', terms)
+                        || websearch_to_tsquery('portuguese', terms)
+                        || websearch_to_tsquery('romanian', terms)
+                        || websearch_to_tsquery('russian', terms)
+                        || websearch_to_tsquery('swedish', terms)
+                        || websearch_to_tsquery('turkish', terms)
+                $$
+                "#,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP FUNCTION IF EXISTS testimonials_search_query(text)")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+//! Migration: add widget languages
+//!
+//! A widget can show only testimonials in the languages it lists, or in the
+//! ones its visitor's browser asks for, falling back to its own list.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Widgets::Table)
+                    .add_column(ColumnDef::new(Widgets::Languages).string_len(255))
+                    .add_column(
+                        ColumnDef::new(Widgets::VisitorLanguage)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Widgets::Table)
+                    .drop_column(Widgets::Languages)
+                    .drop_column(Widgets::VisitorLanguage)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Widgets {
+    Table,
+    Languages,
+    VisitorLanguage,
+}
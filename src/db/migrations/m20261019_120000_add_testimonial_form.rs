@@ -0,0 +1,66 @@
+//! Migration: add testimonial form
+//!
+//! Links testimonials submitted through a collection form to the form, which
+//! only the public submission endpoint sets.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .add_column(ColumnDef::new(Testimonials::FormId).integer())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_testimonials_form_id")
+                            .from_tbl(Testimonials::Table)
+                            .from_col(Testimonials::FormId)
+                            .to_tbl(Forms::Table)
+                            .to_col(Forms::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_testimonials_form_id")
+                    .table(Testimonials::Table)
+                    .col(Testimonials::FormId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Testimonials::Table)
+                    .drop_column(Testimonials::FormId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Testimonials {
+    Table,
+    FormId,
+}
+
+#[derive(DeriveIden)]
+enum Forms {
+    Table,
+    Id,
+}
@@ -0,0 +1,92 @@
+//! Migration: add import dedup
+//!
+//! Makes `(project_id, source_platform, source_id)` unique among testimonials
+//! with a `source_id`, so concurrent syncs of a source cannot import a review
+//! twice. Duplicates imported before keep only their oldest testimonial; the
+//! others lose their `source_id`, and those a connector created are moved to
+//! the trash.
+//!
+//! Also adds `import_sources.sync_claimed_until`, set while a sync of the
+//! source runs, so manual and scheduled syncs never run at once.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            WITH duplicates AS (
+                SELECT id FROM (
+                    SELECT id, row_number() OVER (
+                        PARTITION BY project_id, source_platform, source_id ORDER BY id
+                    ) AS n
+                    FROM testimonials
+                    WHERE source_platform IS NOT NULL AND source_id IS NOT NULL
+                ) ranked
+                WHERE n > 1
+            )
+            UPDATE testimonials
+            SET source_id = NULL,
+                deleted_at = CASE
+                    WHEN source = 'import' THEN coalesce(deleted_at, now())
+                    ELSE deleted_at
+                END
+            WHERE id IN (SELECT id FROM duplicates)
+            "#,
+        )
+        .await?;
+
+        db.execute_unprepared(
+            r#"
+            CREATE UNIQUE INDEX idx_testimonials_project_source
+            ON testimonials (project_id, source_platform, source_id)
+            WHERE source_id IS NOT NULL
+            "#,
+        )
+        .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportSources::Table)
+                    .add_column(
+                        ColumnDef::new(ImportSources::SyncClaimedUntil).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportSources::Table)
+                    .drop_column(ImportSources::SyncClaimedUntil)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared("DROP INDEX IF EXISTS idx_testimonials_project_source")
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ImportSources {
+    Table,
+    SyncClaimedUntil,
+}
@@ -0,0 +1,61 @@
+//! Migration: add import files
+//!
+//! Imports and video processing run as jobs. Adds `imports.file`, the
+//! uploaded CSV an import job reads, kept until the import is done.
+//!
+//! Videos used to be resumed at startup; those left `processing` get a job
+//! instead.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Imports::Table)
+                    .add_column(ColumnDef::new(Imports::File).binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                INSERT INTO jobs (pid, kind, payload, status, max_attempts, run_at)
+                SELECT gen_random_uuid(), 'video.process',
+                       jsonb_build_object('testimonial_id', id), 'queued', 5, now()
+                FROM testimonials
+                WHERE video_status = 'processing' AND deleted_at IS NULL
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Imports::Table)
+                    .drop_column(Imports::File)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Imports {
+    Table,
+    File,
+}
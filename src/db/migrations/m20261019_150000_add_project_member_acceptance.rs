@@ -0,0 +1,44 @@
+//! Migration: add project member acceptance
+//!
+//! Project members are invited, and only hear about a project once they
+//! accept. Adds `project_members.accepted_at`; members added before
+//! invitations existed never agreed to be, so they are invited again.
+
+use rapina::migration::prelude::*;
+use rapina::sea_orm_migration;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProjectMembers::Table)
+                    .add_column(
+                        ColumnDef::new(ProjectMembers::AcceptedAt).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProjectMembers::Table)
+                    .drop_column(ProjectMembers::AcceptedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProjectMembers {
+    Table,
+    AcceptedAt,
+}
@@ -1,4 +1,27 @@
 mod m20260218_182252_convert_pks_to_serial_add_pid;
+mod m20261018_090000_add_testimonial_search;
+mod m20261018_100000_add_testimonial_moderation;
+mod m20261018_110000_add_soft_delete;
+mod m20261018_120000_create_testimonial_revisions;
+mod m20261018_130000_add_tag_timestamps;
+mod m20261018_140000_create_imports;
+mod m20261018_150000_add_import_source_sync;
+mod m20261018_160000_add_import_source_credential_hints;
+mod m20261018_170000_add_revision_merged_testimonials;
+mod m20261018_180000_add_testimonial_video_processing;
+mod m20261018_190000_create_uploads;
+mod m20261018_200000_add_image_variants;
+mod m20261018_210000_create_jobs;
+mod m20261018_220000_create_webhooks;
+mod m20261018_230000_add_notification_preferences;
+mod m20261018_235000_create_incentives;
+mod m20261019_090000_add_testimonial_search_query;
+mod m20261019_100000_add_widget_languages;
+mod m20261019_110000_create_project_members;
+mod m20261019_120000_add_testimonial_form;
+mod m20261019_130000_add_import_dedup;
+mod m20261019_140000_add_import_files;
+mod m20261019_150000_add_project_member_acceptance;
 
 mod m20260218_000001_create_users;
 mod m20260218_000002_create_projects;
@@ -21,4 +44,27 @@ rapina::migrations! {
     m20260218_000008_create_analytics_events,
     m20260218_000009_create_api_keys,
     m20260218_182252_convert_pks_to_serial_add_pid,
+    m20261018_090000_add_testimonial_search,
+    m20261018_100000_add_testimonial_moderation,
+    m20261018_110000_add_soft_delete,
+    m20261018_120000_create_testimonial_revisions,
+    m20261018_130000_add_tag_timestamps,
+    m20261018_140000_create_imports,
+    m20261018_150000_add_import_source_sync,
+    m20261018_160000_add_import_source_credential_hints,
+    m20261018_170000_add_revision_merged_testimonials,
+    m20261018_180000_add_testimonial_video_processing,
+    m20261018_190000_create_uploads,
+    m20261018_200000_add_image_variants,
+    m20261018_210000_create_jobs,
+    m20261018_220000_create_webhooks,
+    m20261018_230000_add_notification_preferences,
+    m20261018_235000_create_incentives,
+    m20261019_090000_add_testimonial_search_query,
+    m20261019_100000_add_widget_languages,
+    m20261019_110000_create_project_members,
+    m20261019_120000_add_testimonial_form,
+    m20261019_130000_add_import_dedup,
+    m20261019_140000_add_import_files,
+    m20261019_150000_add_project_member_acceptance,
 }
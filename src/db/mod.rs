@@ -1,2 +1,3 @@
 pub mod entities;
 pub mod migrations;
+pub mod trash;
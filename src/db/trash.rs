@@ -0,0 +1,63 @@
+//! Purging of soft-deleted rows.
+//!
+//! Deleting a project or testimonial only sets its `deleted_at`. Once a row
+//! has sat in the trash longer than the retention window it is removed for
+//! good, along with everything that cascades from it.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use rapina::prelude::tracing;
+use rapina::sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+
+use crate::db::entities::project::{Column as ProjectColumn, Entity as Project};
+use crate::db::entities::testimonial::{Column as TestimonialColumn, Entity as Testimonial};
+use crate::jobs::{Job, JobError};
+
+/// How often the purge runs.
+pub const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Hard-deletes everything trashed more than `retention` ago. Returns the
+/// number of testimonials and projects removed.
+pub async fn purge(
+    conn: &DatabaseConnection,
+    retention: chrono::Duration,
+) -> Result<(u64, u64), DbErr> {
+    let cutoff = Utc::now().fixed_offset() - retention;
+
+    let testimonials = Testimonial::delete_many()
+        .filter(TestimonialColumn::DeletedAt.lt(cutoff))
+        .exec(conn)
+        .await?
+        .rows_affected;
+
+    let projects = Project::delete_many()
+        .filter(ProjectColumn::DeletedAt.lt(cutoff))
+        .exec(conn)
+        .await?
+        .rows_affected;
+
+    Ok((testimonials, projects))
+}
+
+/// Job that runs [`purge`].
+#[derive(Serialize, Deserialize)]
+pub struct PurgeTrash {
+    pub retention_days: i64,
+}
+
+impl Job for PurgeTrash {
+    const KIND: &'static str = "trash.purge";
+}
+
+impl PurgeTrash {
+    pub async fn run(self, conn: &DatabaseConnection) -> Result<(), JobError> {
+        let (testimonials, projects) =
+            purge(conn, chrono::Duration::days(self.retention_days)).await?;
+        if testimonials > 0 || projects > 0 {
+            tracing::info!(testimonials, projects, "purged expired trash");
+        }
+        Ok(())
+    }
+}
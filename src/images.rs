@@ -0,0 +1,297 @@
+//! Processing of uploaded images such as author avatars and project logos.
+//!
+//! Images are decoded and encoded again, which leaves out EXIF and any other
+//! metadata such as GPS positions; they are first turned upright as their
+//! EXIF orientation says, since viewers would otherwise show them sideways.
+//! GIFs are the exception: they are kept as uploaded, since encoding them
+//! again would keep only their first frame, and the format has no EXIF.
+//! Each image also gets smaller copies to fit a few standard sizes, as JPEG
+//! or as PNG if it has transparency, so widgets need not load the full-size
+//! file. The copies of a GIF are stills of its first frame. There are no WebP
+//! copies: the only WebP encoder at hand is lossless, which makes copies of
+//! photos larger than the JPEGs.
+//!
+//! Testimonials and projects whose avatar or logo is an uploaded image keep
+//! a copy of its variants, so their responses can list them.
+
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageEncoder, ImageFormat, ImageReader, Limits};
+use rapina::schemars::{self, JsonSchema};
+use rapina::sea_orm::{
+    ActiveValue, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::db::entities::upload::{Column as UploadColumn, Entity as Upload};
+use crate::storage::{MediaStorage, StorageError};
+
+/// Sizes variants are made in: each fits in a square this many pixels wide.
+/// Images are never enlarged, so smaller ones get fewer variants.
+pub const VARIANT_SIZES: [u32; 4] = [64, 128, 256, 512];
+
+/// Largest width or height of images that are decoded, which bounds the
+/// memory a small file that decodes to a huge image can take.
+const MAX_DIMENSION: u32 = 8192;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Status of complete uploads, whose variants are final.
+const UPLOAD_COMPLETE: &str = "complete";
+
+#[derive(Debug)]
+pub enum ImageError {
+    /// The file is not an image of its type, or one too large to decode.
+    Invalid(String),
+    Storage(StorageError),
+}
+
+impl std::fmt::Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::Invalid(msg) => write!(f, "{msg}"),
+            ImageError::Storage(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<StorageError> for ImageError {
+    fn from(e: StorageError) -> Self {
+        ImageError::Storage(e)
+    }
+}
+
+/// A resized copy of an image.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ImageVariant {
+    pub width: u32,
+    pub height: u32,
+    pub content_type: String,
+    pub url: String,
+}
+
+/// Variants stored as JSON, or none if there are none or they are unreadable.
+pub fn parse(variants: Option<serde_json::Value>) -> Vec<ImageVariant> {
+    variants
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn format_of(content_type: &str) -> Option<ImageFormat> {
+    match content_type {
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        "image/png" => Some(ImageFormat::Png),
+        "image/webp" => Some(ImageFormat::WebP),
+        "image/gif" => Some(ImageFormat::Gif),
+        _ => None,
+    }
+}
+
+/// Decodes `bytes`, which must be an image in `format`, turned upright.
+fn decode(bytes: &[u8], format: ImageFormat) -> Result<DynamicImage, ImageError> {
+    let invalid = |e: image::ImageError| ImageError::Invalid(format!("not a valid image: {e}"));
+
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| ImageError::Invalid(e.to_string()))?;
+    if reader.format() != Some(format) {
+        return Err(ImageError::Invalid(format!(
+            "not a valid {} image",
+            format.to_mime_type()
+        )));
+    }
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(invalid)?;
+    let orientation = decoder.orientation().map_err(invalid)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Encodes `image` in `format`, without any metadata.
+fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+    let mut out = Vec::new();
+    let (width, height) = (image.width(), image.height());
+    let result = match format {
+        ImageFormat::Jpeg => {
+            let rgb = image.to_rgb8();
+            JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY).write_image(
+                &rgb,
+                width,
+                height,
+                image::ExtendedColorType::Rgb8,
+            )
+        }
+        ImageFormat::Png => {
+            let rgba = image.to_rgba8();
+            PngEncoder::new(&mut out).write_image(
+                &rgba,
+                width,
+                height,
+                image::ExtendedColorType::Rgba8,
+            )
+        }
+        // The WebP encoder is lossless only, so only WebP originals use it.
+        ImageFormat::WebP => {
+            let rgba = image.to_rgba8();
+            WebPEncoder::new_lossless(&mut out).write_image(
+                &rgba,
+                width,
+                height,
+                image::ExtendedColorType::Rgba8,
+            )
+        }
+        other => {
+            return Err(ImageError::Invalid(format!(
+                "cannot encode {}",
+                other.to_mime_type()
+            )));
+        }
+    };
+    result.map_err(|e| ImageError::Invalid(format!("could not encode image: {e}")))?;
+    Ok(out)
+}
+
+/// An encoded image and where it goes.
+struct Output {
+    key: String,
+    content_type: &'static str,
+    width: u32,
+    height: u32,
+    bytes: Vec<u8>,
+}
+
+/// The image in `bytes` without its metadata, or as it is if it is a GIF,
+/// under `key`, followed by its variants.
+fn process(key: &str, content_type: &str, bytes: &[u8]) -> Result<Vec<Output>, ImageError> {
+    let format = format_of(content_type)
+        .ok_or_else(|| ImageError::Invalid(format!("{content_type} is not an image type")))?;
+    let image = decode(bytes, format)?;
+
+    let original = match format {
+        ImageFormat::Gif => bytes.to_vec(),
+        _ => encode(&image, format)?,
+    };
+    let mut outputs = vec![Output {
+        key: key.to_string(),
+        content_type: format.to_mime_type(),
+        width: image.width(),
+        height: image.height(),
+        bytes: original,
+    }];
+
+    // Variants keep transparency if the image has any, and are JPEGs
+    // otherwise, which are far smaller for photos.
+    let variant_format = if image.color().has_alpha() {
+        ImageFormat::Png
+    } else {
+        ImageFormat::Jpeg
+    };
+    let stem = key.rsplit_once('.').map_or(key, |(stem, _)| stem);
+    for size in VARIANT_SIZES {
+        if image.width().max(image.height()) <= size {
+            break;
+        }
+        let resized = image.resize(size, size, FilterType::Lanczos3);
+        outputs.push(Output {
+            key: format!("{stem}-{size}.{}", variant_format.extensions_str()[0]),
+            content_type: variant_format.to_mime_type(),
+            width: resized.width(),
+            height: resized.height(),
+            bytes: encode(&resized, variant_format)?,
+        });
+    }
+    Ok(outputs)
+}
+
+/// Replaces the image under `key` with a copy without metadata, stores its
+/// variants next to it, and returns them.
+pub async fn process_stored(
+    storage: &MediaStorage,
+    key: &str,
+    content_type: &str,
+) -> Result<Vec<ImageVariant>, ImageError> {
+    let object = storage
+        .get(key)
+        .await?
+        .ok_or_else(|| StorageError(format!("{key} is not in storage")))?;
+
+    // Decoding and resizing take a while for large photos.
+    let (key_owned, content_type_owned) = (key.to_string(), content_type.to_string());
+    let outputs = tokio::task::spawn_blocking(move || {
+        process(&key_owned, &content_type_owned, &object.bytes)
+    })
+    .await
+    .map_err(|e| StorageError(format!("image processing failed: {e}")))??;
+
+    let mut variants = Vec::new();
+    for output in outputs {
+        let is_original = output.key == key;
+        if !is_original {
+            variants.push(ImageVariant {
+                width: output.width,
+                height: output.height,
+                content_type: output.content_type.to_string(),
+                url: storage.url(&output.key),
+            });
+        }
+        storage
+            .put(&output.key, output.bytes, output.content_type)
+            .await?;
+    }
+    Ok(variants)
+}
+
+/// The variants of the image at `url`, if it is a complete upload to the
+/// project. Uploads are matched by their file name, which is unique, so this
+/// also works when media is served from a CDN in front of the storage.
+pub async fn variants_of<C: ConnectionTrait>(
+    conn: &C,
+    project_id: i32,
+    url: Option<&str>,
+) -> Result<Option<serde_json::Value>, DbErr> {
+    let Some(name) = url
+        .map(|url| url.split(['?', '#']).next().unwrap_or(url))
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+    else {
+        return Ok(None);
+    };
+
+    let upload = Upload::find()
+        .filter(UploadColumn::Key.eq(name))
+        .filter(UploadColumn::ProjectId.eq(project_id))
+        .filter(UploadColumn::Status.eq(UPLOAD_COMPLETE))
+        .one(conn)
+        .await?;
+    Ok(upload.and_then(|u| u.variants))
+}
+
+/// Points `variants` at those of the image `url` is being set to, unless it
+/// is not changing from `before`.
+pub async fn link<C: ConnectionTrait>(
+    conn: &C,
+    project_id: i32,
+    before: Option<&Option<String>>,
+    url: &ActiveValue<Option<String>>,
+    variants: &mut ActiveValue<Option<serde_json::Value>>,
+) -> Result<(), DbErr> {
+    let Some(url) = url.try_as_ref() else {
+        return Ok(());
+    };
+    if before == Some(url) {
+        return Ok(());
+    }
+    *variants = Set(variants_of(conn, project_id, url.as_deref()).await?);
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Thank-you emails and incentives for the people behind form submissions.
+//!
+//! A testimonial submitted through a collection form's public submission
+//! endpoint is linked to the form by `form_id`. Once it is approved, a
+//! [`SendThankYou`] job emails its author, if the form offers an incentive or
+//! asks to be shared. An incentive can come with a code drawn from the form's
+//! pool of uploaded codes; each code is issued once, and every thank-you is
+//! logged with the incentive it carried.
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+use rapina::sea_orm::sea_query::{LockBehavior, LockType, OnConflict};
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::entities::form::{Column as FormColumn, Entity as Form, Model as FormModel};
+use crate::db::entities::incentive_code::{
+    ActiveModel as CodeActiveModel, Column as CodeColumn, Entity as IncentiveCode,
+};
+use crate::db::entities::project::Model as ProjectModel;
+use crate::db::entities::testimonial::Model as TestimonialModel;
+use crate::db::entities::thank_you_email::{
+    ActiveModel as ThankYouActiveModel, Column as ThankYouColumn, Entity as ThankYouEmail,
+    Model as ThankYouModel,
+};
+use crate::jobs::{self, Job, JobError};
+
+/// Longest code a pool takes.
+pub const MAX_CODE_LENGTH: usize = 255;
+
+/// Job that thanks the author of an approved form submission.
+#[derive(Serialize, Deserialize)]
+pub struct SendThankYou {
+    pub testimonial_id: i32,
+}
+
+impl Job for SendThankYou {
+    const KIND: &'static str = "incentives.thank_you";
+}
+
+/// Queues the thank-you for an approved testimonial, in the same transaction
+/// as the approval if `conn` is one. Only form submissions with an author
+/// email are thanked.
+pub async fn queue_thank_you<C: ConnectionTrait>(
+    conn: &C,
+    testimonial: &TestimonialModel,
+) -> Result<(), DbErr> {
+    if testimonial.form_id.is_none() || testimonial.author_email.is_none() {
+        return Ok(());
+    }
+    jobs::enqueue(
+        conn,
+        &SendThankYou {
+            testimonial_id: testimonial.id,
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+/// The form `testimonial` was submitted through, if it still exists and
+/// still belongs to the testimonial's project.
+pub async fn form_of<C: ConnectionTrait>(
+    conn: &C,
+    testimonial: &TestimonialModel,
+) -> Result<Option<FormModel>, DbErr> {
+    let Some(form_id) = testimonial.form_id else {
+        return Ok(None);
+    };
+
+    Form::find_by_id(form_id)
+        .filter(FormColumn::ProjectId.eq(testimonial.project_id))
+        .one(conn)
+        .await
+}
+
+/// Whether the form's submitters are thanked at all: only if there is an
+/// incentive or share links to send them.
+pub fn thanks_submitters(form: &FormModel) -> bool {
+    form.incentive_enabled || form.share_enabled
+}
+
+/// How many codes of a form's pool are left and how many were issued.
+pub struct Pool {
+    pub available: u64,
+    pub issued: u64,
+}
+
+pub async fn pool<C: ConnectionTrait>(conn: &C, form_id: i32) -> Result<Pool, DbErr> {
+    let codes = IncentiveCode::find().filter(CodeColumn::FormId.eq(form_id));
+    Ok(Pool {
+        available: codes
+            .clone()
+            .filter(CodeColumn::IssuedAt.is_null())
+            .count(conn)
+            .await?,
+        issued: codes
+            .filter(CodeColumn::IssuedAt.is_not_null())
+            .count(conn)
+            .await?,
+    })
+}
+
+/// Adds `codes` to the form's pool, trimmed, and returns how many were
+/// added. Blank codes and codes the pool already has are skipped.
+pub async fn add_codes<C: ConnectionTrait>(
+    conn: &C,
+    form_id: i32,
+    codes: &[String],
+) -> Result<u64, DbErr> {
+    let mut seen = HashSet::new();
+    let codes: Vec<CodeActiveModel> = codes
+        .iter()
+        .map(|code| code.trim())
+        .filter(|code| !code.is_empty() && seen.insert(*code))
+        .map(|code| CodeActiveModel {
+            form_id: Set(form_id),
+            code: Set(code.to_string()),
+            ..Default::default()
+        })
+        .collect();
+    if codes.is_empty() {
+        return Ok(0);
+    }
+
+    IncentiveCode::insert_many(codes)
+        .on_conflict(
+            OnConflict::columns([CodeColumn::FormId, CodeColumn::Code])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
+        .await
+}
+
+/// Removes the codes of the form's pool that were not issued, and returns
+/// how many there were.
+pub async fn clear_codes<C: ConnectionTrait>(conn: &C, form_id: i32) -> Result<u64, DbErr> {
+    let result = IncentiveCode::delete_many()
+        .filter(CodeColumn::FormId.eq(form_id))
+        .filter(CodeColumn::IssuedAt.is_null())
+        .exec(conn)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// A thank-you and the code it carries, if any.
+pub struct Issued {
+    pub thank_you: ThankYouModel,
+    pub code: Option<String>,
+}
+
+/// The thank-you to the author of `testimonial` at `email`. The first call
+/// records it, with a code claimed from the form's pool if the form has one;
+/// later calls return that record, so a code is issued once however often
+/// the thank-you is tried. `conn` should be a transaction, which the claimed
+/// code stays locked in.
+///
+/// Fails if the form's pool has run out, which retrying fixes once more codes
+/// are uploaded. A form without a pool issues its incentive without a code.
+pub async fn issue<C: ConnectionTrait>(
+    conn: &C,
+    form: &FormModel,
+    testimonial: &TestimonialModel,
+    email: &str,
+) -> Result<Issued, JobError> {
+    let existing = ThankYouEmail::find()
+        .filter(ThankYouColumn::TestimonialId.eq(testimonial.id))
+        .one(conn)
+        .await?;
+    if let Some(thank_you) = existing {
+        let code = match thank_you.code_id {
+            Some(id) => IncentiveCode::find_by_id(id)
+                .one(conn)
+                .await?
+                .map(|code| code.code),
+            None => None,
+        };
+        return Ok(Issued { thank_you, code });
+    }
+
+    let mut code = None;
+    if form.incentive_enabled {
+        let available = IncentiveCode::find()
+            .filter(CodeColumn::FormId.eq(form.id))
+            .filter(CodeColumn::IssuedAt.is_null())
+            .order_by_asc(CodeColumn::Id)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .one(conn)
+            .await?;
+        match available {
+            Some(available) => {
+                let mut active = available.into_active_model();
+                active.issued_at = Set(Some(Utc::now().fixed_offset()));
+                code = Some(active.update(conn).await?);
+            }
+            None => {
+                let pool = pool(conn, form.id).await?;
+                if pool.issued > 0 || pool.available > 0 {
+                    return Err(JobError::new(format!(
+                        "form {} has no incentive codes left",
+                        form.pid
+                    )));
+                }
+            }
+        }
+    }
+
+    let thank_you = ThankYouActiveModel {
+        pid: Set(Uuid::new_v4()),
+        form_id: Set(form.id),
+        testimonial_id: Set(testimonial.id),
+        email: Set(email.to_string()),
+        incentive: Set(form
+            .incentive_description
+            .clone()
+            .filter(|_| form.incentive_enabled)),
+        code_id: Set(code.as_ref().map(|code| code.id)),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    Ok(Issued {
+        thank_you,
+        code: code.map(|code| code.code),
+    })
+}
+
+/// Records that the thank-you was sent.
+pub async fn mark_sent<C: ConnectionTrait>(
+    conn: &C,
+    thank_you: ThankYouModel,
+) -> Result<(), DbErr> {
+    let mut active = thank_you.into_active_model();
+    active.sent_at = Set(Some(Utc::now().fixed_offset()));
+    active.update(conn).await?;
+    Ok(())
+}
+
+/// Links that share the project with the form's share message filled in, as
+/// `(label, url)` pairs. Facebook only shares links, so it is left out for
+/// projects without a website.
+pub fn share_links(form: &FormModel, project: &ProjectModel) -> Vec<(&'static str, String)> {
+    let message = form
+        .share_message
+        .clone()
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or_else(|| format!("I just left a testimonial for {}!", project.name));
+    let website = project.website_url.as_deref();
+
+    let mut tweet = vec![("text", message.as_str())];
+    tweet.extend(website.map(|url| ("url", url)));
+    let post = match website {
+        Some(url) => format!("{message} {url}"),
+        None => message.clone(),
+    };
+
+    let mut links = vec![
+        (
+            "Share on X",
+            intent("https://twitter.com/intent/tweet", &tweet),
+        ),
+        (
+            "Share on LinkedIn",
+            intent(
+                "https://www.linkedin.com/feed/",
+                &[("shareActive", "true"), ("text", &post)],
+            ),
+        ),
+    ];
+    if let Some(url) = website {
+        links.push((
+            "Share on Facebook",
+            intent(
+                "https://www.facebook.com/sharer/sharer.php",
+                &[("u", url), ("quote", &message)],
+            ),
+        ));
+    }
+    links
+}
+
+fn intent(base: &str, params: &[(&str, &str)]) -> String {
+    Url::parse_with_params(base, params)
+        .expect("share intent URLs are valid")
+        .into()
+}
@@ -0,0 +1,219 @@
+//! Background jobs, queued in PostgreSQL.
+//!
+//! A job is a value of a type implementing [`Job`], stored as a row of `jobs`
+//! under the type's [`Job::KIND`]. [`enqueue`] takes any connection, so a job
+//! can be queued in the same transaction as the change that calls for it and
+//! only runs if that change commits.
+//!
+//! A [`Worker`] runs the jobs it has handlers for. A job that fails is tried
+//! again after a delay that doubles each time, until it has been tried
+//! [`Job::MAX_ATTEMPTS`] times; it is then `dead`, and stays in the table until
+//! an admin retries or deletes it. A recurring job is a single row that is
+//! queued again after each run.
+
+pub mod worker;
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rapina::prelude::tracing;
+use rapina::sea_orm::sea_query::OnConflict;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::entities::job::{ActiveModel, Column, Entity as JobEntity, Model};
+
+pub use worker::Worker;
+
+pub const STATUS_QUEUED: &str = "queued";
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_SUCCEEDED: &str = "succeeded";
+pub const STATUS_DEAD: &str = "dead";
+
+/// Delay before the first retry of a failed job, in seconds; it doubles with
+/// every further failure.
+const BACKOFF_BASE_SECONDS: i64 = 10;
+
+/// Longest delay between two tries of a job, in seconds.
+const BACKOFF_MAX_SECONDS: i64 = 60 * 60;
+
+/// A kind of background job. Its value is the job's payload, stored as JSON.
+pub trait Job: Serialize + DeserializeOwned + Send + 'static {
+    /// Name jobs of this type are stored under, which must not change while
+    /// any are queued.
+    const KIND: &'static str;
+
+    /// How many times a job is tried before it is dead.
+    const MAX_ATTEMPTS: i32 = 5;
+
+    /// How long one try may take before it is cancelled and counts as failed.
+    const TIMEOUT: Duration = Duration::from_secs(15 * 60);
+}
+
+/// Why a job failed.
+#[derive(Debug)]
+pub struct JobError {
+    message: String,
+    permanent: bool,
+}
+
+impl JobError {
+    /// A failure that may not happen again, such as a network error; the job
+    /// is retried.
+    pub fn new(message: impl std::fmt::Display) -> Self {
+        JobError {
+            message: message.to_string(),
+            permanent: false,
+        }
+    }
+
+    /// A failure retrying would not fix, such as an invalid payload; the job
+    /// is dead straight away.
+    pub fn permanent(message: impl std::fmt::Display) -> Self {
+        JobError {
+            message: message.to_string(),
+            permanent: true,
+        }
+    }
+
+    pub fn is_permanent(&self) -> bool {
+        self.permanent
+    }
+}
+
+impl std::fmt::Display for JobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for JobError {}
+
+impl From<DbErr> for JobError {
+    fn from(e: DbErr) -> Self {
+        JobError::new(e)
+    }
+}
+
+/// How long to wait before trying a job again once it has failed `attempts`
+/// times.
+fn backoff(attempts: i32) -> chrono::Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    let seconds = BACKOFF_BASE_SECONDS
+        .saturating_mul(1 << doublings)
+        .min(BACKOFF_MAX_SECONDS);
+    chrono::Duration::seconds(seconds)
+}
+
+fn payload<J: Job>(job: &J) -> Result<serde_json::Value, DbErr> {
+    serde_json::to_value(job)
+        .map_err(|e| DbErr::Custom(format!("could not serialize {} job: {e}", J::KIND)))
+}
+
+/// Queues `job` to run as soon as a worker is free.
+pub async fn enqueue<J: Job, C: ConnectionTrait>(conn: &C, job: &J) -> Result<Model, DbErr> {
+    enqueue_at(conn, job, Utc::now()).await
+}
+
+/// Queues `job` to run once `run_at` has passed.
+pub async fn enqueue_at<J: Job, C: ConnectionTrait>(
+    conn: &C,
+    job: &J,
+    run_at: DateTime<Utc>,
+) -> Result<Model, DbErr> {
+    ActiveModel {
+        pid: Set(Uuid::new_v4()),
+        kind: Set(J::KIND.to_string()),
+        payload: Set(payload(job)?),
+        status: Set(STATUS_QUEUED.to_string()),
+        max_attempts: Set(J::MAX_ATTEMPTS),
+        run_at: Set(run_at.fixed_offset()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+}
+
+/// Makes `job` run every `interval`, the first time straight away. There is
+/// one schedule per kind, shared by all workers; calling this again only
+/// updates its payload and interval.
+pub async fn schedule<J: Job, C: ConnectionTrait>(
+    conn: &C,
+    job: &J,
+    interval: Duration,
+) -> Result<(), DbErr> {
+    let model = ActiveModel {
+        pid: Set(Uuid::new_v4()),
+        kind: Set(J::KIND.to_string()),
+        payload: Set(payload(job)?),
+        status: Set(STATUS_QUEUED.to_string()),
+        max_attempts: Set(J::MAX_ATTEMPTS),
+        schedule: Set(Some(J::KIND.to_string())),
+        repeat_seconds: Set(Some(interval.as_secs().max(1) as i64)),
+        run_at: Set(Utc::now().fixed_offset()),
+        ..Default::default()
+    };
+
+    JobEntity::insert(model)
+        .on_conflict(
+            OnConflict::column(Column::Schedule)
+                .update_columns([Column::Payload, Column::MaxAttempts, Column::RepeatSeconds])
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
+        .await?;
+    Ok(())
+}
+
+/// Queues a dead job, or one waiting to be retried, to run again straight
+/// away with all its attempts.
+pub async fn retry<C: ConnectionTrait>(conn: &C, job: Model) -> Result<Model, DbErr> {
+    let mut active: ActiveModel = job.into();
+    active.status = Set(STATUS_QUEUED.to_string());
+    active.attempts = Set(0);
+    active.run_at = Set(Utc::now().fixed_offset());
+    active.finished_at = Set(None);
+    active.updated_at = Set(Utc::now().fixed_offset());
+    active.update(conn).await
+}
+
+/// Deletes jobs that succeeded more than `retention` ago, and returns how
+/// many there were. Dead jobs are kept until an admin deals with them.
+pub async fn prune(conn: &DatabaseConnection, retention: chrono::Duration) -> Result<u64, DbErr> {
+    let cutoff = Utc::now().fixed_offset() - retention;
+
+    Ok(JobEntity::delete_many()
+        .filter(Column::Status.eq(STATUS_SUCCEEDED))
+        .filter(Column::FinishedAt.lt(cutoff))
+        .exec(conn)
+        .await?
+        .rows_affected)
+}
+
+/// How often succeeded jobs are pruned.
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Job that runs [`prune`].
+#[derive(Serialize, Deserialize)]
+pub struct PruneJobs {
+    pub retention_days: i64,
+}
+
+impl Job for PruneJobs {
+    const KIND: &'static str = "jobs.prune";
+}
+
+impl PruneJobs {
+    pub async fn run(self, conn: &DatabaseConnection) -> Result<(), JobError> {
+        let count = prune(conn, chrono::Duration::days(self.retention_days)).await?;
+        if count > 0 {
+            tracing::info!(count, "pruned succeeded jobs");
+        }
+        Ok(())
+    }
+}
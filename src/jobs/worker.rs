@@ -0,0 +1,354 @@
+//! Running queued jobs.
+//!
+//! A worker claims one due job at a time by locking its row with `FOR UPDATE
+//! SKIP LOCKED`, so workers in several app instances never claim the same
+//! job, and marks it `running` until its timeout has passed. If the worker
+//! dies meanwhile, the job is claimed again once that lease runs out.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use rapina::prelude::tracing;
+use rapina::sea_orm::sea_query::{Condition, LockBehavior, LockType};
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait, Unchanged,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+use crate::db::entities::job::{ActiveModel, Column, Entity as JobEntity, Model};
+
+use super::{Job, JobError, STATUS_DEAD, STATUS_QUEUED, STATUS_RUNNING, STATUS_SUCCEEDED, backoff};
+
+/// Jobs run at once by a worker unless set otherwise.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// How long an idle worker waits before looking for due jobs again.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long past its timeout a running job is left to its worker before
+/// another may claim it.
+const LEASE_GRACE: chrono::Duration = chrono::Duration::minutes(1);
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<(), JobError>> + Send>>;
+type RunFn = dyn Fn(DatabaseConnection, serde_json::Value) -> JobFuture + Send + Sync;
+
+#[derive(Clone)]
+struct Handler {
+    run: Arc<RunFn>,
+    timeout: Duration,
+    limit: Option<Arc<Semaphore>>,
+}
+
+type ScheduleFn = dyn Fn(DatabaseConnection) -> Pin<Box<dyn Future<Output = Result<(), DbErr>> + Send>>
+    + Send
+    + Sync;
+
+/// Runs queued jobs of the kinds it has handlers for.
+pub struct Worker {
+    conn: DatabaseConnection,
+    id: String,
+    concurrency: usize,
+    poll_interval: Duration,
+    handlers: HashMap<&'static str, Handler>,
+    schedules: Vec<Box<ScheduleFn>>,
+}
+
+impl Worker {
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Worker {
+            conn,
+            id: Uuid::new_v4().to_string(),
+            concurrency: DEFAULT_CONCURRENCY,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            handlers: HashMap::new(),
+            schedules: Vec::new(),
+        }
+    }
+
+    /// Caps how many jobs this worker runs at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Runs jobs of type `J` with `handler`, which is given its own handle on
+    /// the worker's connection pool.
+    pub fn handle<J, F, Fut>(mut self, handler: F) -> Self
+    where
+        J: Job,
+        F: Fn(DatabaseConnection, J) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), JobError>> + Send + 'static,
+    {
+        let run = move |conn: DatabaseConnection, payload: serde_json::Value| -> JobFuture {
+            match serde_json::from_value::<J>(payload) {
+                Ok(job) => Box::pin(handler(conn, job)),
+                Err(e) => Box::pin(async move {
+                    Err(JobError::permanent(format!(
+                        "invalid {} payload: {e}",
+                        J::KIND
+                    )))
+                }),
+            }
+        };
+        let limit = self.handlers.remove(J::KIND).and_then(|h| h.limit);
+        self.handlers.insert(
+            J::KIND,
+            Handler {
+                run: Arc::new(run),
+                timeout: J::TIMEOUT,
+                limit,
+            },
+        );
+        self
+    }
+
+    /// Caps how many jobs of type `J` this worker runs at once. Register the
+    /// handler first.
+    pub fn limit<J: Job>(mut self, limit: usize) -> Self {
+        if let Some(handler) = self.handlers.get_mut(J::KIND) {
+            handler.limit = Some(Arc::new(Semaphore::new(limit.max(1))));
+        }
+        self
+    }
+
+    /// Makes `job` run every `interval`; see [`super::schedule`].
+    pub fn every<J: Job + Sync>(mut self, interval: Duration, job: J) -> Self {
+        let job = Arc::new(job);
+        self.schedules.push(Box::new(move |conn| {
+            let job = job.clone();
+            Box::pin(async move { super::schedule(&conn, job.as_ref(), interval).await })
+        }));
+        self
+    }
+
+    /// Creates or updates the worker's schedules.
+    pub async fn schedule(&self) -> Result<(), DbErr> {
+        for schedule in &self.schedules {
+            schedule(self.conn.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// Runs jobs as they come due, forever.
+    pub async fn run(self) {
+        let mut scheduled = false;
+        let mut tasks = JoinSet::new();
+
+        loop {
+            if !scheduled {
+                match self.schedule().await {
+                    Ok(()) => scheduled = true,
+                    Err(e) => tracing::error!(error = %e, "failed to schedule recurring jobs"),
+                }
+            }
+            if let Err(e) = self.fill(&mut tasks).await {
+                tracing::error!(error = %e, "failed to claim jobs");
+            }
+
+            // Claim more as soon as a slot frees up, or poll for new jobs.
+            tokio::select! {
+                Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
+                _ = tokio::time::sleep(self.poll_interval) => {}
+            }
+        }
+    }
+
+    /// Runs jobs until none are due, and returns how many ran.
+    pub async fn run_until_idle(&self) -> Result<usize, DbErr> {
+        let mut tasks = JoinSet::new();
+        let mut count = 0;
+
+        loop {
+            count += self.fill(&mut tasks).await?;
+            if tasks.join_next().await.is_none() {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Claims due jobs and starts them until every slot is taken or none are
+    /// due, and returns how many were started.
+    async fn fill(&self, tasks: &mut JoinSet<()>) -> Result<usize, DbErr> {
+        let mut started = 0;
+
+        while tasks.len() < self.concurrency {
+            let kinds: Vec<&str> = self
+                .handlers
+                .iter()
+                .filter(|(_, h)| h.limit.as_ref().is_none_or(|l| l.available_permits() > 0))
+                .map(|(kind, _)| *kind)
+                .collect();
+            if kinds.is_empty() {
+                break;
+            }
+
+            let Some(job) = self.claim(&kinds).await? else {
+                break;
+            };
+            let handler = self.handlers[job.kind.as_str()].clone();
+            // Only this loop takes permits, and it just saw one free.
+            let permit = handler
+                .limit
+                .clone()
+                .and_then(|l| l.try_acquire_owned().ok());
+
+            tasks.spawn(execute(self.conn.clone(), handler, job, permit));
+            started += 1;
+        }
+        Ok(started)
+    }
+
+    /// Locks the next due job of one of `kinds`, or one whose worker gave up
+    /// on it, and marks it running.
+    async fn claim(&self, kinds: &[&str]) -> Result<Option<Model>, DbErr> {
+        let now = Utc::now().fixed_offset();
+        let txn = self.conn.begin().await?;
+
+        let job = JobEntity::find()
+            .filter(Column::Kind.is_in(kinds.iter().copied()))
+            .filter(
+                Condition::any()
+                    .add(
+                        Condition::all()
+                            .add(Column::Status.eq(STATUS_QUEUED))
+                            .add(Column::RunAt.lte(now)),
+                    )
+                    .add(
+                        Condition::all()
+                            .add(Column::Status.eq(STATUS_RUNNING))
+                            .add(Column::LockedUntil.lt(now)),
+                    ),
+            )
+            .order_by_asc(Column::RunAt)
+            .order_by_asc(Column::Id)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .one(&txn)
+            .await?;
+        let Some(job) = job else {
+            return Ok(None);
+        };
+
+        let timeout = self.handlers[job.kind.as_str()].timeout;
+        let lease = chrono::Duration::from_std(timeout).unwrap_or(chrono::Duration::MAX);
+        let attempts = job.attempts + 1;
+        let mut active: ActiveModel = job.into();
+        active.status = Set(STATUS_RUNNING.to_string());
+        active.attempts = Set(attempts);
+        active.locked_until = Set(Some(now + lease + LEASE_GRACE));
+        active.locked_by = Set(Some(self.id.clone()));
+        active.updated_at = Set(now);
+        let job = active.update(&txn).await?;
+
+        txn.commit().await?;
+        Ok(Some(job))
+    }
+}
+
+/// Runs a claimed job and records how it went.
+async fn execute(
+    conn: DatabaseConnection,
+    handler: Handler,
+    job: Model,
+    _permit: Option<OwnedSemaphorePermit>,
+) {
+    // Running the job as its own task turns a panic into an error.
+    let task = tokio::spawn((handler.run)(conn.clone(), job.payload.clone()));
+    let abort = task.abort_handle();
+    let result = match tokio::time::timeout(handler.timeout, task).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(JobError::new(format!("job panicked: {e}"))),
+        Err(_) => {
+            abort.abort();
+            Err(JobError::new(format!(
+                "job timed out after {}s",
+                handler.timeout.as_secs()
+            )))
+        }
+    };
+
+    if let Err(e) = finish(&conn, &job, result).await {
+        tracing::error!(job = %job.pid, kind = job.kind, error = %e, "failed to record job result");
+    }
+}
+
+/// Records the result of running `job`: done, to be tried again, or dead.
+async fn finish(
+    conn: &DatabaseConnection,
+    job: &Model,
+    result: Result<(), JobError>,
+) -> Result<(), DbErr> {
+    let now = Utc::now().fixed_offset();
+    let mut active = ActiveModel {
+        id: Unchanged(job.id),
+        locked_until: Set(None),
+        locked_by: Set(None),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+
+    match &result {
+        Ok(()) => active.last_error = Set(None),
+        Err(e) => active.last_error = Set(Some(e.to_string())),
+    }
+
+    if let Err(e) = &result
+        && !e.is_permanent()
+        && job.attempts < job.max_attempts
+    {
+        let delay = backoff(job.attempts);
+        tracing::warn!(
+            job = %job.pid,
+            kind = job.kind,
+            attempt = job.attempts,
+            retry_in = delay.num_seconds(),
+            error = %e,
+            "job failed"
+        );
+        active.status = Set(STATUS_QUEUED.to_string());
+        active.run_at = Set(now + delay);
+    } else if let Some(seconds) = job.repeat_seconds {
+        // A recurring job goes on with its schedule whatever happened.
+        if let Err(e) = &result {
+            tracing::error!(job = %job.pid, kind = job.kind, error = %e, "recurring job failed");
+        }
+        active.status = Set(STATUS_QUEUED.to_string());
+        active.attempts = Set(0);
+        active.run_at = Set(now + chrono::Duration::seconds(seconds));
+    } else if let Err(e) = &result {
+        tracing::error!(
+            job = %job.pid,
+            kind = job.kind,
+            attempts = job.attempts,
+            error = %e,
+            "job is dead"
+        );
+        active.status = Set(STATUS_DEAD.to_string());
+        active.finished_at = Set(Some(now));
+    } else {
+        active.status = Set(STATUS_SUCCEEDED.to_string());
+        active.finished_at = Set(Some(now));
+    }
+
+    // If the lease ran out and another worker claimed the job meanwhile, the
+    // result is that worker's to record.
+    JobEntity::update_many()
+        .set(active)
+        .filter(Column::Id.eq(job.id))
+        .filter(Column::LockedBy.eq(job.locked_by.clone()))
+        .filter(Column::LockedUntil.eq(job.locked_until))
+        .exec(conn)
+        .await?;
+    Ok(())
+}
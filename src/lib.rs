@@ -1,3 +1,16 @@
 pub mod api;
+pub mod connectors;
 pub mod db;
+pub mod images;
+pub mod incentives;
+pub mod jobs;
+pub mod language;
+pub mod mail;
+pub mod net;
+pub mod notifications;
+pub mod secrets;
+pub mod sentiment;
 pub mod static_files;
+pub mod storage;
+pub mod video;
+pub mod webhooks;
@@ -0,0 +1,43 @@
+//! A transport that keeps emails instead of sending them.
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use rapina::prelude::tracing;
+
+use super::{Email, MailError, Transport};
+
+/// Keeps every email in memory and logs it, for tests and for running
+/// locally without an email provider. Clones share the same outbox.
+#[derive(Clone, Default)]
+pub struct Capture {
+    sent: Arc<Mutex<Vec<Email>>>,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The emails sent so far.
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// The emails sent to `to` so far, which are removed from the outbox.
+    pub fn take_for(&self, to: &str) -> Vec<Email> {
+        let mut sent = self.sent.lock().unwrap();
+        let (taken, kept) = sent.drain(..).partition(|e| e.to == to);
+        *sent = kept;
+        taken
+    }
+}
+
+#[async_trait]
+impl Transport for Capture {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        tracing::info!(to = email.to, subject = email.subject, "captured email");
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+//! A transport for transactional email APIs.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{Email, MailError, Transport};
+
+/// How long the email API has to accept a message.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Posts each email as JSON (`from`, `to`, `subject`, `html`, `text`) with a
+/// bearer token, as Resend's API and compatible services take it.
+pub struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+}
+
+impl HttpTransport {
+    pub fn new(url: &str, api_key: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("failed to build HTTP client");
+
+        HttpTransport {
+            client,
+            url: url.to_string(),
+            api_key: api_key.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .bearer_auth(&self.api_key)
+            .json(&json!({
+                "from": email.from,
+                "to": [email.to],
+                "subject": email.subject,
+                "html": email.html,
+                "text": email.text,
+            }))
+            .send()
+            .await
+            .map_err(|e| MailError(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(MailError(format!(
+            "email API answered {status}: {}",
+            body.chars().take(200).collect::<String>()
+        )))
+    }
+}
@@ -0,0 +1,72 @@
+//! Sending email.
+//!
+//! Emails go out through a [`Transport`]: [`HttpTransport`] posts them to a
+//! transactional email API, and [`Capture`] keeps them in memory instead, for
+//! tests and local development. The [`Mailer`] in the app state wraps
+//! whichever is configured along with the sender address.
+
+pub mod capture;
+pub mod http;
+pub mod templates;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+pub use capture::Capture;
+pub use http::HttpTransport;
+
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "mail error: {}", self.0)
+    }
+}
+
+impl std::error::Error for MailError {}
+
+/// An email ready to send, with both an HTML and a plain text body.
+#[derive(Clone, Debug)]
+pub struct Email {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+/// The configured transport and the address emails are sent from.
+#[derive(Clone)]
+pub struct Mailer {
+    transport: Arc<dyn Transport>,
+    from: String,
+}
+
+impl Mailer {
+    pub fn new(transport: impl Transport + 'static, from: &str) -> Self {
+        Mailer {
+            transport: Arc::new(transport),
+            from: from.to_string(),
+        }
+    }
+
+    /// Sends a rendered message to `to`.
+    pub async fn deliver(&self, to: &str, message: templates::Message) -> Result<(), MailError> {
+        self.transport
+            .send(&Email {
+                from: self.from.clone(),
+                to: to.to_string(),
+                subject: message.subject,
+                html: message.html,
+                text: message.text,
+            })
+            .await
+    }
+}
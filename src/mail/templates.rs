@@ -0,0 +1,216 @@
+//! Branded HTML and plain text emails.
+//!
+//! A [`MessageBuilder`] lays out a message from a few kinds of blocks and
+//! renders each one both as HTML and as text, so the two bodies always say
+//! the same thing. The HTML sticks to tables and inline styles, which is what
+//! email clients render reliably.
+
+/// Accent used for projects without a color of their own.
+pub const DEFAULT_ACCENT_COLOR: &str = "#6366f1";
+
+/// How a project presents itself in emails.
+#[derive(Clone, Debug)]
+pub struct Branding {
+    pub name: String,
+    /// Absolute URL of the logo shown above the message.
+    pub logo_url: Option<String>,
+    pub website_url: Option<String>,
+    pub accent_color: String,
+}
+
+impl Branding {
+    pub fn new(name: &str) -> Self {
+        Branding {
+            name: name.to_string(),
+            logo_url: None,
+            website_url: None,
+            accent_color: DEFAULT_ACCENT_COLOR.to_string(),
+        }
+    }
+}
+
+/// A rendered email, before it is addressed.
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Escapes `s` for HTML text and attribute values.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Builds the body of a message block by block.
+pub struct MessageBuilder<'a> {
+    branding: &'a Branding,
+    html: String,
+    text: String,
+}
+
+impl<'a> MessageBuilder<'a> {
+    pub fn new(branding: &'a Branding) -> Self {
+        MessageBuilder {
+            branding,
+            html: String::new(),
+            text: String::new(),
+        }
+    }
+
+    pub fn heading(mut self, heading: &str) -> Self {
+        self.html.push_str(&format!(
+            r#"<h1 style="margin:0 0 16px;font-size:20px;color:#111827">{}</h1>"#,
+            escape(heading)
+        ));
+        self.text.push_str(&format!(
+            "{heading}\n{}\n\n",
+            "=".repeat(heading.chars().count())
+        ));
+        self
+    }
+
+    pub fn paragraph(mut self, text: &str) -> Self {
+        self.html.push_str(&format!(
+            r#"<p style="margin:0 0 16px;line-height:1.5">{}</p>"#,
+            escape(text)
+        ));
+        self.text.push_str(&format!("{text}\n\n"));
+        self
+    }
+
+    /// A quoted piece of text, such as a testimonial, and who it is by.
+    pub fn quote(mut self, quote: &str, attribution: &str) -> Self {
+        self.html.push_str(&format!(
+            concat!(
+                r#"<blockquote style="margin:0 0 16px;padding:12px 16px;border-left:4px solid {};background:#f9fafb">"#,
+                r#"<p style="margin:0 0 8px;line-height:1.5;white-space:pre-line">{}</p>"#,
+                r#"<p style="margin:0;color:#6b7280">— {}</p></blockquote>"#
+            ),
+            escape(&self.branding.accent_color),
+            escape(quote),
+            escape(attribution)
+        ));
+        let quoted: Vec<String> = quote.lines().map(|line| format!("> {line}")).collect();
+        self.text
+            .push_str(&format!("{}\n> — {attribution}\n\n", quoted.join("\n")));
+        self
+    }
+
+    pub fn button(mut self, label: &str, url: &str) -> Self {
+        self.html.push_str(&format!(
+            concat!(
+                r#"<p style="margin:0 0 16px"><a href="{}" "#,
+                r#"style="display:inline-block;padding:10px 18px;border-radius:6px;background:{};color:#ffffff;text-decoration:none">{}</a></p>"#
+            ),
+            escape(url),
+            escape(&self.branding.accent_color),
+            escape(label)
+        ));
+        self.text.push_str(&format!("{label}: {url}\n\n"));
+        self
+    }
+
+    /// A code for the reader to copy, such as a coupon.
+    pub fn code(mut self, code: &str) -> Self {
+        self.html.push_str(&format!(
+            concat!(
+                r#"<p style="margin:0 0 16px"><span style="display:inline-block;padding:10px 18px;"#,
+                r#"border:2px dashed {};border-radius:6px;font-family:monospace;font-size:18px;color:#111827">{}</span></p>"#
+            ),
+            escape(&self.branding.accent_color),
+            escape(code)
+        ));
+        self.text.push_str(&format!("    {code}\n\n"));
+        self
+    }
+
+    /// A row of `(label, url)` links.
+    pub fn links(mut self, links: &[(&str, &str)]) -> Self {
+        let html: Vec<String> = links
+            .iter()
+            .map(|(label, url)| {
+                format!(
+                    r#"<a href="{}" style="color:{}">{}</a>"#,
+                    escape(url),
+                    escape(&self.branding.accent_color),
+                    escape(label)
+                )
+            })
+            .collect();
+        self.html.push_str(&format!(
+            r#"<p style="margin:0 0 16px;line-height:1.5">{}</p>"#,
+            html.join(" &middot; ")
+        ));
+        for (label, url) in links {
+            self.text.push_str(&format!("{label}: {url}\n"));
+        }
+        self.text.push('\n');
+        self
+    }
+
+    /// Wraps the body in the branded layout.
+    pub fn build(self, subject: &str) -> Message {
+        let branding = self.branding;
+        let name = escape(&branding.name);
+
+        let header = match &branding.logo_url {
+            Some(logo) => format!(
+                r#"<img src="{}" alt="{name}" height="40" style="display:block;border:0">"#,
+                escape(logo)
+            ),
+            None => format!(
+                r#"<span style="font-size:18px;font-weight:bold;color:{}">{name}</span>"#,
+                escape(&branding.accent_color)
+            ),
+        };
+        let footer = match &branding.website_url {
+            Some(url) => format!(
+                r#"<a href="{}" style="color:#6b7280">{name}</a>"#,
+                escape(url)
+            ),
+            None => name.clone(),
+        };
+
+        let html = format!(
+            concat!(
+                "<!DOCTYPE html>\n",
+                r#"<html><head><meta charset="utf-8"><title>{subject}</title></head>"#,
+                r#"<body style="margin:0;padding:24px;background:#f3f4f6;font-family:Helvetica,Arial,sans-serif;color:#374151">"#,
+                r#"<table role="presentation" width="100%" cellpadding="0" cellspacing="0"><tr><td align="center">"#,
+                r#"<table role="presentation" width="560" cellpadding="0" cellspacing="0" style="max-width:560px;background:#ffffff;border-radius:8px">"#,
+                r#"<tr><td style="padding:24px 32px 0">{header}</td></tr>"#,
+                r#"<tr><td style="padding:24px 32px">{body}</td></tr>"#,
+                r#"<tr><td style="padding:16px 32px;border-top:1px solid #e5e7eb;font-size:12px;color:#6b7280">{footer}</td></tr>"#,
+                "</table></td></tr></table></body></html>\n"
+            ),
+            subject = escape(subject),
+            header = header,
+            body = self.html,
+            footer = footer,
+        );
+
+        let mut text = self.text;
+        text.push_str(&format!("-- \n{}\n", branding.name));
+        if let Some(url) = &branding.website_url {
+            text.push_str(&format!("{url}\n"));
+        }
+
+        Message {
+            subject: subject.to_string(),
+            html,
+            text,
+        }
+    }
+}
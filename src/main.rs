@@ -2,12 +2,37 @@ use rapina::database::DatabaseConfig;
 use rapina::middleware::RequestLogMiddleware;
 use rapina::prelude::*;
 use rapina::schemars;
+use rapina::sea_orm_migration::MigratorTrait;
 
+use reeverb::api::v1::admin::{self, access::Admins};
 use reeverb::api::v1::auth;
+use reeverb::api::v1::forms::{self, throttle::SubmissionThrottle};
+use reeverb::api::v1::import_sources;
+use reeverb::api::v1::imports::{self, run::RunImport};
+use reeverb::api::v1::media;
+use reeverb::api::v1::openapi::{self, ApiSpec};
 use reeverb::api::v1::projects;
 use reeverb::api::v1::tags;
 use reeverb::api::v1::testimonials;
+use reeverb::api::v1::uploads::{self, expiry, limits::UploadLimits};
+use reeverb::api::v1::webhooks;
+use reeverb::api::v1::widgets;
+use reeverb::connectors::{self, BaseUrls, Connectors, scheduler};
+use reeverb::db::trash;
+use reeverb::incentives::SendThankYou;
+use reeverb::jobs::{self, Worker};
+use reeverb::mail::{Capture, HttpTransport, Mailer};
+use reeverb::net::Destinations;
+use reeverb::notifications::{self, Notifier};
+use reeverb::secrets::{Keyring, SecretError};
+use reeverb::sentiment::{AnalyzeSentiment, SentimentAnalyzer};
 use reeverb::static_files::DashboardMiddleware;
+use reeverb::storage::MediaStorage;
+use reeverb::storage::local::LocalStorage;
+use reeverb::storage::s3::{S3Config, S3Storage};
+use reeverb::storage::sigv4::Credentials;
+use reeverb::video::{self, Ffmpeg, ProcessVideo, Transcriber, VideoPipeline, transcribe};
+use reeverb::webhooks::{Deliver, Sender, reseal_secrets};
 
 #[derive(Clone, Config)]
 struct AppConfig {
@@ -21,6 +46,152 @@ struct AppConfig {
     #[env = "PORT"]
     #[default = "3000"]
     port: u16,
+
+    /// Keys that encrypt import source credentials and webhook secrets, as
+    /// comma-separated `id:base64` pairs. The first seals new values. Without
+    /// keys the server still starts, but creating import sources or webhooks
+    /// fails until keys are set.
+    #[env = "CREDENTIALS_KEYS"]
+    #[default = ""]
+    credentials_keys: String,
+
+    #[env = "TRASH_RETENTION_DAYS"]
+    #[default = "30"]
+    trash_retention_days: i64,
+
+    /// Comma-separated ids of the users who may use the admin endpoints.
+    #[env = "ADMIN_USER_IDS"]
+    #[default = ""]
+    admin_user_ids: String,
+
+    /// Background jobs run at once by this instance.
+    #[env = "JOB_CONCURRENCY"]
+    #[default = "4"]
+    job_concurrency: usize,
+
+    /// Days succeeded jobs are kept for; dead ones are kept until an admin
+    /// retries or deletes them.
+    #[env = "JOB_RETENTION_DAYS"]
+    #[default = "7"]
+    job_retention_days: i64,
+
+    /// Where the app is reached, which links in emails point into.
+    #[env = "APP_URL"]
+    #[default = "http://localhost:3000"]
+    app_url: String,
+
+    /// How emails are sent: `capture` only logs them, `http` posts them to
+    /// the email API at `MAIL_API_URL`.
+    #[env = "MAIL_TRANSPORT"]
+    #[default = "capture"]
+    mail_transport: String,
+
+    #[env = "MAIL_FROM"]
+    #[default = "Reeverb <notifications@localhost>"]
+    mail_from: String,
+
+    #[env = "MAIL_API_URL"]
+    #[default = "https://api.resend.com/emails"]
+    mail_api_url: String,
+
+    #[env = "MAIL_API_KEY"]
+    #[default = ""]
+    mail_api_key: String,
+
+    #[env = "TRUSTPILOT_API_URL"]
+    #[default = "https://api.trustpilot.com"]
+    trustpilot_api_url: String,
+
+    #[env = "G2_API_URL"]
+    #[default = "https://data.g2.com"]
+    g2_api_url: String,
+
+    #[env = "GOOGLE_BUSINESS_API_URL"]
+    #[default = "https://mybusiness.googleapis.com"]
+    google_business_api_url: String,
+
+    #[env = "PRODUCT_HUNT_API_URL"]
+    #[default = "https://api.producthunt.com"]
+    product_hunt_api_url: String,
+
+    #[env = "TWITTER_API_URL"]
+    #[default = "https://api.twitter.com"]
+    twitter_api_url: String,
+
+    /// Let webhooks, and video URLs submitted through forms, reach loopback
+    /// and private network addresses, for receivers running next to the
+    /// server in development. Off by default, so neither can be pointed at
+    /// internal services.
+    #[env = "WEBHOOK_ALLOW_PRIVATE_NETWORKS"]
+    #[default = "false"]
+    webhook_allow_private_networks: bool,
+
+    /// Where uploads and generated media are stored: `local` for
+    /// `MEDIA_DIR`, or `s3` for an S3-compatible bucket.
+    #[env = "STORAGE_BACKEND"]
+    #[default = "local"]
+    storage_backend: String,
+
+    /// Directory media is stored in with the local backend.
+    #[env = "MEDIA_DIR"]
+    #[default = "media"]
+    media_dir: String,
+
+    /// URL generated media is served from, for when the API is behind a
+    /// proxy or on another host than the widgets embedding it.
+    #[env = "MEDIA_BASE_URL"]
+    #[default = "/api/v1/media"]
+    media_base_url: String,
+
+    /// Endpoint of the S3-compatible service, such as
+    /// `https://s3.us-east-1.amazonaws.com` or a MinIO server.
+    #[env = "S3_ENDPOINT"]
+    #[default = ""]
+    s3_endpoint: String,
+
+    #[env = "S3_BUCKET"]
+    #[default = ""]
+    s3_bucket: String,
+
+    #[env = "S3_REGION"]
+    #[default = "us-east-1"]
+    s3_region: String,
+
+    #[env = "S3_ACCESS_KEY_ID"]
+    #[default = ""]
+    s3_access_key_id: String,
+
+    #[env = "S3_SECRET_ACCESS_KEY"]
+    #[default = ""]
+    s3_secret_access_key: String,
+
+    /// Addresses the bucket in the path rather than the host name, as MinIO
+    /// needs.
+    #[env = "S3_FORCE_PATH_STYLE"]
+    #[default = "false"]
+    s3_force_path_style: bool,
+
+    #[env = "UPLOAD_MAX_IMAGE_BYTES"]
+    #[default = "10485760"]
+    upload_max_image_bytes: u64,
+
+    #[env = "UPLOAD_MAX_VIDEO_BYTES"]
+    #[default = "524288000"]
+    upload_max_video_bytes: u64,
+
+    #[env = "FFMPEG_PATH"]
+    #[default = "ffmpeg"]
+    ffmpeg_path: String,
+
+    #[env = "FFPROBE_PATH"]
+    #[default = "ffprobe"]
+    ffprobe_path: String,
+
+    /// Local command that transcribes a video given its URL; videos are not
+    /// transcribed if it is empty.
+    #[env = "TRANSCRIBE_COMMAND"]
+    #[default = ""]
+    transcribe_command: String,
 }
 
 #[derive(Serialize, JsonSchema)]
@@ -38,11 +209,60 @@ async fn health() -> Json<HealthResponse> {
     })
 }
 
+/// Reseals all import source credentials and webhook secrets under the
+/// active key, after which older keys can be removed from `CREDENTIALS_KEYS`.
+async fn reseal_credentials(config: &AppConfig, keyring: &Keyring) -> std::io::Result<()> {
+    let Some(active) = keyring.active_key_id() else {
+        return Err(std::io::Error::other(SecretError::NoKeys.to_string()));
+    };
+    let conn = DatabaseConfig::new(&config.database_url)
+        .connect()
+        .await
+        .map_err(|e| std::io::Error::other(format!("Database connection failed: {e}")))?;
+    reeverb::db::migrations::Migrator::up(&conn, None)
+        .await
+        .map_err(std::io::Error::other)?;
+
+    let updated = connectors::reseal_credentials(&conn, keyring)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    let webhooks = reseal_secrets(&conn, keyring)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    println!(
+        "resealed credentials of {updated} import sources and secrets of {webhooks} webhooks under key {active}"
+    );
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     dotenvy::dotenv().ok();
 
+    let command = std::env::args().nth(1);
+    if command.as_deref() == Some("generate-credentials-key") {
+        println!("{}", Keyring::generate_key());
+        return Ok(());
+    }
+
     let config = AppConfig::from_env().expect("missing required config");
+    let keyring = Keyring::parse(&config.credentials_keys)
+        .map_err(|e| std::io::Error::other(format!("CREDENTIALS_KEYS: {e}")))?;
+    let admins = Admins::parse(&config.admin_user_ids)
+        .map_err(|e| std::io::Error::other(format!("ADMIN_USER_IDS: {e}")))?;
+    let destinations = if config.webhook_allow_private_networks {
+        Destinations::Any
+    } else {
+        Destinations::Public
+    };
+
+    match command.as_deref() {
+        Some("reseal-credentials") => return reseal_credentials(&config, &keyring).await,
+        Some(other) => {
+            return Err(std::io::Error::other(format!("unknown command {other}")));
+        }
+        None => {}
+    }
     let auth_config = AuthConfig::from_env().expect("JWT_SECRET must be set");
 
     let router = Router::new()
@@ -51,25 +271,195 @@ async fn main() -> std::io::Result<()> {
         .group("/api/v1/projects", projects::routes())
         .group("/api/v1/projects", testimonials::project_routes())
         .group("/api/v1/projects", tags::project_routes())
+        .group("/api/v1/projects", imports::project_routes())
+        .group("/api/v1/projects", import_sources::project_routes())
         .group("/api/v1/tags", tags::routes())
         .group("/api/v1/testimonials", testimonials::routes())
-        .group("/api/v1/testimonials", tags::testimonial_tag_routes());
+        .group("/api/v1/testimonials", tags::testimonial_tag_routes())
+        .group("/api/v1/imports", imports::routes())
+        .group("/api/v1/projects", uploads::project_routes())
+        .group("/api/v1/import-sources", import_sources::routes())
+        .group("/api/v1/uploads", uploads::routes())
+        .group("/api/v1/media", media::routes())
+        .group("/api/v1/projects", webhooks::project_routes())
+        .group("/api/v1/webhooks", webhooks::routes())
+        .group("/api/v1/webhook-deliveries", webhooks::delivery_routes())
+        .group("/api/v1/forms", forms::routes())
+        .group("/api/v1/widgets", widgets::routes())
+        .group("/api/v1/admin", admin::routes());
+    let spec = ApiSpec::build("Reeverb API", env!("CARGO_PKG_VERSION"), &router.routes());
+    let router = router.get(openapi::PATH, openapi::openapi_spec);
+
+    let connectors = Connectors::new(BaseUrls {
+        trustpilot: config.trustpilot_api_url.clone(),
+        g2: config.g2_api_url.clone(),
+        google_business: config.google_business_api_url.clone(),
+        product_hunt: config.product_hunt_api_url.clone(),
+        twitter: config.twitter_api_url.clone(),
+    });
+    let analyzer = SentimentAnalyzer::default();
+    let storage = match config.storage_backend.as_str() {
+        "local" => MediaStorage::new(LocalStorage::new(&config.media_dir), &config.media_base_url),
+        "s3" => {
+            let s3 = S3Storage::new(S3Config {
+                endpoint: config.s3_endpoint.clone(),
+                bucket: config.s3_bucket.clone(),
+                region: config.s3_region.clone(),
+                credentials: Credentials {
+                    access_key_id: config.s3_access_key_id.clone(),
+                    secret_access_key: config.s3_secret_access_key.clone(),
+                },
+                path_style: config.s3_force_path_style,
+            })
+            .map_err(|e| std::io::Error::other(format!("S3 storage: {e}")))?;
+            MediaStorage::new(s3, &config.media_base_url)
+        }
+        other => {
+            return Err(std::io::Error::other(format!(
+                "STORAGE_BACKEND must be local or s3, not {other}"
+            )));
+        }
+    };
+    let mailer = match config.mail_transport.as_str() {
+        "capture" => Mailer::new(Capture::new(), &config.mail_from),
+        "http" => Mailer::new(
+            HttpTransport::new(&config.mail_api_url, &config.mail_api_key),
+            &config.mail_from,
+        ),
+        other => {
+            return Err(std::io::Error::other(format!(
+                "MAIL_TRANSPORT must be capture or http, not {other}"
+            )));
+        }
+    };
+    let notifier = Notifier::new(mailer, &config.app_url);
+    let upload_limits = UploadLimits {
+        max_image_bytes: config.upload_max_image_bytes,
+        max_video_bytes: config.upload_max_video_bytes,
+    };
+    let ffmpeg = Ffmpeg::new(&config.ffmpeg_path, &config.ffprobe_path);
+    let transcriber: Box<dyn Transcriber> =
+        match transcribe::Command::parse(&config.transcribe_command) {
+            Some(command) => Box::new(command),
+            None => Box::new(transcribe::Disabled),
+        };
+    let pipeline = VideoPipeline::new(
+        ffmpeg,
+        transcriber,
+        storage.clone(),
+        analyzer.clone(),
+        destinations,
+    );
 
     let mut app = Rapina::new()
         .with_tracing(TracingConfig::new())
         .with_auth(auth_config.clone())
         .public_route("GET", "/health");
 
-    for (method, path) in auth::PUBLIC_ROUTES {
+    for (method, path) in auth::PUBLIC_ROUTES
+        .iter()
+        .chain(media::PUBLIC_ROUTES)
+        .chain(forms::PUBLIC_ROUTES)
+        .chain(widgets::PUBLIC_ROUTES)
+    {
         app = app.public_route(method, path);
     }
 
     let addr = format!("{}:{}", config.host, config.port);
 
-    app.openapi("Reeverb API", env!("CARGO_PKG_VERSION"))
-        .middleware(DashboardMiddleware)
+    let jobs_conn = DatabaseConfig::new(&config.database_url)
+        .connect()
+        .await
+        .map_err(|e| std::io::Error::other(format!("Database connection failed: {e}")))?;
+    // The worker reads the jobs table, so it must exist first.
+    reeverb::db::migrations::Migrator::up(&jobs_conn, None)
+        .await
+        .map_err(std::io::Error::other)?;
+    let worker = Worker::new(jobs_conn)
+        .concurrency(config.job_concurrency)
+        .handle(|conn, job: trash::PurgeTrash| async move { job.run(&conn).await })
+        .handle({
+            let storage = storage.clone();
+            move |conn, job: expiry::ExpireUploads| {
+                let storage = storage.clone();
+                async move { job.run(&conn, &storage).await }
+            }
+        })
+        .handle({
+            let (connectors, keyring) = (connectors.clone(), keyring.clone());
+            move |conn, job: scheduler::SyncDueSources| {
+                let (connectors, keyring) = (connectors.clone(), keyring.clone());
+                async move { job.run(&conn, &connectors, &keyring).await }
+            }
+        })
+        .handle(move |conn, job: ProcessVideo| {
+            let pipeline = pipeline.clone();
+            async move { pipeline.process(&conn, job).await }
+        })
+        .limit::<ProcessVideo>(video::MAX_CONCURRENT)
+        .handle({
+            let analyzer = analyzer.clone();
+            move |conn, job: AnalyzeSentiment| {
+                let analyzer = analyzer.clone();
+                async move { analyzer.analyze(&conn, job).await }
+            }
+        })
+        .handle(|conn, job: RunImport| async move { job.run(&conn).await })
+        .handle(|conn, job: jobs::PruneJobs| async move { job.run(&conn).await })
+        .handle({
+            let notifier = notifier.clone();
+            move |conn, job: notifications::NotifySubmission| {
+                let notifier = notifier.clone();
+                async move { notifier.notify_submission(&conn, job).await }
+            }
+        })
+        .handle({
+            let notifier = notifier.clone();
+            move |conn, job: SendThankYou| {
+                let notifier = notifier.clone();
+                async move { notifier.send_thank_you(&conn, job).await }
+            }
+        })
+        .handle(move |conn, _: notifications::SendDigests| {
+            let notifier = notifier.clone();
+            async move { notifier.send_digests(&conn).await.map(|_| ()) }
+        })
+        .handle({
+            let sender = Sender::new(keyring.clone(), destinations);
+            move |conn, job: Deliver| {
+                let sender = sender.clone();
+                async move { sender.deliver(&conn, job).await }
+            }
+        })
+        .every(
+            trash::PURGE_INTERVAL,
+            trash::PurgeTrash {
+                retention_days: config.trash_retention_days,
+            },
+        )
+        .every(expiry::EXPIRY_INTERVAL, expiry::ExpireUploads)
+        .every(scheduler::SCHEDULE_INTERVAL, scheduler::SyncDueSources)
+        .every(notifications::DIGEST_INTERVAL, notifications::SendDigests)
+        .every(
+            jobs::PRUNE_INTERVAL,
+            jobs::PruneJobs {
+                retention_days: config.job_retention_days,
+            },
+        );
+    tokio::spawn(worker.run());
+
+    app.middleware(DashboardMiddleware)
         .middleware(RequestLogMiddleware::new())
         .state(auth_config)
+        .state(connectors)
+        .state(keyring)
+        .state(destinations)
+        .state(analyzer)
+        .state(storage)
+        .state(upload_limits)
+        .state(SubmissionThrottle::default())
+        .state(admins)
+        .state(spec)
         .with_database(DatabaseConfig::new(&config.database_url))
         .await?
         .run_migrations::<reeverb::db::migrations::Migrator>()
@@ -0,0 +1,151 @@
+//! Guards on outgoing requests to URLs users choose.
+//!
+//! Webhook URLs are set by project owners, so a request to one could
+//! otherwise reach this server's own network: services on loopback, hosts on
+//! a private network, or a cloud metadata endpoint such as
+//! `169.254.169.254`. With [`Destinations::Public`], [`Destinations::check`]
+//! refuses such URLs when they are saved, and clients built by
+//! [`client_builder`] refuse to connect to such addresses, whatever a host
+//! name resolves to at the time of the request.
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use reqwest::Url;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+/// Which addresses outgoing requests may be sent to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Destinations {
+    /// Only public addresses.
+    #[default]
+    Public,
+    /// Any address, including loopback and private networks. Meant for
+    /// development and tests, where receivers run locally.
+    Any,
+}
+
+/// Why a URL may not be requested.
+#[derive(Debug)]
+pub struct UrlError(String);
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UrlError {}
+
+impl Destinations {
+    /// Whether requests may be sent to `ip`.
+    pub fn permits(self, ip: IpAddr) -> bool {
+        self == Destinations::Any || is_public(ip)
+    }
+
+    /// Checks that `url` is an `http(s)` URL whose host, if it is an IP
+    /// address, may be requested. Host names are checked when they are
+    /// resolved, by the client's resolver.
+    pub fn check(self, url: &str) -> Result<(), UrlError> {
+        let url = Url::parse(url).map_err(|e| UrlError(format!("invalid url: {e}")))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(UrlError("url must use http or https".to_string()));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| UrlError("url must have a host".to_string()))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = host.parse::<IpAddr>()
+            && !self.permits(ip)
+        {
+            return Err(UrlError(format!(
+                "url must not point to {ip}, a non-public address"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `ip` is reachable on the public internet, as opposed to loopback,
+/// private, link-local, unspecified and other special-purpose addresses.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // "This network", 0.0.0.0/8.
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10.
+        || (a == 100 && (b & 0xc0) == 64)
+        // Reserved, 240.0.0.0/4.
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    // IPv4-mapped addresses, and the NAT64 prefix 64:ff9b::/96, reach the
+    // IPv4 address they embed.
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_public_v4(v4);
+    }
+    let segments = ip.segments();
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [.., a, b, c, d] = ip.octets();
+        return is_public_v4(Ipv4Addr::new(a, b, c, d));
+    }
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local, fc00::/7.
+        || (segments[0] & 0xfe00) == 0xfc00
+        // Link-local, fe80::/10.
+        || (segments[0] & 0xffc0) == 0xfe80)
+}
+
+/// Resolves host names with the system resolver, keeping only the addresses
+/// `destinations` permits.
+struct Resolver {
+    destinations: Destinations,
+}
+
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let destinations = self.destinations;
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| destinations.permits(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(
+                    UrlError(format!("{host} does not resolve to a public address")).into(),
+                );
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// A client builder whose connections only go to addresses `destinations`
+/// permits.
+///
+/// Requests to URLs with an IP address for a host skip the resolver, so
+/// check those with [`Destinations::check`] before sending. Proxies would
+/// resolve names themselves, so none are used.
+pub fn client_builder(destinations: Destinations) -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .no_proxy()
+        .dns_resolver(Arc::new(Resolver { destinations }))
+}
@@ -0,0 +1,504 @@
+//! Email notifications about new form submissions.
+//!
+//! A project's members, its owner and the users who accepted its invitation,
+//! hear about testimonials submitted through its collection forms as each
+//! one's `submission_notifications` preference says: an email for each, sent
+//! by a [`NotifySubmission`] job queued when the submission arrives; a daily
+//! digest, sent by the recurring [`SendDigests`] job; or nothing at all.
+//!
+//! Once a submission is approved, its author gets a thank-you with the form's
+//! incentive, sent by an [`incentives::SendThankYou`] job.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use rapina::prelude::tracing;
+use rapina::schemars::{self, JsonSchema};
+use rapina::sea_orm::sea_query::{Condition, Expr, Query, SelectStatement};
+use rapina::sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::db::entities::form::Model as FormModel;
+use crate::db::entities::project::{
+    Column as ProjectColumn, Entity as Project, Model as ProjectModel,
+};
+use crate::db::entities::project_member::{Column as MemberColumn, Entity as ProjectMember};
+use crate::db::entities::testimonial::{
+    Column as TestimonialColumn, Entity as Testimonial, Model as TestimonialModel,
+};
+use crate::db::entities::user::{Column as UserColumn, Entity as User, Model as UserModel};
+use crate::incentives::{self, SendThankYou};
+use crate::jobs::{self, Job, JobError};
+use crate::mail::Mailer;
+use crate::mail::templates::{Branding, Message, MessageBuilder};
+
+/// `source` of testimonials submitted through a collection form. Only the
+/// submission endpoint links them to their form, through `form_id`.
+pub const FORM_SOURCE: &str = "form";
+
+/// How often [`SendDigests`] looks for users due a digest.
+pub const DIGEST_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Time between two digests to the same user.
+const DIGEST_PERIOD: chrono::Duration = chrono::Duration::days(1);
+
+/// Submissions listed per project in a digest; the rest are only counted.
+const DIGEST_LIMIT: usize = 10;
+
+/// How a user hears about new form submissions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    /// An email for each submission.
+    #[default]
+    Immediate,
+    /// One email a day listing the day's submissions, if there were any.
+    Digest,
+    Off,
+}
+
+impl Frequency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Frequency::Immediate => "immediate",
+            Frequency::Digest => "digest",
+            Frequency::Off => "off",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "immediate" => Some(Frequency::Immediate),
+            "digest" => Some(Frequency::Digest),
+            "off" => Some(Frequency::Off),
+            _ => None,
+        }
+    }
+}
+
+/// Job that emails one of a project's members about one submission, if they
+/// want to hear about each.
+#[derive(Serialize, Deserialize)]
+pub struct NotifySubmission {
+    pub testimonial_id: i32,
+    pub user_id: i32,
+}
+
+impl Job for NotifySubmission {
+    const KIND: &'static str = "notifications.submission";
+}
+
+/// The members of `project`: its owner, then the users who accepted its
+/// invitation.
+pub async fn members<C: ConnectionTrait>(
+    conn: &C,
+    project: &ProjectModel,
+) -> Result<Vec<UserModel>, DbErr> {
+    let mut members = User::find_by_id(project.user_id).all(conn).await?;
+    let added = User::find()
+        .filter(UserColumn::Id.in_subquery(member_user_ids(project.id)))
+        .filter(UserColumn::Id.ne(project.user_id))
+        .order_by_asc(UserColumn::Id)
+        .all(conn)
+        .await?;
+    members.extend(added);
+    Ok(members)
+}
+
+/// Ids of the users who accepted the invitation to the project `project_id`.
+fn member_user_ids(project_id: i32) -> SelectStatement {
+    Query::select()
+        .column(MemberColumn::UserId)
+        .from(ProjectMember)
+        .and_where(MemberColumn::ProjectId.eq(project_id))
+        .and_where(MemberColumn::AcceptedAt.is_not_null())
+        .to_owned()
+}
+
+/// Ids of the projects whose invitation the user `user_id` accepted.
+fn member_project_ids(user_id: i32) -> SelectStatement {
+    Query::select()
+        .column(MemberColumn::ProjectId)
+        .from(ProjectMember)
+        .and_where(MemberColumn::UserId.eq(user_id))
+        .and_where(MemberColumn::AcceptedAt.is_not_null())
+        .to_owned()
+}
+
+/// Queues the notification about a submission for each project member who
+/// wants an email for each, in the same transaction as the submission if
+/// `conn` is one.
+pub async fn queue_submission<C: ConnectionTrait>(
+    conn: &C,
+    testimonial: &TestimonialModel,
+) -> Result<(), DbErr> {
+    let Some(project) = Project::find_by_id(testimonial.project_id)
+        .one(conn)
+        .await?
+    else {
+        return Ok(());
+    };
+    for member in members(conn, &project).await? {
+        if Frequency::parse(&member.submission_notifications) != Some(Frequency::Immediate) {
+            continue;
+        }
+        jobs::enqueue(
+            conn,
+            &NotifySubmission {
+                testimonial_id: testimonial.id,
+                user_id: member.id,
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Job that sends the digests that are due.
+#[derive(Serialize, Deserialize)]
+pub struct SendDigests;
+
+impl Job for SendDigests {
+    const KIND: &'static str = "notifications.digests";
+}
+
+/// Renders and sends notification emails for the job worker.
+#[derive(Clone)]
+pub struct Notifier {
+    mailer: Mailer,
+    app_url: String,
+}
+
+impl Notifier {
+    /// `app_url` is where the app is reached, which links in emails point
+    /// into.
+    pub fn new(mailer: Mailer, app_url: &str) -> Self {
+        Notifier {
+            mailer,
+            app_url: app_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn dashboard_url(&self) -> String {
+        format!("{}/dashboard", self.app_url)
+    }
+
+    /// `url` made absolute, as links in emails have to be.
+    fn absolute(&self, url: &str) -> String {
+        if url.starts_with('/') {
+            format!("{}{url}", self.app_url)
+        } else {
+            url.to_string()
+        }
+    }
+
+    fn branding(&self, project: &ProjectModel) -> Branding {
+        Branding {
+            logo_url: project.logo_url.as_deref().map(|url| self.absolute(url)),
+            website_url: project.website_url.clone(),
+            ..Branding::new(&project.name)
+        }
+    }
+
+    pub async fn notify_submission(
+        &self,
+        conn: &DatabaseConnection,
+        job: NotifySubmission,
+    ) -> Result<(), JobError> {
+        let Some(testimonial) = Testimonial::find_by_id(job.testimonial_id)
+            .filter(TestimonialColumn::DeletedAt.is_null())
+            .one(conn)
+            .await?
+        else {
+            return Ok(());
+        };
+        let Some(project) = Project::find_by_id(testimonial.project_id)
+            .filter(ProjectColumn::DeletedAt.is_null())
+            .one(conn)
+            .await?
+        else {
+            return Ok(());
+        };
+        // Whoever left the project since, or changed their mind, is skipped.
+        let Some(member) = members(conn, &project)
+            .await?
+            .into_iter()
+            .find(|member| member.id == job.user_id)
+        else {
+            return Ok(());
+        };
+        if Frequency::parse(&member.submission_notifications) != Some(Frequency::Immediate) {
+            return Ok(());
+        }
+
+        let message = self.submission_message(&project, &testimonial);
+        self.mailer
+            .deliver(&member.email, message)
+            .await
+            .map_err(JobError::new)
+    }
+
+    fn submission_message(&self, project: &ProjectModel, t: &TestimonialModel) -> Message {
+        let branding = self.branding(project);
+        let summary = match t.rating {
+            Some(rating) => format!(
+                "{} left a {rating}-star testimonial through your collection form.",
+                t.author_name
+            ),
+            None => format!(
+                "{} left a testimonial through your collection form.",
+                t.author_name
+            ),
+        };
+
+        MessageBuilder::new(&branding)
+            .heading(&format!("New testimonial from {}", t.author_name))
+            .paragraph(&summary)
+            .quote(excerpt(t), &attribution(t))
+            .button("Review it", &self.dashboard_url())
+            .build(&format!("New testimonial for {}", project.name))
+    }
+
+    /// Thanks the author of an approved form submission, with the form's
+    /// incentive. Nothing is sent twice for the same submission.
+    pub async fn send_thank_you(
+        &self,
+        conn: &DatabaseConnection,
+        job: SendThankYou,
+    ) -> Result<(), JobError> {
+        let Some(testimonial) = Testimonial::find_by_id(job.testimonial_id)
+            .filter(TestimonialColumn::DeletedAt.is_null())
+            .one(conn)
+            .await?
+        else {
+            return Ok(());
+        };
+        let Some(email) = testimonial.author_email.clone().filter(|e| !e.is_empty()) else {
+            return Ok(());
+        };
+        if !testimonial.is_approved {
+            return Ok(());
+        }
+        let Some(form) = incentives::form_of(conn, &testimonial).await? else {
+            return Ok(());
+        };
+        if !incentives::thanks_submitters(&form) {
+            return Ok(());
+        }
+        let Some(project) = Project::find_by_id(testimonial.project_id)
+            .filter(ProjectColumn::DeletedAt.is_null())
+            .one(conn)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let txn = conn.begin().await?;
+        let issued = incentives::issue(&txn, &form, &testimonial, &email).await?;
+        txn.commit().await?;
+        if issued.thank_you.sent_at.is_some() {
+            return Ok(());
+        }
+
+        let message = self.thank_you_message(
+            &project,
+            &form,
+            issued.thank_you.incentive.as_deref(),
+            issued.code.as_deref(),
+        );
+        self.mailer
+            .deliver(&issued.thank_you.email, message)
+            .await
+            .map_err(JobError::new)?;
+        incentives::mark_sent(conn, issued.thank_you).await?;
+        Ok(())
+    }
+
+    fn thank_you_message(
+        &self,
+        project: &ProjectModel,
+        form: &FormModel,
+        incentive: Option<&str>,
+        code: Option<&str>,
+    ) -> Message {
+        let project_branding = self.branding(project);
+        let branding = Branding {
+            logo_url: form
+                .logo_url
+                .as_deref()
+                .map(|url| self.absolute(url))
+                .or(project_branding.logo_url.clone()),
+            accent_color: form.accent_color.clone(),
+            ..project_branding
+        };
+        let title = form
+            .thank_you_title
+            .as_deref()
+            .filter(|t| !t.is_empty())
+            .unwrap_or("Thank you!");
+        let thanks = form
+            .thank_you_message
+            .clone()
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| {
+                format!(
+                    "Thanks for sharing your experience with {}. Your testimonial is now published.",
+                    project.name
+                )
+            });
+
+        let mut builder = MessageBuilder::new(&branding)
+            .heading(title)
+            .paragraph(&thanks);
+        if let Some(incentive) = incentive {
+            builder = builder.paragraph(incentive);
+        }
+        if let Some(code) = code {
+            builder = builder.code(code);
+        }
+        if form.share_enabled {
+            let links = incentives::share_links(form, project);
+            let links: Vec<(&str, &str)> = links
+                .iter()
+                .map(|(label, url)| (*label, url.as_str()))
+                .collect();
+            builder = builder
+                .paragraph("Know someone who would like it too? Spread the word:")
+                .links(&links);
+        }
+        if let (Some(label), Some(url)) = (&form.thank_you_cta_text, &form.thank_you_cta_url) {
+            builder = builder.button(label, &self.absolute(url));
+        }
+
+        builder.build(&format!("Thank you from {}", project.name))
+    }
+
+    /// Sends every digest that is due, and returns how many were sent. A
+    /// user with no new submissions gets none, but their next digest still
+    /// starts from now.
+    pub async fn send_digests(&self, conn: &DatabaseConnection) -> Result<usize, JobError> {
+        let now = Utc::now().fixed_offset();
+        let users = User::find()
+            .filter(UserColumn::SubmissionNotifications.eq(Frequency::Digest.as_str()))
+            .filter(
+                Condition::any()
+                    .add(UserColumn::DigestSentAt.is_null())
+                    .add(UserColumn::DigestSentAt.lte(now - DIGEST_PERIOD)),
+            )
+            .all(conn)
+            .await?;
+
+        let mut sent = 0;
+        let mut failed = 0;
+        for user in users {
+            let since = user.digest_sent_at.unwrap_or(now - DIGEST_PERIOD);
+            let projects = Project::find()
+                .filter(
+                    Condition::any()
+                        .add(ProjectColumn::UserId.eq(user.id))
+                        .add(ProjectColumn::Id.in_subquery(member_project_ids(user.id))),
+                )
+                .filter(ProjectColumn::DeletedAt.is_null())
+                .order_by_asc(ProjectColumn::Name)
+                .all(conn)
+                .await?;
+
+            let mut sections = Vec::new();
+            for project in projects {
+                let submissions = Testimonial::find()
+                    .filter(TestimonialColumn::ProjectId.eq(project.id))
+                    .filter(TestimonialColumn::FormId.is_not_null())
+                    .filter(TestimonialColumn::DeletedAt.is_null())
+                    .filter(TestimonialColumn::CreatedAt.gt(since))
+                    .filter(TestimonialColumn::CreatedAt.lte(now))
+                    .order_by_desc(TestimonialColumn::CreatedAt)
+                    .all(conn)
+                    .await?;
+                if !submissions.is_empty() {
+                    sections.push((project, submissions));
+                }
+            }
+
+            if !sections.is_empty() {
+                let message = self.digest_message(&sections);
+                if let Err(e) = self.mailer.deliver(&user.email, message).await {
+                    // Left due, so the next run tries again.
+                    tracing::error!(user = %user.pid, error = %e, "failed to send digest");
+                    failed += 1;
+                    continue;
+                }
+                sent += 1;
+            }
+
+            User::update_many()
+                .col_expr(UserColumn::DigestSentAt, Expr::value(now))
+                .filter(UserColumn::Id.eq(user.id))
+                .exec(conn)
+                .await?;
+        }
+
+        if failed > 0 {
+            return Err(JobError::new(format!("{failed} digests could not be sent")));
+        }
+        if sent > 0 {
+            tracing::info!(count = sent, "sent submission digests");
+        }
+        Ok(sent)
+    }
+
+    fn digest_message(&self, sections: &[(ProjectModel, Vec<TestimonialModel>)]) -> Message {
+        let total: usize = sections.iter().map(|(_, s)| s.len()).sum();
+        // A digest about a single project carries its branding.
+        let branding = match sections {
+            [(project, _)] => self.branding(project),
+            _ => Branding::new("Reeverb"),
+        };
+        let noun = if total == 1 {
+            "testimonial"
+        } else {
+            "testimonials"
+        };
+
+        let mut builder = MessageBuilder::new(&branding)
+            .heading("Your daily testimonial digest")
+            .paragraph(&format!(
+                "You received {total} new {noun} through your collection forms."
+            ));
+        for (project, submissions) in sections {
+            builder = builder.paragraph(&format!("{} ({})", project.name, submissions.len()));
+            for t in submissions.iter().take(DIGEST_LIMIT) {
+                builder = builder.quote(excerpt(t), &attribution(t));
+            }
+            if submissions.len() > DIGEST_LIMIT {
+                builder =
+                    builder.paragraph(&format!("…and {} more.", submissions.len() - DIGEST_LIMIT));
+            }
+        }
+
+        builder
+            .button("Review them", &self.dashboard_url())
+            .build(&format!("{total} new {noun}"))
+    }
+}
+
+fn excerpt(t: &TestimonialModel) -> &str {
+    t.content
+        .as_deref()
+        .or(t.transcription.as_deref())
+        .unwrap_or("(video testimonial)")
+}
+
+fn attribution(t: &TestimonialModel) -> String {
+    let stars = t
+        .rating
+        .map(|rating| format!(", {}", "★".repeat(rating.clamp(0, 5) as usize)))
+        .unwrap_or_default();
+    match (&t.author_title, &t.author_company) {
+        (Some(title), Some(company)) => format!("{}, {title} at {company}{stars}", t.author_name),
+        (Some(role), None) | (None, Some(role)) => format!("{}, {role}{stars}", t.author_name),
+        (None, None) => format!("{}{stars}", t.author_name),
+    }
+}
@@ -0,0 +1,290 @@
+//! Encryption of secrets stored in the database.
+//!
+//! Secrets are sealed with envelope encryption: each value gets its own
+//! random data key, the value is encrypted with that data key, and the data
+//! key is encrypted ("wrapped") with a key encryption key from the
+//! [`Keyring`]. Both use AES-256-GCM. Rotating keys only rewraps the data
+//! keys, so old values never have to be decrypted with a new key at rest.
+//!
+//! Sealed values are JSON objects tagged with `"enc"`, so they fit the JSONB
+//! columns the plaintext used to live in. Values without the tag are plaintext
+//! written before encryption, and are read as is until they are resealed.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// `enc` tag of sealed values.
+const ALGORITHM: &str = "aes-256-gcm";
+
+/// Size of an AES-256 key in bytes.
+const KEY_LEN: usize = 32;
+
+/// Size of an AES-256-GCM nonce in bytes.
+const NONCE_LEN: usize = 12;
+
+/// Characters of a secret a hint shows.
+const HINT_CHARS: usize = 4;
+
+/// Secrets shorter than this get no characters shown in their hint.
+const MIN_HINTED_LEN: usize = 12;
+
+const MASK: &str = "••••";
+
+#[derive(Debug)]
+pub enum SecretError {
+    /// The keyring configuration is malformed.
+    InvalidKeyring(String),
+    /// The keyring has no keys, so nothing can be sealed or opened.
+    NoKeys,
+    /// The value was sealed with a key the keyring does not have.
+    UnknownKey(String),
+    /// The value is damaged, or sealed with a different key of the same id.
+    Decrypt,
+}
+
+impl std::fmt::Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretError::InvalidKeyring(msg) => write!(f, "invalid keyring: {msg}"),
+            SecretError::NoKeys => {
+                f.write_str("no encryption keys are configured; set CREDENTIALS_KEYS")
+            }
+            SecretError::UnknownKey(id) => write!(f, "no key with id {id} in the keyring"),
+            SecretError::Decrypt => f.write_str("failed to decrypt secret"),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    enc: String,
+    /// Id of the key the data key is wrapped with.
+    kid: String,
+    /// Nonce and ciphertext of the wrapped data key.
+    dek: String,
+    /// Nonce and ciphertext of the value.
+    data: String,
+}
+
+fn key_from(bytes: &[u8]) -> Option<Key<Aes256Gcm>> {
+    <[u8; KEY_LEN]>::try_from(bytes)
+        .ok()
+        .map(Key::<Aes256Gcm>::from)
+}
+
+/// Encrypts `plaintext` under `key`, returning the nonce followed by the
+/// ciphertext, base64 encoded.
+fn encrypt(key: &Key<Aes256Gcm>, plaintext: &[u8], aad: &[u8]) -> String {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key)
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .expect("AES-GCM encryption does not fail for in-memory buffers");
+
+    let mut out = nonce.to_vec();
+    out.extend(ciphertext);
+    STANDARD.encode(out)
+}
+
+fn decrypt(key: &Key<Aes256Gcm>, sealed: &str, aad: &[u8]) -> Result<Vec<u8>, SecretError> {
+    let bytes = STANDARD.decode(sealed).map_err(|_| SecretError::Decrypt)?;
+    if bytes.len() < NONCE_LEN {
+        return Err(SecretError::Decrypt);
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| SecretError::Decrypt)?;
+
+    Aes256Gcm::new(key)
+        .decrypt(
+            &Nonce::from(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| SecretError::Decrypt)
+}
+
+/// Key encryption keys by id, one of which seals new values.
+#[derive(Clone)]
+pub struct Keyring {
+    active: Option<String>,
+    keys: Arc<HashMap<String, Key<Aes256Gcm>>>,
+}
+
+impl std::fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keyring")
+            .field("active", &self.active)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Keyring {
+    /// Parses comma-separated `id:key` pairs, where each key is 32 bytes of
+    /// base64. The first key is active; the rest are kept to open values
+    /// sealed before a rotation.
+    ///
+    /// An empty `spec` gives a keyring without keys. It still reads plaintext
+    /// values, but sealing or opening a sealed value fails with
+    /// [`SecretError::NoKeys`].
+    pub fn parse(spec: &str) -> Result<Self, SecretError> {
+        let mut active = None;
+        let mut keys = HashMap::new();
+
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, key) = entry.split_once(':').ok_or_else(|| {
+                SecretError::InvalidKeyring("each key must be given as id:key".to_string())
+            })?;
+            let id = id.trim();
+            if id.is_empty() {
+                return Err(SecretError::InvalidKeyring("empty key id".to_string()));
+            }
+
+            let bytes = STANDARD
+                .decode(key.trim())
+                .map_err(|_| SecretError::InvalidKeyring(format!("key {id} is not base64")))?;
+            let key = key_from(&bytes).ok_or_else(|| {
+                SecretError::InvalidKeyring(format!("key {id} must be {KEY_LEN} bytes"))
+            })?;
+            if keys.insert(id.to_string(), key).is_some() {
+                return Err(SecretError::InvalidKeyring(format!(
+                    "duplicate key id {id}"
+                )));
+            }
+            active.get_or_insert_with(|| id.to_string());
+        }
+
+        Ok(Keyring {
+            active,
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// A new random key, base64 encoded for use in [`Keyring::parse`].
+    pub fn generate_key() -> String {
+        STANDARD.encode(Aes256Gcm::generate_key(OsRng))
+    }
+
+    /// Id of the key new values are sealed with, if there are keys.
+    pub fn active_key_id(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    fn key(&self, id: &str) -> Result<&Key<Aes256Gcm>, SecretError> {
+        if self.keys.is_empty() {
+            return Err(SecretError::NoKeys);
+        }
+        self.keys
+            .get(id)
+            .ok_or_else(|| SecretError::UnknownKey(id.to_string()))
+    }
+
+    /// `dek` wrapped with the active key, and the active key's id.
+    fn wrap_dek(&self, dek: &[u8]) -> Result<(String, String), SecretError> {
+        let active = self.active.as_deref().ok_or(SecretError::NoKeys)?;
+        let wrapped = encrypt(self.key(active)?, dek, active.as_bytes());
+        Ok((active.to_string(), wrapped))
+    }
+
+    fn unwrap_dek(&self, sealed: &Sealed) -> Result<Key<Aes256Gcm>, SecretError> {
+        let key = self.key(&sealed.kid)?;
+        let dek = decrypt(key, &sealed.dek, sealed.kid.as_bytes())?;
+        key_from(&dek).ok_or(SecretError::Decrypt)
+    }
+
+    /// Seals `value` under a fresh data key wrapped with the active key.
+    pub fn seal(&self, value: &Value) -> Result<Value, SecretError> {
+        let dek = Aes256Gcm::generate_key(OsRng);
+        let (kid, wrapped) = self.wrap_dek(&dek)?;
+        let plaintext = serde_json::to_vec(value).expect("JSON values always serialize");
+
+        let sealed = Sealed {
+            enc: ALGORITHM.to_string(),
+            kid,
+            dek: wrapped,
+            data: encrypt(&dek, &plaintext, ALGORITHM.as_bytes()),
+        };
+        Ok(serde_json::to_value(sealed).expect("sealed values always serialize"))
+    }
+
+    /// The value `stored` was sealed from. Plaintext values are returned as
+    /// they are.
+    pub fn open(&self, stored: &Value) -> Result<Value, SecretError> {
+        let Some(sealed) = parse_sealed(stored) else {
+            return Ok(stored.clone());
+        };
+
+        let dek = self.unwrap_dek(&sealed)?;
+        let plaintext = decrypt(&dek, &sealed.data, ALGORITHM.as_bytes())?;
+        serde_json::from_slice(&plaintext).map_err(|_| SecretError::Decrypt)
+    }
+
+    /// `stored` sealed under the active key, or `None` if it already is.
+    /// Sealed values only have their data key rewrapped; plaintext values are
+    /// sealed.
+    pub fn reseal(&self, stored: &Value) -> Result<Option<Value>, SecretError> {
+        let Some(mut sealed) = parse_sealed(stored) else {
+            return self.seal(stored).map(Some);
+        };
+        if self.active.as_ref() == Some(&sealed.kid) {
+            return Ok(None);
+        }
+
+        let dek = self.unwrap_dek(&sealed)?;
+        (sealed.kid, sealed.dek) = self.wrap_dek(&dek)?;
+        Ok(Some(
+            serde_json::to_value(sealed).expect("sealed values always serialize"),
+        ))
+    }
+}
+
+fn parse_sealed(stored: &Value) -> Option<Sealed> {
+    if stored.get("enc").and_then(Value::as_str) != Some(ALGORITHM) {
+        return None;
+    }
+    serde_json::from_value(stored.clone()).ok()
+}
+
+/// Whether `stored` is a sealed value rather than plaintext.
+pub fn is_sealed(stored: &Value) -> bool {
+    parse_sealed(stored).is_some()
+}
+
+fn mask(value: &Value) -> String {
+    match value {
+        Value::String(s) if s.chars().count() >= MIN_HINTED_LEN => {
+            let tail: String = s.chars().skip(s.chars().count() - HINT_CHARS).collect();
+            format!("{MASK}{tail}")
+        }
+        _ => MASK.to_string(),
+    }
+}
+
+/// Masked hints of each field of `credentials`: the last few characters of
+/// long strings, and nothing of anything else. Safe to show in responses.
+pub fn hints(credentials: &Value) -> Value {
+    match credentials {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), Value::String(mask(value))))
+                .collect::<Map<_, _>>(),
+        ),
+        _ => Value::Object(Map::new()),
+    }
+}
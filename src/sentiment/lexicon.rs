@@ -0,0 +1,264 @@
+//! Offline sentiment analysis with a word list.
+//!
+//! Each known word carries a valence from -4 to 4. A word's valence is
+//! boosted by an intensifier right before it ("really good"), flipped and
+//! weakened by a negation shortly before it ("not good"), and weighted down
+//! before a "but" and up after one, since the clause after a "but" usually
+//! carries the verdict. Exclamation marks push the total further from zero,
+//! and the total is squashed into -1..1.
+//!
+//! The word list is English, so text in other languages is not judged.
+
+use async_trait::async_trait;
+
+use super::{Analyzer, AnalyzerError, Sentiment};
+
+/// Valence of known words.
+const WORDS: &[(&str, f32)] = &[
+    ("amazing", 2.8),
+    ("awesome", 3.1),
+    ("beautiful", 2.9),
+    ("best", 3.2),
+    ("better", 1.9),
+    ("brilliant", 2.8),
+    ("cool", 1.3),
+    ("delighted", 2.6),
+    ("easy", 1.9),
+    ("easier", 1.8),
+    ("effective", 2.1),
+    ("efficient", 1.6),
+    ("enjoy", 2.2),
+    ("enjoyed", 2.3),
+    ("excellent", 3.2),
+    ("exceptional", 2.5),
+    ("fabulous", 2.4),
+    ("fantastic", 2.6),
+    ("favorite", 2.0),
+    ("favourite", 2.0),
+    ("friendly", 2.2),
+    ("fun", 2.3),
+    ("glad", 2.0),
+    ("good", 1.9),
+    ("grateful", 2.0),
+    ("great", 3.1),
+    ("happy", 2.7),
+    ("helpful", 1.9),
+    ("impressed", 2.1),
+    ("impressive", 2.3),
+    ("incredible", 2.2),
+    ("intuitive", 1.5),
+    ("lifesaver", 2.0),
+    ("liked", 1.8),
+    ("love", 3.2),
+    ("loved", 2.9),
+    ("loves", 2.7),
+    ("lovely", 2.8),
+    ("nice", 1.8),
+    ("outstanding", 3.0),
+    ("perfect", 2.7),
+    ("pleased", 1.9),
+    ("pleasure", 2.2),
+    ("positive", 2.6),
+    ("professional", 1.0),
+    ("recommend", 1.5),
+    ("recommended", 1.6),
+    ("reliable", 1.5),
+    ("responsive", 1.0),
+    ("satisfied", 1.8),
+    ("seamless", 1.5),
+    ("smooth", 1.0),
+    ("solid", 0.9),
+    ("stellar", 2.8),
+    ("success", 2.7),
+    ("successful", 2.8),
+    ("superb", 3.1),
+    ("thank", 1.5),
+    ("thanks", 1.9),
+    ("useful", 1.9),
+    ("valuable", 2.1),
+    ("win", 2.8),
+    ("wonderful", 2.7),
+    ("worth", 0.9),
+    ("angry", -2.3),
+    ("annoying", -1.8),
+    ("avoid", -1.2),
+    ("awful", -2.0),
+    ("bad", -2.5),
+    ("broken", -1.8),
+    ("bug", -1.0),
+    ("bugs", -1.0),
+    ("buggy", -1.5),
+    ("clunky", -1.3),
+    ("complicated", -0.9),
+    ("confusing", -1.3),
+    ("crash", -1.7),
+    ("crashed", -1.7),
+    ("crashes", -1.7),
+    ("difficult", -1.5),
+    ("disappointed", -1.9),
+    ("disappointing", -2.2),
+    ("disappointment", -2.3),
+    ("expensive", -0.9),
+    ("fail", -2.5),
+    ("failed", -2.3),
+    ("fails", -2.3),
+    ("failure", -2.3),
+    ("frustrated", -2.0),
+    ("frustrating", -1.9),
+    ("garbage", -2.1),
+    ("glitchy", -1.5),
+    ("hate", -2.7),
+    ("hated", -3.2),
+    ("horrible", -2.5),
+    ("issue", -0.8),
+    ("issues", -0.8),
+    ("lacking", -1.0),
+    ("laggy", -1.5),
+    ("mediocre", -1.0),
+    ("missing", -1.2),
+    ("nightmare", -2.8),
+    ("overpriced", -1.5),
+    ("pain", -2.3),
+    ("painful", -2.4),
+    ("poor", -2.1),
+    ("problem", -1.7),
+    ("problems", -1.7),
+    ("rude", -2.0),
+    ("sad", -2.1),
+    ("scam", -2.7),
+    ("slow", -0.8),
+    ("sucks", -1.5),
+    ("terrible", -2.1),
+    ("ugly", -2.3),
+    ("unhappy", -1.8),
+    ("unprofessional", -2.0),
+    ("unreliable", -1.6),
+    ("unresponsive", -1.5),
+    ("unusable", -2.0),
+    ("useless", -1.8),
+    ("waste", -1.8),
+    ("wasted", -1.9),
+    ("worse", -2.1),
+    ("worst", -3.1),
+];
+
+/// Words that strengthen the word after them.
+const INTENSIFIERS: &[&str] = &[
+    "absolutely",
+    "completely",
+    "extremely",
+    "highly",
+    "incredibly",
+    "really",
+    "so",
+    "super",
+    "totally",
+    "truly",
+    "very",
+];
+
+/// Words that weaken the word after them.
+const DAMPENERS: &[&str] = &["barely", "kinda", "slightly", "somewhat"];
+
+/// Words that negate the words shortly after them, besides any ending in n't.
+const NEGATIONS: &[&str] = &[
+    "cannot", "neither", "never", "no", "nobody", "none", "nor", "not", "nothing", "without",
+];
+
+/// How far back a negation reaches.
+const NEGATION_WINDOW: usize = 3;
+
+/// Factor applied to negated words.
+const NEGATION_SCALAR: f32 = -0.74;
+
+/// What an intensifier or dampener adds to or takes from a word's valence.
+const BOOST: f32 = 0.293;
+
+/// Weight of words before and after a "but".
+const BEFORE_BUT: f32 = 0.5;
+const AFTER_BUT: f32 = 1.5;
+
+/// What each exclamation mark adds, and how many count.
+const EXCLAMATION_BOOST: f32 = 0.292;
+const MAX_EXCLAMATIONS: usize = 4;
+
+/// Normalization constant; larger values need more evidence to approach ±1.
+const ALPHA: f32 = 15.0;
+
+/// The built-in offline analyzer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lexicon;
+
+fn supports(language: Option<&str>) -> bool {
+    language.is_none_or(|l| {
+        let l = l.to_ascii_lowercase();
+        l == "en" || l.starts_with("en-") || l.starts_with("en_")
+    })
+}
+
+fn valence(word: &str) -> Option<f32> {
+    WORDS.iter().find(|(w, _)| *w == word).map(|(_, v)| *v)
+}
+
+fn is_negation(word: &str) -> bool {
+    NEGATIONS.contains(&word) || word.ends_with("n't")
+}
+
+/// The score of `text` from -1 to 1.
+pub fn score(text: &str) -> f32 {
+    let words: Vec<String> = text
+        .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’'))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase().replace('’', "'"))
+        .collect();
+    let but = words.iter().rposition(|w| w == "but");
+
+    let mut total = 0.0;
+    for (i, word) in words.iter().enumerate() {
+        let Some(mut v) = valence(word) else {
+            continue;
+        };
+
+        if let Some(prev) = i.checked_sub(1).map(|p| words[p].as_str()) {
+            if INTENSIFIERS.contains(&prev) {
+                v += BOOST * v.signum();
+            } else if DAMPENERS.contains(&prev) {
+                v -= BOOST * v.signum();
+            }
+        }
+        if words[i.saturating_sub(NEGATION_WINDOW)..i]
+            .iter()
+            .any(|w| is_negation(w))
+        {
+            v *= NEGATION_SCALAR;
+        }
+        match but {
+            Some(b) if i < b => v *= BEFORE_BUT,
+            Some(b) if i > b => v *= AFTER_BUT,
+            _ => {}
+        }
+
+        total += v;
+    }
+
+    if total != 0.0 {
+        let exclamations = text.matches('!').count().min(MAX_EXCLAMATIONS);
+        total += EXCLAMATION_BOOST * exclamations as f32 * total.signum();
+    }
+
+    total / (total * total + ALPHA).sqrt()
+}
+
+#[async_trait]
+impl Analyzer for Lexicon {
+    async fn analyze(
+        &self,
+        text: &str,
+        language: Option<&str>,
+    ) -> Result<Option<Sentiment>, AnalyzerError> {
+        if !supports(language) {
+            return Ok(None);
+        }
+        Ok(Some(Sentiment::from_score(score(text))))
+    }
+}
@@ -0,0 +1,241 @@
+//! Sentiment analysis of testimonials.
+//!
+//! Testimonials get a `sentiment` of `positive`, `neutral` or `negative` and a
+//! `sentiment_score` from -1 to 1 when they are created, imported or have
+//! their text edited, unless the client sets them itself. The save that calls
+//! for it queues an [`AnalyzeSentiment`] job, which fills them in shortly
+//! after. A project's testimonials can also be analyzed again all at once.
+//!
+//! Analysis goes through the [`Analyzer`] trait. The built-in [`Lexicon`]
+//! runs offline; an external model can be plugged in by implementing the
+//! trait and handing it to [`SentimentAnalyzer::new`].
+
+mod lexicon;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rapina::sea_orm::sea_query::{Expr, SimpleExpr};
+use rapina::sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::db::entities::testimonial::{ActiveModel, Column, Entity as Testimonial, Model};
+use crate::jobs::{Job, JobError};
+
+pub use lexicon::Lexicon;
+
+/// Scores at or beyond this distance from zero are not neutral.
+const NEUTRAL_BAND: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Label {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+impl Label {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Label::Positive => "positive",
+            Label::Neutral => "neutral",
+            Label::Negative => "negative",
+        }
+    }
+
+    /// The label of a score from -1 to 1.
+    pub fn from_score(score: f32) -> Self {
+        if score >= NEUTRAL_BAND {
+            Label::Positive
+        } else if score <= -NEUTRAL_BAND {
+            Label::Negative
+        } else {
+            Label::Neutral
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sentiment {
+    pub label: Label,
+    /// From -1 (most negative) to 1 (most positive).
+    pub score: f32,
+}
+
+impl Sentiment {
+    /// A sentiment labelled by its score, which is clamped to -1..=1.
+    pub fn from_score(score: f32) -> Self {
+        let score = score.clamp(-1.0, 1.0);
+        Sentiment {
+            label: Label::from_score(score),
+            score,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AnalyzerError(pub String);
+
+impl std::fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sentiment analysis failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for AnalyzerError {}
+
+#[async_trait]
+pub trait Analyzer: Send + Sync {
+    /// The sentiment of `text`, written in `language` if that is known. `None`
+    /// when the analyzer cannot judge the text, for example because it does
+    /// not support the language.
+    async fn analyze(
+        &self,
+        text: &str,
+        language: Option<&str>,
+    ) -> Result<Option<Sentiment>, AnalyzerError>;
+}
+
+/// The analyzer testimonials are scored with, shared as app state.
+#[derive(Clone)]
+pub struct SentimentAnalyzer {
+    inner: Arc<dyn Analyzer>,
+}
+
+impl Default for SentimentAnalyzer {
+    fn default() -> Self {
+        SentimentAnalyzer::new(Lexicon)
+    }
+}
+
+impl SentimentAnalyzer {
+    pub fn new(analyzer: impl Analyzer + 'static) -> Self {
+        SentimentAnalyzer {
+            inner: Arc::new(analyzer),
+        }
+    }
+
+    /// The sentiment of a testimonial's content, or of its transcription if it
+    /// has no content. `None` when it has no text or the text cannot be judged.
+    pub async fn of(
+        &self,
+        content: Option<&str>,
+        transcription: Option<&str>,
+        language: Option<&str>,
+    ) -> Result<Option<Sentiment>, AnalyzerError> {
+        let text = [content, transcription]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .find(|text| !text.is_empty());
+        match text {
+            Some(text) => self.inner.analyze(text, language).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Analyzes the testimonial of `job` and saves its sentiment, unless it
+    /// was given one or its text changed in the meantime.
+    pub async fn analyze(
+        &self,
+        conn: &DatabaseConnection,
+        job: AnalyzeSentiment,
+    ) -> Result<(), JobError> {
+        let testimonial = Testimonial::find_by_id(job.testimonial_id)
+            .filter(Column::DeletedAt.is_null())
+            .one(conn)
+            .await?;
+        let Some(t) = testimonial else {
+            return Ok(());
+        };
+        if t.sentiment.is_some() || t.sentiment_score.is_some() {
+            return Ok(());
+        }
+
+        let sentiment = self
+            .of(
+                t.content.as_deref(),
+                t.transcription.as_deref(),
+                t.language.as_deref(),
+            )
+            .await
+            .map_err(JobError::new)?;
+        let Some(sentiment) = sentiment else {
+            return Ok(());
+        };
+
+        // A change of the text queues its own analysis.
+        Testimonial::update_many()
+            .col_expr(Column::Sentiment, Expr::value(sentiment.label.as_str()))
+            .col_expr(Column::SentimentScore, Expr::value(sentiment.score))
+            .col_expr(
+                Column::UpdatedAt,
+                Expr::value(chrono::Utc::now().fixed_offset()),
+            )
+            .filter(Column::Id.eq(t.id))
+            .filter(Column::Sentiment.is_null())
+            .filter(Column::SentimentScore.is_null())
+            .filter(unchanged(Column::Content, t.content))
+            .filter(unchanged(Column::Transcription, t.transcription))
+            .filter(unchanged(Column::Language, t.language))
+            .exec(conn)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Matches rows whose `column` still holds `value`.
+fn unchanged(column: Column, value: Option<String>) -> SimpleExpr {
+    match value {
+        Some(value) => column.eq(value),
+        None => column.is_null(),
+    }
+}
+
+/// Job that analyzes the sentiment of a testimonial saved without one.
+#[derive(Serialize, Deserialize)]
+pub struct AnalyzeSentiment {
+    pub testimonial_id: i32,
+}
+
+impl Job for AnalyzeSentiment {
+    const KIND: &'static str = "sentiment.analyze";
+}
+
+/// Whether the testimonial `active` will save needs its sentiment analyzed.
+/// `before` is the testimonial being edited, if any.
+///
+/// A new testimonial needs it unless it was given a sentiment. An edit needs
+/// it if it changes the text without setting the sentiment itself; the old
+/// sentiment is cleared until the new one is in.
+pub fn queue(before: Option<&Model>, active: &mut ActiveModel) -> bool {
+    let Some(before) = before else {
+        let given = matches!(active.sentiment.try_as_ref(), Some(Some(_)))
+            || matches!(active.sentiment_score.try_as_ref(), Some(Some(_)));
+        return !given && has_text(active);
+    };
+
+    let text_changed = active.content.try_as_ref() != Some(&before.content)
+        || active.transcription.try_as_ref() != Some(&before.transcription)
+        || active.language.try_as_ref() != Some(&before.language);
+    let sentiment_changed = active.sentiment.try_as_ref() != Some(&before.sentiment)
+        || active.sentiment_score.try_as_ref() != Some(&before.sentiment_score);
+    if !text_changed || sentiment_changed {
+        return false;
+    }
+
+    active.sentiment = Set(None);
+    active.sentiment_score = Set(None);
+    has_text(active)
+}
+
+/// Whether the testimonial `active` will save has any text to judge.
+fn has_text(active: &ActiveModel) -> bool {
+    [&active.content, &active.transcription]
+        .into_iter()
+        .any(|text| {
+            text.try_as_ref()
+                .and_then(Option::as_deref)
+                .is_some_and(|text| !text.trim().is_empty())
+        })
+}
@@ -0,0 +1,176 @@
+//! Storage in a directory on local disk.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use uuid::Uuid;
+
+use super::{Object, PresignedRequest, Storage, StorageError, UploadedPart, check_key};
+
+/// Directory under the root that unfinished uploads keep their parts in.
+/// Keys cannot start with a dot, so it is never served.
+const UPLOADS_DIR: &str = ".uploads";
+
+/// Stores files in `root`, each under its key.
+#[derive(Clone, Debug)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalStorage { root: root.into() }
+    }
+
+    fn parts_dir(&self, upload_id: &str) -> Result<PathBuf, StorageError> {
+        if upload_id.is_empty() || !upload_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(StorageError(format!("invalid upload id {upload_id:?}")));
+        }
+        Ok(self.root.join(UPLOADS_DIR).join(upload_id))
+    }
+
+    /// Moves the finished file at `tmp` to `key`, so readers never see a
+    /// partial file.
+    async fn publish(&self, tmp: &Path, key: &str) -> io::Result<()> {
+        tokio::fs::rename(tmp, self.root.join(key)).await
+    }
+}
+
+fn content_type_of(key: &str) -> String {
+    mime_guess::from_path(key)
+        .first_or_octet_stream()
+        .to_string()
+}
+
+fn not_found_as_none<T>(result: io::Result<T>) -> Result<Option<T>, StorageError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, bytes: Vec<u8>, _: &str) -> Result<(), StorageError> {
+        check_key(key)?;
+        tokio::fs::create_dir_all(&self.root).await?;
+        let tmp = self.root.join(format!(".{key}.tmp"));
+        tokio::fs::write(&tmp, bytes).await?;
+        self.publish(&tmp, key).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Object>, StorageError> {
+        check_key(key)?;
+        let bytes = not_found_as_none(tokio::fs::read(self.root.join(key)).await)?;
+        Ok(bytes.map(|bytes| Object {
+            bytes,
+            content_type: content_type_of(key),
+        }))
+    }
+
+    async fn get_range(
+        &self,
+        key: &str,
+        start: u64,
+        len: u64,
+    ) -> Result<Option<Object>, StorageError> {
+        check_key(key)?;
+        let Some(mut file) = not_found_as_none(tokio::fs::File::open(self.root.join(key)).await)?
+        else {
+            return Ok(None);
+        };
+        file.seek(io::SeekFrom::Start(start)).await?;
+        let mut bytes = Vec::new();
+        file.take(len).read_to_end(&mut bytes).await?;
+        Ok(Some(Object {
+            bytes,
+            content_type: content_type_of(key),
+        }))
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>, StorageError> {
+        check_key(key)?;
+        let meta = not_found_as_none(tokio::fs::metadata(self.root.join(key)).await)?;
+        Ok(meta.map(|m| m.len()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        check_key(key)?;
+        not_found_as_none(tokio::fs::remove_file(self.root.join(key)).await)?;
+        Ok(())
+    }
+
+    async fn start_multipart(&self, key: &str, _: &str) -> Result<String, StorageError> {
+        check_key(key)?;
+        let upload_id = Uuid::new_v4().simple().to_string();
+        tokio::fs::create_dir_all(self.parts_dir(&upload_id)?).await?;
+        Ok(upload_id)
+    }
+
+    async fn put_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        number: u32,
+        bytes: Vec<u8>,
+    ) -> Result<UploadedPart, StorageError> {
+        check_key(key)?;
+        let dir = self.parts_dir(upload_id)?;
+        if !tokio::fs::try_exists(&dir).await? {
+            return Err(StorageError(format!("no upload {upload_id}")));
+        }
+        let tmp = dir.join(format!(".{number}.tmp"));
+        tokio::fs::write(&tmp, bytes).await?;
+        tokio::fs::rename(&tmp, dir.join(number.to_string())).await?;
+        Ok(UploadedPart {
+            number,
+            etag: number.to_string(),
+        })
+    }
+
+    async fn complete_multipart(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), StorageError> {
+        check_key(key)?;
+        let dir = self.parts_dir(upload_id)?;
+        let tmp = self.root.join(format!(".{key}.tmp"));
+
+        let mut out = tokio::fs::File::create(&tmp).await?;
+        for part in parts {
+            let mut file = tokio::fs::File::open(dir.join(part.number.to_string())).await?;
+            tokio::io::copy(&mut file, &mut out).await?;
+        }
+        out.flush().await?;
+        drop(out);
+
+        self.publish(&tmp, key).await?;
+        tokio::fs::remove_dir_all(&dir).await?;
+        Ok(())
+    }
+
+    async fn abort_multipart(&self, key: &str, upload_id: &str) -> Result<(), StorageError> {
+        check_key(key)?;
+        not_found_as_none(tokio::fs::remove_dir_all(self.parts_dir(upload_id)?).await)?;
+        Ok(())
+    }
+
+    fn presign_put(&self, _: &str, _: &str, _: Duration) -> Option<PresignedRequest> {
+        None
+    }
+
+    fn read_location(&self, key: &str) -> String {
+        let path = self.root.join(key);
+        std::path::absolute(&path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
@@ -0,0 +1,188 @@
+//! Where uploaded and generated media lives.
+//!
+//! Media is stored through the [`Storage`] trait under flat keys such as
+//! `3f2c….mp4`. [`LocalStorage`] keeps it in a directory on disk and
+//! [`S3Storage`] in an S3-compatible bucket (AWS, MinIO, R2, …). Either way
+//! it is served from the [`MediaStorage`] base URL, which points at the
+//! `/api/v1/media` route unless a CDN or public bucket is configured.
+//!
+//! Large files are uploaded in parts, so interrupted uploads can pick up
+//! where they left off. Backends that support it also hand out presigned
+//! requests clients can upload to directly.
+
+pub mod local;
+pub mod s3;
+pub mod sigv4;
+
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+#[derive(Debug)]
+pub struct StorageError(pub String);
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "storage error: {}", self.0)
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<std::io::Error> for StorageError {
+    fn from(e: std::io::Error) -> Self {
+        StorageError(e.to_string())
+    }
+}
+
+/// A stored file.
+pub struct Object {
+    pub bytes: Vec<u8>,
+    pub content_type: String,
+}
+
+/// A part of a multipart upload, as returned by [`Storage::put_part`].
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct UploadedPart {
+    pub number: u32,
+    pub etag: String,
+}
+
+/// A request a client can send itself to upload straight to the backend.
+#[derive(Clone, Debug)]
+pub struct PresignedRequest {
+    pub method: &'static str,
+    pub url: String,
+    /// Headers the request must be sent with.
+    pub headers: Vec<(String, String)>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Whether `key` is a flat name that cannot escape where files are stored.
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= 255
+        && !key.starts_with('.')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
+fn check_key(key: &str) -> Result<(), StorageError> {
+    if is_valid_key(key) {
+        Ok(())
+    } else {
+        Err(StorageError(format!("invalid key {key:?}")))
+    }
+}
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Stores `bytes` under `key`, replacing what was there.
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), StorageError>;
+
+    /// The file under `key`, or `None` if there is none.
+    async fn get(&self, key: &str) -> Result<Option<Object>, StorageError>;
+
+    /// Up to `len` bytes of the file under `key` from byte `start`, which
+    /// must be before its end, or `None` if there is no file.
+    async fn get_range(
+        &self,
+        key: &str,
+        start: u64,
+        len: u64,
+    ) -> Result<Option<Object>, StorageError>;
+
+    /// The size in bytes of the file under `key`, or `None` if there is none.
+    async fn size(&self, key: &str) -> Result<Option<u64>, StorageError>;
+
+    /// Removes the file under `key`, if there is one.
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+
+    /// Starts uploading a file to `key` in parts, and returns the id of the
+    /// upload.
+    async fn start_multipart(&self, key: &str, content_type: &str) -> Result<String, StorageError>;
+
+    /// Uploads part `number` (from 1) of an upload. Every part but the last
+    /// must be at least [`MIN_PART_BYTES`] long.
+    async fn put_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        number: u32,
+        bytes: Vec<u8>,
+    ) -> Result<UploadedPart, StorageError>;
+
+    /// Joins the parts of an upload into the file under `key`.
+    async fn complete_multipart(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), StorageError>;
+
+    /// Discards an unfinished upload and its parts.
+    async fn abort_multipart(&self, key: &str, upload_id: &str) -> Result<(), StorageError>;
+
+    /// A request that uploads a whole file of `content_type` to `key`, valid
+    /// for `expires_in`. `None` if clients cannot upload to the backend
+    /// directly.
+    fn presign_put(
+        &self,
+        key: &str,
+        content_type: &str,
+        expires_in: Duration,
+    ) -> Option<PresignedRequest>;
+
+    /// Where command line tools such as ffmpeg can read the file under `key`
+    /// from: a local path or a short-lived URL.
+    fn read_location(&self, key: &str) -> String;
+}
+
+/// The smallest part of a multipart upload other than the last, which is
+/// what S3 accepts.
+pub const MIN_PART_BYTES: u64 = 5 * 1024 * 1024;
+
+/// The storage media is kept in, shared as app state.
+#[derive(Clone)]
+pub struct MediaStorage {
+    inner: Arc<dyn Storage>,
+    base_url: String,
+}
+
+impl Deref for MediaStorage {
+    type Target = dyn Storage;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner.as_ref()
+    }
+}
+
+impl MediaStorage {
+    /// Storage whose files are served from `base_url`.
+    pub fn new(storage: impl Storage + 'static, base_url: &str) -> Self {
+        MediaStorage {
+            inner: Arc::new(storage),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// The public URL of the file under `key`.
+    pub fn url(&self, key: &str) -> String {
+        format!("{}/{key}", self.base_url)
+    }
+
+    /// The key of a file, given its public URL. `None` for URLs of files
+    /// stored elsewhere.
+    pub fn key_of<'a>(&self, url: &'a str) -> Option<&'a str> {
+        url.strip_prefix(self.base_url.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+            .filter(|key| is_valid_key(key))
+    }
+}
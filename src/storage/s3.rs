@@ -0,0 +1,367 @@
+//! Storage in an S3-compatible bucket.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, ETAG};
+use reqwest::{Method, StatusCode, Url};
+
+use super::sigv4::{self, Credentials};
+use super::{Object, PresignedRequest, Storage, StorageError, UploadedPart, check_key};
+
+/// How long presigned URLs for reading files stay valid.
+const READ_URL_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long a single request to the bucket may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Debug)]
+pub struct S3Config {
+    /// Base URL of the S3 API, such as `https://s3.eu-west-1.amazonaws.com`
+    /// or `http://localhost:9000` for MinIO.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub credentials: Credentials,
+    /// Address the bucket in the path (`endpoint/bucket/key`) rather than the
+    /// host (`bucket.endpoint/key`), as MinIO expects.
+    pub path_style: bool,
+}
+
+/// Stores files as objects of a bucket, each under its key.
+#[derive(Clone, Debug)]
+pub struct S3Storage {
+    client: reqwest::Client,
+    config: S3Config,
+    endpoint: Url,
+}
+
+/// The text of the first `tag` element in `xml`.
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(&format!("<{tag}>"))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{tag}>"))?;
+    Some(
+        xml[start..end]
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn request_error(e: reqwest::Error) -> StorageError {
+    StorageError(format!("S3 request failed: {e}"))
+}
+
+/// Reads the error S3 responded with.
+async fn error_of(res: reqwest::Response) -> StorageError {
+    let status = res.status();
+    let body = res.text().await.unwrap_or_default();
+    match (xml_text(&body, "Code"), xml_text(&body, "Message")) {
+        (Some(code), Some(message)) => StorageError(format!("S3 {status}: {code}: {message}")),
+        (Some(code), None) => StorageError(format!("S3 {status}: {code}")),
+        _ => StorageError(format!("S3 {status}")),
+    }
+}
+
+impl S3Storage {
+    pub fn new(config: S3Config) -> Result<Self, StorageError> {
+        let endpoint = Url::parse(config.endpoint.trim_end_matches('/'))
+            .map_err(|e| StorageError(format!("invalid S3 endpoint: {e}")))?;
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(request_error)?;
+        Ok(S3Storage {
+            client,
+            config,
+            endpoint,
+        })
+    }
+
+    fn object_url(&self, key: &str, query: &[(&str, &str)]) -> Url {
+        let mut url = self.endpoint.clone();
+        if self.config.path_style {
+            url.set_path(&format!("{}/{key}", self.config.bucket));
+        } else {
+            let host = format!(
+                "{}.{}",
+                self.config.bucket,
+                self.endpoint.host_str().unwrap_or_default()
+            );
+            // The endpoint parsed, so prefixing its host with a bucket name
+            // made of host-safe characters parses too.
+            let _ = url.set_host(Some(&host));
+            url.set_path(key);
+        }
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        url
+    }
+
+    /// Sends a request signed with the configured credentials.
+    async fn send(
+        &self,
+        method: Method,
+        url: Url,
+        headers: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, StorageError> {
+        let now = Utc::now();
+        let payload = sigv4::payload_hash(&body);
+        let date = sigv4::amz_date(now);
+
+        let mut signed = headers.to_vec();
+        signed.push(("x-amz-date", &date));
+        signed.push(("x-amz-content-sha256", &payload));
+        let authorization = sigv4::authorization(
+            method.as_str(),
+            &url,
+            &signed,
+            &payload,
+            &self.config.credentials,
+            &self.config.region,
+            now,
+        );
+
+        let mut req = self.client.request(method, url);
+        for (name, value) in signed {
+            req = req.header(name, value);
+        }
+        req.header("authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(request_error)
+    }
+
+    async fn expect_success(
+        &self,
+        res: reqwest::Response,
+    ) -> Result<reqwest::Response, StorageError> {
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(error_of(res).await)
+        }
+    }
+
+    async fn get_object(
+        &self,
+        key: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<Option<Object>, StorageError> {
+        let res = self
+            .send(Method::GET, self.object_url(key, &[]), headers, vec![])
+            .await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let res = self.expect_success(res).await?;
+        let content_type = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        let bytes = res.bytes().await.map_err(request_error)?.to_vec();
+        Ok(Some(Object {
+            bytes,
+            content_type,
+        }))
+    }
+
+    fn presign(&self, method: &str, key: &str, headers: &[(&str, &str)], ttl: Duration) -> Url {
+        sigv4::presign(
+            method,
+            &self.object_url(key, &[]),
+            headers,
+            &self.config.credentials,
+            &self.config.region,
+            Utc::now(),
+            ttl,
+        )
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), StorageError> {
+        check_key(key)?;
+        let url = self.object_url(key, &[]);
+        let res = self
+            .send(Method::PUT, url, &[("content-type", content_type)], bytes)
+            .await?;
+        self.expect_success(res).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Object>, StorageError> {
+        check_key(key)?;
+        self.get_object(key, &[]).await
+    }
+
+    async fn get_range(
+        &self,
+        key: &str,
+        start: u64,
+        len: u64,
+    ) -> Result<Option<Object>, StorageError> {
+        check_key(key)?;
+        let last = start + len.max(1) - 1;
+        self.get_object(key, &[("range", &format!("bytes={start}-{last}"))])
+            .await
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>, StorageError> {
+        check_key(key)?;
+        let res = self
+            .send(Method::HEAD, self.object_url(key, &[]), &[], vec![])
+            .await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let res = self.expect_success(res).await?;
+        Ok(res
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        check_key(key)?;
+        let res = self
+            .send(Method::DELETE, self.object_url(key, &[]), &[], vec![])
+            .await?;
+        if res.status() != StatusCode::NOT_FOUND {
+            self.expect_success(res).await?;
+        }
+        Ok(())
+    }
+
+    async fn start_multipart(&self, key: &str, content_type: &str) -> Result<String, StorageError> {
+        check_key(key)?;
+        let url = self.object_url(key, &[("uploads", "")]);
+        let res = self
+            .send(Method::POST, url, &[("content-type", content_type)], vec![])
+            .await?;
+        let body = self
+            .expect_success(res)
+            .await?
+            .text()
+            .await
+            .map_err(request_error)?;
+        xml_text(&body, "UploadId")
+            .ok_or_else(|| StorageError("S3 did not return an upload id".to_string()))
+    }
+
+    async fn put_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        number: u32,
+        bytes: Vec<u8>,
+    ) -> Result<UploadedPart, StorageError> {
+        check_key(key)?;
+        let number_str = number.to_string();
+        let url = self.object_url(
+            key,
+            &[("partNumber", number_str.as_str()), ("uploadId", upload_id)],
+        );
+        let res = self.send(Method::PUT, url, &[], bytes).await?;
+        let res = self.expect_success(res).await?;
+        let etag = res
+            .headers()
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| StorageError("S3 did not return an ETag for the part".to_string()))?;
+        Ok(UploadedPart {
+            number,
+            etag: etag.to_string(),
+        })
+    }
+
+    async fn complete_multipart(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+    ) -> Result<(), StorageError> {
+        check_key(key)?;
+        let mut body = String::from("<CompleteMultipartUpload>");
+        for part in parts {
+            body.push_str(&format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                part.number,
+                xml_escape(&part.etag)
+            ));
+        }
+        body.push_str("</CompleteMultipartUpload>");
+
+        let url = self.object_url(key, &[("uploadId", upload_id)]);
+        let res = self
+            .send(
+                Method::POST,
+                url,
+                &[("content-type", "application/xml")],
+                body.into_bytes(),
+            )
+            .await?;
+        let body = self
+            .expect_success(res)
+            .await?
+            .text()
+            .await
+            .map_err(request_error)?;
+        // Completing can fail after S3 has already answered 200.
+        if body.contains("<Error>") {
+            let code = xml_text(&body, "Code").unwrap_or_default();
+            let message = xml_text(&body, "Message").unwrap_or_default();
+            return Err(StorageError(format!(
+                "S3 could not complete the upload: {code}: {message}"
+            )));
+        }
+        Ok(())
+    }
+
+    async fn abort_multipart(&self, key: &str, upload_id: &str) -> Result<(), StorageError> {
+        check_key(key)?;
+        let url = self.object_url(key, &[("uploadId", upload_id)]);
+        let res = self.send(Method::DELETE, url, &[], vec![]).await?;
+        if res.status() != StatusCode::NOT_FOUND {
+            self.expect_success(res).await?;
+        }
+        Ok(())
+    }
+
+    fn presign_put(
+        &self,
+        key: &str,
+        content_type: &str,
+        expires_in: Duration,
+    ) -> Option<PresignedRequest> {
+        let url = self.presign("PUT", key, &[("content-type", content_type)], expires_in);
+        Some(PresignedRequest {
+            method: "PUT",
+            url: url.to_string(),
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            expires_at: Utc::now()
+                + chrono::Duration::from_std(expires_in).unwrap_or(chrono::Duration::zero()),
+        })
+    }
+
+    fn read_location(&self, key: &str) -> String {
+        self.presign("GET", key, &[], READ_URL_TTL).to_string()
+    }
+}
@@ -0,0 +1,201 @@
+//! AWS Signature Version 4 for S3 requests.
+//!
+//! Requests are signed either with an `Authorization` header, for requests
+//! the server sends itself, or in the query string, for presigned URLs that
+//! clients and tools use without credentials of their own.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const SERVICE: &str = "s3";
+
+/// Payload hash of presigned requests, whose body is not known up front.
+pub const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+#[derive(Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Hex SHA-256 of `bytes`, as sent in `x-amz-content-sha256`.
+pub fn payload_hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// `x-amz-date` of `now`.
+pub fn amz_date(now: DateTime<Utc>) -> String {
+    now.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// The `Host` header of requests to `url`.
+pub fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    }
+}
+
+fn uri_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn scope(now: DateTime<Utc>, region: &str) -> String {
+    format!("{}/{region}/{SERVICE}/aws4_request", now.format("%Y%m%d"))
+}
+
+/// The signature of a request to `url`, signing `headers` (lowercase names,
+/// sorted) and a body with hash `payload`.
+fn signature(
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    payload: &str,
+    credentials: &Credentials,
+    region: &str,
+    now: DateTime<Utc>,
+) -> String {
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+        .collect();
+    query.sort();
+    let query = query
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = signed_headers(headers);
+
+    let canonical_request = format!(
+        "{method}\n{}\n{query}\n{canonical_headers}\n{signed_headers}\n{payload}",
+        url.path()
+    );
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{}\n{}\n{}",
+        amz_date(now),
+        scope(now, region),
+        payload_hash(canonical_request.as_bytes())
+    );
+
+    let key = hmac(
+        format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+        &now.format("%Y%m%d").to_string(),
+    );
+    let key = hmac(&key, region);
+    let key = hmac(&key, SERVICE);
+    let key = hmac(&key, "aws4_request");
+    hex::encode(hmac(&key, &string_to_sign))
+}
+
+fn signed_headers(headers: &[(String, String)]) -> String {
+    headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Lowercases and sorts headers, adding `Host`.
+fn canonical(url: &Url, headers: &[(&str, &str)]) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.to_string()))
+        .filter(|(name, _)| name != "host")
+        .collect();
+    headers.push(("host".to_string(), host(url)));
+    headers.sort();
+    headers
+}
+
+/// The `Authorization` header of a request to `url`. `headers` must include
+/// `x-amz-date` for `now` and `x-amz-content-sha256` for `payload`; `Host`
+/// is added.
+pub fn authorization(
+    method: &str,
+    url: &Url,
+    headers: &[(&str, &str)],
+    payload: &str,
+    credentials: &Credentials,
+    region: &str,
+    now: DateTime<Utc>,
+) -> String {
+    let headers = canonical(url, headers);
+    let signature = signature(method, url, &headers, payload, credentials, region, now);
+    format!(
+        "{ALGORITHM} Credential={}/{},SignedHeaders={},Signature={signature}",
+        credentials.access_key_id,
+        scope(now, region),
+        signed_headers(&headers),
+    )
+}
+
+/// `url` signed in its query string, valid for `expires_in` from `now`. The
+/// request must be sent with `headers` as given.
+pub fn presign(
+    method: &str,
+    url: &Url,
+    headers: &[(&str, &str)],
+    credentials: &Credentials,
+    region: &str,
+    now: DateTime<Utc>,
+    expires_in: Duration,
+) -> Url {
+    let headers = canonical(url, headers);
+    let mut url = url.clone();
+    url.query_pairs_mut()
+        .append_pair("X-Amz-Algorithm", ALGORITHM)
+        .append_pair(
+            "X-Amz-Credential",
+            &format!("{}/{}", credentials.access_key_id, scope(now, region)),
+        )
+        .append_pair("X-Amz-Date", &amz_date(now))
+        .append_pair("X-Amz-Expires", &expires_in.as_secs().to_string())
+        .append_pair("X-Amz-SignedHeaders", &signed_headers(&headers));
+
+    let signature = signature(
+        method,
+        &url,
+        &headers,
+        UNSIGNED_PAYLOAD,
+        credentials,
+        region,
+        now,
+    );
+    url.query_pairs_mut()
+        .append_pair("X-Amz-Signature", &signature);
+    url
+}
@@ -0,0 +1,156 @@
+//! Downloading videos to local files for the tools that process them.
+//!
+//! ffmpeg and the transcription command are only ever given local files. A
+//! remote `video_url` is whatever the testimonial was saved with, so letting
+//! ffmpeg fetch it would let anyone who can save a testimonial make this
+//! server request its own network, and a video that is really a playlist
+//! could point ffmpeg anywhere else. Remote videos are downloaded instead,
+//! with a client that only connects to the addresses [`Destinations`]
+//! permits, redirects included.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use super::VideoError;
+use crate::net::{self, Destinations};
+
+/// Largest video downloaded, the same as the largest video upload by default.
+pub const MAX_DOWNLOAD_BYTES: u64 = 500 * 1024 * 1024;
+
+/// How long a download may take, leaving the rest of the job's time for
+/// processing.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Redirects followed before giving up.
+const MAX_REDIRECTS: usize = 5;
+
+/// A downloaded video, removed when dropped.
+pub struct Download {
+    path: PathBuf,
+}
+
+impl Download {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Downloads videos for the pipeline.
+#[derive(Clone)]
+pub struct Fetcher {
+    /// For URLs users chose.
+    remote: reqwest::Client,
+    /// For short-lived URLs of videos in storage, which may be on a private
+    /// network.
+    stored: reqwest::Client,
+    destinations: Destinations,
+}
+
+impl Fetcher {
+    /// A fetcher whose downloads of remote videos only reach the addresses
+    /// `destinations` permits.
+    pub fn new(destinations: Destinations) -> Self {
+        let remote = net::client_builder(destinations)
+            .timeout(DOWNLOAD_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else if let Err(e) = destinations.check(attempt.url().as_str()) {
+                    attempt.error(e)
+                } else {
+                    attempt.follow()
+                }
+            }))
+            .build()
+            .expect("failed to build HTTP client");
+        let stored = reqwest::Client::builder()
+            .timeout(DOWNLOAD_TIMEOUT)
+            .build()
+            .expect("failed to build HTTP client");
+
+        Fetcher {
+            remote,
+            stored,
+            destinations,
+        }
+    }
+
+    /// Downloads a video from a URL a user chose.
+    pub async fn remote(&self, url: &str) -> Result<Download, VideoError> {
+        self.destinations
+            .check(url)
+            .map_err(|e| VideoError(format!("could not fetch video: {e}")))?;
+        download(&self.remote, url).await
+    }
+
+    /// Downloads a video from the short-lived URL its storage gave for it.
+    pub async fn stored(&self, url: &str) -> Result<Download, VideoError> {
+        download(&self.stored, url).await
+    }
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Download, VideoError> {
+    let too_large = || VideoError(format!("video is larger than {MAX_DOWNLOAD_BYTES} bytes"));
+
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| VideoError(format!("could not fetch video: {}", describe(&e))))?;
+    if !response.status().is_success() {
+        return Err(VideoError(format!(
+            "fetching video returned {}",
+            response.status()
+        )));
+    }
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_DOWNLOAD_BYTES)
+    {
+        return Err(too_large());
+    }
+
+    let download = Download {
+        path: std::env::temp_dir().join(format!("reeverb-video-{}", Uuid::new_v4())),
+    };
+    let write_error = |e: std::io::Error| VideoError(format!("could not save video: {e}"));
+    let mut file = tokio::fs::File::create(&download.path)
+        .await
+        .map_err(write_error)?;
+    let mut written = 0;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| VideoError(format!("could not fetch video: {}", describe(&e))))?
+    {
+        written += chunk.len() as u64;
+        if written > MAX_DOWNLOAD_BYTES {
+            return Err(too_large());
+        }
+        file.write_all(&chunk).await.map_err(write_error)?;
+    }
+    file.flush().await.map_err(write_error)?;
+    Ok(download)
+}
+
+/// An error and what caused it, since reqwest's own message leaves out why a
+/// request failed, such as an address being refused.
+fn describe(e: &reqwest::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = std::error::Error::source(e);
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
@@ -0,0 +1,388 @@
+//! Background processing of video testimonials.
+//!
+//! When a testimonial's `video_url` is set, it is marked `processing` and the
+//! save queues a [`ProcessVideo`] job for the [`VideoPipeline`]. The pipeline
+//! downloads a remote video with the [`Fetcher`], probes it for its duration,
+//! extracts a thumbnail into [`MediaStorage`], transcribes it, and writes whatever the testimonial is missing back to it: the duration,
+//! thumbnail, transcription, and the language and sentiment of the
+//! transcription. Fields that are already set are left alone. The testimonial
+//! ends up `ready`, or `failed` with a `video_error`.
+//!
+//! Probing and transcription go through the [`VideoProbe`] and [`Transcriber`]
+//! traits. [`Ffmpeg`] probes with the ffmpeg command line tools; transcription
+//! is [`transcribe::Disabled`] unless a local command is configured.
+//! Webhooks subscribed to `testimonial.video_processed` are sent the outcome,
+//! queued with the update that records it.
+
+pub mod fetch;
+pub mod probe;
+pub mod transcribe;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rapina::prelude::tracing;
+use rapina::sea_orm::sea_query::Expr;
+use rapina::sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::db::entities::testimonial::{ActiveModel, Column, Entity as Testimonial, Model};
+use crate::jobs::{Job, JobError};
+use crate::language;
+use crate::net::Destinations;
+use crate::sentiment::SentimentAnalyzer;
+use crate::storage::MediaStorage;
+use crate::webhooks::{self, Event};
+
+pub use fetch::{Download, Fetcher};
+pub use probe::{Ffmpeg, VideoInfo, VideoProbe};
+pub use transcribe::Transcriber;
+
+pub const STATUS_PROCESSING: &str = "processing";
+pub const STATUS_READY: &str = "ready";
+pub const STATUS_FAILED: &str = "failed";
+
+/// Videos a worker processes at the same time; the rest wait their turn.
+pub const MAX_CONCURRENT: usize = 2;
+
+#[derive(Debug)]
+pub struct VideoError(pub String);
+
+impl std::fmt::Display for VideoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for VideoError {}
+
+/// Runs `program` with `args` and returns what it wrote to stdout. Fails if it
+/// cannot be started, exits unsuccessfully or takes longer than `timeout`.
+pub(crate) async fn run_command(
+    program: &str,
+    args: &[&str],
+    envs: &[(&str, &str)],
+    timeout: Duration,
+) -> Result<Vec<u8>, VideoError> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .envs(envs.iter().copied())
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+
+    let output = tokio::time::timeout(timeout, output)
+        .await
+        .map_err(|_| VideoError(format!("{program} timed out")))?
+        .map_err(|e| VideoError(format!("could not run {program}: {e}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr.trim().lines().last().unwrap_or("no output");
+        return Err(VideoError(format!(
+            "{program} exited with {}: {detail}",
+            output.status
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Whether `url` is a remote URL the pipeline will download. Videos uploaded
+/// to local storage are read in place; no other local files are read.
+fn is_remote(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// What `testimonial.video_processed` webhooks are sent: the testimonial's id
+/// and its video fields, as the testimonial endpoints return them.
+fn processed(t: &Model) -> Value {
+    json!({
+        "id": t.pid,
+        "video_url": t.video_url,
+        "video_status": t.video_status,
+        "video_error": t.video_error,
+        "video_duration_seconds": t.video_duration_seconds,
+        "video_thumbnail_url": t.video_thumbnail_url,
+        "transcription": t.transcription,
+    })
+}
+
+/// Job that processes the video of a testimonial marked `processing`.
+#[derive(Serialize, Deserialize)]
+pub struct ProcessVideo {
+    pub testimonial_id: i32,
+}
+
+impl Job for ProcessVideo {
+    const KIND: &'static str = "video.process";
+}
+
+/// Marks the testimonial `active` will save for processing if the save sets a
+/// new video, and returns whether it did. The caller queues a [`ProcessVideo`]
+/// job in the same transaction as the save. `before` is the testimonial being
+/// edited, if any.
+///
+/// The thumbnail, duration and transcription of the previous video are
+/// cleared so they are made again for the new one, unless the same save sets
+/// them. Removing the video clears its processing state.
+pub fn queue(before: Option<&Model>, active: &mut ActiveModel) -> bool {
+    let video_url = active.video_url.try_as_ref().cloned().flatten();
+    if let Some(before) = before {
+        if video_url == before.video_url {
+            return false;
+        }
+        if active.video_thumbnail_url.try_as_ref() == Some(&before.video_thumbnail_url) {
+            active.video_thumbnail_url = Set(None);
+        }
+        if active.video_duration_seconds.try_as_ref() == Some(&before.video_duration_seconds) {
+            active.video_duration_seconds = Set(None);
+        }
+        if active.transcription.try_as_ref() == Some(&before.transcription) {
+            active.transcription = Set(None);
+        }
+    }
+
+    let queued = video_url.is_some();
+    active.video_status = Set(queued.then(|| STATUS_PROCESSING.to_string()));
+    active.video_error = Set(None);
+    queued
+}
+
+/// What processing found out about a video.
+#[derive(Default)]
+struct Outcome {
+    duration_seconds: Option<i32>,
+    thumbnail_url: Option<String>,
+    transcription: Option<String>,
+    language: Option<String>,
+    sentiment: Option<crate::sentiment::Sentiment>,
+    error: Option<String>,
+}
+
+/// The pipeline video testimonials are processed by, shared as app state.
+#[derive(Clone)]
+pub struct VideoPipeline {
+    probe: Arc<dyn VideoProbe>,
+    transcriber: Arc<dyn Transcriber>,
+    storage: MediaStorage,
+    analyzer: SentimentAnalyzer,
+    fetcher: Fetcher,
+}
+
+impl VideoPipeline {
+    /// A pipeline whose downloads of remote videos only reach the addresses
+    /// `destinations` permits.
+    pub fn new(
+        probe: impl VideoProbe + 'static,
+        transcriber: impl Transcriber + 'static,
+        storage: MediaStorage,
+        analyzer: SentimentAnalyzer,
+        destinations: Destinations,
+    ) -> Self {
+        VideoPipeline {
+            probe: Arc::new(probe),
+            transcriber: Arc::new(transcriber),
+            storage,
+            analyzer,
+            fetcher: Fetcher::new(destinations),
+        }
+    }
+
+    /// Processes the video of the testimonial of `job`. A video that cannot be
+    /// processed leaves the testimonial `failed`; only failing to save that
+    /// fails the job.
+    pub async fn process(
+        &self,
+        conn: &DatabaseConnection,
+        job: ProcessVideo,
+    ) -> Result<(), JobError> {
+        let Some(done) = self.save(conn, job.testimonial_id).await? else {
+            return Ok(());
+        };
+        match &done.video_error {
+            Some(error) => tracing::warn!(
+                testimonial = %done.pid,
+                error = %error,
+                "video processing failed"
+            ),
+            None => tracing::info!(testimonial = %done.pid, "video processed"),
+        }
+        Ok(())
+    }
+
+    /// Processes the video of a testimonial marked `processing`, saves the
+    /// results and queues the webhook deliveries about them, and returns the
+    /// saved testimonial. `None` if there was nothing to process, or the video
+    /// was changed or removed in the meantime.
+    async fn save(
+        &self,
+        conn: &DatabaseConnection,
+        testimonial_id: i32,
+    ) -> Result<Option<Model>, DbErr> {
+        let testimonial = Testimonial::find_by_id(testimonial_id)
+            .filter(Column::VideoStatus.eq(STATUS_PROCESSING))
+            .filter(Column::DeletedAt.is_null())
+            .one(conn)
+            .await?;
+        let Some(t) = testimonial else {
+            return Ok(None);
+        };
+        let Some(video_url) = t.video_url.clone() else {
+            return Ok(None);
+        };
+
+        let outcome = self.run(&t, &video_url).await;
+        let status = if outcome.error.is_some() {
+            STATUS_FAILED
+        } else {
+            STATUS_READY
+        };
+
+        let mut update = Testimonial::update_many()
+            .col_expr(Column::VideoStatus, Expr::value(status))
+            .col_expr(Column::VideoError, Expr::value(outcome.error.clone()))
+            .col_expr(
+                Column::UpdatedAt,
+                Expr::value(chrono::Utc::now().fixed_offset()),
+            );
+        if let Some(duration) = outcome.duration_seconds {
+            update = update.col_expr(Column::VideoDurationSeconds, Expr::value(duration));
+        }
+        if let Some(url) = outcome.thumbnail_url {
+            update = update.col_expr(Column::VideoThumbnailUrl, Expr::value(url));
+        }
+        if let Some(transcription) = outcome.transcription {
+            update = update.col_expr(Column::Transcription, Expr::value(transcription));
+        }
+        if let Some(language) = outcome.language {
+            update = update.col_expr(Column::Language, Expr::value(language));
+        }
+        if let Some(sentiment) = outcome.sentiment {
+            update = update
+                .col_expr(Column::Sentiment, Expr::value(sentiment.label.as_str()))
+                .col_expr(Column::SentimentScore, Expr::value(sentiment.score));
+        }
+
+        // Only write back if the testimonial still has the video processed.
+        let txn = conn.begin().await?;
+        let result = update
+            .filter(Column::Id.eq(t.id))
+            .filter(Column::VideoUrl.eq(&video_url))
+            .filter(Column::VideoStatus.eq(STATUS_PROCESSING))
+            .filter(Column::DeletedAt.is_null())
+            .exec(&txn)
+            .await?;
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+        let Some(done) = Testimonial::find_by_id(t.id).one(&txn).await? else {
+            return Ok(None);
+        };
+        webhooks::dispatch(
+            &txn,
+            done.project_id,
+            Event::TestimonialVideoProcessed,
+            &processed(&done),
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok(Some(done))
+    }
+
+    /// Works out what `t` is missing about its video. Stops at the first
+    /// error, keeping what was found before it.
+    async fn run(&self, t: &Model, video_url: &str) -> Outcome {
+        let mut outcome = Outcome::default();
+        // Downloads are kept until the tools are done with them, and removed
+        // with them.
+        let downloaded = |download: Download| {
+            (
+                download.path().to_string_lossy().into_owned(),
+                Some(download),
+            )
+        };
+        let located = match self.storage.key_of(video_url) {
+            Some(key) => {
+                let location = self.storage.read_location(key);
+                if is_remote(&location) {
+                    self.fetcher.stored(&location).await.map(downloaded)
+                } else {
+                    Ok((location, None))
+                }
+            }
+            None if is_remote(video_url) => self.fetcher.remote(video_url).await.map(downloaded),
+            None => {
+                outcome.error =
+                    Some("video_url must be an http or https URL, or an uploaded file".to_string());
+                return outcome;
+            }
+        };
+        let (location, _download) = match located {
+            Ok(located) => located,
+            Err(e) => {
+                outcome.error = Some(e.to_string());
+                return outcome;
+            }
+        };
+
+        let info = match self.probe.probe(&location).await {
+            Ok(info) => info,
+            Err(e) => {
+                outcome.error = Some(format!("could not probe video: {e}"));
+                return outcome;
+            }
+        };
+        if t.video_duration_seconds.is_none() {
+            outcome.duration_seconds = info.duration_seconds;
+        }
+        if t.video_thumbnail_url.is_none()
+            && let Some(thumbnail) = info.thumbnail
+        {
+            let key = format!("{}.jpg", t.pid);
+            match self.storage.put(&key, thumbnail, "image/jpeg").await {
+                Ok(()) => outcome.thumbnail_url = Some(self.storage.url(&key)),
+                Err(e) => {
+                    outcome.error = Some(format!("could not save thumbnail: {e}"));
+                    return outcome;
+                }
+            }
+        }
+
+        if t.transcription.is_some() {
+            return outcome;
+        }
+        let transcription = match self
+            .transcriber
+            .transcribe(&location, t.language.as_deref())
+            .await
+        {
+            Ok(transcription) => transcription.filter(|text| !text.trim().is_empty()),
+            Err(e) => {
+                outcome.error = Some(format!("could not transcribe video: {e}"));
+                return outcome;
+            }
+        };
+        let Some(transcription) = transcription else {
+            return outcome;
+        };
+
+        if t.language.is_none() {
+            outcome.language = language::of(t.content.as_deref(), Some(&transcription));
+        }
+        // The transcription is only judged if there is no content to judge.
+        let has_content = t.content.as_deref().is_some_and(|c| !c.trim().is_empty());
+        if t.sentiment.is_none() && t.sentiment_score.is_none() && !has_content {
+            let language = t.language.as_deref().or(outcome.language.as_deref());
+            match self.analyzer.of(None, Some(&transcription), language).await {
+                Ok(sentiment) => outcome.sentiment = sentiment,
+                Err(e) => tracing::warn!(error = %e, "could not analyze transcription sentiment"),
+            }
+        }
+        outcome.transcription = Some(transcription);
+        outcome
+    }
+}
@@ -0,0 +1,133 @@
+//! Probing videos for their duration and a thumbnail.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{VideoError, run_command};
+
+/// How long each ffprobe or ffmpeg run may take.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How far into the video the thumbnail is taken, in seconds, so it does not
+/// show a black first frame. Short videos use their midpoint instead.
+const THUMBNAIL_AT: f64 = 1.0;
+
+/// Width thumbnails are scaled to; the height keeps the aspect ratio.
+const THUMBNAIL_WIDTH: u32 = 640;
+
+/// Protocols ffmpeg may use. Videos are downloaded before they are probed
+/// (see [`super::fetch`]), so a video that is really a playlist cannot make
+/// ffmpeg request anything.
+const PROTOCOLS: &str = "file";
+
+/// What probing a video found out.
+#[derive(Clone, Debug, Default)]
+pub struct VideoInfo {
+    /// Rounded to the nearest second; `None` if the video does not say.
+    pub duration_seconds: Option<i32>,
+    /// A JPEG frame from the video, if one could be extracted.
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+#[async_trait]
+pub trait VideoProbe: Send + Sync {
+    /// Probes the video at `video`, a local path.
+    async fn probe(&self, video: &str) -> Result<VideoInfo, VideoError>;
+}
+
+/// Probes with the `ffprobe` and `ffmpeg` command line tools.
+#[derive(Clone, Debug)]
+pub struct Ffmpeg {
+    ffmpeg: String,
+    ffprobe: String,
+}
+
+impl Default for Ffmpeg {
+    fn default() -> Self {
+        Ffmpeg::new("ffmpeg", "ffprobe")
+    }
+}
+
+impl Ffmpeg {
+    /// Runs the tools at the given paths, or looks them up on `PATH` if they
+    /// are bare names.
+    pub fn new(ffmpeg: impl Into<String>, ffprobe: impl Into<String>) -> Self {
+        Ffmpeg {
+            ffmpeg: ffmpeg.into(),
+            ffprobe: ffprobe.into(),
+        }
+    }
+
+    async fn duration(&self, video: &str) -> Result<Option<f64>, VideoError> {
+        let out = run_command(
+            &self.ffprobe,
+            &[
+                "-v",
+                "error",
+                "-protocol_whitelist",
+                PROTOCOLS,
+                "-show_entries",
+                "format=duration",
+                "-of",
+                "default=noprint_wrappers=1:nokey=1",
+                video,
+            ],
+            &[],
+            COMMAND_TIMEOUT,
+        )
+        .await?;
+        // Streams without a known duration print "N/A".
+        Ok(String::from_utf8_lossy(&out)
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|d| d.is_finite() && *d >= 0.0))
+    }
+
+    async fn thumbnail(&self, video: &str, at: f64) -> Result<Option<Vec<u8>>, VideoError> {
+        let at = format!("{at:.3}");
+        let scale = format!("scale={THUMBNAIL_WIDTH}:-2");
+        let out = run_command(
+            &self.ffmpeg,
+            &[
+                "-v",
+                "error",
+                "-protocol_whitelist",
+                PROTOCOLS,
+                "-ss",
+                &at,
+                "-i",
+                video,
+                "-frames:v",
+                "1",
+                "-vf",
+                &scale,
+                "-f",
+                "image2",
+                "-c:v",
+                "mjpeg",
+                "pipe:1",
+            ],
+            &[],
+            COMMAND_TIMEOUT,
+        )
+        .await?;
+        // Audio-only files have no frame to take.
+        Ok((!out.is_empty()).then_some(out))
+    }
+}
+
+#[async_trait]
+impl VideoProbe for Ffmpeg {
+    async fn probe(&self, video: &str) -> Result<VideoInfo, VideoError> {
+        let duration = self.duration(video).await?;
+        let at = duration.map_or(0.0, |d| THUMBNAIL_AT.min(d / 2.0));
+        let thumbnail = self.thumbnail(video, at).await?;
+
+        Ok(VideoInfo {
+            duration_seconds: duration.map(|d| d.round() as i32),
+            thumbnail,
+        })
+    }
+}
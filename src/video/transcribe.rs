@@ -0,0 +1,99 @@
+//! Transcribing what is said in videos.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{VideoError, run_command};
+
+/// How long a transcription command may take.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[async_trait]
+pub trait Transcriber: Send + Sync {
+    /// What is said in the video at `video`, a local path, spoken in
+    /// `language` if that is known. `None` when the transcriber has nothing to offer.
+    async fn transcribe(
+        &self,
+        video: &str,
+        language: Option<&str>,
+    ) -> Result<Option<String>, VideoError>;
+}
+
+#[async_trait]
+impl<T: Transcriber + ?Sized> Transcriber for Box<T> {
+    async fn transcribe(
+        &self,
+        video: &str,
+        language: Option<&str>,
+    ) -> Result<Option<String>, VideoError> {
+        (**self).transcribe(video, language).await
+    }
+}
+
+/// Leaves videos untranscribed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Disabled;
+
+#[async_trait]
+impl Transcriber for Disabled {
+    async fn transcribe(&self, _: &str, _: Option<&str>) -> Result<Option<String>, VideoError> {
+        Ok(None)
+    }
+}
+
+/// Transcribes every video as the same text, for tests and demos.
+#[derive(Clone, Debug)]
+pub struct Stub(pub String);
+
+#[async_trait]
+impl Transcriber for Stub {
+    async fn transcribe(&self, _: &str, _: Option<&str>) -> Result<Option<String>, VideoError> {
+        Ok(Some(self.0.clone()))
+    }
+}
+
+/// Transcribes with a local command, such as a wrapper around whisper.cpp.
+///
+/// The command is run with the video's path as its last argument and the
+/// language, if known, in `TRANSCRIPTION_LANGUAGE`. Whatever it prints to
+/// stdout is the transcription.
+#[derive(Clone, Debug)]
+pub struct Command {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Command {
+    /// A command from a whitespace-separated command line, or `None` if it is
+    /// blank.
+    pub fn parse(command_line: &str) -> Option<Self> {
+        let mut parts = command_line.split_whitespace().map(str::to_string);
+        Some(Command {
+            program: parts.next()?,
+            args: parts.collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl Transcriber for Command {
+    async fn transcribe(
+        &self,
+        video: &str,
+        language: Option<&str>,
+    ) -> Result<Option<String>, VideoError> {
+        let mut args: Vec<&str> = self.args.iter().map(String::as_str).collect();
+        args.push(video);
+        let envs: Vec<(&str, &str)> = language
+            .map(|l| ("TRANSCRIPTION_LANGUAGE", l))
+            .into_iter()
+            .collect();
+
+        let out = run_command(&self.program, &args, &envs, COMMAND_TIMEOUT).await?;
+        let text = String::from_utf8(out)
+            .map_err(|_| VideoError(format!("{} printed invalid UTF-8", self.program)))?;
+        let text = text.trim();
+        Ok((!text.is_empty()).then(|| text.to_string()))
+    }
+}
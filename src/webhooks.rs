@@ -0,0 +1,472 @@
+//! Outgoing webhooks.
+//!
+//! A project's webhooks are URLs subscribed to some [`Event`]s. When one
+//! happens, [`dispatch`] records a delivery for each subscribed webhook and
+//! queues a [`Deliver`] job, which POSTs the delivery's JSON payload and
+//! retries with backoff until the webhook answers with a 2xx.
+//!
+//! Each request is signed with the webhook's secret in the
+//! `Reeverb-Signature` header, as `t=<unix time>,v1=<hex HMAC-SHA256 of
+//! "<unix time>.<body>">`. Receivers recompute the HMAC to check the request
+//! came from us, and reject old timestamps so it cannot be replayed.
+//!
+//! Webhook URLs are chosen by users, so deliveries only go to public
+//! addresses unless the [`Sender`] is told otherwise; see [`crate::net`].
+
+use std::time::{Duration, Instant};
+
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rapina::schemars::{self, JsonSchema};
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, Set,
+};
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::db::entities::webhook::{
+    ActiveModel as WebhookActiveModel, Column as WebhookColumn, Entity as Webhook,
+    Model as WebhookModel,
+};
+use crate::db::entities::webhook_delivery::{ActiveModel, Entity as Delivery, Model};
+use crate::jobs::{self, Job, JobError};
+use crate::net::{self, Destinations};
+use crate::secrets::{Keyring, SecretError};
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_SUCCEEDED: &str = "succeeded";
+pub const STATUS_FAILED: &str = "failed";
+
+pub const SIGNATURE_HEADER: &str = "Reeverb-Signature";
+pub const EVENT_HEADER: &str = "Reeverb-Event";
+pub const DELIVERY_HEADER: &str = "Reeverb-Delivery";
+
+/// Tries of a delivery before it is given up on. With the job queue's
+/// backoff, the last comes about an hour and a half after the first.
+pub const DELIVERY_ATTEMPTS: i32 = 10;
+
+/// How long a webhook has to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How much of a response body is kept in the delivery log.
+const RESPONSE_BODY_LIMIT: usize = 2048;
+
+const SECRET_PREFIX: &str = "whsec_";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum Event {
+    /// A testimonial was added. Imported testimonials do not count, so a
+    /// large import does not flood the webhook.
+    #[serde(rename = "testimonial.created")]
+    TestimonialCreated,
+    #[serde(rename = "testimonial.approved")]
+    TestimonialApproved,
+    #[serde(rename = "testimonial.featured")]
+    TestimonialFeatured,
+    /// A testimonial's video is done processing, whether it is now `ready`
+    /// or `failed`. The data is the testimonial's id and video fields.
+    #[serde(rename = "testimonial.video_processed")]
+    TestimonialVideoProcessed,
+    /// A testimonial was submitted through a collection form's public
+    /// submission endpoint.
+    #[serde(rename = "form.submitted")]
+    FormSubmitted,
+}
+
+impl Event {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Event::TestimonialCreated => "testimonial.created",
+            Event::TestimonialApproved => "testimonial.approved",
+            Event::TestimonialFeatured => "testimonial.featured",
+            Event::TestimonialVideoProcessed => "testimonial.video_processed",
+            Event::FormSubmitted => "form.submitted",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "testimonial.created" => Some(Event::TestimonialCreated),
+            "testimonial.approved" => Some(Event::TestimonialApproved),
+            "testimonial.featured" => Some(Event::TestimonialFeatured),
+            "testimonial.video_processed" => Some(Event::TestimonialVideoProcessed),
+            "form.submitted" => Some(Event::FormSubmitted),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub enum WebhookError {
+    Db(DbErr),
+    Secret(SecretError),
+}
+
+impl std::fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookError::Db(e) => write!(f, "{e}"),
+            WebhookError::Secret(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for WebhookError {}
+
+impl From<DbErr> for WebhookError {
+    fn from(e: DbErr) -> Self {
+        WebhookError::Db(e)
+    }
+}
+
+impl From<SecretError> for WebhookError {
+    fn from(e: SecretError) -> Self {
+        WebhookError::Secret(e)
+    }
+}
+
+/// A new random signing secret.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{SECRET_PREFIX}{}", hex::encode(bytes))
+}
+
+/// The `Reeverb-Signature` of `body` sent at `timestamp`.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!(
+        "t={timestamp},v1={}",
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+/// The stored form of `secret`, sealed like import source credentials.
+pub fn seal_secret(keyring: &Keyring, secret: &str) -> Result<Value, SecretError> {
+    keyring.seal(&Value::String(secret.to_string()))
+}
+
+fn open_secret(keyring: &Keyring, webhook: &WebhookModel) -> Result<String, SecretError> {
+    match keyring.open(&webhook.secret)? {
+        Value::String(secret) => Ok(secret),
+        _ => Err(SecretError::Decrypt),
+    }
+}
+
+/// The events `webhook` is subscribed to.
+pub fn events_of(webhook: &WebhookModel) -> Vec<Event> {
+    webhook
+        .events
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.as_str().and_then(Event::parse))
+        .collect()
+}
+
+async fn subscribers<C: ConnectionTrait>(
+    conn: &C,
+    project_id: i32,
+) -> Result<Vec<WebhookModel>, DbErr> {
+    Webhook::find()
+        .filter(WebhookColumn::ProjectId.eq(project_id))
+        .filter(WebhookColumn::IsActive.eq(true))
+        .all(conn)
+        .await
+}
+
+/// Those of `events` an active webhook of the project is subscribed to, so
+/// callers can skip building payloads nobody receives.
+pub async fn subscribed<C: ConnectionTrait>(
+    conn: &C,
+    project_id: i32,
+    events: &[Event],
+) -> Result<Vec<Event>, DbErr> {
+    if events.is_empty() {
+        return Ok(Vec::new());
+    }
+    let webhooks = subscribers(conn, project_id).await?;
+
+    Ok(events
+        .iter()
+        .copied()
+        .filter(|event| webhooks.iter().any(|w| events_of(w).contains(event)))
+        .collect())
+}
+
+/// Queues a delivery of `event` about `data` to every active webhook of the
+/// project subscribed to it, and returns how many there were.
+pub async fn dispatch<C: ConnectionTrait>(
+    conn: &C,
+    project_id: i32,
+    event: Event,
+    data: &Value,
+) -> Result<usize, DbErr> {
+    let mut count = 0;
+    for webhook in subscribers(conn, project_id).await? {
+        if events_of(&webhook).contains(&event) {
+            queue_delivery(conn, webhook.id, event.as_str(), data.clone(), None).await?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Sends the data of `delivery` again, as a new delivery.
+pub async fn redeliver<C: ConnectionTrait>(conn: &C, delivery: &Model) -> Result<Model, DbErr> {
+    queue_delivery(
+        conn,
+        delivery.webhook_id,
+        &delivery.event,
+        delivery.payload["data"].clone(),
+        Some(delivery.id),
+    )
+    .await
+}
+
+async fn queue_delivery<C: ConnectionTrait>(
+    conn: &C,
+    webhook_id: i32,
+    event: &str,
+    data: Value,
+    redelivery_of: Option<i32>,
+) -> Result<Model, DbErr> {
+    let pid = Uuid::new_v4();
+    let payload = json!({
+        "id": pid.to_string(),
+        "event": event,
+        "created_at": Utc::now().to_rfc3339(),
+        "data": data,
+    });
+
+    let delivery = ActiveModel {
+        pid: Set(pid),
+        webhook_id: Set(webhook_id),
+        event: Set(event.to_string()),
+        payload: Set(payload),
+        status: Set(STATUS_PENDING.to_string()),
+        redelivery_of: Set(redelivery_of),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    jobs::enqueue(
+        conn,
+        &Deliver {
+            delivery_id: delivery.id,
+        },
+    )
+    .await?;
+    Ok(delivery)
+}
+
+/// Reseals every webhook secret under the keyring's active key, and returns
+/// how many were updated. Run alongside resealing import source credentials.
+pub async fn reseal_secrets(
+    conn: &DatabaseConnection,
+    keyring: &Keyring,
+) -> Result<usize, WebhookError> {
+    let mut updated = 0;
+    for webhook in Webhook::find().all(conn).await? {
+        let Some(resealed) = keyring.reseal(&webhook.secret)? else {
+            continue;
+        };
+        let mut active: WebhookActiveModel = webhook.into_active_model();
+        active.secret = Set(resealed);
+        active.update(conn).await?;
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+/// Job that sends one delivery.
+#[derive(Serialize, Deserialize)]
+pub struct Deliver {
+    pub delivery_id: i32,
+}
+
+impl Job for Deliver {
+    const KIND: &'static str = "webhooks.deliver";
+    const MAX_ATTEMPTS: i32 = DELIVERY_ATTEMPTS;
+    const TIMEOUT: Duration = Duration::from_secs(60);
+}
+
+/// What one try of a delivery got.
+struct Attempt {
+    response_status: Option<i32>,
+    response_body: Option<String>,
+    error: Option<String>,
+}
+
+impl Attempt {
+    fn failed(error: impl std::fmt::Display) -> Self {
+        Attempt {
+            response_status: None,
+            response_body: None,
+            error: Some(error.to_string()),
+        }
+    }
+
+    fn succeeded(&self) -> bool {
+        self.response_status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+
+    fn describe(&self) -> String {
+        match (&self.error, self.response_status) {
+            (Some(error), _) => error.clone(),
+            (None, Some(status)) => format!("webhook answered {status}"),
+            (None, None) => "no response".to_string(),
+        }
+    }
+}
+
+/// Sends webhook deliveries for the job worker.
+#[derive(Clone)]
+pub struct Sender {
+    client: reqwest::Client,
+    keyring: Keyring,
+    destinations: Destinations,
+}
+
+impl Sender {
+    /// A sender whose deliveries only reach the addresses `destinations`
+    /// permits.
+    pub fn new(keyring: Keyring, destinations: Destinations) -> Self {
+        let client = net::client_builder(destinations)
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("reeverb-webhooks/", env!("CARGO_PKG_VERSION")))
+            // A redirect could point the signed payload anywhere.
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("failed to build HTTP client");
+
+        Sender {
+            client,
+            keyring,
+            destinations,
+        }
+    }
+
+    /// Tries to send a delivery, and records what happened in its log.
+    pub async fn deliver(&self, conn: &DatabaseConnection, job: Deliver) -> Result<(), JobError> {
+        // Deliveries go with their webhook when it is deleted.
+        let Some(delivery) = Delivery::find_by_id(job.delivery_id).one(conn).await? else {
+            return Ok(());
+        };
+        if delivery.status != STATUS_PENDING {
+            return Ok(());
+        }
+        let Some(webhook) = Webhook::find_by_id(delivery.webhook_id).one(conn).await? else {
+            return Ok(());
+        };
+
+        let started = Instant::now();
+        let attempt = if webhook.is_active {
+            self.send(&webhook, &delivery).await
+        } else {
+            Attempt::failed("webhook is disabled")
+        };
+        let duration_ms = i32::try_from(started.elapsed().as_millis()).unwrap_or(i32::MAX);
+
+        let attempts = delivery.attempts + 1;
+        let give_up = attempts >= DELIVERY_ATTEMPTS || !webhook.is_active;
+        let status = if attempt.succeeded() {
+            STATUS_SUCCEEDED
+        } else if give_up {
+            STATUS_FAILED
+        } else {
+            STATUS_PENDING
+        };
+
+        let mut active: ActiveModel = delivery.into_active_model();
+        active.status = Set(status.to_string());
+        active.attempts = Set(attempts);
+        active.response_status = Set(attempt.response_status);
+        active.response_body = Set(attempt.response_body.clone());
+        active.error = Set(attempt.error.clone());
+        active.duration_ms = Set(Some(duration_ms));
+        active.delivered_at = Set(Some(Utc::now().fixed_offset()));
+        active.update(conn).await?;
+
+        match status {
+            STATUS_SUCCEEDED => Ok(()),
+            STATUS_FAILED => Err(JobError::permanent(attempt.describe())),
+            _ => Err(JobError::new(attempt.describe())),
+        }
+    }
+
+    async fn send(&self, webhook: &WebhookModel, delivery: &Model) -> Attempt {
+        // Host names are checked as they resolve, IP addresses here.
+        if let Err(e) = self.destinations.check(&webhook.url) {
+            return Attempt::failed(e);
+        }
+        let secret = match open_secret(&self.keyring, webhook) {
+            Ok(secret) => secret,
+            Err(e) => return Attempt::failed(format!("could not open webhook secret: {e}")),
+        };
+        let body = serde_json::to_vec(&delivery.payload).expect("JSON values always serialize");
+        let signature = sign(&secret, Utc::now().timestamp(), &body);
+
+        let response = self
+            .client
+            .post(&webhook.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &delivery.event)
+            .header(DELIVERY_HEADER, delivery.pid.to_string())
+            .header(SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await;
+        let mut response = match response {
+            Ok(response) => response,
+            Err(e) => return Attempt::failed(error_chain(&e)),
+        };
+
+        let status = i32::from(response.status().as_u16());
+        let mut body = Vec::new();
+        while body.len() < RESPONSE_BODY_LIMIT {
+            match response.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                _ => break,
+            }
+        }
+        body.truncate(RESPONSE_BODY_LIMIT);
+
+        Attempt {
+            response_status: Some(status),
+            response_body: Some(String::from_utf8_lossy(&body).into_owned()),
+            error: None,
+        }
+    }
+}
+
+/// `e` with its sources, which say why a request could not be sent, such as
+/// the resolver refusing the webhook's host.
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(e) = source {
+        message.push_str(": ");
+        message.push_str(&e.to_string());
+        source = e.source();
+    }
+    message
+}
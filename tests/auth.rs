@@ -84,6 +84,31 @@ async fn register_returns_token_and_user() {
     assert_eq!(body["user"]["name"], "Test User");
 }
 
+#[tokio::test]
+async fn register_rejects_invalid_fields_with_422() {
+    let client = setup().await;
+
+    let res = client
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": "not-an-email",
+            "password": "letmeinplease"
+        }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+    assert_eq!(body["error"]["details"]["email"][0]["code"], "email");
+    assert_eq!(
+        body["error"]["details"]["password"][0]["code"],
+        "password_strength"
+    );
+    assert!(!body.to_string().contains("letmeinplease"));
+}
+
 #[tokio::test]
 async fn register_duplicate_email_returns_conflict() {
     let client = setup().await;
@@ -102,7 +127,7 @@ async fn register_duplicate_email_returns_conflict() {
         .post("/api/v1/auth/register")
         .json(&json!({
             "email": email,
-            "password": "other"
+            "password": "another123"
         }))
         .send()
         .await;
@@ -202,7 +227,7 @@ async fn me_with_valid_token_returns_user() {
 
     let res = client
         .get("/api/v1/auth/me")
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
@@ -0,0 +1,715 @@
+use std::collections::HashMap;
+
+use rapina::auth::{AuthMiddleware, PublicRoutes};
+use rapina::database::DatabaseConfig;
+use rapina::prelude::*;
+use rapina::sea_orm::sea_query::Condition;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+};
+use rapina::testing::TestClient;
+
+use serde_json::{Value, json};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use reeverb::api::v1::auth;
+use reeverb::api::v1::import_sources;
+use reeverb::api::v1::projects;
+use reeverb::api::v1::testimonials;
+use reeverb::connectors::{self, BaseUrls, Connectors, scheduler};
+use reeverb::db::entities::import_source::{self, Entity as ImportSource};
+use reeverb::db::migrations::Migrator;
+use reeverb::secrets::{Keyring, SecretError};
+use reeverb::sentiment::SentimentAnalyzer;
+
+static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
+
+const TRUSTPILOT_KEY: &str = "tp-secret-key-1234";
+
+const OLD_KEY: &str = "k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+const NEW_KEY: &str = "k2:ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=";
+
+/// Seals with `k1`, and can open what the rotation test reseals with `k2`
+/// while other tests run.
+fn keyring() -> Keyring {
+    Keyring::parse(&format!("{OLD_KEY},{NEW_KEY}")).unwrap()
+}
+
+fn database_url() -> String {
+    dotenvy::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for integration tests")
+}
+
+async fn run_migrations_once() {
+    MIGRATIONS
+        .get_or_init(|| async {
+            let config = DatabaseConfig::new(database_url());
+            let conn = config.connect().await.expect("failed to connect");
+
+            use rapina::sea_orm_migration::MigratorTrait;
+            Migrator::up(&conn, None)
+                .await
+                .expect("failed to run migrations");
+        })
+        .await;
+}
+
+// Stand-ins for each platform's API.
+
+fn trustpilot_review(id: &str, created_at: &str) -> Value {
+    json!({
+        "id": id,
+        "stars": 5,
+        "title": "Great",
+        "text": "Great service",
+        "language": "en",
+        "createdAt": created_at,
+        "consumer": { "displayName": "Tina Pilot" }
+    })
+}
+
+/// Two pages, newest first, so that incremental syncs can stop early.
+#[get("/v1/business-units/:id/reviews")]
+async fn trustpilot_reviews(
+    headers: Headers,
+    query: Query<HashMap<String, String>>,
+) -> Result<Json<Value>> {
+    if headers.0.get("apikey").and_then(|v| v.to_str().ok()) != Some(TRUSTPILOT_KEY) {
+        return Err(Error::unauthorized("invalid api key"));
+    }
+
+    let page = query.0.get("page").map(String::as_str);
+    Ok(Json(if page == Some("2") {
+        json!({
+            "reviews": [trustpilot_review("tp-0", "2026-01-01T00:00:00Z")],
+            "links": []
+        })
+    } else {
+        json!({
+            "reviews": [
+                trustpilot_review("tp-2", "2026-01-03T00:00:00Z"),
+                trustpilot_review("tp-1", "2026-01-02T00:00:00Z")
+            ],
+            "links": [{ "rel": "next-page" }]
+        })
+    }))
+}
+
+#[get("/api/v1/survey-responses")]
+async fn g2_reviews() -> Json<Value> {
+    Json(json!({
+        "data": [{
+            "id": "g2-1",
+            "attributes": {
+                "star_rating": 4.5,
+                "title": "Solid tool",
+                "user_name": "Gina Two",
+                "public_url": "https://www.g2.com/survey_responses/g2-1",
+                "submitted_at": "2026-02-03T04:05:06Z",
+                "comment_answers": { "love": { "value": "The reports" } }
+            }
+        }],
+        "links": {}
+    }))
+}
+
+#[get("/v4/accounts/:account/locations/:location/reviews")]
+async fn google_reviews() -> Json<Value> {
+    Json(json!({
+        "reviews": [{
+            "reviewId": "gb-1",
+            "reviewer": { "displayName": "Gus Maps" },
+            "starRating": "FOUR",
+            "comment": "Friendly staff",
+            "createTime": "2026-03-04T05:06:07Z"
+        }]
+    }))
+}
+
+#[post("/v2/api/graphql")]
+async fn product_hunt_graphql() -> Json<Value> {
+    Json(json!({
+        "data": { "post": { "comments": {
+            "edges": [{ "node": {
+                "id": "ph-1",
+                "body": "Congrats on the launch!",
+                "url": "https://www.producthunt.com/posts/acme?comment=ph-1",
+                "createdAt": "2026-04-05T06:07:08Z",
+                "user": { "name": "Pat Hunter", "headline": "Maker" }
+            }}],
+            "pageInfo": { "hasNextPage": false, "endCursor": null }
+        }}}
+    }))
+}
+
+#[get("/2/tweets/search/recent")]
+async fn tweets() -> Json<Value> {
+    Json(json!({
+        "data": [{
+            "id": "tw-1",
+            "text": "Loving @acme",
+            "author_id": "u-1",
+            "created_at": "2026-05-06T07:08:09Z",
+            "lang": "en"
+        }],
+        "includes": { "users": [{ "id": "u-1", "name": "Tom Tweet", "username": "tomtweet" }] },
+        "meta": {}
+    }))
+}
+
+async fn platforms() -> TestClient {
+    let router = Router::new()
+        .get("/v1/business-units/:id/reviews", trustpilot_reviews)
+        .get("/api/v1/survey-responses", g2_reviews)
+        .get(
+            "/v4/accounts/:account/locations/:location/reviews",
+            google_reviews,
+        )
+        .post("/v2/api/graphql", product_hunt_graphql)
+        .get("/2/tweets/search/recent", tweets);
+
+    TestClient::new(Rapina::new().with_introspection(false).router(router)).await
+}
+
+/// The API client, and the platform server its connectors talk to, which
+/// stops when dropped.
+async fn setup() -> (TestClient, TestClient) {
+    run_migrations_once().await;
+
+    let platforms = platforms().await;
+    let base_url = format!("http://{}", platforms.addr());
+    let auth_config = AuthConfig::new("test-secret", 3600);
+
+    let mut public_routes = PublicRoutes::new();
+    for (method, path) in auth::PUBLIC_ROUTES {
+        public_routes.add(method, path);
+    }
+
+    let auth_middleware = AuthMiddleware::with_public_routes(auth_config.clone(), public_routes);
+
+    let router = Router::new()
+        .group("/api/v1/auth", auth::routes())
+        .group("/api/v1/projects", projects::routes())
+        .group("/api/v1/projects", testimonials::project_routes())
+        .group("/api/v1/projects", import_sources::project_routes())
+        .group("/api/v1/import-sources", import_sources::routes());
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(auth_config)
+        .state(SentimentAnalyzer::default())
+        .state(Connectors::new(BaseUrls::all(&base_url)))
+        .state(keyring())
+        .middleware(auth_middleware)
+        .with_database(DatabaseConfig::new(database_url()))
+        .await
+        .expect("failed to connect to test database")
+        .router(router);
+
+    (TestClient::new(app).await, platforms)
+}
+
+fn unique_email() -> String {
+    format!("test-{}@example.com", Uuid::new_v4())
+}
+
+async fn register_and_get_token(client: &TestClient) -> String {
+    let email = unique_email();
+    let res = client
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": email,
+            "password": "password123",
+            "name": "Test User"
+        }))
+        .send()
+        .await;
+
+    let body: Value = res.json();
+    body["token"].as_str().unwrap().to_string()
+}
+
+async fn create_project(client: &TestClient, token: &str) -> String {
+    let slug = format!("project-{}", Uuid::new_v4());
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "name": "Test Project", "slug": slug }))
+        .send()
+        .await;
+
+    let body: Value = res.json();
+    body["id"].as_str().unwrap().to_string()
+}
+
+async fn create_source(
+    client: &TestClient,
+    token: &str,
+    project_id: &str,
+    body: Value,
+) -> rapina::testing::TestResponse {
+    client
+        .post(&format!("/api/v1/projects/{project_id}/import-sources"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&body)
+        .send()
+        .await
+}
+
+async fn sync(client: &TestClient, token: &str, source_id: &str) -> rapina::testing::TestResponse {
+    client
+        .post(&format!("/api/v1/import-sources/{source_id}/sync"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await
+}
+
+async fn get_json(client: &TestClient, token: &str, path: &str) -> Value {
+    let res = client
+        .get(path)
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    res.json()
+}
+
+async fn connect() -> DatabaseConnection {
+    DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect")
+}
+
+/// Runs one scheduler pass against the platform server.
+async fn sync_due(platforms: &TestClient) {
+    let conn = connect().await;
+    let connectors = Connectors::new(BaseUrls::all(&format!("http://{}", platforms.addr())));
+
+    scheduler::sync_due(&conn, &connectors, &keyring())
+        .await
+        .expect("scheduler pass failed");
+}
+
+/// Minutes from now until the RFC 3339 time `at`.
+fn minutes_until(at: &Value) -> i64 {
+    let at = chrono::DateTime::parse_from_rfc3339(at.as_str().unwrap()).unwrap();
+    (at.to_utc() - chrono::Utc::now()).num_minutes()
+}
+
+fn trustpilot(api_key: &str) -> Value {
+    json!({
+        "platform": "trustpilot",
+        "credentials": { "api_key": api_key, "business_unit_id": "bu-1" }
+    })
+}
+
+#[tokio::test]
+async fn create_hides_credentials_and_validates_them() {
+    let (client, _platforms) = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    let res = create_source(&client, &token, &project_id, trustpilot(TRUSTPILOT_KEY)).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body: Value = res.json();
+    assert_eq!(body["platform"], "trustpilot");
+    assert_eq!(body["has_credentials"], true);
+    assert_eq!(body["auto_sync"], false);
+    assert!(body.get("credentials").is_none());
+    assert!(body["last_synced_at"].is_null());
+    let source_id = body["id"].as_str().unwrap().to_string();
+
+    let res = client
+        .put(&format!("/api/v1/import-sources/{source_id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "auto_sync": true, "sync_interval_hours": 6 }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = res.json();
+    assert_eq!(body["auto_sync"], true);
+    assert_eq!(body["sync_interval_hours"], 6);
+
+    let missing_field = json!({
+        "platform": "trustpilot",
+        "credentials": { "api_key": TRUSTPILOT_KEY }
+    });
+    let res = create_source(&client, &token, &project_id, missing_field).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let mut bad_interval = trustpilot(TRUSTPILOT_KEY);
+    bad_interval["sync_interval_hours"] = json!(0);
+    let res = create_source(&client, &token, &project_id, bad_interval).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let res = client
+        .get(&format!("/api/v1/projects/{project_id}/import-sources"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: Value = res.json();
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+    let res = client
+        .delete(&format!("/api/v1/import-sources/{source_id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn sync_imports_reviews_from_each_platform_once() {
+    let (client, _platforms) = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    let sources = [
+        (trustpilot(TRUSTPILOT_KEY), &["tp-2", "tp-1", "tp-0"][..]),
+        (
+            json!({
+                "platform": "g2",
+                "credentials": { "api_token": "g2-token", "product_id": "p-1" }
+            }),
+            &["g2-1"][..],
+        ),
+        (
+            json!({
+                "platform": "google_business",
+                "credentials": {
+                    "access_token": "gb-token",
+                    "account_id": "a-1",
+                    "location_id": "l-1",
+                    "place_id": "place-1"
+                }
+            }),
+            &["gb-1"][..],
+        ),
+        (
+            json!({
+                "platform": "product_hunt",
+                "credentials": { "access_token": "ph-token", "post_slug": "acme" }
+            }),
+            &["ph-1"][..],
+        ),
+        (
+            json!({
+                "platform": "twitter",
+                "credentials": { "bearer_token": "tw-token", "query": "@acme" }
+            }),
+            &["tw-1"][..],
+        ),
+    ];
+
+    for (body, review_ids) in sources {
+        let platform = body["platform"].clone();
+        let res = create_source(&client, &token, &project_id, body).await;
+        let source: Value = res.json();
+        let source_id = source["id"].as_str().unwrap();
+
+        let res = sync(&client, &token, source_id).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: Value = res.json();
+        assert_eq!(body["fetched"], review_ids.len(), "{platform}");
+        assert_eq!(body["created"], review_ids.len(), "{platform}");
+        assert!(body["last_synced_at"].is_string());
+
+        let res = client
+            .get(&format!(
+                "/api/v1/projects/{project_id}/testimonials?source_platform={}",
+                platform.as_str().unwrap()
+            ))
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+        let body: Value = res.json();
+        let data = body["data"].as_array().unwrap();
+        assert_eq!(data.len(), review_ids.len(), "{platform}");
+        for (testimonial, review_id) in data.iter().zip(review_ids) {
+            assert_eq!(testimonial["source"], "import");
+            assert_eq!(testimonial["source_id"], *review_id);
+            assert!(testimonial["source_url"].is_string(), "{platform}");
+        }
+
+        // Syncing again only fetches from the newest review on, and skips
+        // the reviews already imported.
+        let res = sync(&client, &token, source_id).await;
+        let body: Value = res.json();
+        assert_eq!(body["fetched"], 1, "{platform}");
+        assert_eq!(body["created"], 0, "{platform}");
+        assert_eq!(body["skipped"], 1, "{platform}");
+    }
+
+    let res = client
+        .get(&format!("/api/v1/projects/{project_id}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: Value = res.json();
+    let data = body["data"].as_array().unwrap();
+    let trustpilot = data.iter().find(|t| t["source_id"] == "tp-2").unwrap();
+    assert_eq!(trustpilot["author_name"], "Tina Pilot");
+    assert_eq!(trustpilot["rating"], 5);
+    assert_eq!(trustpilot["status"], "pending");
+    let tweet = data.iter().find(|t| t["source_id"] == "tw-1").unwrap();
+    assert_eq!(tweet["source_url"], "https://x.com/tomtweet/status/tw-1");
+    assert!(tweet["rating"].is_null());
+}
+
+#[tokio::test]
+async fn sync_rejected_by_platform_returns_502() {
+    let (client, _platforms) = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    let res = create_source(&client, &token, &project_id, trustpilot("wrong-key")).await;
+    let body: Value = res.json();
+    let source_id = body["id"].as_str().unwrap();
+
+    let res = sync(&client, &token, source_id).await;
+    assert_eq!(res.status(), StatusCode::BAD_GATEWAY);
+    let body: Value = res.json();
+    assert_eq!(body["error"]["code"], "BAD_GATEWAY");
+
+    let res = client
+        .get(&format!("/api/v1/import-sources/{source_id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: Value = res.json();
+    assert!(body["last_synced_at"].is_null());
+}
+
+#[tokio::test]
+async fn import_source_in_another_users_project_returns_403() {
+    let (client, _platforms) = setup().await;
+    let owner = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &owner).await;
+    let other = register_and_get_token(&client).await;
+
+    let res = create_source(&client, &other, &project_id, trustpilot(TRUSTPILOT_KEY)).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = create_source(&client, &owner, &project_id, trustpilot(TRUSTPILOT_KEY)).await;
+    let body: Value = res.json();
+    let source_id = body["id"].as_str().unwrap();
+
+    let res = sync(&client, &other, source_id).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn scheduler_syncs_due_sources_and_records_runs() {
+    let (client, platforms) = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    let mut body = trustpilot(TRUSTPILOT_KEY);
+    body["auto_sync"] = json!(true);
+    body["sync_interval_hours"] = json!(6);
+    let res = create_source(&client, &token, &project_id, body).await;
+    let source: Value = res.json();
+    let source_id = source["id"].as_str().unwrap();
+    let path = format!("/api/v1/import-sources/{source_id}");
+
+    sync_due(&platforms).await;
+
+    let source = get_json(&client, &token, &path).await;
+    assert!(source["last_synced_at"].is_string());
+    assert_eq!(source["consecutive_failures"], 0);
+    let minutes = minutes_until(&source["next_sync_at"]);
+    assert!((5 * 60..=6 * 60).contains(&minutes), "{minutes}");
+
+    let runs = get_json(&client, &token, &format!("{path}/runs")).await;
+    let runs = runs["data"].as_array().unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0]["trigger"], "scheduled");
+    assert_eq!(runs[0]["status"], "succeeded");
+    assert_eq!(runs[0]["created"], 3);
+    assert!(runs[0]["finished_at"].is_string());
+
+    // Not due again until the interval has passed.
+    sync_due(&platforms).await;
+    let runs = get_json(&client, &token, &format!("{path}/runs")).await;
+    assert_eq!(runs["data"].as_array().unwrap().len(), 1);
+
+    let res = sync(&client, &token, source_id).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let runs = get_json(&client, &token, &format!("{path}/runs")).await;
+    let runs = runs["data"].as_array().unwrap();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0]["trigger"], "manual");
+    assert_eq!(runs[0]["fetched"], 1);
+    assert_eq!(runs[0]["skipped"], 1);
+
+    client
+        .delete(&path)
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+}
+
+#[tokio::test]
+async fn scheduler_backs_off_failing_sources() {
+    let (client, platforms) = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    let mut body = trustpilot("wrong-key");
+    body["auto_sync"] = json!(true);
+    let res = create_source(&client, &token, &project_id, body).await;
+    let source: Value = res.json();
+    let source_id = source["id"].as_str().unwrap();
+    let path = format!("/api/v1/import-sources/{source_id}");
+
+    sync_due(&platforms).await;
+
+    let source = get_json(&client, &token, &path).await;
+    assert!(source["last_synced_at"].is_null());
+    assert_eq!(source["consecutive_failures"], 1);
+    let minutes = minutes_until(&source["next_sync_at"]);
+    assert!((14..=15).contains(&minutes), "{minutes}");
+
+    let runs = get_json(&client, &token, &format!("{path}/runs")).await;
+    assert_eq!(runs["data"][0]["status"], "failed");
+    assert!(runs["data"][0]["error"].as_str().unwrap().contains("401"));
+
+    // Backing off, so not retried yet.
+    sync_due(&platforms).await;
+    let runs = get_json(&client, &token, &format!("{path}/runs")).await;
+    assert_eq!(runs["data"].as_array().unwrap().len(), 1);
+
+    // Each failure in a row doubles the wait.
+    let res = sync(&client, &token, source_id).await;
+    assert_eq!(res.status(), StatusCode::BAD_GATEWAY);
+    let source = get_json(&client, &token, &path).await;
+    assert_eq!(source["consecutive_failures"], 2);
+    let minutes = minutes_until(&source["next_sync_at"]);
+    assert!((29..=30).contains(&minutes), "{minutes}");
+
+    // Fixed credentials are tried on the next pass.
+    let res = client
+        .put(&path)
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "credentials": { "api_key": TRUSTPILOT_KEY, "business_unit_id": "bu-1" }
+        }))
+        .send()
+        .await;
+    let source: Value = res.json();
+    assert_eq!(source["consecutive_failures"], 0);
+    assert!(source["next_sync_at"].is_null());
+
+    sync_due(&platforms).await;
+    let runs = get_json(&client, &token, &format!("{path}/runs")).await;
+    assert_eq!(runs["data"][0]["status"], "succeeded");
+
+    client
+        .delete(&path)
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+}
+
+async fn stored_source(id: &str) -> import_source::Model {
+    ImportSource::find()
+        .filter(import_source::Column::Pid.eq(Uuid::parse_str(id).unwrap()))
+        .one(&connect().await)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn credentials_are_stored_sealed_and_reseal_on_rotation() {
+    let (client, _platforms) = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    let res = create_source(&client, &token, &project_id, trustpilot(TRUSTPILOT_KEY)).await;
+    let body: Value = res.json();
+    assert_eq!(
+        body["credential_hints"],
+        json!({ "api_key": "••••1234", "business_unit_id": "••••" })
+    );
+    let source_id = body["id"].as_str().unwrap();
+
+    let stored = stored_source(source_id).await.credentials.unwrap();
+    assert_eq!(stored["enc"], "aes-256-gcm");
+    assert_eq!(stored["kid"], "k1");
+    assert!(!stored.to_string().contains(TRUSTPILOT_KEY));
+    assert!(!stored.to_string().contains("bu-1"));
+
+    let res = sync(&client, &token, source_id).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let rotated = Keyring::parse(&format!("{NEW_KEY},{OLD_KEY}")).unwrap();
+    let updated = connectors::reseal_credentials(&connect().await, &rotated)
+        .await
+        .unwrap();
+    assert!(updated >= 1);
+
+    let resealed = stored_source(source_id).await.credentials.unwrap();
+    assert_eq!(resealed["kid"], "k2");
+    assert_eq!(
+        rotated.open(&resealed).unwrap(),
+        trustpilot(TRUSTPILOT_KEY)["credentials"]
+    );
+    // The old key alone can no longer open it.
+    assert!(Keyring::parse(OLD_KEY).unwrap().open(&resealed).is_err());
+}
+
+#[tokio::test]
+async fn keyring_without_keys_fails_only_on_sealed_values() {
+    let empty = Keyring::parse("").unwrap();
+    assert_eq!(empty.active_key_id(), None);
+
+    let plaintext = json!({ "api_key": "plain" });
+    assert_eq!(empty.open(&plaintext).unwrap(), plaintext);
+    assert!(matches!(empty.seal(&plaintext), Err(SecretError::NoKeys)));
+
+    let sealed = keyring().seal(&plaintext).unwrap();
+    assert!(matches!(empty.open(&sealed), Err(SecretError::NoKeys)));
+    assert!(matches!(empty.reseal(&sealed), Err(SecretError::NoKeys)));
+    assert!(
+        SecretError::NoKeys
+            .to_string()
+            .contains("set CREDENTIALS_KEYS")
+    );
+}
+
+#[tokio::test]
+async fn sync_of_a_claimed_source_returns_409() {
+    let (client, _platforms) = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+    let res = create_source(&client, &token, &project_id, trustpilot(TRUSTPILOT_KEY)).await;
+    let body: Value = res.json();
+    let source_id = body["id"].as_str().unwrap();
+
+    let stored = stored_source(source_id).await;
+    let lease = chrono::Utc::now() + chrono::Duration::minutes(10);
+    assert!(
+        connectors::claim(&connect().await, stored.id, Condition::all())
+            .await
+            .unwrap()
+    );
+    assert!(stored_source(source_id).await.sync_claimed_until.unwrap() > lease);
+
+    let res = sync(&client, &token, source_id).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let body: Value = res.json();
+    assert_eq!(body["error"]["code"], "CONFLICT");
+
+    // Once the claim lapses, the source syncs and releases its own claim.
+    let mut active: import_source::ActiveModel = stored.into();
+    active.sync_claimed_until = Set(Some(
+        (chrono::Utc::now() - chrono::Duration::minutes(1)).into(),
+    ));
+    active.update(&connect().await).await.unwrap();
+
+    let res = sync(&client, &token, source_id).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(stored_source(source_id).await.sync_claimed_until.is_none());
+}
@@ -0,0 +1,337 @@
+use rapina::auth::{AuthMiddleware, PublicRoutes};
+use rapina::database::DatabaseConfig;
+use rapina::prelude::*;
+use rapina::testing::TestClient;
+use serde_json::json;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use reeverb::api::v1::auth;
+use reeverb::api::v1::imports::{self, run::RunImport};
+use reeverb::api::v1::projects;
+use reeverb::api::v1::testimonials;
+use reeverb::db::migrations::Migrator;
+use reeverb::jobs::Worker;
+use reeverb::sentiment::SentimentAnalyzer;
+
+static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
+
+const BOUNDARY: &str = "reeverb-test-boundary";
+
+fn database_url() -> String {
+    dotenvy::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for integration tests")
+}
+
+async fn run_migrations_once() {
+    MIGRATIONS
+        .get_or_init(|| async {
+            let config = DatabaseConfig::new(database_url());
+            let conn = config.connect().await.expect("failed to connect");
+
+            use rapina::sea_orm_migration::MigratorTrait;
+            Migrator::up(&conn, None)
+                .await
+                .expect("failed to run migrations");
+        })
+        .await;
+}
+
+async fn setup() -> TestClient {
+    run_migrations_once().await;
+
+    let auth_config = AuthConfig::new("test-secret", 3600);
+
+    let mut public_routes = PublicRoutes::new();
+    for (method, path) in auth::PUBLIC_ROUTES {
+        public_routes.add(method, path);
+    }
+
+    let auth_middleware = AuthMiddleware::with_public_routes(auth_config.clone(), public_routes);
+
+    let router = Router::new()
+        .group("/api/v1/auth", auth::routes())
+        .group("/api/v1/projects", projects::routes())
+        .group("/api/v1/projects", testimonials::project_routes())
+        .group("/api/v1/projects", imports::project_routes())
+        .group("/api/v1/imports", imports::routes());
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(auth_config)
+        .state(SentimentAnalyzer::default())
+        .middleware(auth_middleware)
+        .with_database(DatabaseConfig::new(database_url()))
+        .await
+        .expect("failed to connect to test database")
+        .router(router);
+
+    TestClient::new(app).await
+}
+
+fn unique_email() -> String {
+    format!("test-{}@example.com", Uuid::new_v4())
+}
+
+async fn register_and_get_token(client: &TestClient) -> String {
+    let email = unique_email();
+    let res = client
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": email,
+            "password": "password123",
+            "name": "Test User"
+        }))
+        .send()
+        .await;
+
+    let body: serde_json::Value = res.json();
+    body["token"].as_str().unwrap().to_string()
+}
+
+async fn create_project(client: &TestClient, token: &str) -> String {
+    let slug = format!("project-{}", Uuid::new_v4());
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "name": "Test Project", "slug": slug }))
+        .send()
+        .await;
+
+    let body: serde_json::Value = res.json();
+    body["id"].as_str().unwrap().to_string()
+}
+
+fn upload(csv: &str, mapping: &serde_json::Value) -> String {
+    format!(
+        "--{BOUNDARY}\r\n\
+         Content-Disposition: form-data; name=\"file\"; filename=\"testimonials.csv\"\r\n\
+         Content-Type: text/csv\r\n\r\n\
+         {csv}\r\n\
+         --{BOUNDARY}\r\n\
+         Content-Disposition: form-data; name=\"mapping\"\r\n\r\n\
+         {mapping}\r\n\
+         --{BOUNDARY}--\r\n"
+    )
+}
+
+async fn post_upload(
+    client: &TestClient,
+    token: &str,
+    path: &str,
+    csv: &str,
+    mapping: &serde_json::Value,
+) -> rapina::testing::TestResponse {
+    client
+        .post(path)
+        .header("Authorization", &format!("Bearer {token}"))
+        .header(
+            "Content-Type",
+            &format!("multipart/form-data; boundary={BOUNDARY}"),
+        )
+        .body(upload(csv, mapping))
+        .send()
+        .await
+}
+
+/// Runs import jobs as the worker would until the import `import_id` is
+/// done, and returns it.
+async fn finish_import(client: &TestClient, token: &str, import_id: &str) -> serde_json::Value {
+    let conn = DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect");
+    let worker =
+        Worker::new(conn).handle(|conn, job: RunImport| async move { job.run(&conn).await });
+
+    for _ in 0..100 {
+        worker.run_until_idle().await.unwrap();
+        let res = client
+            .get(&format!("/api/v1/imports/{import_id}"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+        let body: serde_json::Value = res.json();
+        if body["status"] != "processing" {
+            return body;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("import {import_id} was still processing");
+}
+
+const CSV: &str = "Name,Quote,Stars,Email,Id,Tags\n\
+                   Jane Doe,Great product!,5,jane@example.com,r-1,\"vip, Launch\"\n\
+                   ,No name,4,,r-2,\n\
+                   Bob,Too many stars,9,not-an-email,r-3,\n\
+                   ,,,,,\n\
+                   Sam,Solid,4,,r-4,launch\n";
+
+fn mapping() -> serde_json::Value {
+    json!({
+        "columns": {
+            "Name": "author_name",
+            "Quote": "content",
+            "Stars": "rating",
+            "Email": "author_email",
+            "Id": "source_id"
+        },
+        "tag_column": "Tags"
+    })
+}
+
+#[tokio::test]
+async fn dry_run_reports_row_errors_without_importing() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    let res = post_upload(
+        &client,
+        &token,
+        &format!("/api/v1/projects/{project_id}/imports/dry-run"),
+        CSV,
+        &mapping(),
+    )
+    .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["total_rows"], 5);
+    assert_eq!(body["valid_count"], 2);
+    assert_eq!(body["skipped_count"], 1);
+    assert_eq!(body["failed_count"], 2);
+
+    let errors = body["errors"].as_array().unwrap();
+    assert_eq!(errors[0]["line"], 3);
+    assert_eq!(errors[0]["errors"]["author_name"][0]["code"], "length");
+    assert_eq!(errors[1]["line"], 4);
+    assert_eq!(errors[1]["errors"]["rating"][0]["code"], "range");
+    assert_eq!(errors[1]["errors"]["author_email"][0]["code"], "email");
+
+    let res = client
+        .get(&format!("/api/v1/projects/{project_id}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert!(body["data"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn import_creates_testimonials_and_tracks_job() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+    let path = format!("/api/v1/projects/{project_id}/imports");
+
+    let res = post_upload(&client, &token, &path, CSV, &mapping()).await;
+
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "processing");
+    assert_eq!(body["created_count"], 0);
+    assert_eq!(body["failed_count"], 2);
+    assert!(body["completed_at"].is_null());
+    let import_id = body["id"].as_str().unwrap().to_string();
+
+    let body = finish_import(&client, &token, &import_id).await;
+    assert_eq!(body["status"], "completed");
+    assert_eq!(body["filename"], "testimonials.csv");
+    assert_eq!(body["created_count"], 2);
+    assert_eq!(body["skipped_count"], 1);
+    assert_eq!(body["failed_count"], 2);
+    assert_eq!(body["errors"].as_array().unwrap().len(), 2);
+    assert!(body["completed_at"].is_string());
+    assert_eq!(body["mapping"]["tag_column"], "Tags");
+
+    let res = client
+        .get(&format!("/api/v1/projects/{project_id}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    let data = body["data"].as_array().unwrap();
+    assert_eq!(data.len(), 2);
+    let jane = data
+        .iter()
+        .find(|t| t["author_name"] == "Jane Doe")
+        .unwrap();
+    assert_eq!(jane["source"], "csv");
+    assert_eq!(jane["source_id"], "r-1");
+    assert_eq!(jane["tags"].as_array().unwrap().len(), 2);
+    // "launch" reuses the tag created for "Launch".
+    let sam = data.iter().find(|t| t["author_name"] == "Sam").unwrap();
+    assert_eq!(sam["tags"].as_array().unwrap().len(), 1);
+    assert_eq!(sam["tags"][0]["name"], "Launch");
+
+    // Importing the same file again skips the rows it already created.
+    let res = post_upload(&client, &token, &path, CSV, &mapping()).await;
+    let body: serde_json::Value = res.json();
+    let body = finish_import(&client, &token, body["id"].as_str().unwrap()).await;
+    assert_eq!(body["created_count"], 0);
+    assert_eq!(body["skipped_count"], 3);
+
+    let res = client
+        .get(&path)
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn invalid_mapping_returns_400() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+    let path = format!("/api/v1/projects/{project_id}/imports/dry-run");
+
+    let unknown_field = json!({ "columns": { "Name": "author_name", "Quote": "body" } });
+    let res = post_upload(&client, &token, &path, CSV, &unknown_field).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let missing_column = json!({ "columns": { "Author": "author_name" } });
+    let res = post_upload(&client, &token, &path, CSV, &missing_column).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let no_name = json!({ "columns": { "Quote": "content" } });
+    let res = post_upload(&client, &token, &path, CSV, &no_name).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn import_requires_multipart_body() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_id}/imports"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "file": CSV }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn import_in_another_users_project_returns_403() {
+    let client = setup().await;
+    let owner = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &owner).await;
+    let other = register_and_get_token(&client).await;
+
+    let res = post_upload(
+        &client,
+        &other,
+        &format!("/api/v1/projects/{project_id}/imports"),
+        CSV,
+        &mapping(),
+    )
+    .await;
+
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,521 @@
+use rapina::auth::{AuthMiddleware, PublicRoutes};
+use rapina::database::DatabaseConfig;
+use rapina::prelude::*;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+};
+use rapina::testing::TestClient;
+use serde_json::{Value, json};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use reeverb::api::v1::auth;
+use reeverb::api::v1::forms::{self, throttle::SubmissionThrottle};
+use reeverb::api::v1::projects;
+use reeverb::api::v1::testimonials;
+use reeverb::db::entities::form::{
+    ActiveModel as FormActiveModel, Column as FormColumn, Entity as Form,
+};
+use reeverb::db::entities::job::{Column as JobColumn, Entity as JobEntity};
+use reeverb::db::entities::project::{Column as ProjectColumn, Entity as Project};
+use reeverb::db::entities::testimonial::{Column as TestimonialColumn, Entity as Testimonial};
+use reeverb::db::migrations::Migrator;
+use reeverb::incentives::SendThankYou;
+use reeverb::jobs::Job;
+use reeverb::mail::{Capture, Mailer};
+use reeverb::net::Destinations;
+use reeverb::notifications::Notifier;
+use reeverb::sentiment::SentimentAnalyzer;
+use reeverb::storage::MediaStorage;
+use reeverb::storage::local::LocalStorage;
+
+static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
+
+fn database_url() -> String {
+    dotenvy::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for integration tests")
+}
+
+async fn run_migrations_once() {
+    MIGRATIONS
+        .get_or_init(|| async {
+            let config = DatabaseConfig::new(database_url());
+            let conn = config.connect().await.expect("failed to connect");
+
+            use rapina::sea_orm_migration::MigratorTrait;
+            Migrator::up(&conn, None)
+                .await
+                .expect("failed to run migrations");
+        })
+        .await;
+}
+
+async fn setup() -> TestClient {
+    run_migrations_once().await;
+
+    let auth_config = AuthConfig::new("test-secret", 3600);
+
+    let mut public_routes = PublicRoutes::new();
+    for (method, path) in auth::PUBLIC_ROUTES.iter().chain(forms::PUBLIC_ROUTES) {
+        public_routes.add(method, path);
+    }
+
+    let auth_middleware = AuthMiddleware::with_public_routes(auth_config.clone(), public_routes);
+
+    let router = Router::new()
+        .group("/api/v1/auth", auth::routes())
+        .group("/api/v1/projects", projects::routes())
+        .group("/api/v1/projects", testimonials::project_routes())
+        .group("/api/v1/testimonials", testimonials::routes())
+        .group("/api/v1/forms", forms::routes());
+
+    let storage = MediaStorage::new(
+        LocalStorage::new(std::env::temp_dir().join("reeverb-test-media")),
+        "/api/v1/media",
+    );
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(auth_config)
+        .state(SentimentAnalyzer::default())
+        .state(SubmissionThrottle::default())
+        .state(storage)
+        .state(Destinations::Public)
+        .middleware(auth_middleware)
+        .with_database(DatabaseConfig::new(database_url()))
+        .await
+        .expect("failed to connect to test database")
+        .router(router);
+
+    TestClient::new(app).await
+}
+
+async fn connect() -> DatabaseConnection {
+    DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect")
+}
+
+/// A notifier whose emails end up in the returned outbox.
+fn notifier() -> (Notifier, Capture) {
+    let capture = Capture::new();
+    let mailer = Mailer::new(capture.clone(), "Reeverb <notifications@example.com>");
+    (Notifier::new(mailer, "https://app.example.com"), capture)
+}
+
+async fn register(client: &TestClient) -> String {
+    let res = client
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": format!("test-{}@example.com", Uuid::new_v4()),
+            "password": "password123",
+            "name": "Test User"
+        }))
+        .send()
+        .await;
+
+    let body: Value = res.json();
+    body["token"].as_str().unwrap().to_string()
+}
+
+async fn create_project(client: &TestClient, token: &str) -> String {
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "name": "Acme",
+            "slug": format!("project-{}", Uuid::new_v4()),
+            "website_url": "https://acme.example.com"
+        }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let body: Value = res.json();
+    body["id"].as_str().unwrap().to_string()
+}
+
+/// Creates a form of the project with `incentive` as its incentive, and
+/// returns its public id.
+async fn create_form(
+    conn: &DatabaseConnection,
+    project_pid: &str,
+    incentive: Option<&str>,
+    share: bool,
+) -> String {
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(Uuid::parse_str(project_pid).unwrap()))
+        .one(conn)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let form = FormActiveModel {
+        pid: Set(Uuid::new_v4()),
+        project_id: Set(project.id),
+        name: Set("Feedback".to_string()),
+        slug: Set(format!("form-{}", Uuid::new_v4())),
+        accent_color: Set("#ff5500".to_string()),
+        thank_you_cta_text: Set(Some("Visit our store".to_string())),
+        thank_you_cta_url: Set(Some("https://acme.example.com/store".to_string())),
+        incentive_enabled: Set(incentive.is_some()),
+        incentive_description: Set(incentive.map(str::to_string)),
+        share_enabled: Set(share),
+        share_message: Set(Some("Acme rocks & rolls".to_string())),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+    .unwrap();
+
+    form.pid.to_string()
+}
+
+/// Submits a testimonial through the form, as a visitor, and returns its
+/// public and internal ids.
+async fn submit(client: &TestClient, form_pid: &str, email: Option<&str>) -> (String, i32) {
+    let form = Form::find()
+        .filter(FormColumn::Pid.eq(Uuid::parse_str(form_pid).unwrap()))
+        .one(&connect().await)
+        .await
+        .unwrap()
+        .unwrap();
+    let res = client
+        .post(&format!("/api/v1/forms/{}/submissions", form.slug))
+        .json(&json!({
+            "author_name": "Jane Doe",
+            "author_email": email,
+            "content": "Great!"
+        }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let body: Value = res.json();
+    let pid = body["id"].as_str().unwrap().to_string();
+    testimonial_id(&pid).await
+}
+
+async fn testimonial_id(pid: &str) -> (String, i32) {
+    let id = Testimonial::find()
+        .filter(TestimonialColumn::Pid.eq(Uuid::parse_str(pid).unwrap()))
+        .one(&connect().await)
+        .await
+        .unwrap()
+        .unwrap()
+        .id;
+    (pid.to_string(), id)
+}
+
+async fn moderate(client: &TestClient, token: &str, testimonial_pid: &str, action: &str) {
+    let res = client
+        .post(&format!("/api/v1/testimonials/{testimonial_pid}/{action}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+async fn add_codes(client: &TestClient, token: &str, form_pid: &str, codes: Value) -> Value {
+    let res = client
+        .post(&format!("/api/v1/forms/{form_pid}/incentive-codes"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "codes": codes }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    res.json()
+}
+
+/// Whether a thank-you for the testimonial was queued.
+async fn queued(conn: &DatabaseConnection, testimonial_id: i32) -> bool {
+    JobEntity::find()
+        .filter(JobColumn::Kind.eq(SendThankYou::KIND))
+        .filter(JobColumn::Payload.eq(json!({ "testimonial_id": testimonial_id })))
+        .one(conn)
+        .await
+        .unwrap()
+        .is_some()
+}
+
+fn email() -> String {
+    format!("author-{}@example.com", Uuid::new_v4())
+}
+
+#[tokio::test]
+async fn approved_submissions_are_thanked_with_a_code_once() {
+    let client = setup().await;
+    let conn = connect().await;
+    let (notifier, outbox) = notifier();
+    let token = register(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let form_pid = create_form(
+        &conn,
+        &project_pid,
+        Some("Here is 10% off your next order."),
+        true,
+    )
+    .await;
+
+    let body = add_codes(
+        &client,
+        &token,
+        &form_pid,
+        json!(["SAVE-1", "SAVE-2", " SAVE-1 ", ""]),
+    )
+    .await;
+    assert_eq!(body["added"], 2);
+    assert_eq!(body["skipped"], 2);
+    assert_eq!(body["available"], 2);
+    assert_eq!(body["issued"], 0);
+
+    let author = email();
+    let (pid, id) = submit(&client, &form_pid, Some(&author)).await;
+    assert!(!queued(&conn, id).await);
+    moderate(&client, &token, &pid, "approve").await;
+    assert!(queued(&conn, id).await);
+
+    notifier
+        .send_thank_you(&conn, SendThankYou { testimonial_id: id })
+        .await
+        .unwrap();
+
+    let sent = outbox.take_for(&author);
+    assert_eq!(sent.len(), 1);
+    let message = &sent[0];
+    assert_eq!(message.subject, "Thank you from Acme");
+    assert!(message.text.contains("Thank you!"));
+    assert!(message.text.contains("Here is 10% off your next order."));
+    assert!(message.text.contains("SAVE-1"));
+    assert!(message.html.contains("#ff5500"));
+    assert!(
+        message
+            .text
+            .contains("https://twitter.com/intent/tweet?text=Acme+rocks+%26+rolls&url=https%3A%2F%2Facme.example.com")
+    );
+    assert!(
+        message
+            .text
+            .contains("https://www.linkedin.com/feed/?shareActive=true")
+    );
+    assert!(
+        message
+            .text
+            .contains("https://www.facebook.com/sharer/sharer.php?u=")
+    );
+    assert!(
+        message
+            .text
+            .contains("Visit our store: https://acme.example.com/store")
+    );
+
+    // Running the job again, or approving again, sends nothing more.
+    notifier
+        .send_thank_you(&conn, SendThankYou { testimonial_id: id })
+        .await
+        .unwrap();
+    moderate(&client, &token, &pid, "reopen").await;
+    moderate(&client, &token, &pid, "approve").await;
+    notifier
+        .send_thank_you(&conn, SendThankYou { testimonial_id: id })
+        .await
+        .unwrap();
+    assert!(outbox.take_for(&author).is_empty());
+
+    let res = client
+        .get(&format!("/api/v1/forms/{form_pid}/incentive-codes"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = res.json();
+    assert_eq!(body["available"], 1);
+    assert_eq!(body["issued"], 1);
+
+    let res = client
+        .get(&format!("/api/v1/forms/{form_pid}/thank-you-emails"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = res.json();
+    let data = body["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0]["testimonial_id"], pid);
+    assert_eq!(data[0]["email"], author);
+    assert_eq!(data[0]["code"], "SAVE-1");
+    assert_eq!(data[0]["incentive"], "Here is 10% off your next order.");
+    assert!(data[0]["sent_at"].is_string());
+}
+
+#[tokio::test]
+async fn an_empty_pool_holds_thank_yous_back_until_codes_are_added() {
+    let client = setup().await;
+    let conn = connect().await;
+    let (notifier, outbox) = notifier();
+    let token = register(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let form_pid = create_form(&conn, &project_pid, Some("A free month."), false).await;
+    add_codes(&client, &token, &form_pid, json!(["ONLY-ONE"])).await;
+
+    let (first_author, second_author) = (email(), email());
+    let (first, first_id) = submit(&client, &form_pid, Some(&first_author)).await;
+    let (second, second_id) = submit(&client, &form_pid, Some(&second_author)).await;
+    moderate(&client, &token, &first, "approve").await;
+    moderate(&client, &token, &second, "approve").await;
+
+    notifier
+        .send_thank_you(
+            &conn,
+            SendThankYou {
+                testimonial_id: first_id,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(outbox.take_for(&first_author)[0].text.contains("ONLY-ONE"));
+
+    let result = notifier
+        .send_thank_you(
+            &conn,
+            SendThankYou {
+                testimonial_id: second_id,
+            },
+        )
+        .await;
+    assert!(result.is_err());
+    assert!(outbox.take_for(&second_author).is_empty());
+
+    add_codes(&client, &token, &form_pid, json!(["ONLY-ONE", "ANOTHER"])).await;
+    notifier
+        .send_thank_you(
+            &conn,
+            SendThankYou {
+                testimonial_id: second_id,
+            },
+        )
+        .await
+        .unwrap();
+    let sent = outbox.take_for(&second_author);
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].text.contains("ANOTHER"));
+    assert!(!sent[0].text.contains("Share on"));
+}
+
+#[tokio::test]
+async fn only_forms_with_something_to_offer_thank_submitters() {
+    let client = setup().await;
+    let conn = connect().await;
+    let (notifier, outbox) = notifier();
+    let token = register(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    // An incentive without a pool is described, with no code.
+    let form_pid = create_form(&conn, &project_pid, Some("A sticker pack."), false).await;
+    let author = email();
+    let (pid, id) = submit(&client, &form_pid, Some(&author)).await;
+    moderate(&client, &token, &pid, "approve").await;
+    notifier
+        .send_thank_you(&conn, SendThankYou { testimonial_id: id })
+        .await
+        .unwrap();
+    let sent = outbox.take_for(&author);
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].text.contains("A sticker pack."));
+
+    let plain_pid = create_form(&conn, &project_pid, None, false).await;
+    let author = email();
+    let (pid, id) = submit(&client, &plain_pid, Some(&author)).await;
+    moderate(&client, &token, &pid, "approve").await;
+    notifier
+        .send_thank_you(&conn, SendThankYou { testimonial_id: id })
+        .await
+        .unwrap();
+    assert!(outbox.take_for(&author).is_empty());
+
+    // Submissions without an email are not thanked at all.
+    let (pid, id) = submit(&client, &form_pid, None).await;
+    moderate(&client, &token, &pid, "approve").await;
+    assert!(!queued(&conn, id).await);
+}
+
+#[tokio::test]
+async fn only_testimonials_submitted_through_the_form_are_thanked() {
+    let client = setup().await;
+    let conn = connect().await;
+    let token = register(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let form_pid = create_form(&conn, &project_pid, Some("A free month."), false).await;
+    add_codes(&client, &token, &form_pid, json!(["FREE"])).await;
+
+    // Naming a form as the source does not make a testimonial a submission,
+    // so it cannot draw from the form's pool.
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "author_name": "Jane Doe",
+            "author_email": email(),
+            "content": "Great!",
+            "source": "form",
+            "source_id": form_pid
+        }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body: Value = res.json();
+    let (pid, id) = testimonial_id(body["id"].as_str().unwrap()).await;
+    moderate(&client, &token, &pid, "approve").await;
+    assert!(!queued(&conn, id).await);
+}
+
+#[tokio::test]
+async fn incentive_pools_belong_to_the_forms_owner() {
+    let client = setup().await;
+    let conn = connect().await;
+    let token = register(&client).await;
+    let other = register(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let form_pid = create_form(&conn, &project_pid, Some("A discount."), false).await;
+    add_codes(&client, &token, &form_pid, json!(["A", "B"])).await;
+
+    for path in ["incentive-codes", "thank-you-emails"] {
+        let res = client
+            .get(&format!("/api/v1/forms/{form_pid}/{path}"))
+            .header("Authorization", &format!("Bearer {other}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+
+    let res = client
+        .get(&format!("/api/v1/forms/{}/incentive-codes", Uuid::new_v4()))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let res = client
+        .post(&format!("/api/v1/forms/{form_pid}/incentive-codes"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "codes": ["X".repeat(256)] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let res = client
+        .delete(&format!("/api/v1/forms/{form_pid}/incentive-codes"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let res = client
+        .get(&format!("/api/v1/forms/{form_pid}/incentive-codes"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: Value = res.json();
+    assert_eq!(body["available"], 0);
+}
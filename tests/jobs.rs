@@ -0,0 +1,665 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use rapina::auth::{AuthMiddleware, PublicRoutes};
+use rapina::database::DatabaseConfig;
+use rapina::prelude::*;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+};
+use rapina::testing::TestClient;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use reeverb::api::v1::admin::{self, access::Admins};
+use reeverb::api::v1::auth;
+use reeverb::db::entities::job::{ActiveModel, Column, Entity as JobEntity, Model};
+use reeverb::db::migrations::Migrator;
+use reeverb::jobs::{self, Job, JobError, Worker};
+
+static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
+
+fn database_url() -> String {
+    dotenvy::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for integration tests")
+}
+
+async fn run_migrations_once() {
+    MIGRATIONS
+        .get_or_init(|| async {
+            let config = DatabaseConfig::new(database_url());
+            let conn = config.connect().await.expect("failed to connect");
+
+            use rapina::sea_orm_migration::MigratorTrait;
+            Migrator::up(&conn, None)
+                .await
+                .expect("failed to run migrations");
+        })
+        .await;
+}
+
+async fn connect() -> DatabaseConnection {
+    run_migrations_once().await;
+    DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect")
+}
+
+async fn reload(conn: &DatabaseConnection, job: &Model) -> Model {
+    JobEntity::find_by_id(job.id)
+        .one(conn)
+        .await
+        .unwrap()
+        .expect("job is gone")
+}
+
+/// Makes a queued job due now, as if its backoff had passed.
+async fn make_due(conn: &DatabaseConnection, job: &Model) {
+    let mut active: ActiveModel = reload(conn, job).await.into();
+    active.run_at = Set(Utc::now().fixed_offset());
+    active.update(conn).await.unwrap();
+}
+
+// Each test has job kinds of its own, so tests running at once do not run
+// each other's jobs.
+
+#[derive(Serialize, Deserialize)]
+struct Greet {
+    id: Uuid,
+}
+
+impl Job for Greet {
+    const KIND: &'static str = "test.greet";
+}
+
+#[tokio::test]
+async fn each_job_runs_once_across_workers() {
+    let conn = connect().await;
+    let ran = Arc::new(Mutex::new(Vec::new()));
+    let worker = |ran: Arc<Mutex<Vec<Uuid>>>| {
+        Worker::new(conn.clone()).handle(move |_, job: Greet| {
+            let ran = ran.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                ran.lock().unwrap().push(job.id);
+                Ok(())
+            }
+        })
+    };
+    let (first, second) = (worker(ran.clone()), worker(ran.clone()));
+
+    let mut queued = Vec::new();
+    for _ in 0..6 {
+        let job = Greet { id: Uuid::new_v4() };
+        queued.push((job.id, jobs::enqueue(&conn, &job).await.unwrap()));
+    }
+    let (a, b) = tokio::join!(first.run_until_idle(), second.run_until_idle());
+    a.unwrap();
+    b.unwrap();
+
+    let ran = ran.lock().unwrap().clone();
+    for (id, job) in &queued {
+        assert_eq!(ran.iter().filter(|r| *r == id).count(), 1);
+
+        let job = reload(&conn, job).await;
+        assert_eq!(job.status, jobs::STATUS_SUCCEEDED);
+        assert_eq!(job.attempts, 1);
+        assert!(job.finished_at.is_some());
+        assert!(job.locked_until.is_none());
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Flaky;
+
+impl Job for Flaky {
+    const KIND: &'static str = "test.flaky";
+    const MAX_ATTEMPTS: i32 = 2;
+}
+
+#[tokio::test]
+async fn failed_job_is_retried_with_backoff_then_dead() {
+    let conn = connect().await;
+    let worker = Worker::new(conn.clone())
+        .handle(|_, _: Flaky| async { Err(JobError::new("upstream unavailable")) });
+    let job = jobs::enqueue(&conn, &Flaky).await.unwrap();
+
+    worker.run_until_idle().await.unwrap();
+    let retried = reload(&conn, &job).await;
+    assert_eq!(retried.status, jobs::STATUS_QUEUED);
+    assert_eq!(retried.attempts, 1);
+    assert_eq!(retried.last_error.as_deref(), Some("upstream unavailable"));
+    assert!(retried.run_at > Utc::now().fixed_offset() + chrono::Duration::seconds(5));
+
+    // Not due until the backoff has passed.
+    worker.run_until_idle().await.unwrap();
+    assert_eq!(reload(&conn, &job).await.attempts, 1);
+
+    make_due(&conn, &job).await;
+    worker.run_until_idle().await.unwrap();
+    let dead = reload(&conn, &job).await;
+    assert_eq!(dead.status, jobs::STATUS_DEAD);
+    assert_eq!(dead.attempts, 2);
+    assert!(dead.finished_at.is_some());
+}
+
+#[derive(Serialize, Deserialize)]
+struct Recovering;
+
+impl Job for Recovering {
+    const KIND: &'static str = "test.recovering";
+}
+
+#[tokio::test]
+async fn job_that_failed_succeeds_on_retry() {
+    let conn = connect().await;
+    let tries = Arc::new(AtomicUsize::new(0));
+    let worker = Worker::new(conn.clone()).handle({
+        let tries = tries.clone();
+        move |_, _: Recovering| {
+            let first = tries.fetch_add(1, Ordering::SeqCst) == 0;
+            async move {
+                if first {
+                    return Err(JobError::new("timed out"));
+                }
+                Ok(())
+            }
+        }
+    });
+    let job = jobs::enqueue(&conn, &Recovering).await.unwrap();
+
+    worker.run_until_idle().await.unwrap();
+    make_due(&conn, &job).await;
+    worker.run_until_idle().await.unwrap();
+
+    let job = reload(&conn, &job).await;
+    assert_eq!(job.status, jobs::STATUS_SUCCEEDED);
+    assert_eq!(job.attempts, 2);
+    assert!(job.last_error.is_none());
+}
+
+#[derive(Serialize, Deserialize)]
+struct Strict {
+    name: String,
+}
+
+impl Job for Strict {
+    const KIND: &'static str = "test.strict";
+}
+
+#[tokio::test]
+async fn permanent_failures_are_dead_straight_away() {
+    let conn = connect().await;
+    let worker = Worker::new(conn.clone()).handle(|_, job: Strict| async move {
+        Err(JobError::permanent(format!("no {}", job.name)))
+    });
+
+    let refused = jobs::enqueue(
+        &conn,
+        &Strict {
+            name: "such user".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    let mut unreadable: ActiveModel = jobs::enqueue(
+        &conn,
+        &Strict {
+            name: "payload".to_string(),
+        },
+    )
+    .await
+    .unwrap()
+    .into();
+    unreadable.payload = Set(json!({ "nom": 1 }));
+    let unreadable = unreadable.update(&conn).await.unwrap();
+
+    worker.run_until_idle().await.unwrap();
+
+    let refused = reload(&conn, &refused).await;
+    assert_eq!(refused.status, jobs::STATUS_DEAD);
+    assert_eq!(refused.attempts, 1);
+    assert_eq!(refused.last_error.as_deref(), Some("no such user"));
+
+    let unreadable = reload(&conn, &unreadable).await;
+    assert_eq!(unreadable.status, jobs::STATUS_DEAD);
+    assert!(
+        unreadable
+            .last_error
+            .unwrap()
+            .starts_with("invalid test.strict payload")
+    );
+}
+
+#[derive(Serialize, Deserialize)]
+struct Hang;
+
+impl Job for Hang {
+    const KIND: &'static str = "test.hang";
+    const MAX_ATTEMPTS: i32 = 1;
+    const TIMEOUT: Duration = Duration::from_millis(50);
+}
+
+#[derive(Serialize, Deserialize)]
+struct Crash;
+
+impl Job for Crash {
+    const KIND: &'static str = "test.crash";
+    const MAX_ATTEMPTS: i32 = 1;
+}
+
+#[tokio::test]
+async fn jobs_that_time_out_or_panic_fail() {
+    let conn = connect().await;
+    let worker = Worker::new(conn.clone())
+        .handle(|_, _: Hang| async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .handle(|_, _: Crash| async { panic!("handler bug") });
+    let hang = jobs::enqueue(&conn, &Hang).await.unwrap();
+    let crash = jobs::enqueue(&conn, &Crash).await.unwrap();
+
+    worker.run_until_idle().await.unwrap();
+
+    let hang = reload(&conn, &hang).await;
+    assert_eq!(hang.status, jobs::STATUS_DEAD);
+    assert!(hang.last_error.unwrap().contains("timed out"));
+
+    let crash = reload(&conn, &crash).await;
+    assert_eq!(crash.status, jobs::STATUS_DEAD);
+    assert!(crash.last_error.unwrap().contains("panicked"));
+}
+
+#[derive(Serialize, Deserialize)]
+struct Narrow;
+
+impl Job for Narrow {
+    const KIND: &'static str = "test.narrow";
+}
+
+#[derive(Serialize, Deserialize)]
+struct Wide;
+
+impl Job for Wide {
+    const KIND: &'static str = "test.wide";
+}
+
+/// Tracks how many handlers run at once, and the most there ever were.
+#[derive(Clone, Default)]
+struct Gauge {
+    current: Arc<AtomicUsize>,
+    max: Arc<AtomicUsize>,
+}
+
+impl Gauge {
+    async fn hold(&self) -> std::result::Result<(), JobError> {
+        let now = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.current.fetch_sub(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn concurrency_limits_are_respected() {
+    let conn = connect().await;
+    let (narrow, wide) = (Gauge::default(), Gauge::default());
+    let worker = Worker::new(conn.clone())
+        .concurrency(3)
+        .handle({
+            let narrow = narrow.clone();
+            move |_, _: Narrow| {
+                let narrow = narrow.clone();
+                async move { narrow.hold().await }
+            }
+        })
+        .limit::<Narrow>(1)
+        .handle({
+            let wide = wide.clone();
+            move |_, _: Wide| {
+                let wide = wide.clone();
+                async move { wide.hold().await }
+            }
+        });
+
+    for _ in 0..4 {
+        jobs::enqueue(&conn, &Narrow).await.unwrap();
+        jobs::enqueue(&conn, &Wide).await.unwrap();
+    }
+    worker.run_until_idle().await.unwrap();
+
+    assert_eq!(narrow.max.load(Ordering::SeqCst), 1);
+    // The narrow job takes one of the three slots.
+    assert_eq!(wide.max.load(Ordering::SeqCst), 2);
+}
+
+#[derive(Serialize, Deserialize)]
+struct Tick {
+    n: i32,
+}
+
+impl Job for Tick {
+    const KIND: &'static str = "test.tick";
+}
+
+#[tokio::test]
+async fn scheduled_job_runs_again_after_its_interval() {
+    let conn = connect().await;
+    JobEntity::delete_many()
+        .filter(Column::Schedule.eq(Tick::KIND))
+        .exec(&conn)
+        .await
+        .unwrap();
+
+    let ran = Arc::new(Mutex::new(Vec::new()));
+    let worker = |n: i32| {
+        let ran = ran.clone();
+        Worker::new(conn.clone())
+            .handle(move |_, tick: Tick| {
+                let ran = ran.clone();
+                async move {
+                    ran.lock().unwrap().push(tick.n);
+                    // Failing does not take a recurring job off its schedule.
+                    Err(JobError::permanent("tick failed"))
+                }
+            })
+            .every(Duration::from_secs(3600), Tick { n })
+    };
+
+    // Every instance schedules the same job.
+    let first = worker(1);
+    first.schedule().await.unwrap();
+    first.schedule().await.unwrap();
+    let second = worker(2);
+    second.schedule().await.unwrap();
+
+    let scheduled = JobEntity::find()
+        .filter(Column::Schedule.eq(Tick::KIND))
+        .all(&conn)
+        .await
+        .unwrap();
+    assert_eq!(scheduled.len(), 1);
+    assert_eq!(scheduled[0].payload, json!({ "n": 2 }));
+
+    second.run_until_idle().await.unwrap();
+    first.run_until_idle().await.unwrap();
+    assert_eq!(*ran.lock().unwrap(), vec![2]);
+
+    let job = reload(&conn, &scheduled[0]).await;
+    assert_eq!(job.status, jobs::STATUS_QUEUED);
+    assert_eq!(job.attempts, 0);
+    assert_eq!(job.last_error.as_deref(), Some("tick failed"));
+    let next = job.run_at - Utc::now().fixed_offset();
+    assert!(next > chrono::Duration::minutes(59) && next <= chrono::Duration::minutes(60));
+}
+
+#[derive(Serialize, Deserialize)]
+struct Orphan {
+    id: Uuid,
+}
+
+impl Job for Orphan {
+    const KIND: &'static str = "test.orphan";
+}
+
+async fn claim_for(conn: &DatabaseConnection, job: Model, lease: chrono::Duration) -> Model {
+    let mut active: ActiveModel = job.into();
+    active.status = Set(jobs::STATUS_RUNNING.to_string());
+    active.attempts = Set(1);
+    active.locked_by = Set(Some("gone".to_string()));
+    active.locked_until = Set(Some(Utc::now().fixed_offset() + lease));
+    active.update(conn).await.unwrap()
+}
+
+#[tokio::test]
+async fn job_of_a_dead_worker_is_claimed_again_once_its_lease_runs_out() {
+    let conn = connect().await;
+    let ran = Arc::new(Mutex::new(Vec::new()));
+    let worker = Worker::new(conn.clone()).handle({
+        let ran = ran.clone();
+        move |_, job: Orphan| {
+            ran.lock().unwrap().push(job.id);
+            async { Ok(()) }
+        }
+    });
+
+    let (expired, held) = (Uuid::new_v4(), Uuid::new_v4());
+    let orphaned = jobs::enqueue(&conn, &Orphan { id: expired }).await.unwrap();
+    let orphaned = claim_for(&conn, orphaned, chrono::Duration::minutes(-1)).await;
+    let running = jobs::enqueue(&conn, &Orphan { id: held }).await.unwrap();
+    let running = claim_for(&conn, running, chrono::Duration::minutes(10)).await;
+
+    worker.run_until_idle().await.unwrap();
+
+    let ran = ran.lock().unwrap().clone();
+    assert!(ran.contains(&expired));
+    assert!(!ran.contains(&held));
+
+    let orphaned = reload(&conn, &orphaned).await;
+    assert_eq!(orphaned.status, jobs::STATUS_SUCCEEDED);
+    assert_eq!(orphaned.attempts, 2);
+    assert_eq!(reload(&conn, &running).await.status, jobs::STATUS_RUNNING);
+
+    JobEntity::delete_by_id(running.id)
+        .exec(&conn)
+        .await
+        .unwrap();
+}
+
+// Admin endpoints.
+
+async fn setup(admin_ids: &str) -> TestClient {
+    run_migrations_once().await;
+
+    let auth_config = AuthConfig::new("test-secret", 3600);
+
+    let mut public_routes = PublicRoutes::new();
+    for (method, path) in auth::PUBLIC_ROUTES {
+        public_routes.add(method, path);
+    }
+
+    let auth_middleware = AuthMiddleware::with_public_routes(auth_config.clone(), public_routes);
+
+    let router = Router::new()
+        .group("/api/v1/auth", auth::routes())
+        .group("/api/v1/admin", admin::routes());
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(auth_config)
+        .state(Admins::parse(&format!("{}, {admin_ids}", Uuid::new_v4())).unwrap())
+        .middleware(auth_middleware)
+        .with_database(DatabaseConfig::new(database_url()))
+        .await
+        .expect("failed to connect to test database")
+        .router(router);
+
+    TestClient::new(app).await
+}
+
+fn unique_email() -> String {
+    format!("test-{}@example.com", Uuid::new_v4())
+}
+
+/// Registers a user, returning their token and id.
+async fn register(client: &TestClient, email: &str) -> (String, String) {
+    let res = client
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": email,
+            "password": "password123",
+            "name": "Test User"
+        }))
+        .send()
+        .await;
+
+    let body: serde_json::Value = res.json();
+    (
+        body["token"].as_str().unwrap().to_string(),
+        body["user"]["id"].as_str().unwrap().to_string(),
+    )
+}
+
+/// A client whose app makes a newly registered user an admin, and that
+/// user's token.
+async fn setup_admin() -> (TestClient, String) {
+    let (token, id) = register(&setup("").await, &unique_email()).await;
+    (setup(&id).await, token)
+}
+
+#[derive(Serialize, Deserialize)]
+struct Report;
+
+impl Job for Report {
+    const KIND: &'static str = "test.report";
+}
+
+/// A job queued with `status`, having failed if it is dead.
+async fn job_with_status(conn: &DatabaseConnection, status: &str) -> Model {
+    let mut active: ActiveModel = jobs::enqueue(conn, &Report).await.unwrap().into();
+    active.status = Set(status.to_string());
+    if status == jobs::STATUS_DEAD {
+        active.attempts = Set(5);
+        active.last_error = Set(Some("mailbox full".to_string()));
+        active.finished_at = Set(Some(Utc::now().fixed_offset()));
+    }
+    active.update(conn).await.unwrap()
+}
+
+#[tokio::test]
+async fn admins_inspect_and_retry_dead_jobs() {
+    let conn = connect().await;
+    let (client, token) = setup_admin().await;
+    let auth = format!("Bearer {token}");
+
+    let dead = job_with_status(&conn, jobs::STATUS_DEAD).await;
+    let queued = job_with_status(&conn, jobs::STATUS_QUEUED).await;
+
+    let res = client
+        .get("/api/v1/admin/jobs?status=dead&kind=test.report&limit=100")
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    let listed = body["data"].as_array().unwrap();
+    assert!(listed.iter().all(|j| j["status"] == "dead"));
+    let listed = listed
+        .iter()
+        .find(|j| j["id"] == dead.pid.to_string())
+        .expect("dead job is listed");
+    assert_eq!(listed["kind"], "test.report");
+    assert_eq!(listed["attempts"], 5);
+    assert_eq!(listed["last_error"], "mailbox full");
+
+    let res = client
+        .get(&format!("/api/v1/admin/jobs/{}", queued.pid))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "queued");
+    assert_eq!(body["payload"], serde_json::Value::Null);
+
+    // A queued job that has not failed has nothing to retry.
+    let res = client
+        .post(&format!("/api/v1/admin/jobs/{}/retry", queued.pid))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    let res = client
+        .post(&format!("/api/v1/admin/jobs/{}/retry", dead.pid))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "queued");
+    assert_eq!(body["attempts"], 0);
+    assert_eq!(body["finished_at"], serde_json::Value::Null);
+
+    let ran = Arc::new(AtomicUsize::new(0));
+    let worker = Worker::new(conn.clone()).handle({
+        let ran = ran.clone();
+        move |_, _: Report| {
+            ran.fetch_add(1, Ordering::SeqCst);
+            async { Ok(()) }
+        }
+    });
+    worker.run_until_idle().await.unwrap();
+    assert!(ran.load(Ordering::SeqCst) >= 2);
+    assert_eq!(reload(&conn, &dead).await.status, jobs::STATUS_SUCCEEDED);
+}
+
+#[tokio::test]
+async fn admins_delete_jobs_that_are_not_running() {
+    let conn = connect().await;
+    let (client, token) = setup_admin().await;
+    let auth = format!("Bearer {token}");
+
+    let running = job_with_status(&conn, jobs::STATUS_RUNNING).await;
+    let res = client
+        .delete(&format!("/api/v1/admin/jobs/{}", running.pid))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    JobEntity::delete_by_id(running.id)
+        .exec(&conn)
+        .await
+        .unwrap();
+
+    let dead = job_with_status(&conn, jobs::STATUS_DEAD).await;
+    let res = client
+        .delete(&format!("/api/v1/admin/jobs/{}", dead.pid))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let res = client
+        .get(&format!("/api/v1/admin/jobs/{}", dead.pid))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn only_admins_manage_jobs() {
+    let conn = connect().await;
+    let client = setup(&Uuid::new_v4().to_string()).await;
+    let (token, _) = register(&client, &unique_email()).await;
+    let auth = format!("Bearer {token}");
+    let dead = job_with_status(&conn, jobs::STATUS_DEAD).await;
+
+    let res = client
+        .get("/api/v1/admin/jobs")
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .post(&format!("/api/v1/admin/jobs/{}/retry", dead.pid))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    assert_eq!(reload(&conn, &dead).await.status, jobs::STATUS_DEAD);
+
+    let res = client.get("/api/v1/admin/jobs").send().await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    JobEntity::delete_by_id(dead.id).exec(&conn).await.unwrap();
+}
@@ -0,0 +1,782 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use rapina::auth::{AuthMiddleware, PublicRoutes};
+use rapina::database::DatabaseConfig;
+use rapina::prelude::*;
+use rapina::sea_orm::sea_query::Expr;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+};
+use rapina::testing::TestClient;
+use serde_json::{Value, json};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use reeverb::api::v1::auth;
+use reeverb::api::v1::forms::{
+    self,
+    throttle::{Limit, SubmissionThrottle},
+};
+use reeverb::api::v1::projects;
+use reeverb::api::v1::testimonials;
+use reeverb::db::entities::form::{
+    ActiveModel as FormActiveModel, Column as FormColumn, Entity as Form,
+};
+use reeverb::db::entities::job::{Column as JobColumn, Entity as JobEntity};
+use reeverb::db::entities::project::{Column as ProjectColumn, Entity as Project};
+use reeverb::db::entities::testimonial::{Column as TestimonialColumn, Entity as Testimonial};
+use reeverb::db::entities::user::{Column as UserColumn, Entity as User};
+use reeverb::db::migrations::Migrator;
+use reeverb::jobs::Job;
+use reeverb::mail::{Capture, Mailer};
+use reeverb::net::Destinations;
+use reeverb::notifications::{Notifier, NotifySubmission};
+use reeverb::sentiment::SentimentAnalyzer;
+use reeverb::storage::MediaStorage;
+use reeverb::storage::local::LocalStorage;
+
+static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
+
+const APP_URL: &str = "https://app.example.com/";
+
+fn database_url() -> String {
+    dotenvy::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for integration tests")
+}
+
+async fn run_migrations_once() {
+    MIGRATIONS
+        .get_or_init(|| async {
+            let config = DatabaseConfig::new(database_url());
+            let conn = config.connect().await.expect("failed to connect");
+
+            use rapina::sea_orm_migration::MigratorTrait;
+            Migrator::up(&conn, None)
+                .await
+                .expect("failed to run migrations");
+        })
+        .await;
+}
+
+async fn setup() -> TestClient {
+    setup_with(SubmissionThrottle::default()).await
+}
+
+async fn setup_with(throttle: SubmissionThrottle) -> TestClient {
+    run_migrations_once().await;
+
+    let auth_config = AuthConfig::new("test-secret", 3600);
+
+    let mut public_routes = PublicRoutes::new();
+    for (method, path) in auth::PUBLIC_ROUTES.iter().chain(forms::PUBLIC_ROUTES) {
+        public_routes.add(method, path);
+    }
+
+    let auth_middleware = AuthMiddleware::with_public_routes(auth_config.clone(), public_routes);
+
+    let router = Router::new()
+        .group("/api/v1/auth", auth::routes())
+        .group("/api/v1/projects", projects::routes())
+        .group("/api/v1/projects", testimonials::project_routes())
+        .group("/api/v1/testimonials", testimonials::routes())
+        .group("/api/v1/forms", forms::routes());
+
+    let storage = MediaStorage::new(
+        LocalStorage::new(std::env::temp_dir().join("reeverb-test-media")),
+        "/api/v1/media",
+    );
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(auth_config)
+        .state(SentimentAnalyzer::default())
+        .state(throttle)
+        .state(storage)
+        .state(Destinations::Public)
+        .middleware(auth_middleware)
+        .with_database(DatabaseConfig::new(database_url()))
+        .await
+        .expect("failed to connect to test database")
+        .router(router);
+
+    TestClient::new(app).await
+}
+
+async fn connect() -> DatabaseConnection {
+    DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect")
+}
+
+/// A notifier whose emails end up in the returned outbox.
+fn notifier() -> (Notifier, Capture) {
+    let capture = Capture::new();
+    let mailer = Mailer::new(capture.clone(), "Reeverb <notifications@example.com>");
+    (Notifier::new(mailer, APP_URL), capture)
+}
+
+/// Registers a user, and returns their token and email.
+async fn register(client: &TestClient) -> (String, String) {
+    let email = format!("test-{}@example.com", Uuid::new_v4());
+    let res = client
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": email,
+            "password": "password123",
+            "name": "Test User"
+        }))
+        .send()
+        .await;
+
+    let body: Value = res.json();
+    (body["token"].as_str().unwrap().to_string(), email)
+}
+
+async fn create_project(client: &TestClient, token: &str, body: Value) -> String {
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&body)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let body: Value = res.json();
+    body["id"].as_str().unwrap().to_string()
+}
+
+fn project(name: &str) -> Value {
+    json!({ "name": name, "slug": format!("project-{}", Uuid::new_v4()) })
+}
+
+/// Creates a form for the project, and returns its slug.
+async fn create_form(project_pid: &str, configure: impl FnOnce(&mut FormActiveModel)) -> String {
+    let conn = connect().await;
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(Uuid::parse_str(project_pid).unwrap()))
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let slug = format!("form-{}", Uuid::new_v4());
+    let mut form = FormActiveModel {
+        pid: Set(Uuid::new_v4()),
+        project_id: Set(project.id),
+        name: Set("Feedback".to_string()),
+        slug: Set(slug.clone()),
+        ..Default::default()
+    };
+    configure(&mut form);
+    form.insert(&conn).await.unwrap();
+    slug
+}
+
+async fn post_submission(
+    client: &TestClient,
+    slug: &str,
+    body: Value,
+) -> rapina::testing::TestResponse {
+    client
+        .post(&format!("/api/v1/forms/{slug}/submissions"))
+        .json(&body)
+        .send()
+        .await
+}
+
+/// Submits a testimonial through the form, as a visitor, and returns its
+/// internal id.
+async fn submit(client: &TestClient, slug: &str, body: Value) -> i32 {
+    let res = post_submission(client, slug, body).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let body: Value = res.json();
+    testimonial_id(body["id"].as_str().unwrap()).await
+}
+
+/// Creates a testimonial through the authenticated API, and returns its
+/// internal id.
+async fn create_testimonial(
+    client: &TestClient,
+    token: &str,
+    project_id: &str,
+    body: Value,
+) -> i32 {
+    let res = client
+        .post(&format!("/api/v1/projects/{project_id}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&body)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let body: Value = res.json();
+    testimonial_id(body["id"].as_str().unwrap()).await
+}
+
+async fn testimonial_id(pid: &str) -> i32 {
+    Testimonial::find()
+        .filter(TestimonialColumn::Pid.eq(Uuid::parse_str(pid).unwrap()))
+        .one(&connect().await)
+        .await
+        .unwrap()
+        .unwrap()
+        .id
+}
+
+async fn user_id(conn: &DatabaseConnection, email: &str) -> i32 {
+    User::find()
+        .filter(UserColumn::Email.eq(email))
+        .one(conn)
+        .await
+        .unwrap()
+        .unwrap()
+        .id
+}
+
+/// Whether a notification about the testimonial was queued for the user.
+async fn queued(conn: &DatabaseConnection, testimonial_id: i32, user_id: i32) -> bool {
+    JobEntity::find()
+        .filter(JobColumn::Kind.eq(NotifySubmission::KIND))
+        .filter(
+            JobColumn::Payload.eq(json!({ "testimonial_id": testimonial_id, "user_id": user_id })),
+        )
+        .one(conn)
+        .await
+        .unwrap()
+        .is_some()
+}
+
+async fn set_preference(client: &TestClient, token: &str, submissions: &str) -> Value {
+    let res = client
+        .put("/api/v1/auth/me/notifications")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "submissions": submissions }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    res.json()
+}
+
+#[tokio::test]
+async fn preferences_default_to_immediate() {
+    let client = setup().await;
+    let (token, _) = register(&client).await;
+
+    let res = client
+        .get("/api/v1/auth/me/notifications")
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = res.json();
+    assert_eq!(body["submissions"], "immediate");
+    assert!(body["digest_sent_at"].is_null());
+
+    let body = set_preference(&client, &token, "digest").await;
+    assert_eq!(body["submissions"], "digest");
+    assert!(body["digest_sent_at"].is_string());
+
+    let body = set_preference(&client, &token, "off").await;
+    assert_eq!(body["submissions"], "off");
+
+    let res = client
+        .put("/api/v1/auth/me/notifications")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "submissions": "hourly" }))
+        .send()
+        .await;
+    assert!(res.status().is_client_error());
+}
+
+#[tokio::test]
+async fn form_submissions_are_emailed_to_the_owner() {
+    let client = setup().await;
+    let conn = connect().await;
+    let (notifier, outbox) = notifier();
+    let (token, email) = register(&client).await;
+    let project_id = create_project(
+        &client,
+        &token,
+        json!({
+            "name": "Acme & Co",
+            "slug": format!("project-{}", Uuid::new_v4()),
+            "logo_url": "/api/v1/media/logo.png",
+            "website_url": "https://acme.example.com"
+        }),
+    )
+    .await;
+    let slug = create_form(&project_id, |_| {}).await;
+    let owner = user_id(&conn, &email).await;
+
+    let submission = submit(
+        &client,
+        &slug,
+        json!({
+            "author_name": "Jane <Doe>",
+            "author_company": "Initech",
+            "content": "Loved it, <b>really</b>!",
+            "rating": 5
+        }),
+    )
+    .await;
+    // Owners cannot pass their own testimonials off as submissions.
+    let added = create_testimonial(
+        &client,
+        &token,
+        &project_id,
+        json!({ "author_name": "John Doe", "content": "Added by hand", "source": "form" }),
+    )
+    .await;
+    assert!(queued(&conn, submission, owner).await);
+    assert!(!queued(&conn, added, owner).await);
+
+    notifier
+        .notify_submission(
+            &conn,
+            NotifySubmission {
+                testimonial_id: submission,
+                user_id: owner,
+            },
+        )
+        .await
+        .unwrap();
+
+    let sent = outbox.take_for(&email);
+    assert_eq!(sent.len(), 1);
+    let message = &sent[0];
+    assert_eq!(message.from, "Reeverb <notifications@example.com>");
+    assert_eq!(message.subject, "New testimonial for Acme & Co");
+
+    assert!(
+        message
+            .html
+            .contains("Loved it, &lt;b&gt;really&lt;/b&gt;!")
+    );
+    assert!(message.html.contains("Jane &lt;Doe&gt;, Initech"));
+    assert!(!message.html.contains("<b>really</b>"));
+    assert!(
+        message
+            .html
+            .contains(r#"src="https://app.example.com/api/v1/media/logo.png""#)
+    );
+    assert!(message.html.contains("https://acme.example.com"));
+    assert!(message.html.contains("https://app.example.com/dashboard"));
+
+    assert!(message.text.contains("> Loved it, <b>really</b>!"));
+    assert!(
+        message
+            .text
+            .contains("Jane <Doe> left a 5-star testimonial")
+    );
+    assert!(
+        message
+            .text
+            .contains("Review it: https://app.example.com/dashboard")
+    );
+}
+
+#[tokio::test]
+async fn form_submissions_are_emailed_to_every_member() {
+    let client = setup().await;
+    let conn = connect().await;
+    let (notifier, outbox) = notifier();
+    let (token, email) = register(&client).await;
+    let (member_token, member_email) = register(&client).await;
+    let (opted_out_token, opted_out_email) = register(&client).await;
+    let (outsider_token, outsider_email) = register(&client).await;
+    let (invited_token, invited_email) = register(&client).await;
+    let project_id = create_project(&client, &token, project("Acme")).await;
+
+    let res = client
+        .put(&format!("/api/v1/projects/{project_id}/members"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "emails": [member_email, opted_out_email, invited_email] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = res.json();
+    assert_eq!(body["members"].as_array().unwrap().len(), 4);
+    assert_eq!(body["members"][0]["email"], email.as_str());
+    assert_eq!(body["members"][0]["is_owner"], true);
+    assert_eq!(body["members"][0]["accepted"], true);
+    assert_eq!(body["members"][1]["accepted"], false);
+
+    // Users added are invited, and become members once they accept.
+    let res = client
+        .get("/api/v1/projects/invitations")
+        .header("Authorization", &format!("Bearer {member_token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = res.json();
+    assert_eq!(body["invitations"][0]["project_id"], project_id.as_str());
+    assert_eq!(body["invitations"][0]["project_name"], "Acme");
+    for member_token in [&member_token, &opted_out_token] {
+        let res = client
+            .post(&format!("/api/v1/projects/{project_id}/members/me"))
+            .header("Authorization", &format!("Bearer {member_token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    }
+    let res = client
+        .get("/api/v1/projects/invitations")
+        .header("Authorization", &format!("Bearer {member_token}"))
+        .send()
+        .await;
+    assert_eq!(res.json::<Value>()["invitations"], json!([]));
+    let res = client
+        .post(&format!("/api/v1/projects/{project_id}/members/me"))
+        .header("Authorization", &format!("Bearer {outsider_token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // Saving the same list again keeps who accepted.
+    let res = client
+        .put(&format!("/api/v1/projects/{project_id}/members"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "emails": [member_email, opted_out_email, invited_email] }))
+        .send()
+        .await;
+    let body: Value = res.json();
+    let accepted: Vec<bool> = body["members"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["accepted"].as_bool().unwrap())
+        .collect();
+    assert_eq!(accepted, [true, true, true, false]);
+
+    // Only the owner manages members.
+    let res = client
+        .put(&format!("/api/v1/projects/{project_id}/members"))
+        .header("Authorization", &format!("Bearer {member_token}"))
+        .json(&json!({ "emails": [] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    // Which email has no account is not said.
+    let res = client
+        .put(&format!("/api/v1/projects/{project_id}/members"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "emails": ["nobody@example.com"] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(!res.text().contains("nobody@example.com"));
+
+    set_preference(&client, &opted_out_token, "off").await;
+    set_preference(&client, &outsider_token, "immediate").await;
+
+    let slug = create_form(&project_id, |_| {}).await;
+    let submission = submit(
+        &client,
+        &slug,
+        json!({ "author_name": "Jane Doe", "content": "Great!" }),
+    )
+    .await;
+
+    let mut notified = Vec::new();
+    for email in [
+        &email,
+        &member_email,
+        &opted_out_email,
+        &outsider_email,
+        &invited_email,
+    ] {
+        let user_id = user_id(&conn, email).await;
+        if queued(&conn, submission, user_id).await {
+            notified.push(email);
+        }
+    }
+    assert_eq!(notified, [&email, &member_email]);
+
+    for user_id in [
+        user_id(&conn, &email).await,
+        user_id(&conn, &member_email).await,
+    ] {
+        notifier
+            .notify_submission(
+                &conn,
+                NotifySubmission {
+                    testimonial_id: submission,
+                    user_id,
+                },
+            )
+            .await
+            .unwrap();
+    }
+    assert_eq!(outbox.take_for(&email).len(), 1);
+    assert_eq!(outbox.take_for(&member_email).len(), 1);
+
+    // Members who leave are no longer notified, even about submissions
+    // queued before. Invitations can be declined the same way.
+    for leaving_token in [&member_token, &invited_token] {
+        let res = client
+            .delete(&format!("/api/v1/projects/{project_id}/members/me"))
+            .header("Authorization", &format!("Bearer {leaving_token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    }
+    let res = client
+        .get(&format!("/api/v1/projects/{project_id}/members"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.json::<Value>()["members"].as_array().unwrap().len(), 2);
+    notifier
+        .notify_submission(
+            &conn,
+            NotifySubmission {
+                testimonial_id: submission,
+                user_id: user_id(&conn, &member_email).await,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(outbox.take_for(&member_email).is_empty());
+}
+
+#[tokio::test]
+async fn form_submissions_are_checked_against_the_form() {
+    let client = setup().await;
+    let conn = connect().await;
+    let (token, _) = register(&client).await;
+    let project_id = create_project(&client, &token, project("Acme")).await;
+    let slug = create_form(&project_id, |form| {
+        form.allow_video = Set(false);
+        form.require_rating = Set(true);
+    })
+    .await;
+
+    for body in [
+        json!({ "author_name": "Jane Doe", "content": "Great!" }),
+        json!({ "author_name": "Jane Doe", "rating": 5 }),
+        json!({ "author_name": "Jane Doe", "content": "Great!", "rating": 6 }),
+        json!({
+            "author_name": "Jane Doe",
+            "video_url": "https://videos.example.com/jane.mp4",
+            "rating": 5
+        }),
+    ] {
+        let res = post_submission(&client, &slug, body).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let res = post_submission(
+        &client,
+        &slug,
+        json!({ "author_name": "Jane Doe", "content": "Great!", "rating": 5 }),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body: Value = res.json();
+    let testimonial = Testimonial::find()
+        .filter(TestimonialColumn::Pid.eq(Uuid::parse_str(body["id"].as_str().unwrap()).unwrap()))
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(testimonial.status, "pending");
+    assert_eq!(testimonial.source.as_deref(), Some("form"));
+
+    let form = Form::find()
+        .filter(FormColumn::Slug.eq(&slug))
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(testimonial.form_id, Some(form.id));
+    assert_eq!(form.submission_count, 1);
+
+    let inactive = create_form(&project_id, |form| form.is_active = Set(false)).await;
+    let res = post_submission(
+        &client,
+        &inactive,
+        json!({ "author_name": "Jane Doe", "content": "Great!" }),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn form_submissions_are_throttled() {
+    let hour = Duration::from_secs(3600);
+    let client = setup_with(SubmissionThrottle::new(
+        Limit {
+            burst: 2,
+            every: hour,
+        },
+        Limit {
+            burst: 3,
+            every: hour,
+        },
+    ))
+    .await;
+    let (token, _) = register(&client).await;
+    let project_id = create_project(&client, &token, project("Acme")).await;
+    let slug = create_form(&project_id, |_| {}).await;
+    let other_slug = create_form(&project_id, |_| {}).await;
+    let send = |slug: &str, forwarded_for: &str| {
+        client
+            .post(&format!("/api/v1/forms/{slug}/submissions"))
+            .header("X-Forwarded-For", forwarded_for)
+            .json(&json!({ "author_name": "Jane Doe", "content": "Great!" }))
+            .send()
+    };
+
+    assert_eq!(
+        send(&slug, "203.0.113.1").await.status(),
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        send(&slug, "203.0.113.1").await.status(),
+        StatusCode::CREATED
+    );
+    // Only the entry the proxy appended counts as the client's address.
+    let res = send(&other_slug, "198.51.100.7, 203.0.113.1").await;
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    let body: Value = res.json();
+    assert_eq!(body["error"]["code"], "RATE_LIMITED");
+
+    assert_eq!(
+        send(&slug, "203.0.113.2").await.status(),
+        StatusCode::CREATED
+    );
+    // The form has taken all it allows, whoever sends the next one.
+    let res = send(&slug, "203.0.113.3").await;
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(
+        send(&other_slug, "203.0.113.3").await.status(),
+        StatusCode::CREATED
+    );
+}
+
+#[tokio::test]
+async fn owners_who_opted_out_get_no_email() {
+    let client = setup().await;
+    let conn = connect().await;
+    let (notifier, outbox) = notifier();
+    let (token, email) = register(&client).await;
+    let project_id = create_project(&client, &token, project("Acme")).await;
+    let slug = create_form(&project_id, |_| {}).await;
+    set_preference(&client, &token, "off").await;
+    let owner = user_id(&conn, &email).await;
+
+    let submission = submit(
+        &client,
+        &slug,
+        json!({ "author_name": "Jane Doe", "content": "Great!" }),
+    )
+    .await;
+    assert!(!queued(&conn, submission, owner).await);
+    notifier
+        .notify_submission(
+            &conn,
+            NotifySubmission {
+                testimonial_id: submission,
+                user_id: owner,
+            },
+        )
+        .await
+        .unwrap();
+
+    assert!(outbox.take_for(&email).is_empty());
+}
+
+#[tokio::test]
+async fn digests_list_a_days_submissions_once() {
+    let client = setup().await;
+    let conn = connect().await;
+    let (notifier, outbox) = notifier();
+    let (token, email) = register(&client).await;
+    let first = create_project(&client, &token, project("Acme")).await;
+    let second = create_project(&client, &token, project("Globex")).await;
+    let first_form = create_form(&first, |_| {}).await;
+    let second_form = create_form(&second, |_| {}).await;
+    set_preference(&client, &token, "digest").await;
+    let owner = user_id(&conn, &email).await;
+
+    let submission = submit(
+        &client,
+        &first_form,
+        json!({ "author_name": "Jane Doe", "content": "Great!" }),
+    )
+    .await;
+    submit(
+        &client,
+        &second_form,
+        json!({ "author_name": "John Doe", "content": "Superb!", "rating": 4 }),
+    )
+    .await;
+    create_testimonial(
+        &client,
+        &token,
+        &second,
+        json!({ "author_name": "Imported", "content": "Not a submission", "source": "form" }),
+    )
+    .await;
+
+    // Digest users get no email for each submission.
+    assert!(!queued(&conn, submission, owner).await);
+    notifier
+        .notify_submission(
+            &conn,
+            NotifySubmission {
+                testimonial_id: submission,
+                user_id: owner,
+            },
+        )
+        .await
+        .unwrap();
+    assert!(outbox.take_for(&email).is_empty());
+
+    // Not due until a day has passed since switching to digests.
+    notifier.send_digests(&conn).await.unwrap();
+    assert!(outbox.take_for(&email).is_empty());
+
+    let a_day_ago = Utc::now().fixed_offset() - chrono::Duration::hours(25);
+    User::update_many()
+        .col_expr(UserColumn::DigestSentAt, Expr::value(a_day_ago))
+        .filter(UserColumn::Email.eq(&email))
+        .exec(&conn)
+        .await
+        .unwrap();
+    // Submissions from before the last digest are not listed again.
+    Testimonial::update_many()
+        .col_expr(
+            TestimonialColumn::CreatedAt,
+            Expr::value(a_day_ago - chrono::Duration::hours(1)),
+        )
+        .filter(TestimonialColumn::Id.eq(submission))
+        .exec(&conn)
+        .await
+        .unwrap();
+
+    notifier.send_digests(&conn).await.unwrap();
+    let sent = outbox.take_for(&email);
+    assert_eq!(sent.len(), 1);
+    let digest = &sent[0];
+    assert_eq!(digest.subject, "1 new testimonial");
+    assert!(digest.text.contains("Globex (1)"));
+    assert!(digest.text.contains("> Superb!"));
+    assert!(digest.text.contains("John Doe, ★★★★"));
+    assert!(!digest.text.contains("Great!"));
+    assert!(!digest.text.contains("Not a submission"));
+
+    let user = User::find()
+        .filter(UserColumn::Email.eq(&email))
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(user.digest_sent_at.unwrap() > a_day_ago);
+
+    notifier.send_digests(&conn).await.unwrap();
+    assert!(outbox.take_for(&email).is_empty());
+}
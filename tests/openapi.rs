@@ -0,0 +1,64 @@
+use rapina::prelude::*;
+use rapina::testing::TestClient;
+
+use reeverb::api::v1::openapi::{self, ApiSpec};
+use reeverb::api::v1::{projects, testimonials, uploads};
+
+async fn setup() -> TestClient {
+    let router = Router::new()
+        .group("/api/v1/projects", projects::routes())
+        .group("/api/v1/testimonials", testimonials::routes())
+        .group("/api/v1/uploads", uploads::routes());
+    let spec = ApiSpec::build("Reeverb API", "test", &router.routes());
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(spec)
+        .router(router.get(openapi::PATH, openapi::openapi_spec));
+
+    TestClient::new(app).await
+}
+
+#[tokio::test]
+async fn patch_operations_are_documented_with_merge_patch_bodies() {
+    let client = setup().await;
+
+    let res = client.get(openapi::PATH).send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let spec: serde_json::Value = res.json();
+
+    for (path, operation_id, request) in [
+        (
+            "/api/v1/projects/{id}",
+            "patch_project",
+            "PatchProjectRequest",
+        ),
+        (
+            "/api/v1/testimonials/{id}",
+            "patch_testimonial",
+            "PatchTestimonialRequest",
+        ),
+    ] {
+        let patch = &spec["paths"][path]["patch"];
+        assert_eq!(patch["operationId"], operation_id);
+        assert_eq!(patch["parameters"][0]["name"], "id");
+        assert_eq!(patch["requestBody"]["required"], true);
+        let body = &patch["requestBody"]["content"]["application/merge-patch+json"]["schema"];
+        assert_eq!(body["title"], request);
+        assert!(patch["responses"]["200"]["content"]["application/json"]["schema"].is_object());
+        assert!(patch["responses"]["412"].is_object());
+    }
+
+    let chunk = &spec["paths"]["/api/v1/uploads/{id}"]["patch"];
+    assert_eq!(chunk["operationId"], "upload_chunk");
+    assert_eq!(
+        chunk["requestBody"]["content"]["application/octet-stream"]["schema"]["format"],
+        "binary"
+    );
+
+    // The PUT next to each PATCH is still documented.
+    assert_eq!(
+        spec["paths"]["/api/v1/projects/{id}"]["put"]["operationId"],
+        "update_project"
+    );
+}
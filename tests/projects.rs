@@ -91,7 +91,7 @@ async fn create_project_returns_201() {
 
     let res = client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({
             "name": "My Project",
             "slug": slug,
@@ -110,6 +110,31 @@ async fn create_project_returns_201() {
     assert!(body["created_at"].is_string());
 }
 
+#[tokio::test]
+async fn create_project_rejects_invalid_fields_with_422() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "name": "Invalid",
+            "slug": "Not A Slug",
+            "website_url": "example dot com"
+        }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body: serde_json::Value = res.json();
+    let details = &body["error"]["details"];
+    assert_eq!(details["slug"][0]["code"], "regex");
+    assert_eq!(details["website_url"][0]["code"], "url");
+    assert!(details["name"].is_null());
+}
+
 #[tokio::test]
 async fn list_projects_returns_only_own() {
     let client = setup().await;
@@ -121,31 +146,70 @@ async fn list_projects_returns_only_own() {
 
     client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token_a))
+        .header("Authorization", &format!("Bearer {token_a}"))
         .json(&json!({ "name": "Project A", "slug": slug_a }))
         .send()
         .await;
 
     client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token_b))
+        .header("Authorization", &format!("Bearer {token_b}"))
         .json(&json!({ "name": "Project B", "slug": slug_b }))
         .send()
         .await;
 
     let res = client
         .get("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token_a))
+        .header("Authorization", &format!("Bearer {token_a}"))
         .send()
         .await;
 
     assert_eq!(res.status(), StatusCode::OK);
 
-    let body: Vec<serde_json::Value> = res.json();
+    let body: serde_json::Value = res.json();
+    let body = body["data"].as_array().unwrap();
     assert!(body.iter().all(|p| p["slug"] != slug_b));
     assert!(body.iter().any(|p| p["slug"] == slug_a));
 }
 
+#[tokio::test]
+async fn list_projects_paginates_with_cursor() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+
+    for name in ["First", "Second"] {
+        client
+            .post("/api/v1/projects")
+            .header("Authorization", &format!("Bearer {token}"))
+            .json(&json!({ "name": name, "slug": unique_slug() }))
+            .send()
+            .await;
+    }
+
+    let res = client
+        .get("/api/v1/projects?limit=1&include_total=true")
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["data"][0]["name"], "Second");
+    let cursor = body["next_cursor"].as_str().unwrap().to_string();
+
+    let res = client
+        .get(&format!("/api/v1/projects?limit=1&cursor={cursor}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"][0]["name"], "First");
+    assert!(body["next_cursor"].is_null());
+}
+
 #[tokio::test]
 async fn get_project_by_pid() {
     let client = setup().await;
@@ -154,7 +218,7 @@ async fn get_project_by_pid() {
 
     let res = client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "Get Test", "slug": slug }))
         .send()
         .await;
@@ -163,8 +227,8 @@ async fn get_project_by_pid() {
     let pid = created["id"].as_str().unwrap();
 
     let res = client
-        .get(&format!("/api/v1/projects/{}", pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
@@ -183,7 +247,7 @@ async fn update_project_partial() {
 
     let res = client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "Before", "slug": slug }))
         .send()
         .await;
@@ -192,8 +256,8 @@ async fn update_project_partial() {
     let pid = created["id"].as_str().unwrap();
 
     let res = client
-        .put(&format!("/api/v1/projects/{}", pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .put(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "After" }))
         .send()
         .await;
@@ -205,6 +269,52 @@ async fn update_project_partial() {
     assert_eq!(body["slug"], slug);
 }
 
+#[tokio::test]
+async fn patch_project_clears_nullable_fields() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let slug = unique_slug();
+
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "name": "Patched",
+            "slug": slug,
+            "logo_url": "https://example.com/logo.png",
+            "website_url": "https://example.com"
+        }))
+        .send()
+        .await;
+
+    let created: serde_json::Value = res.json();
+    let pid = created["id"].as_str().unwrap();
+
+    let res = client
+        .patch(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "logo_url": null, "name": "Renamed" }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["name"], "Renamed");
+    assert!(body["logo_url"].is_null());
+    assert_eq!(body["website_url"], "https://example.com");
+    assert_eq!(body["slug"], slug);
+
+    let res = client
+        .patch(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "name": null }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn delete_project_returns_204() {
     let client = setup().await;
@@ -213,7 +323,7 @@ async fn delete_project_returns_204() {
 
     let res = client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "To Delete", "slug": slug }))
         .send()
         .await;
@@ -222,22 +332,157 @@ async fn delete_project_returns_204() {
     let pid = created["id"].as_str().unwrap();
 
     let res = client
-        .delete(&format!("/api/v1/projects/{}", pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .delete(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
     assert_eq!(res.status(), StatusCode::NO_CONTENT);
 
     let res = client
-        .get(&format!("/api/v1/projects/{}", pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
     assert_eq!(res.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn if_match_guards_update_and_delete() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let slug = unique_slug();
+
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "name": "Versioned", "slug": slug }))
+        .send()
+        .await;
+
+    let created_etag = res.headers()["etag"].to_str().unwrap().to_string();
+    let created: serde_json::Value = res.json();
+    let pid = created["id"].as_str().unwrap();
+
+    let res = client
+        .get(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let etag = res.headers()["etag"].to_str().unwrap().to_string();
+    assert_eq!(etag, created_etag);
+
+    let res = client
+        .get("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert!(res.headers().get("etag").is_none());
+
+    let res = client
+        .put(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &etag)
+        .json(&json!({ "name": "First tab" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let fresh = res.headers()["etag"].to_str().unwrap().to_string();
+    assert_ne!(fresh, etag);
+
+    let res = client
+        .put(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &etag)
+        .json(&json!({ "name": "Second tab" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "PRECONDITION_FAILED");
+
+    let res = client
+        .delete(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &etag)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+    let res = client
+        .get(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["name"], "First tab");
+
+    let res = client
+        .delete(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &fresh)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn deleted_project_moves_to_trash_and_restores() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let slug = unique_slug();
+
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "name": "Trashed", "slug": slug }))
+        .send()
+        .await;
+
+    let created: serde_json::Value = res.json();
+    let pid = created["id"].as_str().unwrap();
+
+    client
+        .delete(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    let res = client
+        .get("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert!(body["data"].as_array().unwrap().is_empty());
+
+    let res = client
+        .get("/api/v1/projects/trash")
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"][0]["id"], pid);
+
+    let res = client
+        .post(&format!("/api/v1/projects/{pid}/restore"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert!(body["deleted_at"].is_null());
+}
+
 #[tokio::test]
 async fn slug_uniqueness_returns_409() {
     let client = setup().await;
@@ -246,14 +491,14 @@ async fn slug_uniqueness_returns_409() {
 
     client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "First", "slug": slug }))
         .send()
         .await;
 
     let res = client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "Second", "slug": slug }))
         .send()
         .await;
@@ -270,7 +515,7 @@ async fn ownership_enforcement_returns_403() {
 
     let res = client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token_owner))
+        .header("Authorization", &format!("Bearer {token_owner}"))
         .json(&json!({ "name": "Private", "slug": slug }))
         .send()
         .await;
@@ -279,16 +524,16 @@ async fn ownership_enforcement_returns_403() {
     let pid = created["id"].as_str().unwrap();
 
     let res = client
-        .get(&format!("/api/v1/projects/{}", pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .get(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token_other}"))
         .send()
         .await;
 
     assert_eq!(res.status(), StatusCode::FORBIDDEN);
 
     let res = client
-        .put(&format!("/api/v1/projects/{}", pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .put(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token_other}"))
         .json(&json!({ "name": "Hacked" }))
         .send()
         .await;
@@ -296,8 +541,8 @@ async fn ownership_enforcement_returns_403() {
     assert_eq!(res.status(), StatusCode::FORBIDDEN);
 
     let res = client
-        .delete(&format!("/api/v1/projects/{}", pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .delete(&format!("/api/v1/projects/{pid}"))
+        .header("Authorization", &format!("Bearer {token_other}"))
         .send()
         .await;
 
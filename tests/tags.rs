@@ -11,6 +11,7 @@ use reeverb::api::v1::projects;
 use reeverb::api::v1::tags;
 use reeverb::api::v1::testimonials;
 use reeverb::db::migrations::Migrator;
+use reeverb::sentiment::SentimentAnalyzer;
 
 static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
 
@@ -57,6 +58,7 @@ async fn setup() -> TestClient {
     let app = Rapina::new()
         .with_introspection(false)
         .state(auth_config)
+        .state(SentimentAnalyzer::default())
         .middleware(auth_middleware)
         .with_database(DatabaseConfig::new(database_url()))
         .await
@@ -94,7 +96,7 @@ async fn create_project(client: &TestClient, token: &str) -> String {
     let slug = unique_slug();
     let res = client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "Test Project", "slug": slug }))
         .send()
         .await;
@@ -105,8 +107,8 @@ async fn create_project(client: &TestClient, token: &str) -> String {
 
 async fn create_test_testimonial(client: &TestClient, token: &str, project_pid: &str) -> String {
     let res = client
-        .post(&format!("/api/v1/projects/{}/testimonials", project_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({
             "author_name": "Jane Doe",
             "content": "Great product!",
@@ -134,8 +136,8 @@ async fn create_test_tag(
     }
 
     let res = client
-        .post(&format!("/api/v1/projects/{}/tags", project_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .post(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&payload)
         .send()
         .await;
@@ -152,8 +154,8 @@ async fn create_tag_returns_201() {
     let project_pid = create_project(&client, &token).await;
 
     let res = client
-        .post(&format!("/api/v1/projects/{}/tags", project_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .post(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "bug", "color": "#ff0000" }))
         .send()
         .await;
@@ -167,6 +169,25 @@ async fn create_tag_returns_201() {
     assert!(body["id"].is_string());
 }
 
+#[tokio::test]
+async fn invalid_color_returns_422() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "name": "colorful", "color": "rebeccapurple" }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["details"]["color"][0]["code"], "regex");
+}
+
 #[tokio::test]
 async fn create_duplicate_name_returns_409() {
     let client = setup().await;
@@ -176,8 +197,8 @@ async fn create_duplicate_name_returns_409() {
     create_test_tag(&client, &token, &project_pid, "duplicate", None).await;
 
     let res = client
-        .post(&format!("/api/v1/projects/{}/tags", project_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .post(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "duplicate" }))
         .send()
         .await;
@@ -194,14 +215,15 @@ async fn list_tags_for_project() {
     create_test_tag(&client, &token, &project_pid, "feature", Some("#00ff00")).await;
 
     let res = client
-        .get(&format!("/api/v1/projects/{}/tags", project_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
     assert_eq!(res.status(), StatusCode::OK);
 
-    let body: Vec<serde_json::Value> = res.json();
+    let body: serde_json::Value = res.json();
+    let body = body["data"].as_array().unwrap();
     assert!(!body.is_empty());
     let found = body.iter().any(|t| t["name"] == "feature");
     assert!(found);
@@ -215,8 +237,8 @@ async fn update_tag_partial() {
     let tag_pid = create_test_tag(&client, &token, &project_pid, "old-name", Some("#000000")).await;
 
     let res = client
-        .put(&format!("/api/v1/tags/{}", tag_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .put(&format!("/api/v1/tags/{tag_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "color": "#ffffff" }))
         .send()
         .await;
@@ -238,8 +260,8 @@ async fn update_tag_name_to_existing_returns_409() {
     let tag_pid = create_test_tag(&client, &token, &project_pid, "other-name", None).await;
 
     let res = client
-        .put(&format!("/api/v1/tags/{}", tag_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .put(&format!("/api/v1/tags/{tag_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "taken-name" }))
         .send()
         .await;
@@ -247,6 +269,57 @@ async fn update_tag_name_to_existing_returns_409() {
     assert_eq!(res.status(), StatusCode::CONFLICT);
 }
 
+#[tokio::test]
+async fn stale_if_match_returns_412() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "name": "versioned" }))
+        .send()
+        .await;
+    let etag = res.headers()["etag"].to_str().unwrap().to_string();
+    let body: serde_json::Value = res.json();
+    let tag_pid = body["id"].as_str().unwrap().to_string();
+
+    let res = client
+        .put(&format!("/api/v1/tags/{tag_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &etag)
+        .json(&json!({ "color": "#ffffff" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .put(&format!("/api/v1/tags/{tag_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &etag)
+        .json(&json!({ "color": "#000000" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+    let res = client
+        .delete(&format!("/api/v1/tags/{tag_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &etag)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+    let res = client
+        .delete(&format!("/api/v1/tags/{tag_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", "*")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+}
+
 #[tokio::test]
 async fn delete_tag_returns_204() {
     let client = setup().await;
@@ -255,8 +328,8 @@ async fn delete_tag_returns_204() {
     let tag_pid = create_test_tag(&client, &token, &project_pid, "to-delete", None).await;
 
     let res = client
-        .delete(&format!("/api/v1/tags/{}", tag_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .delete(&format!("/api/v1/tags/{tag_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
@@ -264,12 +337,13 @@ async fn delete_tag_returns_204() {
 
     // Verify it's gone from list
     let res = client
-        .get(&format!("/api/v1/projects/{}/tags", project_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
-    let body: Vec<serde_json::Value> = res.json();
+    let body: serde_json::Value = res.json();
+    let body = body["data"].as_array().unwrap();
     let found = body.iter().any(|t| t["id"] == tag_pid);
     assert!(!found);
 }
@@ -284,8 +358,8 @@ async fn set_testimonial_tags() {
     let tag2 = create_test_tag(&client, &token, &project_pid, "tag-b", None).await;
 
     let res = client
-        .put(&format!("/api/v1/testimonials/{}/tags", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "tag_ids": [tag1, tag2] }))
         .send()
         .await;
@@ -307,16 +381,16 @@ async fn set_testimonial_tags_empty_clears() {
 
     // Set one tag
     client
-        .put(&format!("/api/v1/testimonials/{}/tags", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "tag_ids": [tag1] }))
         .send()
         .await;
 
     // Clear all tags
     let res = client
-        .put(&format!("/api/v1/testimonials/{}/tags", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "tag_ids": [] }))
         .send()
         .await;
@@ -338,8 +412,8 @@ async fn set_tags_from_different_project_fails() {
     let foreign_tag = create_test_tag(&client, &token, &project2, "foreign", None).await;
 
     let res = client
-        .put(&format!("/api/v1/testimonials/{}/tags", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "tag_ids": [foreign_tag] }))
         .send()
         .await;
@@ -356,15 +430,15 @@ async fn testimonial_get_includes_tags() {
     let tag_pid = create_test_tag(&client, &token, &project_pid, "included", Some("#abcdef")).await;
 
     client
-        .put(&format!("/api/v1/testimonials/{}/tags", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "tag_ids": [tag_pid] }))
         .send()
         .await;
 
     let res = client
-        .get(&format!("/api/v1/testimonials/{}", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
@@ -377,6 +451,104 @@ async fn testimonial_get_includes_tags() {
     assert_eq!(tags[0]["color"], "#abcdef");
 }
 
+#[tokio::test]
+async fn list_testimonials_filters_by_tags_any_and_all() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let both = create_test_testimonial(&client, &token, &project_pid).await;
+    let only_a = create_test_testimonial(&client, &token, &project_pid).await;
+    create_test_testimonial(&client, &token, &project_pid).await;
+    let tag_a = create_test_tag(&client, &token, &project_pid, "a", None).await;
+    let tag_b = create_test_tag(&client, &token, &project_pid, "b", None).await;
+
+    for (testimonial, tags) in [(&both, vec![&tag_a, &tag_b]), (&only_a, vec![&tag_a])] {
+        client
+            .put(&format!("/api/v1/testimonials/{testimonial}/tags"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .json(&json!({ "tag_ids": tags }))
+            .send()
+            .await;
+    }
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?tag_ids={tag_a},{tag_b}"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?tag_ids={tag_a},{tag_b}&tag_match=all"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    let data = body["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0]["id"], both);
+}
+
+#[tokio::test]
+async fn bulk_add_and_remove_tags() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let first = create_test_testimonial(&client, &token, &project_pid).await;
+    let second = create_test_testimonial(&client, &token, &project_pid).await;
+    let tag = create_test_tag(&client, &token, &project_pid, "imported", None).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials/bulk"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "filter": {}, "action": "add_tags", "tag_ids": [tag] }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["results"].as_array().unwrap().len(), 2);
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?tag_ids={tag}"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials/bulk"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "ids": [first], "action": "remove_tags", "tag_ids": [tag] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?tag_ids={tag}"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    let data = body["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0]["id"], second);
+}
+
 #[tokio::test]
 async fn ownership_enforcement_returns_403() {
     let client = setup().await;
@@ -387,16 +559,16 @@ async fn ownership_enforcement_returns_403() {
 
     // List tags
     let res = client
-        .get(&format!("/api/v1/projects/{}/tags", project_pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .get(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token_other}"))
         .send()
         .await;
     assert_eq!(res.status(), StatusCode::FORBIDDEN);
 
     // Create tag
     let res = client
-        .post(&format!("/api/v1/projects/{}/tags", project_pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .post(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token_other}"))
         .json(&json!({ "name": "hacked" }))
         .send()
         .await;
@@ -404,8 +576,8 @@ async fn ownership_enforcement_returns_403() {
 
     // Update tag
     let res = client
-        .put(&format!("/api/v1/tags/{}", tag_pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .put(&format!("/api/v1/tags/{tag_pid}"))
+        .header("Authorization", &format!("Bearer {token_other}"))
         .json(&json!({ "name": "hacked" }))
         .send()
         .await;
@@ -413,8 +585,8 @@ async fn ownership_enforcement_returns_403() {
 
     // Delete tag
     let res = client
-        .delete(&format!("/api/v1/tags/{}", tag_pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .delete(&format!("/api/v1/tags/{tag_pid}"))
+        .header("Authorization", &format!("Bearer {token_other}"))
         .send()
         .await;
     assert_eq!(res.status(), StatusCode::FORBIDDEN);
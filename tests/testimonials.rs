@@ -1,18 +1,75 @@
+use std::collections::HashSet;
+
 use rapina::auth::{AuthMiddleware, PublicRoutes};
 use rapina::database::DatabaseConfig;
 use rapina::prelude::*;
+use rapina::sea_orm::sea_query::Condition;
+use rapina::sea_orm::{
+    ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect,
+    QueryTrait, Statement, TransactionTrait,
+};
 use rapina::testing::TestClient;
+use reeverb::video::{
+    Fetcher, ProcessVideo, VideoError, VideoInfo, VideoPipeline, VideoProbe, transcribe,
+};
 use serde_json::json;
 use tokio::sync::OnceCell;
 use uuid::Uuid;
 
 use reeverb::api::v1::auth;
+use reeverb::api::v1::media;
 use reeverb::api::v1::projects;
-use reeverb::api::v1::testimonials;
+use reeverb::api::v1::tags;
+use reeverb::api::v1::testimonials::{self, search};
+use reeverb::db::entities::job::{Column as JobColumn, Entity as JobEntity};
+use reeverb::db::entities::testimonial::{Column as TestimonialColumn, Entity as Testimonial};
 use reeverb::db::migrations::Migrator;
+use reeverb::db::trash;
+use reeverb::jobs::{self, Job, Worker};
+use reeverb::net::Destinations;
+use reeverb::sentiment::{AnalyzeSentiment, SentimentAnalyzer};
+use reeverb::storage::MediaStorage;
+use reeverb::storage::local::LocalStorage;
 
 static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
 
+const THUMBNAIL: &[u8] = b"\xff\xd8\xff\xe0 not really a jpeg";
+
+const TRANSCRIPT: &str =
+    "Honestly the best tool we have used, setup was easy and support was great.";
+
+/// Probes every video as 42 seconds long, except those with "broken" in
+/// them. The video server serves each video as its name.
+struct FakeProbe;
+
+#[async_trait::async_trait]
+impl VideoProbe for FakeProbe {
+    async fn probe(&self, video: &str) -> std::result::Result<VideoInfo, VideoError> {
+        let contents = std::fs::read(video).map_err(|e| VideoError(e.to_string()))?;
+        if String::from_utf8_lossy(&contents).contains("broken") {
+            return Err(VideoError("moov atom not found".to_string()));
+        }
+        Ok(VideoInfo {
+            duration_seconds: Some(42),
+            thumbnail: Some(THUMBNAIL.to_vec()),
+        })
+    }
+}
+
+#[get("/videos/:name")]
+async fn serve_video(name: Path<String>) -> String {
+    name.into_inner()
+}
+
+/// A server standing in for where videos are hosted, which stops when
+/// dropped.
+async fn video_server() -> TestClient {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(Router::new().get("/videos/:name", serve_video));
+    TestClient::new(app).await
+}
+
 fn database_url() -> String {
     dotenvy::dotenv().ok();
     std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for integration tests")
@@ -38,7 +95,7 @@ async fn setup() -> TestClient {
     let auth_config = AuthConfig::new("test-secret", 3600);
 
     let mut public_routes = PublicRoutes::new();
-    for (method, path) in auth::PUBLIC_ROUTES {
+    for (method, path) in auth::PUBLIC_ROUTES.iter().chain(media::PUBLIC_ROUTES) {
         public_routes.add(method, path);
     }
 
@@ -48,11 +105,16 @@ async fn setup() -> TestClient {
         .group("/api/v1/auth", auth::routes())
         .group("/api/v1/projects", projects::routes())
         .group("/api/v1/projects", testimonials::project_routes())
-        .group("/api/v1/testimonials", testimonials::routes());
+        .group("/api/v1/projects", tags::project_routes())
+        .group("/api/v1/testimonials", testimonials::routes())
+        .group("/api/v1/testimonials", tags::testimonial_tag_routes())
+        .group("/api/v1/media", media::routes());
 
     let app = Rapina::new()
         .with_introspection(false)
         .state(auth_config)
+        .state(SentimentAnalyzer::default())
+        .state(storage())
         .middleware(auth_middleware)
         .with_database(DatabaseConfig::new(database_url()))
         .await
@@ -62,6 +124,61 @@ async fn setup() -> TestClient {
     TestClient::new(app).await
 }
 
+fn storage() -> MediaStorage {
+    MediaStorage::new(
+        LocalStorage::new(std::env::temp_dir().join("reeverb-test-media")),
+        "/api/v1/media",
+    )
+}
+
+/// Runs the queued video and sentiment jobs that are due, as the worker
+/// would, until none are left. Jobs other tests' workers took are waited for.
+async fn run_jobs() {
+    let conn = DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect");
+    let pipeline = VideoPipeline::new(
+        FakeProbe,
+        transcribe::Stub(TRANSCRIPT.to_string()),
+        storage(),
+        SentimentAnalyzer::default(),
+        // The video server is local.
+        Destinations::Any,
+    );
+    let worker = Worker::new(conn.clone())
+        .handle(move |conn, job: ProcessVideo| {
+            let pipeline = pipeline.clone();
+            async move { pipeline.process(&conn, job).await }
+        })
+        .handle(|conn, job: AnalyzeSentiment| async move {
+            SentimentAnalyzer::default().analyze(&conn, job).await
+        });
+
+    loop {
+        worker.run_until_idle().await.expect("failed to run jobs");
+        let now = chrono::Utc::now().fixed_offset();
+        let pending = JobEntity::find()
+            .filter(JobColumn::Kind.is_in([ProcessVideo::KIND, AnalyzeSentiment::KIND]))
+            .filter(
+                Condition::any()
+                    .add(JobColumn::Status.eq(jobs::STATUS_RUNNING))
+                    .add(
+                        Condition::all()
+                            .add(JobColumn::Status.eq(jobs::STATUS_QUEUED))
+                            .add(JobColumn::RunAt.lte(now)),
+                    ),
+            )
+            .count(&conn)
+            .await
+            .unwrap();
+        if pending == 0 {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+}
+
 fn unique_email() -> String {
     format!("test-{}@example.com", Uuid::new_v4())
 }
@@ -90,7 +207,7 @@ async fn create_project(client: &TestClient, token: &str) -> String {
     let slug = unique_slug();
     let res = client
         .post("/api/v1/projects")
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({ "name": "Test Project", "slug": slug }))
         .send()
         .await;
@@ -101,8 +218,8 @@ async fn create_project(client: &TestClient, token: &str) -> String {
 
 async fn create_test_testimonial(client: &TestClient, token: &str, project_pid: &str) -> String {
     let res = client
-        .post(&format!("/api/v1/projects/{}/testimonials", project_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({
             "author_name": "Jane Doe",
             "content": "Great product!",
@@ -117,6 +234,24 @@ async fn create_test_testimonial(client: &TestClient, token: &str, project_pid:
     body["id"].as_str().unwrap().to_string()
 }
 
+async fn create_testimonial_with(
+    client: &TestClient,
+    token: &str,
+    project_pid: &str,
+    payload: serde_json::Value,
+) -> String {
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&payload)
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body: serde_json::Value = res.json();
+    body["id"].as_str().unwrap().to_string()
+}
+
 #[tokio::test]
 async fn create_testimonial_returns_201() {
     let client = setup().await;
@@ -124,8 +259,8 @@ async fn create_testimonial_returns_201() {
     let project_pid = create_project(&client, &token).await;
 
     let res = client
-        .post(&format!("/api/v1/projects/{}/testimonials", project_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
         .json(&json!({
             "author_name": "Jane Doe",
             "content": "Amazing service!",
@@ -151,6 +286,95 @@ async fn create_testimonial_returns_201() {
     assert!(body["created_at"].is_string());
 }
 
+#[tokio::test]
+async fn testimonial_with_a_taken_source_returns_409() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let source = json!({
+        "author_name": "Jane Doe",
+        "content": "Imported elsewhere",
+        "source_platform": "g2",
+        "source_id": "review-1"
+    });
+    create_testimonial_with(&client, &token, &project_pid, source.clone()).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&source)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    let other = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "John", "content": "Another review", "source_platform": "g2", "source_id": "review-2" }),
+    )
+    .await;
+    let res = client
+        .put(&format!("/api/v1/testimonials/{other}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "source_id": "review-1" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // Saving a testimonial with its own source is fine.
+    let res = client
+        .put(&format!("/api/v1/testimonials/{other}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "source_id": "review-2", "content": "Edited" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn invalid_fields_return_422() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "author_name": "Jane Doe",
+            "type": "hologram",
+            "rating": 9,
+            "author_email": "jane"
+        }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body: serde_json::Value = res.json();
+    let details = &body["error"]["details"];
+    assert_eq!(details["type"][0]["code"], "regex");
+    assert_eq!(details["rating"][0]["code"], "range");
+    assert_eq!(details["author_email"][0]["code"], "email");
+
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .patch(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "rating": 0, "author_url": null }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body: serde_json::Value = res.json();
+    let details = &body["error"]["details"];
+    assert_eq!(details["rating"][0]["code"], "range");
+    assert!(details["author_url"].is_null());
+}
+
 #[tokio::test]
 async fn list_testimonials_for_project() {
     let client = setup().await;
@@ -160,14 +384,15 @@ async fn list_testimonials_for_project() {
     create_test_testimonial(&client, &token, &project_pid).await;
 
     let res = client
-        .get(&format!("/api/v1/projects/{}/testimonials", project_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
     assert_eq!(res.status(), StatusCode::OK);
 
-    let body: Vec<serde_json::Value> = res.json();
+    let body: serde_json::Value = res.json();
+    let body = body["data"].as_array().unwrap();
     assert!(!body.is_empty());
     assert_eq!(body[0]["author_name"], "Jane Doe");
 }
@@ -182,181 +407,1795 @@ async fn list_testimonials_with_approved_filter() {
 
     let res = client
         .get(&format!(
-            "/api/v1/projects/{}/testimonials?is_approved=true",
-            project_pid
+            "/api/v1/projects/{project_pid}/testimonials?is_approved=true"
         ))
-        .header("Authorization", &format!("Bearer {}", token))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
     assert_eq!(res.status(), StatusCode::OK);
 
-    let body: Vec<serde_json::Value> = res.json();
+    let body: serde_json::Value = res.json();
+    let body = body["data"].as_array().unwrap();
     assert!(body.is_empty());
 }
 
 #[tokio::test]
-async fn get_testimonial_by_pid() {
+async fn list_testimonials_paginates_with_cursor() {
     let client = setup().await;
     let token = register_and_get_token(&client).await;
     let project_pid = create_project(&client, &token).await;
-    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let mut created = Vec::new();
+    for _ in 0..3 {
+        created.push(create_test_testimonial(&client, &token, &project_pid).await);
+    }
 
     let res = client
-        .get(&format!("/api/v1/testimonials/{}", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?limit=2&include_total=true"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
     assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["total"], 3);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert_eq!(body["data"][0]["id"], created[2]);
+    assert_eq!(body["data"][1]["id"], created[1]);
+    let cursor = body["next_cursor"].as_str().unwrap().to_string();
 
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?limit=2&cursor={cursor}"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
     let body: serde_json::Value = res.json();
-    assert_eq!(body["id"], testimonial_pid);
-    assert_eq!(body["author_name"], "Jane Doe");
-    assert_eq!(body["project_id"], project_pid);
+    assert!(body.get("total").is_none());
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["id"], created[0]);
+    assert!(body["next_cursor"].is_null());
 }
 
 #[tokio::test]
-async fn update_testimonial_partial() {
+async fn list_testimonials_invalid_cursor_returns_400() {
     let client = setup().await;
     let token = register_and_get_token(&client).await;
     let project_pid = create_project(&client, &token).await;
-    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
 
     let res = client
-        .put(&format!("/api/v1/testimonials/{}", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
-        .json(&json!({ "author_name": "John Smith" }))
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?cursor=not-a-cursor"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
-    assert_eq!(res.status(), StatusCode::OK);
-
-    let body: serde_json::Value = res.json();
-    assert_eq!(body["author_name"], "John Smith");
-    assert_eq!(body["content"], "Great product!");
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
-async fn delete_testimonial_returns_204() {
+async fn list_testimonials_filters_by_rating_type_and_video() {
     let client = setup().await;
     let token = register_and_get_token(&client).await;
     let project_pid = create_project(&client, &token).await;
-    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Low", "rating": 2 }),
+    )
+    .await;
+    let high = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "High", "rating": 5, "language": "en" }),
+    )
+    .await;
+    let video = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Video",
+            "type": "video",
+            "rating": 4,
+            "video_url": "https://example.com/v.mp4"
+        }),
+    )
+    .await;
 
     let res = client
-        .delete(&format!("/api/v1/testimonials/{}", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?min_rating=4&max_rating=5"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
-    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    let ids: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec![video.as_str(), high.as_str()]);
 
     let res = client
-        .get(&format!("/api/v1/testimonials/{}", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?type=video&has_video=true"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
-    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["id"], video);
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?language=en&has_video=false"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["id"], high);
 }
 
 #[tokio::test]
-async fn approve_toggle() {
+async fn export_testimonials_honors_filters_in_each_format() {
     let client = setup().await;
     let token = register_and_get_token(&client).await;
     let project_pid = create_project(&client, &token).await;
-    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Low", "rating": 2 }),
+    )
+    .await;
+    create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "High, Esq.",
+            "content": "Said \"wow\"",
+            "rating": 5,
+            "video_url": "https://example.com/v.mp4"
+        }),
+    )
+    .await;
+    create_test_testimonial(&client, &token, &project_pid).await;
+    let export = format!("/api/v1/projects/{project_pid}/testimonials/export");
 
     let res = client
-        .post(&format!("/api/v1/testimonials/{}/approve", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!(
+            "{export}?format=csv&min_rating=4&sort=rating&order=asc"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
-
     assert_eq!(res.status(), StatusCode::OK);
+    assert!(
+        res.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/csv")
+    );
+    assert!(
+        res.headers()["content-disposition"]
+            .to_str()
+            .unwrap()
+            .ends_with(".csv\"")
+    );
+    let csv = res.text();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("id,type,status,content,"));
+    assert!(csv.contains("\"High, Esq.\""));
+    assert!(csv.contains("\"Said \"\"wow\"\"\""));
+    assert!(csv.contains("https://example.com/v.mp4"));
+
+    let res = client
+        .get(&format!("{export}?min_rating=4"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
     let body: serde_json::Value = res.json();
-    assert_eq!(body["is_approved"], true);
+    let data = body.as_array().unwrap();
+    assert_eq!(data.len(), 2);
+    assert!(data.iter().all(|t| t["tags"].is_array()));
 
     let res = client
-        .post(&format!("/api/v1/testimonials/{}/approve", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!("{export}?format=ndjson"))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
+    assert_eq!(
+        res.headers()["content-type"].to_str().unwrap(),
+        "application/x-ndjson"
+    );
+    let text = res.text();
+    let rows: Vec<serde_json::Value> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(rows.len(), 3);
+}
+
+#[tokio::test]
+async fn export_pages_through_every_testimonial() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let conn = DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect");
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"INSERT INTO testimonials (pid, project_id, author_name, rating)
+           SELECT gen_random_uuid(), projects.id, 'Bulk ' || n, 1 + n % 5
+           FROM projects, generate_series(1, $2) AS n
+           WHERE projects.pid = $1"#,
+        [
+            Uuid::parse_str(&project_pid).unwrap().into(),
+            (testimonials::export::PAGE_SIZE as i64 + 1).into(),
+        ],
+    ))
+    .await
+    .unwrap();
+    let export = format!("/api/v1/projects/{project_pid}/testimonials/export");
+
+    let mut ids = HashSet::new();
+    let mut pages = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let url = match &cursor {
+            Some(cursor) => format!("{export}?format=ndjson&sort=rating&cursor={cursor}"),
+            None => format!("{export}?format=ndjson&sort=rating"),
+        };
+        let res = client
+            .get(&url)
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        cursor = res
+            .headers()
+            .get("x-next-cursor")
+            .map(|c| c.to_str().unwrap().to_string());
+        let text = res.text();
+        pages.push(text.lines().count() as u64);
+        for line in text.lines() {
+            let row: serde_json::Value = serde_json::from_str(line).unwrap();
+            ids.insert(row["id"].as_str().unwrap().to_string());
+        }
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(pages, [testimonials::export::PAGE_SIZE, 1]);
+    assert_eq!(ids.len() as u64, testimonials::export::PAGE_SIZE + 1);
 
+    // Exports that fit one page have no next one.
+    let res = client
+        .get(&format!("{export}?format=ndjson&min_rating=5"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
     assert_eq!(res.status(), StatusCode::OK);
-    let body: serde_json::Value = res.json();
-    assert_eq!(body["is_approved"], false);
+    assert!(res.headers().get("x-next-cursor").is_none());
+    assert_eq!(
+        res.text().lines().count() as u64,
+        (testimonials::export::PAGE_SIZE + 1) / 5
+    );
+
+    let res = client
+        .get(&format!("{export}?cursor=not-a-cursor"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
-async fn feature_toggle() {
+async fn list_testimonials_sorts_by_rating_across_pages() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let three = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Three", "rating": 3 }),
+    )
+    .await;
+    let unrated = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Unrated" }),
+    )
+    .await;
+    let five = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Five", "rating": 5 }),
+    )
+    .await;
+    let four = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Four", "rating": 4 }),
+    )
+    .await;
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut url =
+            format!("/api/v1/projects/{project_pid}/testimonials?sort=rating&order=desc&limit=2");
+        if let Some(ref c) = cursor {
+            url.push_str(&format!("&cursor={c}"));
+        }
+
+        let res = client
+            .get(&url)
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let body: serde_json::Value = res.json();
+        for t in body["data"].as_array().unwrap() {
+            seen.push(t["id"].as_str().unwrap().to_string());
+        }
+        match body["next_cursor"].as_str() {
+            Some(c) => cursor = Some(c.to_string()),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen, vec![five, four, three, unrated]);
+}
+
+#[tokio::test]
+async fn list_testimonials_invalid_filter_returns_400() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?created_after=yesterday"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn search_testimonials_ranks_and_highlights_matches() {
     let client = setup().await;
     let token = register_and_get_token(&client).await;
     let project_pid = create_project(&client, &token).await;
-    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let strong = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Ana",
+            "content": "Onboarding was painless. The onboarding checklist <b>rocks</b>.",
+            "language": "en"
+        }),
+    )
+    .await;
+    let weak = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Ben",
+            "content": "Great support team.",
+            "transcription": "We onboarded the whole team in a day.",
+            "language": "en"
+        }),
+    )
+    .await;
+    create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Cleo", "content": "Pricing is fair." }),
+    )
+    .await;
 
     let res = client
-        .post(&format!("/api/v1/testimonials/{}/feature", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/search?q=onboarding&include_total=true"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
 
     assert_eq!(res.status(), StatusCode::OK);
     let body: serde_json::Value = res.json();
-    assert_eq!(body["is_featured"], true);
+    assert_eq!(body["total"], 2);
+    let hits = body["data"].as_array().unwrap();
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0]["testimonial"]["id"], strong);
+    assert_eq!(hits[1]["testimonial"]["id"], weak);
+    assert!(hits[0]["rank"].as_f64().unwrap() > hits[1]["rank"].as_f64().unwrap());
+
+    let snippet = hits[0]["snippet"].as_str().unwrap();
+    assert!(snippet.contains("<mark>Onboarding</mark>"));
+    assert!(snippet.contains("&lt;b&gt;"));
 
     let res = client
-        .post(&format!("/api/v1/testimonials/{}/feature", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token))
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/search?q=onboarding&limit=1"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"][0]["testimonial"]["id"], strong);
+    let cursor = body["next_cursor"].as_str().unwrap().to_string();
 
-    assert_eq!(res.status(), StatusCode::OK);
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/search?q=onboarding&limit=1&cursor={cursor}"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
     let body: serde_json::Value = res.json();
-    assert_eq!(body["is_featured"], false);
+    assert_eq!(body["data"][0]["testimonial"]["id"], weak);
+    assert!(body["next_cursor"].is_null());
 }
 
 #[tokio::test]
-async fn ownership_enforcement_returns_403() {
+async fn search_testimonials_matches_author_and_respects_language() {
     let client = setup().await;
-    let token_owner = register_and_get_token(&client).await;
-    let token_other = register_and_get_token(&client).await;
-    let project_pid = create_project(&client, &token_owner).await;
-    let testimonial_pid = create_test_testimonial(&client, &token_owner, &project_pid).await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let spanish = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Lucía",
+            "author_company": "Acme Logistics",
+            "content": "Las integraciones funcionan perfectamente.",
+            "language": "es"
+        }),
+    )
+    .await;
 
     let res = client
-        .get(&format!("/api/v1/testimonials/{}", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/search?q=integraci%C3%B3n"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"][0]["testimonial"]["id"], spanish);
 
-    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/search?q=acme"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"][0]["testimonial"]["id"], spanish);
 
     let res = client
-        .put(&format!("/api/v1/testimonials/{}", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
-        .json(&json!({ "author_name": "Hacked" }))
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/search?q=acme&language=en"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert!(body["data"].as_array().unwrap().is_empty());
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/search?q=%20"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
 
-    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+#[tokio::test]
+async fn search_finds_candidates_through_the_index() {
+    run_migrations_once().await;
+    let conn = DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect");
+
+    let query = Testimonial::find()
+        .select_only()
+        .column(TestimonialColumn::Id)
+        .filter(search::matches("onboarding"))
+        .build(DbBackend::Postgres);
+    let explain = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        format!("EXPLAIN {}", query.sql),
+        query.values.map(|values| values.0).unwrap_or_default(),
+    );
+
+    // Small test tables are cheaper to scan, so only the index is left.
+    let txn = conn.begin().await.unwrap();
+    txn.execute_unprepared("SET LOCAL enable_seqscan = off")
+        .await
+        .unwrap();
+    let plan: Vec<String> = txn
+        .query_all(explain)
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.try_get("", "QUERY PLAN").unwrap())
+        .collect();
+    txn.rollback().await.unwrap();
+
+    assert!(
+        plan.iter()
+            .any(|line| line.contains("Bitmap Index Scan on idx_testimonials_search")),
+        "{plan:#?}"
+    );
+}
+
+async fn get_testimonial(client: &TestClient, token: &str, pid: &str) -> serde_json::Value {
+    let res = client
+        .get(&format!("/api/v1/testimonials/{pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    res.json()
+}
+
+#[tokio::test]
+async fn sentiment_is_analyzed_unless_given() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let praise = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Ann", "content": "Really great support, I love it!" }),
+    )
+    .await;
+    let body = get_testimonial(&client, &token, &praise).await;
+    assert!(body["sentiment"].is_null());
+    run_jobs().await;
+    let body = get_testimonial(&client, &token, &praise).await;
+    assert_eq!(body["sentiment"], "positive");
+    assert!(body["sentiment_score"].as_f64().unwrap() > 0.5);
+
+    let complaint = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Bo", "content": "Buggy and slow. Not good, a waste of money." }),
+    )
+    .await;
+    run_jobs().await;
+    let body = get_testimonial(&client, &token, &complaint).await;
+    assert_eq!(body["sentiment"], "negative");
+    assert!(body["sentiment_score"].as_f64().unwrap() < -0.5);
+
+    let given = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Cy",
+            "content": "Great product!",
+            "sentiment": "neutral",
+            "sentiment_score": 0.0
+        }),
+    )
+    .await;
+    let body = get_testimonial(&client, &token, &given).await;
+    assert_eq!(body["sentiment"], "neutral");
+
+    let foreign = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Di", "content": "Produit génial !", "language": "fr" }),
+    )
+    .await;
+    run_jobs().await;
+    let body = get_testimonial(&client, &token, &foreign).await;
+    assert!(body["sentiment"].is_null());
 
     let res = client
-        .delete(&format!("/api/v1/testimonials/{}", testimonial_pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .patch(&format!("/api/v1/testimonials/{praise}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "content": "Terrible. It crashes constantly." }))
         .send()
         .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert!(body["sentiment"].is_null());
+    run_jobs().await;
+    let body = get_testimonial(&client, &token, &praise).await;
+    assert_eq!(body["sentiment"], "negative");
 
-    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = client
+        .post(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/sentiment"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["analyzed"], 4);
+    assert_eq!(body["updated"], 1);
+    assert_eq!(body["failed"], 0);
+    let body = get_testimonial(&client, &token, &given).await;
+    assert_eq!(body["sentiment"], "positive");
+}
+
+#[tokio::test]
+async fn language_is_detected_and_filterable() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let english = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Ann",
+            "content": "The team was always quick to help and the product keeps getting better."
+        }),
+    )
+    .await;
+    let german = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Jonas",
+            "content": "Wir sind mit der Software sehr zufrieden und würden sie jederzeit weiterempfehlen."
+        }),
+    )
+    .await;
+    let brazilian = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Bia", "content": "Muito bom!", "language": "pt-BR" }),
+    )
+    .await;
+    let short = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Cy", "content": "Great!" }),
+    )
+    .await;
+
+    assert_eq!(
+        get_testimonial(&client, &token, &english).await["language"],
+        "en"
+    );
+    assert_eq!(
+        get_testimonial(&client, &token, &german).await["language"],
+        "de"
+    );
+    assert!(get_testimonial(&client, &token, &short).await["language"].is_null());
+
+    for (filter, expected) in [
+        ("de", vec![german.as_str()]),
+        ("pt", vec![brazilian.as_str()]),
+        ("PT-br", vec![brazilian.as_str()]),
+        ("pt-PT", vec![]),
+        ("en,de", vec![german.as_str(), english.as_str()]),
+    ] {
+        let res = client
+            .get(&format!(
+                "/api/v1/projects/{project_pid}/testimonials?language={filter}"
+            ))
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: serde_json::Value = res.json();
+        let ids: Vec<&str> = body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, expected, "language={filter}");
+    }
 
     let res = client
-        .get(&format!("/api/v1/projects/{}/testimonials", project_pid))
-        .header("Authorization", &format!("Bearer {}", token_other))
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?language=en;drop"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
         .send()
         .await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
 
-    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+/// Runs the queued jobs and returns the testimonial once its video is no
+/// longer processing.
+async fn wait_for_video(client: &TestClient, token: &str, pid: &str) -> serde_json::Value {
+    run_jobs().await;
+    let body = get_testimonial(client, token, pid).await;
+    assert_ne!(
+        body["video_status"], "processing",
+        "video of {pid} was not processed"
+    );
+    body
+}
+
+#[tokio::test]
+async fn video_is_probed_transcribed_and_written_back() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+    let videos = video_server().await;
+    let video_url = |name: &str| format!("http://{}/videos/{name}", videos.addr());
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &auth)
+        .json(&json!({
+            "author_name": "Video Vera",
+            "type": "video",
+            "video_url": video_url("vera.mp4")
+        }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["video_status"], "processing");
+    let pid = body["id"].as_str().unwrap().to_string();
+
+    let body = wait_for_video(&client, &token, &pid).await;
+    assert_eq!(body["video_status"], "ready");
+    assert!(body["video_error"].is_null());
+    assert_eq!(body["video_duration_seconds"], 42);
+    assert_eq!(body["transcription"], TRANSCRIPT);
+    assert_eq!(body["language"], "en");
+    assert_eq!(body["sentiment"], "positive");
+
+    // The thumbnail is served without auth so widgets can embed it.
+    let thumbnail_url = body["video_thumbnail_url"].as_str().unwrap().to_string();
+    assert_eq!(thumbnail_url, format!("/api/v1/media/{pid}.jpg"));
+    let res = client.get(&thumbnail_url).send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["content-type"], "image/jpeg");
+    assert_eq!(res.bytes().as_ref(), THUMBNAIL);
+    let res = client
+        .get("/api/v1/media/..%2F..%2Fetc%2Fpasswd")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // What the client set is kept.
+    let given = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Given Gus",
+            "video_url": video_url("gus.mp4"),
+            "video_thumbnail_url": "https://cdn.example.com/gus.jpg",
+            "transcription": "Mine",
+            "sentiment": "neutral"
+        }),
+    )
+    .await;
+    let body = wait_for_video(&client, &token, &given).await;
+    assert_eq!(body["video_status"], "ready");
+    assert_eq!(body["video_duration_seconds"], 42);
+    assert_eq!(
+        body["video_thumbnail_url"],
+        "https://cdn.example.com/gus.jpg"
+    );
+    assert_eq!(body["transcription"], "Mine");
+    assert_eq!(body["sentiment"], "neutral");
+
+    // A broken video fails, keeping the error, and can be processed again
+    // once it is replaced.
+    let res = client
+        .patch(&format!("/api/v1/testimonials/{given}"))
+        .header("Authorization", &auth)
+        .json(&json!({ "video_url": video_url("broken.mp4") }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["video_status"], "processing");
+    assert!(body["video_thumbnail_url"].is_null());
+    assert!(body["video_duration_seconds"].is_null());
+    assert!(body["transcription"].is_null());
+
+    let body = wait_for_video(&client, &token, &given).await;
+    assert_eq!(body["video_status"], "failed");
+    assert!(
+        body["video_error"]
+            .as_str()
+            .unwrap()
+            .contains("moov atom not found")
+    );
+    assert!(body["video_duration_seconds"].is_null());
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{given}/video/process"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["video_status"], "processing");
+    assert!(body["video_error"].is_null());
+    let body = wait_for_video(&client, &token, &given).await;
+    assert_eq!(body["video_status"], "failed");
+
+    // Only remote videos and stored media are fetched.
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &auth)
+        .json(&json!({ "author_name": "Local Lou", "video_url": "file:///etc/passwd" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["details"]["video_url"][0]["code"], "url");
+
+    let local = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({ "author_name": "Local Lou", "video_url": "/etc/passwd" }),
+    )
+    .await;
+    let body = wait_for_video(&client, &token, &local).await;
+    assert_eq!(body["video_status"], "failed");
+    assert!(body["video_duration_seconds"].is_null());
+
+    // Removing the video clears its processing state.
+    let res = client
+        .patch(&format!("/api/v1/testimonials/{local}"))
+        .header("Authorization", &auth)
+        .json(&json!({ "video_url": null }))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert!(body["video_status"].is_null());
+    assert!(body["video_error"].is_null());
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{local}/video/process"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn remote_videos_on_private_addresses_are_not_fetched() {
+    let videos = video_server().await;
+    let port = videos.addr().port();
+
+    let download = Fetcher::new(Destinations::Any)
+        .remote(&format!("http://127.0.0.1:{port}/videos/vera.mp4"))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(download.path()).unwrap(), b"vera.mp4");
+    let path = download.path().to_path_buf();
+    drop(download);
+    assert!(!path.exists());
+
+    let public = Fetcher::new(Destinations::Public);
+    for url in [
+        format!("http://127.0.0.1:{port}/videos/vera.mp4"),
+        format!("http://localhost:{port}/videos/vera.mp4"),
+        "http://169.254.169.254/latest/meta-data/".to_string(),
+    ] {
+        let Err(e) = public.remote(&url).await else {
+            panic!("{url} was fetched");
+        };
+        assert!(e.to_string().contains("public"), "{url}: {e}");
+    }
+}
+
+#[tokio::test]
+async fn get_testimonial_by_pid() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["id"], testimonial_pid);
+    assert_eq!(body["author_name"], "Jane Doe");
+    assert_eq!(body["project_id"], project_pid);
+}
+
+#[tokio::test]
+async fn update_testimonial_partial() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "author_name": "John Smith" }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["author_name"], "John Smith");
+    assert_eq!(body["content"], "Great product!");
+}
+
+#[tokio::test]
+async fn patch_testimonial_clears_nullable_fields() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .patch(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "author_email": null, "rating": 4 }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let body: serde_json::Value = res.json();
+    assert!(body["author_email"].is_null());
+    assert_eq!(body["rating"], 4);
+    assert_eq!(body["author_name"], "Jane Doe");
+    assert_eq!(body["content"], "Great product!");
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}/revisions"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    let changes = &body["data"][0]["changes"]["author_email"];
+    assert_eq!(changes["before"], "jane@example.com");
+    assert!(changes["after"].is_null());
+
+    let res = client
+        .patch(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "author_name": null }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn stale_if_match_returns_412() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let etag = res.headers()["etag"].to_str().unwrap().to_string();
+
+    let res = client
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &etag)
+        .json(&json!({ "content": "Edited in one tab" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let fresh = res.headers()["etag"].to_str().unwrap().to_string();
+
+    let res = client
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &etag)
+        .json(&json!({ "content": "Edited in another tab" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+    // Any write moves the ETag on, not just PUT.
+    client
+        .post(&format!("/api/v1/testimonials/{testimonial_pid}/feature"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    let res = client
+        .delete(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &fresh)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let current = res.headers()["etag"].to_str().unwrap().to_string();
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["content"], "Edited in one tab");
+
+    let res = client
+        .delete(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("If-Match", &current)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn updates_are_recorded_and_can_be_reverted() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    for payload in [
+        json!({ "content": "Great product, honestly!", "rating": 4 }),
+        json!({ "content": "A great product." }),
+    ] {
+        let res = client
+            .put(&format!("/api/v1/testimonials/{testimonial_pid}"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .json(&payload)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["content"], "A great product.");
+    assert_eq!(body["original_content"], "Great product!");
+    assert_eq!(body["content_edited"], true);
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}/revisions"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    let revisions = body["data"].as_array().unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[0]["action"], "update");
+    assert_eq!(
+        revisions[0]["changes"]["content"]["before"],
+        "Great product, honestly!"
+    );
+    assert!(revisions[0]["changes"]["rating"].is_null());
+    assert_eq!(revisions[1]["changes"]["rating"]["before"], 5);
+    assert_eq!(revisions[1]["changes"]["rating"]["after"], 4);
+    assert!(revisions[1]["user_id"].is_string());
+    let first_revision = revisions[1]["id"].as_str().unwrap().to_string();
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{testimonial_pid}/revert"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "revision_id": first_revision }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["content"], "Great product!");
+    assert_eq!(body["rating"], 5);
+    assert_eq!(body["content_edited"], false);
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}/revisions"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"][0]["action"], "revert");
+    assert_eq!(body["data"][0]["reverted_revision_id"], first_revision);
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{testimonial_pid}/revert"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "revision_id": Uuid::new_v4() }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+async fn tag_testimonial(
+    client: &TestClient,
+    token: &str,
+    project_pid: &str,
+    pid: &str,
+    tag: &str,
+) {
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "name": tag }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body: serde_json::Value = res.json();
+    let tag_id = body["id"].as_str().unwrap().to_string();
+
+    let res = client
+        .put(&format!("/api/v1/testimonials/{pid}/tags"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "tag_ids": [tag_id] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn duplicates_are_detected_and_merged() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let original = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Jane Doe",
+            "author_email": "jane@example.com",
+            "content": "The onboarding was smooth and support answered every question within minutes."
+        }),
+    )
+    .await;
+    let resubmitted = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "jane  doe",
+            "author_email": "Jane+reviews@Example.com",
+            "content": "The onboarding was smooth, and support answered every question within minutes!",
+            "rating": 5,
+            "author_company": "Acme"
+        }),
+    )
+    .await;
+    let copied = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "J. D.",
+            "content": "The onboarding was smooth and support answered every question within minutes."
+        }),
+    )
+    .await;
+    let unrelated = create_testimonial_with(
+        &client,
+        &token,
+        &project_pid,
+        json!({
+            "author_name": "Jane Doe",
+            "author_email": "jane@example.com",
+            "content": "Pricing is fair for what you get, and the reports save us hours every week."
+        }),
+    )
+    .await;
+    tag_testimonial(&client, &token, &project_pid, &original, "onboarding").await;
+    tag_testimonial(&client, &token, &project_pid, &resubmitted, "support").await;
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/duplicates"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["scanned"], 4);
+    let groups = body["data"].as_array().unwrap();
+    assert_eq!(groups.len(), 1);
+    let ids: Vec<&str> = groups[0]["testimonials"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        [copied.as_str(), resubmitted.as_str(), original.as_str()]
+    );
+    assert!(!ids.contains(&unrelated.as_str()));
+    assert_eq!(
+        groups[0]["reasons"],
+        json!(["same_email", "same_name", "similar_content"])
+    );
+    assert!(groups[0]["similarity"].as_f64().unwrap() > 0.85);
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{original}/merge"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "duplicate_ids": [original] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{original}/merge"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "duplicate_ids": [resubmitted, copied] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(
+        body["content"],
+        "The onboarding was smooth and support answered every question within minutes."
+    );
+    assert_eq!(body["rating"], 5);
+    assert_eq!(body["author_company"], "Acme");
+    let mut tags: Vec<&str> = body["tags"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    tags.sort();
+    assert_eq!(tags, ["onboarding", "support"]);
+
+    for merged in [&resubmitted, &copied] {
+        let res = client
+            .get(&format!("/api/v1/testimonials/{merged}"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{original}/revisions"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"][0]["action"], "merge");
+    assert_eq!(
+        body["data"][0]["merged_testimonial_ids"],
+        json!([resubmitted, copied])
+    );
+    assert_eq!(body["data"][0]["changes"]["rating"]["after"], 5);
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/duplicates"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"], json!([]));
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{original}/merge"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "duplicate_ids": [resubmitted] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn delete_testimonial_returns_204() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .delete(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn deleted_testimonial_moves_to_trash_and_restores() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .delete(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let res = client
+        .get(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert!(body["data"].as_array().unwrap().is_empty());
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/trash"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"][0]["id"], testimonial_pid);
+    assert!(body["data"][0]["deleted_at"].is_string());
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{testimonial_pid}/restore"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert!(body["deleted_at"].is_null());
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn purge_removes_expired_trash_only() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let expired = create_test_testimonial(&client, &token, &project_pid).await;
+    let recent = create_test_testimonial(&client, &token, &project_pid).await;
+
+    for pid in [&expired, &recent] {
+        client
+            .delete(&format!("/api/v1/testimonials/{pid}"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+    }
+
+    let conn = DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect");
+    conn.execute_unprepared(&format!(
+        "UPDATE testimonials SET deleted_at = now() - interval '40 days' WHERE pid = '{expired}'"
+    ))
+    .await
+    .unwrap();
+
+    trash::purge(&conn, chrono::Duration::days(30))
+        .await
+        .unwrap();
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/trash"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    let data = body["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0]["id"], recent);
+}
+
+#[tokio::test]
+async fn approve_is_idempotent() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    for _ in 0..2 {
+        let res = client
+            .post(&format!("/api/v1/testimonials/{testimonial_pid}/approve"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: serde_json::Value = res.json();
+        assert_eq!(body["status"], "approved");
+        assert_eq!(body["is_approved"], true);
+        assert!(body["moderated_by"].is_string());
+        assert!(body["moderated_at"].is_string());
+    }
+}
+
+#[tokio::test]
+async fn moderation_workflow() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "pending");
+    assert!(body["moderated_by"].is_null());
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{testimonial_pid}/reject"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "reason": "Off-topic" }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "rejected");
+    assert_eq!(body["is_approved"], false);
+    assert_eq!(body["rejection_reason"], "Off-topic");
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?status=rejected"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{testimonial_pid}/archive"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "archived");
+    assert!(body["rejection_reason"].is_null());
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{testimonial_pid}/approve"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // An edit that asks for a transition that is not allowed is not saved.
+    let res = client
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "author_name": "Edited", "is_approved": true }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_ne!(body["author_name"], "Edited");
+    assert_eq!(body["status"], "archived");
+
+    let res = client
+        .post(&format!("/api/v1/testimonials/{testimonial_pid}/reopen"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "pending");
+
+    let res = client
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "is_approved": true }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "approved");
+}
+
+#[tokio::test]
+async fn feature_and_unfeature() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    for _ in 0..2 {
+        let res = client
+            .post(&format!("/api/v1/testimonials/{testimonial_pid}/feature"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        let body: serde_json::Value = res.json();
+        assert_eq!(body["is_featured"], true);
+    }
+
+    let res = client
+        .delete(&format!("/api/v1/testimonials/{testimonial_pid}/feature"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["is_featured"], false);
+}
+
+#[tokio::test]
+async fn bulk_approve_by_ids() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let first = create_test_testimonial(&client, &token, &project_pid).await;
+    let second = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials/bulk"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "ids": [first, second], "action": "approve" }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r["ok"] == true));
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials?status=approved"
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn bulk_rolls_back_when_any_item_fails() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let token_other = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let other_project_pid = create_project(&client, &token_other).await;
+    let mine = create_test_testimonial(&client, &token, &project_pid).await;
+    let theirs = create_test_testimonial(&client, &token_other, &other_project_pid).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials/bulk"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "ids": [mine, theirs], "action": "delete" }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = res.json();
+    let results = body["error"]["details"]["results"].as_array().unwrap();
+    assert_eq!(results[0]["ok"], true);
+    assert_eq!(results[1]["ok"], false);
+    assert_eq!(results[1]["error"], "you do not own this project");
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{mine}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn bulk_move_to_another_project() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let target_pid = create_project(&client, &token).await;
+    let testimonial_pid = create_test_testimonial(&client, &token, &project_pid).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials/bulk"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "ids": [testimonial_pid],
+            "action": "move",
+            "project_id": target_pid
+        }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["project_id"], target_pid);
+}
+
+#[tokio::test]
+async fn ownership_enforcement_returns_403() {
+    let client = setup().await;
+    let token_owner = register_and_get_token(&client).await;
+    let token_other = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token_owner).await;
+    let testimonial_pid = create_test_testimonial(&client, &token_owner, &project_pid).await;
+
+    let res = client
+        .get(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token_other}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .put(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token_other}"))
+        .json(&json!({ "author_name": "Hacked" }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .delete(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &format!("Bearer {token_other}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .get(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token_other}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .get(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/duplicates"
+        ))
+        .header("Authorization", &format!("Bearer {token_other}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .post(&format!(
+            "/api/v1/projects/{project_pid}/testimonials/sentiment"
+        ))
+        .header("Authorization", &format!("Bearer {token_other}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .post(&format!(
+            "/api/v1/testimonials/{testimonial_pid}/video/process"
+        ))
+        .header("Authorization", &format!("Bearer {token_other}"))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let other_project = create_project(&client, &token_other).await;
+    let other_testimonial = create_test_testimonial(&client, &token_other, &other_project).await;
+    let res = client
+        .post(&format!("/api/v1/testimonials/{other_testimonial}/merge"))
+        .header("Authorization", &format!("Bearer {token_other}"))
+        .json(&json!({ "duplicate_ids": [testimonial_pid] }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
 }
 
 #[tokio::test]
@@ -0,0 +1,938 @@
+use rapina::auth::{AuthMiddleware, PublicRoutes};
+use rapina::database::DatabaseConfig;
+use rapina::prelude::*;
+use rapina::sea_orm::sea_query::Expr;
+use rapina::sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use rapina::testing::TestClient;
+use serde_json::json;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use reeverb::api::v1::auth;
+use reeverb::api::v1::media;
+use reeverb::api::v1::projects;
+use reeverb::api::v1::testimonials;
+use reeverb::api::v1::uploads::{self, expiry, limits::UploadLimits};
+use reeverb::db::entities::upload::{Column as UploadColumn, Entity as Upload};
+use reeverb::db::migrations::Migrator;
+use reeverb::jobs::Worker;
+use reeverb::net::Destinations;
+use reeverb::sentiment::SentimentAnalyzer;
+use reeverb::storage::local::LocalStorage;
+use reeverb::storage::{MIN_PART_BYTES, MediaStorage};
+use reeverb::video::{ProcessVideo, VideoError, VideoInfo, VideoPipeline, VideoProbe, transcribe};
+
+static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
+
+/// The start of a PNG file, enough to pass as one until it is decoded.
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0";
+
+/// The start of an MP4 file: an `ftyp` box.
+const MP4_HEAD: &[u8] = b"\0\0\0\x18ftypisom\0\0\x02\0isomiso2";
+
+/// Probes only files that exist on disk, as uploaded videos are read from
+/// local storage.
+struct LocalFileProbe;
+
+#[async_trait::async_trait]
+impl VideoProbe for LocalFileProbe {
+    async fn probe(&self, video: &str) -> std::result::Result<VideoInfo, VideoError> {
+        if !std::path::Path::new(video).is_file() {
+            return Err(VideoError(format!("{video} is not a local file")));
+        }
+        Ok(VideoInfo {
+            duration_seconds: Some(7),
+            thumbnail: None,
+        })
+    }
+}
+
+fn database_url() -> String {
+    dotenvy::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for integration tests")
+}
+
+async fn run_migrations_once() {
+    MIGRATIONS
+        .get_or_init(|| async {
+            let config = DatabaseConfig::new(database_url());
+            let conn = config.connect().await.expect("failed to connect");
+
+            use rapina::sea_orm_migration::MigratorTrait;
+            Migrator::up(&conn, None)
+                .await
+                .expect("failed to run migrations");
+        })
+        .await;
+}
+
+fn storage() -> MediaStorage {
+    MediaStorage::new(
+        LocalStorage::new(std::env::temp_dir().join("reeverb-test-media")),
+        "/api/v1/media",
+    )
+}
+
+async fn setup() -> TestClient {
+    run_migrations_once().await;
+
+    let auth_config = AuthConfig::new("test-secret", 3600);
+
+    let mut public_routes = PublicRoutes::new();
+    for (method, path) in auth::PUBLIC_ROUTES.iter().chain(media::PUBLIC_ROUTES) {
+        public_routes.add(method, path);
+    }
+
+    let auth_middleware = AuthMiddleware::with_public_routes(auth_config.clone(), public_routes);
+
+    let router = Router::new()
+        .group("/api/v1/auth", auth::routes())
+        .group("/api/v1/projects", projects::routes())
+        .group("/api/v1/projects", testimonials::project_routes())
+        .group("/api/v1/projects", uploads::project_routes())
+        .group("/api/v1/testimonials", testimonials::routes())
+        .group("/api/v1/uploads", uploads::routes())
+        .group("/api/v1/media", media::routes());
+
+    let storage = storage();
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(auth_config)
+        .state(SentimentAnalyzer::default())
+        .state(storage)
+        .state(UploadLimits {
+            max_image_bytes: 1024 * 1024,
+            max_video_bytes: 2 * MIN_PART_BYTES,
+        })
+        .middleware(auth_middleware)
+        .with_database(DatabaseConfig::new(database_url()))
+        .await
+        .expect("failed to connect to test database")
+        .router(router);
+
+    TestClient::new(app).await
+}
+
+fn unique_email() -> String {
+    format!("test-{}@example.com", Uuid::new_v4())
+}
+
+async fn register_and_get_token(client: &TestClient) -> String {
+    let res = client
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": unique_email(),
+            "password": "password123",
+            "name": "Test User"
+        }))
+        .send()
+        .await;
+
+    let body: serde_json::Value = res.json();
+    body["token"].as_str().unwrap().to_string()
+}
+
+async fn create_project(client: &TestClient, token: &str) -> String {
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "name": "Test Project",
+            "slug": format!("project-{}", Uuid::new_v4())
+        }))
+        .send()
+        .await;
+
+    let body: serde_json::Value = res.json();
+    body["id"].as_str().unwrap().to_string()
+}
+
+async fn create_upload(
+    client: &TestClient,
+    token: &str,
+    project_pid: &str,
+    content_type: &str,
+    size: u64,
+) -> serde_json::Value {
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/uploads"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "filename": "file",
+            "content_type": content_type,
+            "size": size
+        }))
+        .send()
+        .await;
+
+    assert_eq!(res.status(), StatusCode::CREATED);
+    res.json()
+}
+
+/// A `width` by `height` PNG.
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let image = image::RgbaImage::from_pixel(width, height, image::Rgba([200, 30, 30, 255]));
+    let mut out = Vec::new();
+    image::DynamicImage::ImageRgba8(image)
+        .write_to(&mut std::io::Cursor::new(&mut out), image::ImageFormat::Png)
+        .unwrap();
+    out
+}
+
+/// A `width` by `height` GIF of two frames, red then blue.
+fn animated_gif(width: u32, height: u32) -> Vec<u8> {
+    let frame = |color: [u8; 4]| {
+        image::Frame::new(image::RgbaImage::from_pixel(
+            width,
+            height,
+            image::Rgba(color),
+        ))
+    };
+    let mut out = Vec::new();
+    image::codecs::gif::GifEncoder::new(&mut out)
+        .encode_frames([frame([200, 30, 30, 255]), frame([30, 30, 200, 255])])
+        .unwrap();
+    out
+}
+
+/// A `width` by `height` JPEG whose EXIF says to rotate it a quarter turn
+/// clockwise, followed by `marker` as a stand-in for GPS data.
+fn jpeg_with_exif(width: u32, height: u32, marker: &[u8]) -> Vec<u8> {
+    let image = image::RgbImage::from_pixel(width, height, image::Rgb([30, 120, 200]));
+    let mut jpeg = Vec::new();
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(
+            &mut std::io::Cursor::new(&mut jpeg),
+            image::ImageFormat::Jpeg,
+        )
+        .unwrap();
+
+    // A little-endian TIFF holding one IFD entry: Orientation (0x0112), a
+    // SHORT of 6.
+    let mut exif = b"Exif\0\0II*\0\x08\0\0\0\x01\0".to_vec();
+    exif.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0]);
+    exif.extend_from_slice(marker);
+    let len = (exif.len() + 2) as u16;
+
+    let mut out = jpeg[..2].to_vec();
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(&exif);
+    out.extend_from_slice(&jpeg[2..]);
+    out
+}
+
+/// Uploads `file` in one chunk, and returns the completed upload.
+async fn upload_file(
+    client: &TestClient,
+    token: &str,
+    project_pid: &str,
+    content_type: &str,
+    file: Vec<u8>,
+) -> serde_json::Value {
+    let upload = create_upload(client, token, project_pid, content_type, file.len() as u64).await;
+    let res = client
+        .patch(&format!(
+            "/api/v1/uploads/{}",
+            upload["id"].as_str().unwrap()
+        ))
+        .header("Authorization", &format!("Bearer {token}"))
+        .header("Upload-Offset", "0")
+        .body(file)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK, "{}", res.text());
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "complete");
+    body
+}
+
+/// A video file of `size` bytes.
+fn video(size: u64) -> Vec<u8> {
+    let mut bytes = vec![0u8; size as usize];
+    bytes[..MP4_HEAD.len()].copy_from_slice(MP4_HEAD);
+    bytes
+}
+
+#[tokio::test]
+async fn image_uploaded_in_one_chunk_is_served() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+
+    let file = png(4, 3);
+    let upload = create_upload(
+        &client,
+        &token,
+        &project_pid,
+        "image/png",
+        file.len() as u64,
+    )
+    .await;
+    assert_eq!(upload["status"], "pending");
+    assert_eq!(upload["offset"], 0);
+    assert_eq!(upload["project_id"], project_pid);
+    assert!(upload["url"].is_null());
+    // Local storage cannot take uploads from clients directly.
+    assert!(upload["presigned"].is_null());
+
+    let id = upload["id"].as_str().unwrap();
+    let res = client
+        .patch(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .header("Upload-Offset", "0")
+        .body(file.clone())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "complete");
+    assert_eq!(body["offset"], file.len());
+    // Too small to need smaller copies.
+    assert_eq!(body["variants"], json!([]));
+    assert!(body["completed_at"].is_string());
+
+    let url = body["url"].as_str().unwrap();
+    assert!(url.starts_with("/api/v1/media/"));
+    assert!(url.ends_with(".png"));
+
+    let res = client.get(url).send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["content-type"], "image/png");
+    let served = image::load_from_memory(res.bytes()).unwrap();
+    assert_eq!((served.width(), served.height()), (4, 3));
+
+    // Completing again does nothing.
+    let res = client
+        .post(&format!("/api/v1/uploads/{id}/complete"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.json::<serde_json::Value>()["url"], url);
+}
+
+#[tokio::test]
+async fn video_upload_resumes_from_its_offset() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+
+    let size = MIN_PART_BYTES + 10;
+    let file = video(size);
+    let upload = create_upload(&client, &token, &project_pid, "video/mp4", size).await;
+    let id = upload["id"].as_str().unwrap();
+    let split = MIN_PART_BYTES as usize;
+
+    let res = client
+        .patch(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .header("Upload-Offset", "0")
+        .body(file[..split].to_vec())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.json::<serde_json::Value>()["status"], "pending");
+
+    // A client that lost track asks where to carry on.
+    let res = client
+        .get(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["offset"], split);
+    assert!(body["presigned"].is_null());
+
+    // Resending the first chunk is refused with the offset to use.
+    let res = client
+        .patch(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .header("Upload-Offset", "0")
+        .body(file[..split].to_vec())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["details"]["offset"], split);
+
+    // Completing before every byte arrived is refused too.
+    let res = client
+        .post(&format!("/api/v1/uploads/{id}/complete"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    let res = client
+        .patch(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .header("Upload-Offset", &split.to_string())
+        .body(file[split..].to_vec())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["status"], "complete");
+
+    let res = client.get(body["url"].as_str().unwrap()).send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["content-type"], "video/mp4");
+    assert_eq!(res.bytes().as_ref(), file.as_slice());
+}
+
+#[tokio::test]
+async fn uploaded_video_is_processed_from_storage() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+
+    let file = video(64);
+    let upload = create_upload(&client, &token, &project_pid, "video/mp4", 64).await;
+    let res = client
+        .patch(&format!(
+            "/api/v1/uploads/{}",
+            upload["id"].as_str().unwrap()
+        ))
+        .header("Authorization", &auth)
+        .header("Upload-Offset", "0")
+        .body(file)
+        .send()
+        .await;
+    let url = res.json::<serde_json::Value>()["url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &auth)
+        .json(&json!({
+            "author_name": "Video Vera",
+            "type": "video",
+            "video_url": url
+        }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let pid = res.json::<serde_json::Value>()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Runs video jobs as the worker would.
+    let pipeline = VideoPipeline::new(
+        LocalFileProbe,
+        transcribe::Disabled,
+        storage(),
+        SentimentAnalyzer::default(),
+        Destinations::Public,
+    );
+    let conn = DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect");
+    let worker = Worker::new(conn).handle(move |conn, job: ProcessVideo| {
+        let pipeline = pipeline.clone();
+        async move { pipeline.process(&conn, job).await }
+    });
+    for _ in 0..100 {
+        worker.run_until_idle().await.unwrap();
+        let res = client
+            .get(&format!("/api/v1/testimonials/{pid}"))
+            .header("Authorization", &auth)
+            .send()
+            .await;
+        let body: serde_json::Value = res.json();
+        if body["video_status"] != "processing" {
+            assert_eq!(body["video_status"], "ready", "{body}");
+            assert_eq!(body["video_duration_seconds"], 7);
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("video of {pid} was still processing");
+}
+
+#[tokio::test]
+async fn uploaded_image_is_stripped_turned_upright_and_resized() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let marker = b"GPS 51.5007N 0.1246W";
+
+    let file = jpeg_with_exif(600, 400, marker);
+    let upload = upload_file(&client, &token, &project_pid, "image/jpeg", file).await;
+
+    let res = client.get(upload["url"].as_str().unwrap()).send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let bytes = res.bytes().to_vec();
+    assert!(!bytes.windows(marker.len()).any(|w| w == marker));
+    assert!(!bytes.windows(4).any(|w| w == b"Exif"));
+    let served = image::load_from_memory(&bytes).unwrap();
+    // Turned a quarter as the EXIF orientation said.
+    assert_eq!((served.width(), served.height()), (400, 600));
+
+    let variants = upload["variants"].as_array().unwrap();
+    let sizes: Vec<(u64, u64, &str)> = variants
+        .iter()
+        .map(|v| {
+            (
+                v["width"].as_u64().unwrap(),
+                v["height"].as_u64().unwrap(),
+                v["content_type"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        sizes,
+        vec![
+            (43, 64, "image/jpeg"),
+            (85, 128, "image/jpeg"),
+            (171, 256, "image/jpeg"),
+            (341, 512, "image/jpeg"),
+        ]
+    );
+
+    for variant in variants {
+        let res = client.get(variant["url"].as_str().unwrap()).send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()["content-type"],
+            variant["content_type"].as_str().unwrap()
+        );
+        let image = image::load_from_memory(res.bytes()).unwrap();
+        assert_eq!(image.width() as u64, variant["width"].as_u64().unwrap());
+        assert_eq!(image.height() as u64, variant["height"].as_u64().unwrap());
+    }
+}
+
+#[tokio::test]
+async fn animated_gif_is_kept_as_uploaded() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    let file = animated_gif(100, 80);
+    let upload = upload_file(&client, &token, &project_pid, "image/gif", file.clone()).await;
+
+    let res = client.get(upload["url"].as_str().unwrap()).send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.bytes().as_ref(), file.as_slice());
+
+    // The variant is a still of the first frame.
+    let variants = upload["variants"].as_array().unwrap();
+    assert_eq!(variants.len(), 1);
+    assert_eq!(variants[0]["content_type"], "image/png");
+    assert_eq!(variants[0]["width"], 64);
+    let res = client
+        .get(variants[0]["url"].as_str().unwrap())
+        .send()
+        .await;
+    let still = image::load_from_memory(res.bytes()).unwrap().to_rgba8();
+    assert_eq!(still.get_pixel(32, 25), &image::Rgba([200, 30, 30, 255]));
+}
+
+#[tokio::test]
+async fn avatars_and_logos_list_the_variants_of_their_upload() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let other_project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+
+    let upload = upload_file(&client, &token, &project_pid, "image/png", png(300, 300)).await;
+    let url = upload["url"].as_str().unwrap();
+    let variants = upload["variants"].clone();
+    // 64, 128 and 256, each as PNG.
+    assert_eq!(variants.as_array().unwrap().len(), 3);
+    assert_eq!(variants[0]["content_type"], "image/png");
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &auth)
+        .json(&json!({ "author_name": "Ava", "content": "Nice!", "author_avatar_url": url }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["author_avatar_variants"], variants);
+    let testimonial_pid = body["id"].as_str().unwrap().to_string();
+
+    let res = client
+        .patch(&format!("/api/v1/testimonials/{testimonial_pid}"))
+        .header("Authorization", &auth)
+        .json(&json!({ "author_avatar_url": "https://cdn.example.com/ava.png" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        res.json::<serde_json::Value>()["author_avatar_variants"],
+        json!([])
+    );
+
+    let res = client
+        .patch(&format!("/api/v1/projects/{project_pid}"))
+        .header("Authorization", &auth)
+        .json(&json!({ "logo_url": url }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.json::<serde_json::Value>()["logo_variants"], variants);
+
+    let res = client
+        .get(&format!("/api/v1/projects/{project_pid}"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.json::<serde_json::Value>()["logo_variants"], variants);
+
+    // Uploads to another project are not linked.
+    let res = client
+        .post(&format!(
+            "/api/v1/projects/{other_project_pid}/testimonials"
+        ))
+        .header("Authorization", &auth)
+        .json(&json!({ "author_name": "Ava", "content": "Nice!", "author_avatar_url": url }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert_eq!(
+        res.json::<serde_json::Value>()["author_avatar_variants"],
+        json!([])
+    );
+}
+
+#[tokio::test]
+async fn upload_that_is_not_a_valid_image_is_discarded() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+
+    // Starts like a PNG, but is not one.
+    let mut file = PNG.to_vec();
+    file.resize(64, 7);
+    let upload = create_upload(&client, &token, &project_pid, "image/png", 64).await;
+    let id = upload["id"].as_str().unwrap();
+
+    let res = client
+        .patch(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .header("Upload-Offset", "0")
+        .body(file)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let res = client
+        .get(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn uploads_outside_the_limits_are_refused() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+    let create = |content_type: &str, size: u64| {
+        client
+            .post(&format!("/api/v1/projects/{project_pid}/uploads"))
+            .header("Authorization", &auth)
+            .json(&json!({ "content_type": content_type, "size": size }))
+            .send()
+    };
+
+    let res = create("application/pdf", 100).await;
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let res = create("image/png", 1024 * 1024 + 1).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let res = create("video/mp4", 2 * MIN_PART_BYTES + 1).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let res = create("image/png", 0).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn chunks_are_checked() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+
+    let upload = create_upload(&client, &token, &project_pid, "image/png", 64).await;
+    let id = upload["id"].as_str().unwrap();
+    let send = |offset: &str, body: Vec<u8>| {
+        client
+            .patch(&format!("/api/v1/uploads/{id}"))
+            .header("Authorization", &auth)
+            .header("Upload-Offset", offset)
+            .body(body)
+            .send()
+    };
+
+    // Not a PNG, whatever the declared type says.
+    let mut gif = b"GIF89a".to_vec();
+    gif.resize(64, 0);
+    let res = send("0", gif).await;
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    // Past the declared size.
+    let mut oversize = PNG.to_vec();
+    oversize.resize(65, 0);
+    let res = send("0", oversize).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Too short to be anything but the last chunk.
+    let res = send("0", PNG.to_vec()).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = send("0", Vec::new()).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = send("not a number", PNG.to_vec()).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+    let res = send("5", PNG.to_vec()).await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // Nothing was stored, so nothing can be completed.
+    let res = client
+        .post(&format!("/api/v1/uploads/{id}/complete"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn cancelled_upload_is_gone() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+
+    let upload = create_upload(&client, &token, &project_pid, "image/png", 64).await;
+    let id = upload["id"].as_str().unwrap();
+
+    let res = client
+        .delete(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+    let res = client
+        .get(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn complete_upload_cannot_be_cancelled() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+
+    let upload = upload_file(&client, &token, &project_pid, "image/png", png(2, 2)).await;
+    let id = upload["id"].as_str().unwrap();
+
+    let res = client
+        .delete(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn expired_uploads_are_discarded() {
+    let client = setup().await;
+    let token = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let auth = format!("Bearer {token}");
+
+    let upload = create_upload(&client, &token, &project_pid, "image/png", 64).await;
+    let id = upload["id"].as_str().unwrap();
+    let pid = Uuid::parse_str(id).unwrap();
+
+    let conn = DatabaseConfig::new(database_url()).connect().await.unwrap();
+    Upload::update_many()
+        .col_expr(
+            UploadColumn::ExpiresAt,
+            Expr::value(chrono::Utc::now().fixed_offset() - chrono::Duration::minutes(1)),
+        )
+        .filter(UploadColumn::Pid.eq(pid))
+        .exec(&conn)
+        .await
+        .unwrap();
+
+    let res = client
+        .patch(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .header("Upload-Offset", "0")
+        .body(PNG.to_vec())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::GONE);
+
+    let discarded = expiry::expire(&conn, &storage()).await.unwrap();
+    assert!(discarded >= 1);
+
+    let res = client
+        .get(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &auth)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn uploads_of_other_users_are_forbidden() {
+    let client = setup().await;
+    let owner = register_and_get_token(&client).await;
+    let other = register_and_get_token(&client).await;
+    let project_pid = create_project(&client, &owner).await;
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/uploads"))
+        .header("Authorization", &format!("Bearer {other}"))
+        .json(&json!({ "content_type": "image/png", "size": 64 }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let upload = create_upload(&client, &owner, &project_pid, "image/png", 64).await;
+    let id = upload["id"].as_str().unwrap();
+    let other = format!("Bearer {other}");
+
+    let res = client
+        .get(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &other)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .patch(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &other)
+        .header("Upload-Offset", "0")
+        .body(PNG.to_vec())
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .delete(&format!("/api/v1/uploads/{id}"))
+        .header("Authorization", &other)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client.get(&format!("/api/v1/uploads/{id}")).send().await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn media_is_served_in_ranges() {
+    let client = setup().await;
+    let storage = storage();
+
+    let small = format!("{}.mp4", Uuid::new_v4());
+    storage
+        .put(&small, b"0123456789".to_vec(), "video/mp4")
+        .await
+        .unwrap();
+    let url = storage.url(&small);
+
+    let res = client.get(&url).send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["accept-ranges"], "bytes");
+    assert_eq!(res.bytes().as_ref(), b"0123456789");
+
+    let ranged = |range: &'static str| client.get(&url).header("Range", range).send();
+    let res = ranged("bytes=2-4").await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.headers()["content-range"], "bytes 2-4/10");
+    assert_eq!(res.headers()["content-type"], "video/mp4");
+    assert_eq!(res.bytes().as_ref(), b"234");
+
+    let res = ranged("bytes=7-").await;
+    assert_eq!(res.headers()["content-range"], "bytes 7-9/10");
+    assert_eq!(res.bytes().as_ref(), b"789");
+
+    let res = ranged("bytes=-3").await;
+    assert_eq!(res.headers()["content-range"], "bytes 7-9/10");
+
+    let res = ranged("bytes=5-100").await;
+    assert_eq!(res.headers()["content-range"], "bytes 5-9/10");
+
+    let res = ranged("bytes=10-").await;
+    assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(res.headers()["content-range"], "bytes */10");
+
+    // Ranges that are not supported are ignored.
+    let res = ranged("bytes=0-1,4-5").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.bytes().len(), 10);
+
+    // Large files are sent a part at a time.
+    let max = media::handlers::MAX_RESPONSE_BYTES;
+    let large = format!("{}.mp4", Uuid::new_v4());
+    let bytes: Vec<u8> = (0..max + 10).map(|i| (i % 251) as u8).collect();
+    storage
+        .put(&large, bytes.clone(), "video/mp4")
+        .await
+        .unwrap();
+    let url = storage.url(&large);
+
+    let res = client.get(&url).send().await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        res.headers()["content-range"],
+        format!("bytes 0-{}/{}", max - 1, max + 10).as_str()
+    );
+    assert_eq!(res.bytes().as_ref(), &bytes[..max as usize]);
+
+    let res = client.get(&url).header("Range", "bytes=1-").send().await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.bytes().len() as u64, max);
+    let res = client
+        .get(&url)
+        .header("Range", &format!("bytes={max}-"))
+        .send()
+        .await;
+    assert_eq!(res.bytes().as_ref(), &bytes[max as usize..]);
+
+    storage.delete(&small).await.unwrap();
+    storage.delete(&large).await.unwrap();
+}
+
+#[tokio::test]
+async fn missing_media_is_not_found() {
+    let client = setup().await;
+
+    let res = client.get("/api/v1/media/nothing-here.png").send().await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let res = client
+        .get("/api/v1/media/..%2F..%2Fetc%2Fpasswd")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
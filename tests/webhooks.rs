@@ -0,0 +1,969 @@
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+
+use hmac::{Hmac, Mac};
+use rapina::auth::{AuthMiddleware, PublicRoutes};
+use rapina::database::DatabaseConfig;
+use rapina::prelude::*;
+use rapina::sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use rapina::testing::TestClient;
+use serde_json::{Value, json};
+use sha2::Sha256;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use reeverb::api::v1::auth;
+use reeverb::api::v1::forms::{self, throttle::SubmissionThrottle};
+use reeverb::api::v1::projects;
+use reeverb::api::v1::testimonials;
+use reeverb::api::v1::webhooks;
+use reeverb::db::entities::form::ActiveModel as FormActiveModel;
+use reeverb::db::entities::job::{Column as JobColumn, Entity as JobEntity};
+use reeverb::db::entities::project::{Column as ProjectColumn, Entity as Project};
+use reeverb::db::entities::testimonial::{Column as TestimonialColumn, Entity as Testimonial};
+use reeverb::db::entities::webhook::{Column as WebhookColumn, Entity as Webhook};
+use reeverb::db::entities::webhook_delivery::{
+    ActiveModel as DeliveryActiveModel, Column as DeliveryColumn, Entity as Delivery,
+    Model as DeliveryModel,
+};
+use reeverb::db::migrations::Migrator;
+use reeverb::jobs::{Job, STATUS_QUEUED};
+use reeverb::net::Destinations;
+use reeverb::secrets::Keyring;
+use reeverb::sentiment::SentimentAnalyzer;
+use reeverb::storage::MediaStorage;
+use reeverb::storage::local::LocalStorage;
+use reeverb::video::{ProcessVideo, VideoError, VideoInfo, VideoPipeline, VideoProbe, transcribe};
+use reeverb::webhooks::{
+    DELIVERY_ATTEMPTS, DELIVERY_HEADER, Deliver, EVENT_HEADER, SIGNATURE_HEADER, Sender,
+};
+
+static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
+
+const KEY: &str = "k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+
+fn keyring() -> Keyring {
+    Keyring::parse(KEY).unwrap()
+}
+
+fn database_url() -> String {
+    dotenvy::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for integration tests")
+}
+
+async fn run_migrations_once() {
+    MIGRATIONS
+        .get_or_init(|| async {
+            let config = DatabaseConfig::new(database_url());
+            let conn = config.connect().await.expect("failed to connect");
+
+            use rapina::sea_orm_migration::MigratorTrait;
+            Migrator::up(&conn, None)
+                .await
+                .expect("failed to run migrations");
+        })
+        .await;
+}
+
+/// A request the receiver got.
+struct Received {
+    event: String,
+    delivery: String,
+    signature: String,
+    body: Value,
+}
+
+/// What the receiver got so far, and the status it answers with.
+#[derive(Clone)]
+struct Receiver {
+    received: Arc<Mutex<Vec<Received>>>,
+    status: Arc<AtomicU16>,
+}
+
+impl Receiver {
+    fn answer(&self, status: u16) {
+        self.status.store(status, Ordering::SeqCst);
+    }
+
+    fn take(&self) -> Vec<Received> {
+        std::mem::take(&mut *self.received.lock().unwrap())
+    }
+}
+
+#[post("/hook")]
+async fn hook(receiver: State<Receiver>, headers: Headers, body: Json<Value>) -> StatusCode {
+    let receiver = receiver.into_inner();
+    let header = |name: &str| {
+        headers
+            .0
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    receiver.received.lock().unwrap().push(Received {
+        event: header(EVENT_HEADER),
+        delivery: header(DELIVERY_HEADER),
+        signature: header(SIGNATURE_HEADER),
+        body: body.into_inner(),
+    });
+    StatusCode::from_u16(receiver.status.load(Ordering::SeqCst)).unwrap()
+}
+
+/// A server standing in for the receiving end, which stops when dropped.
+async fn receiver() -> (TestClient, Receiver) {
+    let receiver = Receiver {
+        received: Arc::new(Mutex::new(Vec::new())),
+        status: Arc::new(AtomicU16::new(200)),
+    };
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(receiver.clone())
+        .router(Router::new().post("/hook", hook));
+
+    (TestClient::new(app).await, receiver)
+}
+
+/// The app, with webhooks allowed to reach the local receivers.
+async fn setup() -> TestClient {
+    setup_with(Destinations::Any).await
+}
+
+async fn setup_with(destinations: Destinations) -> TestClient {
+    run_migrations_once().await;
+
+    let auth_config = AuthConfig::new("test-secret", 3600);
+
+    let mut public_routes = PublicRoutes::new();
+    for (method, path) in auth::PUBLIC_ROUTES.iter().chain(forms::PUBLIC_ROUTES) {
+        public_routes.add(method, path);
+    }
+
+    let auth_middleware = AuthMiddleware::with_public_routes(auth_config.clone(), public_routes);
+
+    let router = Router::new()
+        .group("/api/v1/auth", auth::routes())
+        .group("/api/v1/projects", projects::routes())
+        .group("/api/v1/projects", testimonials::project_routes())
+        .group("/api/v1/testimonials", testimonials::routes())
+        .group("/api/v1/forms", forms::routes())
+        .group("/api/v1/projects", webhooks::project_routes())
+        .group("/api/v1/webhooks", webhooks::routes())
+        .group("/api/v1/webhook-deliveries", webhooks::delivery_routes());
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(auth_config)
+        .state(SentimentAnalyzer::default())
+        .state(SubmissionThrottle::default())
+        .state(storage())
+        .state(keyring())
+        .state(destinations)
+        .middleware(auth_middleware)
+        .with_database(DatabaseConfig::new(database_url()))
+        .await
+        .expect("failed to connect to test database")
+        .router(router);
+
+    TestClient::new(app).await
+}
+
+fn storage() -> MediaStorage {
+    MediaStorage::new(
+        LocalStorage::new(std::env::temp_dir().join("reeverb-test-media")),
+        "/api/v1/media",
+    )
+}
+
+async fn connect() -> DatabaseConnection {
+    DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect")
+}
+
+fn unique_email() -> String {
+    format!("test-{}@example.com", Uuid::new_v4())
+}
+
+async fn register_and_get_token(client: &TestClient) -> String {
+    let res = client
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": unique_email(),
+            "password": "password123",
+            "name": "Test User"
+        }))
+        .send()
+        .await;
+
+    let body: Value = res.json();
+    body["token"].as_str().unwrap().to_string()
+}
+
+async fn create_project(client: &TestClient, token: &str) -> String {
+    let slug = format!("project-{}", Uuid::new_v4());
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "name": "Test Project", "slug": slug }))
+        .send()
+        .await;
+
+    let body: Value = res.json();
+    body["id"].as_str().unwrap().to_string()
+}
+
+/// Creates a webhook posting to `receiver`, and returns its response.
+async fn create_webhook(
+    client: &TestClient,
+    token: &str,
+    project_id: &str,
+    receiver: &TestClient,
+    events: Value,
+) -> Value {
+    let res = client
+        .post(&format!("/api/v1/projects/{project_id}/webhooks"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "url": format!("http://{}/hook", receiver.addr()),
+            "events": events,
+            "description": "CRM sync"
+        }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    res.json()
+}
+
+async fn create_testimonial(
+    client: &TestClient,
+    token: &str,
+    project_id: &str,
+    body: Value,
+) -> Value {
+    let res = client
+        .post(&format!("/api/v1/projects/{project_id}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&body)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    res.json()
+}
+
+/// Submits a testimonial through a new form of the project, as a visitor.
+async fn submit(client: &TestClient, project_id: &str, body: Value) -> Value {
+    let conn = connect().await;
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(Uuid::parse_str(project_id).unwrap()))
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap();
+    let slug = format!("form-{}", Uuid::new_v4());
+    FormActiveModel {
+        pid: Set(Uuid::new_v4()),
+        project_id: Set(project.id),
+        name: Set("Feedback".to_string()),
+        slug: Set(slug.clone()),
+        ..Default::default()
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+
+    let res = client
+        .post(&format!("/api/v1/forms/{slug}/submissions"))
+        .json(&body)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    res.json()
+}
+
+async fn post(client: &TestClient, token: &str, path: &str) -> rapina::testing::TestResponse {
+    client
+        .post(path)
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await
+}
+
+async fn get_json(client: &TestClient, token: &str, path: &str) -> Value {
+    let res = client
+        .get(path)
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    res.json()
+}
+
+async fn deliveries(conn: &DatabaseConnection, webhook_id: &str) -> Vec<DeliveryModel> {
+    let webhook = Webhook::find()
+        .filter(WebhookColumn::Pid.eq(Uuid::parse_str(webhook_id).unwrap()))
+        .one(conn)
+        .await
+        .unwrap()
+        .unwrap();
+    Delivery::find()
+        .filter(DeliveryColumn::WebhookId.eq(webhook.id))
+        .order_by_asc(DeliveryColumn::Id)
+        .all(conn)
+        .await
+        .unwrap()
+}
+
+/// Sends the webhook's pending deliveries once each, as the worker would.
+/// Deliveries are sent directly rather than through a worker, so tests
+/// running at once do not send each other's.
+async fn deliver_pending(conn: &DatabaseConnection, webhook_id: &str) -> Vec<bool> {
+    deliver_pending_to(Destinations::Any, conn, webhook_id).await
+}
+
+/// Like [`deliver_pending`], with a sender that only reaches `destinations`.
+async fn deliver_pending_to(
+    destinations: Destinations,
+    conn: &DatabaseConnection,
+    webhook_id: &str,
+) -> Vec<bool> {
+    let sender = Sender::new(keyring(), destinations);
+    let mut results = Vec::new();
+    for delivery in deliveries(conn, webhook_id).await {
+        if delivery.status != "pending" {
+            continue;
+        }
+        let job = JobEntity::find()
+            .filter(JobColumn::Kind.eq(Deliver::KIND))
+            .filter(JobColumn::Payload.eq(json!({ "delivery_id": delivery.id })))
+            .one(conn)
+            .await
+            .unwrap();
+        assert!(job.is_some_and(|j| j.status == STATUS_QUEUED));
+
+        let result = sender
+            .deliver(
+                conn,
+                Deliver {
+                    delivery_id: delivery.id,
+                },
+            )
+            .await;
+        results.push(result.is_ok());
+    }
+    results
+}
+
+/// Checks `signature` the way a receiver would.
+fn verify(secret: &str, signature: &str, body: &Value) -> bool {
+    let Some((t, v1)) = signature
+        .strip_prefix("t=")
+        .and_then(|rest| rest.split_once(",v1="))
+    else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("{t}.").as_bytes());
+    mac.update(&serde_json::to_vec(body).unwrap());
+    mac.verify_slice(&hex::decode(v1).unwrap()).is_ok()
+}
+
+#[tokio::test]
+async fn create_returns_secret_once_and_validates() {
+    let client = setup().await;
+    let (receiver_server, _receiver) = receiver().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    let webhook = create_webhook(
+        &client,
+        &token,
+        &project_id,
+        &receiver_server,
+        json!([
+            "testimonial.created",
+            "testimonial.created",
+            "form.submitted"
+        ]),
+    )
+    .await;
+    assert!(webhook["secret"].as_str().unwrap().starts_with("whsec_"));
+    assert_eq!(
+        webhook["events"],
+        json!(["form.submitted", "testimonial.created"])
+    );
+    assert_eq!(webhook["is_active"], true);
+
+    let id = webhook["id"].as_str().unwrap();
+    let fetched = get_json(&client, &token, &format!("/api/v1/webhooks/{id}")).await;
+    assert!(fetched.get("secret").is_none());
+    assert_eq!(fetched["description"], "CRM sync");
+
+    let stored = Webhook::find()
+        .filter(WebhookColumn::Pid.eq(Uuid::parse_str(id).unwrap()))
+        .one(&connect().await)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!stored.secret.to_string().contains("whsec_"));
+
+    for body in [
+        json!({ "url": "not a url", "events": ["testimonial.created"] }),
+        json!({ "url": "https://example.com/hook", "events": [] }),
+        json!({ "url": "https://example.com/hook", "events": ["testimonial.deleted"] }),
+    ] {
+        let res = client
+            .post(&format!("/api/v1/projects/{project_id}/webhooks"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .json(&body)
+            .send()
+            .await;
+        assert!(res.status().is_client_error(), "{body}");
+    }
+}
+
+#[tokio::test]
+async fn webhook_urls_must_be_public_http() {
+    let client = setup_with(Destinations::Public).await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+
+    for url in [
+        "http://127.0.0.1:8080/hook",
+        "http://[::1]/hook",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://[::ffff:169.254.169.254]/latest/meta-data/",
+        "http://10.0.0.5/hook",
+        "http://0.0.0.0/hook",
+        "ftp://example.com/hook",
+        "file:///etc/passwd",
+    ] {
+        let res = client
+            .post(&format!("/api/v1/projects/{project_id}/webhooks"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .json(&json!({ "url": url, "events": ["testimonial.created"] }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY, "{url}");
+    }
+
+    let res = client
+        .post(&format!("/api/v1/projects/{project_id}/webhooks"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "url": "https://example.com/hook", "events": ["testimonial.created"] }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let webhook: Value = res.json();
+    let id = webhook["id"].as_str().unwrap();
+
+    let res = client
+        .put(&format!("/api/v1/webhooks/{id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "url": "http://169.254.169.254/latest/meta-data/" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn deliveries_only_reach_public_addresses() {
+    // Saved while private networks were allowed, then sent by a sender that
+    // only reaches public addresses.
+    let client = setup().await;
+    let (receiver_server, receiver) = receiver().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+    let conn = connect().await;
+
+    let port = receiver_server.addr().port();
+    for (url, error) in [
+        (
+            format!("http://127.0.0.1:{port}/hook"),
+            "non-public address",
+        ),
+        (
+            format!("http://localhost:{port}/hook"),
+            "localhost does not resolve to a public address",
+        ),
+    ] {
+        let res = client
+            .post(&format!("/api/v1/projects/{project_id}/webhooks"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .json(&json!({ "url": url, "events": ["testimonial.created"] }))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let webhook: Value = res.json();
+        let webhook_id = webhook["id"].as_str().unwrap();
+
+        create_testimonial(
+            &client,
+            &token,
+            &project_id,
+            json!({ "author_name": "Jane Doe", "content": "Amazing!" }),
+        )
+        .await;
+
+        assert_eq!(
+            deliver_pending_to(Destinations::Public, &conn, webhook_id).await,
+            vec![false]
+        );
+        let delivery = &deliveries(&conn, webhook_id).await[0];
+        assert!(delivery.response_status.is_none());
+        assert!(
+            delivery.error.as_deref().unwrap().contains(error),
+            "{:?}",
+            delivery.error
+        );
+
+        // The same delivery goes through once private networks are allowed.
+        assert_eq!(deliver_pending(&conn, webhook_id).await, vec![true]);
+    }
+    assert_eq!(receiver.take().len(), 2);
+}
+
+#[tokio::test]
+async fn events_are_delivered_signed() {
+    let client = setup().await;
+    let (receiver_server, receiver) = receiver().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+    let conn = connect().await;
+
+    let webhook = create_webhook(
+        &client,
+        &token,
+        &project_id,
+        &receiver_server,
+        json!([
+            "testimonial.created",
+            "testimonial.approved",
+            "testimonial.featured",
+            "form.submitted"
+        ]),
+    )
+    .await;
+    let webhook_id = webhook["id"].as_str().unwrap();
+    let secret = webhook["secret"].as_str().unwrap();
+
+    let testimonial = submit(
+        &client,
+        &project_id,
+        json!({ "author_name": "Jane Doe", "content": "Amazing!" }),
+    )
+    .await;
+    let testimonial_id = testimonial["id"].as_str().unwrap();
+
+    assert_eq!(deliver_pending(&conn, webhook_id).await, vec![true, true]);
+    let received = receiver.take();
+    let events: Vec<&str> = received.iter().map(|r| r.event.as_str()).collect();
+    assert_eq!(events, ["testimonial.created", "form.submitted"]);
+    for request in &received {
+        assert!(verify(secret, &request.signature, &request.body));
+        assert!(!verify("whsec_wrong", &request.signature, &request.body));
+        assert_eq!(request.body["id"], request.delivery.as_str());
+        assert_eq!(request.body["event"], request.event.as_str());
+        assert_eq!(request.body["data"]["id"], testimonial_id);
+        assert_eq!(request.body["data"]["author_name"], "Jane Doe");
+        assert_eq!(request.body["data"]["project_id"], project_id.as_str());
+    }
+
+    let res = post(
+        &client,
+        &token,
+        &format!("/api/v1/testimonials/{testimonial_id}/approve"),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let res = post(
+        &client,
+        &token,
+        &format!("/api/v1/testimonials/{testimonial_id}/feature"),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    // Approving or featuring again changes nothing, so sends nothing.
+    post(
+        &client,
+        &token,
+        &format!("/api/v1/testimonials/{testimonial_id}/feature"),
+    )
+    .await;
+
+    assert_eq!(deliver_pending(&conn, webhook_id).await, vec![true, true]);
+    let received = receiver.take();
+    let events: Vec<&str> = received.iter().map(|r| r.event.as_str()).collect();
+    assert_eq!(events, ["testimonial.approved", "testimonial.featured"]);
+    assert_eq!(received[0].body["data"]["status"], "approved");
+    assert_eq!(received[1].body["data"]["is_featured"], true);
+
+    let log = get_json(
+        &client,
+        &token,
+        &format!("/api/v1/webhooks/{webhook_id}/deliveries"),
+    )
+    .await;
+    let log = log["data"].as_array().unwrap();
+    assert_eq!(log.len(), 4);
+    assert_eq!(log[0]["event"], "testimonial.featured");
+    for delivery in log {
+        assert_eq!(delivery["status"], "succeeded");
+        assert_eq!(delivery["attempts"], 1);
+        assert_eq!(delivery["response_status"], 200);
+        assert!(delivery["delivered_at"].is_string());
+    }
+}
+
+#[tokio::test]
+async fn only_subscribed_events_of_active_webhooks_are_sent() {
+    let client = setup().await;
+    let (receiver_server, _receiver) = receiver().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+    let conn = connect().await;
+
+    let webhook = create_webhook(
+        &client,
+        &token,
+        &project_id,
+        &receiver_server,
+        json!(["testimonial.approved"]),
+    )
+    .await;
+    let webhook_id = webhook["id"].as_str().unwrap();
+
+    let testimonial = create_testimonial(
+        &client,
+        &token,
+        &project_id,
+        json!({ "author_name": "Jane Doe", "content": "Amazing!" }),
+    )
+    .await;
+    assert!(deliveries(&conn, webhook_id).await.is_empty());
+
+    let res = client
+        .put(&format!("/api/v1/webhooks/{webhook_id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "is_active": false }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = res.json();
+    assert_eq!(body["is_active"], false);
+    assert_eq!(body["events"], json!(["testimonial.approved"]));
+
+    let testimonial_id = testimonial["id"].as_str().unwrap();
+    post(
+        &client,
+        &token,
+        &format!("/api/v1/testimonials/{testimonial_id}/approve"),
+    )
+    .await;
+    assert!(deliveries(&conn, webhook_id).await.is_empty());
+
+    // Bulk actions send events too.
+    client
+        .put(&format!("/api/v1/webhooks/{webhook_id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "is_active": true }))
+        .send()
+        .await;
+    let other = create_testimonial(
+        &client,
+        &token,
+        &project_id,
+        json!({ "author_name": "John Doe", "content": "Great!" }),
+    )
+    .await;
+    let res = client
+        .post(&format!("/api/v1/projects/{project_id}/testimonials/bulk"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "ids": [testimonial_id, other["id"]], "action": "approve" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let sent = deliveries(&conn, webhook_id).await;
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].event, "testimonial.approved");
+    assert_eq!(sent[0].payload["data"]["id"], other["id"]);
+}
+
+#[tokio::test]
+async fn events_are_queued_with_the_change_that_caused_them() {
+    let client = setup().await;
+    let (receiver_server, _receiver) = receiver().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+    let conn = connect().await;
+
+    let webhook = create_webhook(
+        &client,
+        &token,
+        &project_id,
+        &receiver_server,
+        json!(["testimonial.approved"]),
+    )
+    .await;
+    let webhook_id = webhook["id"].as_str().unwrap();
+    let testimonial = create_testimonial(
+        &client,
+        &token,
+        &project_id,
+        json!({ "author_name": "Jane Doe", "content": "Amazing!" }),
+    )
+    .await;
+    let testimonial_id = testimonial["id"].as_str().unwrap();
+
+    // An edit that approves sends the edited testimonial.
+    let res = client
+        .put(&format!("/api/v1/testimonials/{testimonial_id}"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "author_name": "Jane D.", "is_approved": true }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let sent = deliveries(&conn, webhook_id).await;
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].payload["data"]["author_name"], "Jane D.");
+    assert_eq!(sent[0].payload["data"]["status"], "approved");
+
+    // A bulk action that is rolled back sends nothing.
+    let other = create_testimonial(
+        &client,
+        &token,
+        &project_id,
+        json!({ "author_name": "John Doe", "content": "Great!" }),
+    )
+    .await;
+    let res = client
+        .post(&format!("/api/v1/projects/{project_id}/testimonials/bulk"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "ids": [other["id"], Uuid::new_v4()], "action": "approve" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    assert_eq!(deliveries(&conn, webhook_id).await.len(), 1);
+}
+
+/// Probes every video as 7 seconds long, without a thumbnail.
+struct SevenSeconds;
+
+#[async_trait::async_trait]
+impl VideoProbe for SevenSeconds {
+    async fn probe(&self, _: &str) -> std::result::Result<VideoInfo, VideoError> {
+        Ok(VideoInfo {
+            duration_seconds: Some(7),
+            thumbnail: None,
+        })
+    }
+}
+
+#[tokio::test]
+async fn processed_videos_are_sent_with_their_status() {
+    let client = setup().await;
+    let (receiver_server, _receiver) = receiver().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+    let conn = connect().await;
+
+    let webhook = create_webhook(
+        &client,
+        &token,
+        &project_id,
+        &receiver_server,
+        json!(["testimonial.video_processed"]),
+    )
+    .await;
+    let webhook_id = webhook["id"].as_str().unwrap();
+
+    let storage = storage();
+    let key = format!("{}.mp4", Uuid::new_v4());
+    storage
+        .put(&key, b"not really a video".to_vec(), "video/mp4")
+        .await
+        .unwrap();
+    let testimonial = create_testimonial(
+        &client,
+        &token,
+        &project_id,
+        json!({ "author_name": "Video Vera", "video_url": storage.url(&key) }),
+    )
+    .await;
+    assert!(deliveries(&conn, webhook_id).await.is_empty());
+
+    // Processed as the worker would, so other tests' videos are left alone.
+    let pid = Uuid::parse_str(testimonial["id"].as_str().unwrap()).unwrap();
+    let testimonial_id = Testimonial::find()
+        .filter(TestimonialColumn::Pid.eq(pid))
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap()
+        .id;
+    let pipeline = VideoPipeline::new(
+        SevenSeconds,
+        transcribe::Disabled,
+        storage,
+        SentimentAnalyzer::default(),
+        Destinations::Public,
+    );
+    pipeline
+        .process(&conn, ProcessVideo { testimonial_id })
+        .await
+        .unwrap();
+
+    let sent = deliveries(&conn, webhook_id).await;
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].event, "testimonial.video_processed");
+    let data = &sent[0].payload["data"];
+    assert_eq!(data["id"], testimonial["id"]);
+    assert_eq!(data["video_status"], "ready");
+    assert!(data["video_error"].is_null());
+    assert_eq!(data["video_duration_seconds"], 7);
+
+    // Nothing is left to process, so nothing more is sent.
+    pipeline
+        .process(&conn, ProcessVideo { testimonial_id })
+        .await
+        .unwrap();
+    assert_eq!(deliveries(&conn, webhook_id).await.len(), 1);
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried_and_can_be_redelivered() {
+    let client = setup().await;
+    let (receiver_server, receiver) = receiver().await;
+    let token = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &token).await;
+    let conn = connect().await;
+
+    let webhook = create_webhook(
+        &client,
+        &token,
+        &project_id,
+        &receiver_server,
+        json!(["testimonial.created"]),
+    )
+    .await;
+    let webhook_id = webhook["id"].as_str().unwrap();
+    create_testimonial(
+        &client,
+        &token,
+        &project_id,
+        json!({ "author_name": "Jane Doe", "content": "Amazing!" }),
+    )
+    .await;
+
+    // A failure leaves the delivery pending, to be tried again.
+    receiver.answer(503);
+    assert_eq!(deliver_pending(&conn, webhook_id).await, vec![false]);
+    let delivery = &deliveries(&conn, webhook_id).await[0];
+    assert_eq!(delivery.status, "pending");
+    assert_eq!(delivery.attempts, 1);
+    assert_eq!(delivery.response_status, Some(503));
+
+    let log = get_json(
+        &client,
+        &token,
+        &format!("/api/v1/webhook-deliveries/{}", delivery.pid),
+    )
+    .await;
+    assert_eq!(log["status"], "pending");
+    let res = post(
+        &client,
+        &token,
+        &format!("/api/v1/webhook-deliveries/{}/redeliver", delivery.pid),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    // Once its attempts run out it fails for good.
+    let mut active: DeliveryActiveModel = delivery.clone().into();
+    active.attempts = Set(DELIVERY_ATTEMPTS - 1);
+    active.update(&conn).await.unwrap();
+    assert_eq!(deliver_pending(&conn, webhook_id).await, vec![false]);
+    let failed = deliveries(&conn, webhook_id).await.remove(0);
+    assert_eq!(failed.status, "failed");
+    assert_eq!(failed.attempts, DELIVERY_ATTEMPTS);
+    let first_data = receiver.take().remove(0).body["data"].clone();
+
+    // Redelivering sends the same data as a new delivery.
+    receiver.answer(200);
+    let res = post(
+        &client,
+        &token,
+        &format!("/api/v1/webhook-deliveries/{}/redeliver", failed.pid),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    let redelivery: Value = res.json();
+    assert_eq!(redelivery["status"], "pending");
+    assert_eq!(redelivery["redelivery_of"], failed.pid.to_string());
+    assert_ne!(redelivery["id"], failed.pid.to_string());
+
+    assert_eq!(deliver_pending(&conn, webhook_id).await, vec![true]);
+    let received = receiver.take();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].delivery, redelivery["id"].as_str().unwrap());
+    assert_eq!(received[0].body["data"], first_data);
+
+    let log = get_json(
+        &client,
+        &token,
+        &format!("/api/v1/webhooks/{webhook_id}/deliveries"),
+    )
+    .await;
+    assert_eq!(log["data"][0]["status"], "succeeded");
+    assert_eq!(log["data"][0]["redelivery_of"], failed.pid.to_string());
+    assert_eq!(log["data"][1]["status"], "failed");
+}
+
+#[tokio::test]
+async fn webhooks_of_another_users_project_return_403() {
+    let client = setup().await;
+    let (receiver_server, _receiver) = receiver().await;
+    let owner = register_and_get_token(&client).await;
+    let other = register_and_get_token(&client).await;
+    let project_id = create_project(&client, &owner).await;
+
+    let webhook = create_webhook(
+        &client,
+        &owner,
+        &project_id,
+        &receiver_server,
+        json!(["testimonial.created"]),
+    )
+    .await;
+    let webhook_id = webhook["id"].as_str().unwrap();
+
+    let res = client
+        .get(&format!("/api/v1/projects/{project_id}/webhooks"))
+        .header("Authorization", &format!("Bearer {other}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = client
+        .delete(&format!("/api/v1/webhooks/{webhook_id}"))
+        .header("Authorization", &format!("Bearer {other}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .delete(&format!("/api/v1/webhooks/{webhook_id}"))
+        .header("Authorization", &format!("Bearer {owner}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let res = client
+        .get(&format!("/api/v1/webhooks/{webhook_id}"))
+        .header("Authorization", &format!("Bearer {owner}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
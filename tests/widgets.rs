@@ -0,0 +1,308 @@
+use rapina::auth::{AuthMiddleware, PublicRoutes};
+use rapina::database::DatabaseConfig;
+use rapina::prelude::*;
+use rapina::sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use rapina::testing::TestClient;
+use serde_json::{Value, json};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use reeverb::api::v1::auth;
+use reeverb::api::v1::projects;
+use reeverb::api::v1::testimonials;
+use reeverb::api::v1::widgets;
+use reeverb::db::entities::project::{Column as ProjectColumn, Entity as Project};
+use reeverb::db::entities::widget::ActiveModel as WidgetActiveModel;
+use reeverb::db::migrations::Migrator;
+use reeverb::sentiment::SentimentAnalyzer;
+use reeverb::storage::MediaStorage;
+use reeverb::storage::local::LocalStorage;
+
+static MIGRATIONS: OnceCell<()> = OnceCell::const_new();
+
+fn database_url() -> String {
+    dotenvy::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for integration tests")
+}
+
+async fn run_migrations_once() {
+    MIGRATIONS
+        .get_or_init(|| async {
+            let config = DatabaseConfig::new(database_url());
+            let conn = config.connect().await.expect("failed to connect");
+
+            use rapina::sea_orm_migration::MigratorTrait;
+            Migrator::up(&conn, None)
+                .await
+                .expect("failed to run migrations");
+        })
+        .await;
+}
+
+async fn setup() -> TestClient {
+    run_migrations_once().await;
+
+    let auth_config = AuthConfig::new("test-secret", 3600);
+
+    let mut public_routes = PublicRoutes::new();
+    for (method, path) in auth::PUBLIC_ROUTES.iter().chain(widgets::PUBLIC_ROUTES) {
+        public_routes.add(method, path);
+    }
+
+    let auth_middleware = AuthMiddleware::with_public_routes(auth_config.clone(), public_routes);
+
+    let router = Router::new()
+        .group("/api/v1/auth", auth::routes())
+        .group("/api/v1/projects", projects::routes())
+        .group("/api/v1/projects", testimonials::project_routes())
+        .group("/api/v1/testimonials", testimonials::routes())
+        .group("/api/v1/widgets", widgets::routes());
+
+    let storage = MediaStorage::new(
+        LocalStorage::new(std::env::temp_dir().join("reeverb-test-media")),
+        "/api/v1/media",
+    );
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .state(auth_config)
+        .state(SentimentAnalyzer::default())
+        .state(storage)
+        .middleware(auth_middleware)
+        .with_database(DatabaseConfig::new(database_url()))
+        .await
+        .expect("failed to connect to test database")
+        .router(router);
+
+    TestClient::new(app).await
+}
+
+async fn register(client: &TestClient) -> String {
+    let res = client
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": format!("test-{}@example.com", Uuid::new_v4()),
+            "password": "password123",
+            "name": "Test User"
+        }))
+        .send()
+        .await;
+
+    let body: Value = res.json();
+    body["token"].as_str().unwrap().to_string()
+}
+
+async fn create_project(client: &TestClient, token: &str) -> String {
+    let res = client
+        .post("/api/v1/projects")
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "name": "Acme",
+            "slug": format!("project-{}", Uuid::new_v4()),
+            "website_url": "https://acme.example.com"
+        }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    let body: Value = res.json();
+    body["id"].as_str().unwrap().to_string()
+}
+
+/// Creates a testimonial in `language`, approved unless `approve` is false.
+async fn create_testimonial(
+    client: &TestClient,
+    token: &str,
+    project_pid: &str,
+    author_name: &str,
+    language: &str,
+    approve: bool,
+) -> String {
+    let res = client
+        .post(&format!("/api/v1/projects/{project_pid}/testimonials"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({
+            "author_name": author_name,
+            "author_email": "author@example.com",
+            "content": "Great product.",
+            "rating": 5,
+            "language": language
+        }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body: Value = res.json();
+    let pid = body["id"].as_str().unwrap().to_string();
+
+    if approve {
+        let res = client
+            .post(&format!("/api/v1/testimonials/{pid}/approve"))
+            .header("Authorization", &format!("Bearer {token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+    pid
+}
+
+/// Widgets have no endpoints to create them yet, so they are inserted.
+async fn create_widget(project_pid: &str, languages: Option<&str>) -> String {
+    let conn = DatabaseConfig::new(database_url())
+        .connect()
+        .await
+        .expect("failed to connect");
+
+    let project = Project::find()
+        .filter(ProjectColumn::Pid.eq(Uuid::parse_str(project_pid).unwrap()))
+        .one(&conn)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let pid = Uuid::new_v4();
+    WidgetActiveModel {
+        pid: Set(pid),
+        project_id: Set(project.id),
+        name: Set("Homepage".to_string()),
+        widget_type: Set("carousel".to_string()),
+        languages: Set(languages.map(String::from)),
+        ..Default::default()
+    }
+    .insert(&conn)
+    .await
+    .unwrap();
+
+    pid.to_string()
+}
+
+fn author_names(body: &Value) -> Vec<&str> {
+    let mut names: Vec<&str> = body["testimonials"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["author_name"].as_str().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn widget_shows_approved_testimonials_in_its_languages() {
+    let client = setup().await;
+    let token = register(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    create_testimonial(&client, &token, &project_pid, "Ann", "en", true).await;
+    create_testimonial(&client, &token, &project_pid, "Bia", "pt-BR", true).await;
+    create_testimonial(&client, &token, &project_pid, "Chloé", "fr", true).await;
+    create_testimonial(&client, &token, &project_pid, "Dan", "en", false).await;
+
+    let widget_pid = create_widget(&project_pid, Some("en,pt")).await;
+
+    // Served without auth.
+    let res = client
+        .get(&format!("/api/v1/widgets/{widget_pid}/testimonials"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = res.json();
+    assert_eq!(body["widget_id"], widget_pid.as_str());
+    assert_eq!(body["language"], "en,pt");
+    assert_eq!(author_names(&body), ["Ann", "Bia"]);
+    assert!(body["testimonials"][0].get("author_email").is_none());
+
+    // Without languages, every approved testimonial is shown.
+    let widget_pid = create_widget(&project_pid, None).await;
+    let res = client
+        .get(&format!("/api/v1/widgets/{widget_pid}/testimonials"))
+        .send()
+        .await;
+    let body: Value = res.json();
+    assert!(body["language"].is_null());
+    assert_eq!(author_names(&body), ["Ann", "Bia", "Chloé"]);
+
+    let res = client
+        .get(&format!("/api/v1/widgets/{}/testimonials", Uuid::new_v4()))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn widget_follows_the_visitor_language() {
+    let client = setup().await;
+    let token = register(&client).await;
+    let project_pid = create_project(&client, &token).await;
+
+    create_testimonial(&client, &token, &project_pid, "Ann", "en", true).await;
+    create_testimonial(&client, &token, &project_pid, "Bia", "pt", true).await;
+    create_testimonial(&client, &token, &project_pid, "Chloé", "fr", true).await;
+
+    let widget_pid = create_widget(&project_pid, None).await;
+
+    let res = client
+        .put(&format!("/api/v1/widgets/{widget_pid}/language"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "languages": " fr, ", "visitor_language": true }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers().get("etag").is_some());
+    let body: Value = res.json();
+    assert_eq!(body["languages"], "fr");
+    assert_eq!(body["visitor_language"], true);
+
+    // `pt-BR` also takes testimonials only known to be in `pt`.
+    let res = client
+        .get(&format!("/api/v1/widgets/{widget_pid}/testimonials"))
+        .header("Accept-Language", "pt-BR, de;q=0, *;q=0.5")
+        .send()
+        .await;
+    let body: Value = res.json();
+    assert_eq!(body["language"], "pt-BR,pt");
+    assert_eq!(author_names(&body), ["Bia"]);
+
+    // Visitors whose browser names no language get the widget's own.
+    let res = client
+        .get(&format!("/api/v1/widgets/{widget_pid}/testimonials"))
+        .send()
+        .await;
+    let body: Value = res.json();
+    assert_eq!(body["language"], "fr");
+    assert_eq!(author_names(&body), ["Chloé"]);
+}
+
+#[tokio::test]
+async fn widget_language_is_validated_and_owner_only() {
+    let client = setup().await;
+    let token = register(&client).await;
+    let project_pid = create_project(&client, &token).await;
+    let widget_pid = create_widget(&project_pid, None).await;
+
+    let res = client
+        .put(&format!("/api/v1/widgets/{widget_pid}/language"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .json(&json!({ "languages": "en,not a language" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let other = register(&client).await;
+    let res = client
+        .put(&format!("/api/v1/widgets/{widget_pid}/language"))
+        .header("Authorization", &format!("Bearer {other}"))
+        .json(&json!({ "languages": "en" }))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = client
+        .get(&format!("/api/v1/widgets/{widget_pid}/language"))
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body: Value = res.json();
+    assert!(body["languages"].is_null());
+    assert_eq!(body["visitor_language"], false);
+}